@@ -0,0 +1,45 @@
+//! Measures dispatch latency for a trait with many methods. Run with `cargo bench`.
+//!
+//! A call to a method that doesn't exist is the worst case, every candidate name is ruled out.
+
+use easy_jsonrpc::{Handler, Params};
+use std::hint::black_box;
+use std::time::Instant;
+
+macro_rules! big_trait {
+    ($($method:ident)*) => {
+        #[easy_jsonrpc::rpc]
+        trait Big {
+            $(fn $method(&self) {})*
+        }
+    };
+}
+
+big_trait!(
+    m000 m001 m002 m003 m004 m005 m006 m007 m008 m009 m010 m011 m012 m013 m014 m015 m016
+    m017 m018 m019 m020 m021 m022 m023 m024 m025 m026 m027 m028 m029 m030 m031 m032 m033
+    m034 m035 m036 m037 m038 m039 m040 m041 m042 m043 m044 m045 m046 m047 m048 m049 m050
+    m051 m052 m053 m054 m055 m056 m057 m058 m059 m060 m061 m062 m063 m064 m065 m066 m067
+    m068 m069 m070 m071 m072 m073 m074 m075 m076 m077 m078 m079 m080 m081 m082 m083 m084
+    m085 m086 m087 m088 m089 m090 m091 m092 m093 m094 m095 m096 m097 m098 m099 m100 m101
+    m102 m103 m104 m105 m106 m107 m108 m109 m110 m111 m112 m113 m114 m115 m116 m117 m118
+    m119
+);
+
+impl Big for () {}
+
+fn measure(name: &str, method: &str) {
+    const ITERATIONS: u32 = 1_000_000;
+    let mut handler = &() as &dyn Big;
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let _ = black_box(handler.handle(black_box(method), Params::Positional(vec![])));
+    }
+    println!("{}: {:?} per call", name, start.elapsed() / ITERATIONS);
+}
+
+fn main() {
+    measure("hit first", "m000");
+    measure("hit last", "m119");
+    measure("miss", "m999");
+}
@@ -0,0 +1,93 @@
+//! Compares handling a request with large params through the borrowing and the owned paths,
+//! counting time and allocations per request. Run with `cargo bench`.
+//!
+//! `put` takes owned arguments, which are moved out of the params. `put_ref` takes its key as a
+//! `&str`, borrowing from the params, so its records are copied out of them instead.
+
+use easy_jsonrpc::{types, Handler};
+use serde_json::json;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+#[easy_jsonrpc::rpc]
+trait Store {
+    fn put(&self, key: String, records: Vec<Record>) -> usize {
+        black_box(key);
+        records.len()
+    }
+    fn put_ref(&self, key: &str, records: Vec<Record>) -> usize {
+        black_box(key);
+        records.len()
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Record {
+    name: String,
+    tags: Vec<String>,
+    score: f64,
+}
+
+impl Store for () {}
+
+// The request is cloned outside the measurement, so only handling it is counted.
+fn measure<R: Clone>(name: &str, request: &R, handle: impl Fn(R)) {
+    const ITERATIONS: u32 = 100;
+    let requests = vec![request.clone(); ITERATIONS as usize];
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for request in requests {
+        handle(black_box(request));
+    }
+    let elapsed = start.elapsed() / ITERATIONS;
+    let allocations = (ALLOCATIONS.load(Ordering::Relaxed) - allocations) / ITERATIONS as usize;
+    println!(
+        "{}: {:?}, {} allocations per request",
+        name, elapsed, allocations
+    );
+}
+
+fn main() {
+    let records: Vec<_> = (0..10_000)
+        .map(|i| json!({ "name": format!("record {}", i), "tags": ["a", "b"], "score": i }))
+        .collect();
+    for method in ["put_ref", "put"] {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": ["key", records],
+            "id": 1
+        });
+        measure(&format!("{} (Value)", method), &request, |request| {
+            black_box((&() as &dyn Store).handle_request(request));
+        });
+        let request: types::Request = serde_json::from_value(request).unwrap();
+        measure(
+            &format!("{} (owned Request)", method),
+            &request,
+            |request| {
+                black_box((&() as &dyn Store).handle_request_owned(request));
+            },
+        );
+    }
+}
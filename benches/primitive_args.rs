@@ -0,0 +1,39 @@
+//! Measures argument extraction for a method taking only primitive arguments. Run with
+//! `cargo bench`.
+
+use easy_jsonrpc::{Handler, Params};
+use serde_json::json;
+use std::hint::black_box;
+use std::time::Instant;
+
+#[easy_jsonrpc::rpc]
+trait Arith {
+    fn add(&self, a: i64, b: i64) -> i64 {
+        a.wrapping_add(b)
+    }
+    fn select(&self, flag: bool, a: u32, b: f64) -> f64 {
+        if flag {
+            a.into()
+        } else {
+            b
+        }
+    }
+}
+
+impl Arith for () {}
+
+fn measure(name: &str, method: &str, args: Vec<serde_json::Value>) {
+    const ITERATIONS: u32 = 1_000_000;
+    let mut handler = &() as &dyn Arith;
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let params = Params::Positional(black_box(args.clone()));
+        let _ = black_box(handler.handle(black_box(method), params));
+    }
+    println!("{}: {:?} per call", name, start.elapsed() / ITERATIONS);
+}
+
+fn main() {
+    measure("add", "add", vec![json!(1), json!(2)]);
+    measure("select", "select", vec![json!(true), json!(1), json!(2.5)]);
+}
@@ -0,0 +1,54 @@
+//! Compares handling a request with a large payload through Values and through RawValues. Run
+//! with `cargo bench`.
+
+use easy_jsonrpc::{HandleRaw, Handler};
+use serde_json::json;
+use std::hint::black_box;
+use std::time::Instant;
+
+#[easy_jsonrpc::rpc]
+trait Store {
+    fn put(&self, key: String, records: Vec<Record>) -> usize {
+        black_box(key);
+        records.len()
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Record {
+    name: String,
+    tags: Vec<String>,
+    score: f64,
+}
+
+impl Store for () {}
+
+fn measure(name: &str, request: &str, handle: impl Fn(&str) -> Option<String>) {
+    const ITERATIONS: u32 = 1_000;
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(handle(black_box(request)));
+    }
+    println!("{}: {:?} per request", name, start.elapsed() / ITERATIONS);
+}
+
+fn main() {
+    let records: Vec<_> = (0..10_000)
+        .map(|i| json!({ "name": format!("record {}", i), "tags": ["a", "b"], "score": i }))
+        .collect();
+    for (label, method) in [("large", "put"), ("large, unknown method", "missing")] {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": ["key", records],
+            "id": 1
+        })
+        .to_string();
+        measure(&format!("value ({})", label), &request, |request| {
+            (&() as &dyn Store).handle_request_str(request)
+        });
+        measure(&format!("raw ({})", label), &request, |request| {
+            (&() as &dyn Store).handle_request_raw(request)
+        });
+    }
+}
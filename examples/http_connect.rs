@@ -41,13 +41,15 @@ fn rpc<R: Deserialize<'static>>(
 fn post(addr: &SocketAddrV6, body: &Value) -> Result<Value, reqwest::Error> {
     let client = Client::new();
     client
-        .post(&format!("http://{}", addr))
+        .post(format!("http://{}", addr))
         .json(body)
         .send()?
         .error_for_status()?
         .json()
 }
 
+// Fields are only read through Debug when unwrapping in main.
+#[allow(dead_code)]
 #[derive(Debug)]
 enum RpcErr {
     Http(reqwest::Error),
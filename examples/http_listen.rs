@@ -2,11 +2,12 @@
 
 mod common;
 use crate::common::create_frob_server;
-use easy_jsonrpc::{Handler, MaybeReply};
+use easy_jsonrpc::{Handler, MaybeReply, StatusHint};
 use serde_json::{self, json};
 use std::net::{Ipv6Addr, SocketAddrV6};
 use std::sync::Arc;
 use warp::filters::body::content_length_limit;
+use warp::http::StatusCode;
 use warp::post;
 use warp::Filter;
 use warp::Reply;
@@ -20,17 +21,23 @@ fn main() {
         .and(content_length_limit(1024 * 32))
         .and(warp::body::json::<serde_json::Value>())
         .map(move |request| {
-            let response: MaybeReply = (&**rpc_handler).handle_request(request);
+            let (status, response) = (&**rpc_handler).handle_request_with_status(request);
             let reply = match response {
                 MaybeReply::Reply(json_val) => json_val,
                 MaybeReply::DontReply => json!(null),
             };
-            to_warp_result(reply)
+            to_warp_result(status, reply)
         });
 
-    warp::serve(responder).run(addr);
+    tokio::runtime::Runtime::new()
+        .expect("failed to start tokio runtime")
+        .block_on(warp::serve(responder).run(addr));
 }
 
-fn to_warp_result(json_value: serde_json::Value) -> impl Reply {
-    Ok::<_, String>(warp::reply::json(&json_value))
+fn to_warp_result(status: StatusHint, json_value: serde_json::Value) -> impl Reply {
+    let status = StatusCode::from_u16(status.code()).expect("StatusHint codes are valid");
+    Ok::<_, String>(warp::reply::with_status(
+        warp::reply::json(&json_value),
+        status,
+    ))
 }
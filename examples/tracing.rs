@@ -0,0 +1,61 @@
+//! Log every dispatch through the `tracing` feature.
+//!
+//! Real applications would install a subscriber from a crate like tracing-subscriber. This one
+//! prints span fields to stdout as they are recorded.
+
+mod common;
+use crate::common::create_frob_server;
+use easy_jsonrpc::Handler;
+use serde_json::json;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+struct Printer(String);
+
+impl Visit for Printer {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0 += &format!(" {}={:?}", field.name(), value);
+    }
+}
+
+#[derive(Default)]
+struct StdoutSubscriber {
+    next_id: AtomicU64,
+}
+
+impl Subscriber for StdoutSubscriber {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut printer = Printer(span.metadata().name().into());
+        span.record(&mut printer);
+        println!("{}", printer.0);
+        Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _: &Id, values: &Record<'_>) {
+        let mut printer = Printer(String::from("  recorded"));
+        values.record(&mut printer);
+        println!("{}", printer.0);
+    }
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, _: &Event<'_>) {}
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+fn main() {
+    tracing::subscriber::set_global_default(StdoutSubscriber::default()).unwrap();
+    let mut handler = create_frob_server();
+    handler.handle_request(json!({"jsonrpc": "2.0", "method": "frob", "params": [], "id": 1}));
+    handler.handle_request(json!({"jsonrpc": "2.0", "method": "frob", "params": [1], "id": 2}));
+}
@@ -3,7 +3,6 @@
 mod common;
 use crate::common::create_frob_server;
 use easy_jsonrpc::{Handler, MaybeReply};
-use serde_json;
 use std::convert::TryInto;
 use std::io::Cursor;
 use std::net::Ipv6Addr;
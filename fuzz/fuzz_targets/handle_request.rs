@@ -0,0 +1,41 @@
+//! Feed arbitrary bytes to a generated handler. Run with `cargo fuzz run handle_request`.
+
+#![no_main]
+
+use easy_jsonrpc::{rpc, Handler, RequestLimits};
+use libfuzzer_sys::fuzz_target;
+
+#[rpc]
+pub trait Target {
+    fn add(&self, a: i64, b: i64) -> Option<i64>;
+    fn concat(&self, a: String, b: &str, c: Option<Vec<u8>>) -> String;
+    fn flags(&self, a: bool, b: f64, c: Option<u8>) -> bool;
+}
+
+struct TargetImpl;
+
+impl Target for TargetImpl {
+    fn add(&self, a: i64, b: i64) -> Option<i64> {
+        a.checked_add(b)
+    }
+
+    fn concat(&self, a: String, b: &str, c: Option<Vec<u8>>) -> String {
+        format!("{}{}{:?}", a, b, c)
+    }
+
+    fn flags(&self, a: bool, b: f64, c: Option<u8>) -> bool {
+        a && b > 0.0 && c.is_some()
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let mut handler = &TargetImpl as &dyn Target;
+    let limits = RequestLimits::default().max_size(64 * 1024);
+    let response = handler.handle_request_bytes(data, &limits);
+    if let Ok(raw) = std::str::from_utf8(data) {
+        handler.handle_request_str(raw);
+    }
+    if let Some(response) = response {
+        assert!(easy_jsonrpc::serde_json::from_str::<easy_jsonrpc::Value>(&response).is_ok());
+    }
+});
@@ -4,12 +4,18 @@
 #![recursion_limit = "256"]
 
 extern crate proc_macro;
-use heck::SnakeCase;
+use heck::{CamelCase, KebabCase, MixedCase, ShoutyKebabCase, ShoutySnakeCase, SnakeCase};
 use proc_macro2::{self, Span, TokenStream};
-use quote::{quote, quote_spanned};
+use quote::{quote, quote_spanned, ToTokens};
 use syn::{
-    parse_macro_input, punctuated::Punctuated, spanned::Spanned, token::Paren, ArgSelfRef, FnArg,
-    FnDecl, Ident, ItemTrait, MethodSig, Pat, PatIdent, ReturnType, TraitItem, Type, TypeTuple,
+    ext::IdentExt,
+    meta::{self, ParseNestedMeta},
+    parse::Parser,
+    parse_macro_input, parse_quote, parse_quote_spanned,
+    spanned::Spanned,
+    Attribute, Data, DeriveInput, Expr, Fields, FnArg, GenericArgument, Ident, ItemTrait, Lifetime,
+    LitInt, LitStr, Meta, Pat, PatIdent, PatType, PathArguments, Receiver, ReturnType, Signature,
+    TraitItem, TraitItemFn, Type, TypeParamBound, WherePredicate,
 };
 
 /// Generate a Handler implementation and client helpers for trait input.
@@ -31,7 +37,7 @@ use syn::{
 ///    ..
 /// }
 ///
-/// pub enum my_api {}
+/// enum my_api {} // with the same visibility as MyApi
 ///
 /// impl my_api {
 ///     fn my_method(arg0: usize) -> Result<BoundMethod<'static, ()>, ArgSerializeError> {
@@ -43,263 +49,2825 @@ use syn::{
 ///     }
 /// }
 /// ```
+///
+/// Trailing `Option<T>` arguments may be omitted by callers. A `my_api_method_params` function
+/// listing each method's params, and whether they are required, is generated alongside the
+/// Handler implementation. So is `my_api_validate_request`, which parses a request's arguments
+/// without calling the method. Unless the methods take a `#[rpc_ctx]`, `easy_jsonrpc::HandleRaw`
+/// is implemented too.
+///
+/// With the `schema` feature of easy-jsonrpc, `my_api_method_schema(method)` returns JSON Schema
+/// (draft 7) documents for a method's params, positional or named, and for its result. The schemas
+/// are worked out from how the types are written, so a type defined elsewhere, e.g. a struct
+/// argument, accepts any value and is only given the type's name as its title.
+///
+/// An argument which can't be deserialized is answered with an invalid params error whose data
+/// names the argument, its type and serde's error. serde's error for an untagged enum matching none
+/// of its variants is followed by the value received, e.g. ", got boolean `true`", unless the enum
+/// describes its variants with `#[serde(expecting = "..")]`. With the `serde_path` feature of
+/// easy-jsonrpc, an error deep inside an argument is prefixed with the path to the value which
+/// failed, e.g. `settings.retries: invalid type: string "3", expected u32`.
+///
+/// Methods and their arguments accept additional options:
+///
+/// - `#[rpc(hex)]` on a method returning `Vec<u8>` sends the result as a `0x` prefixed hex string.
+/// - A method returning `Result<easy_jsonrpc::Value, easy_jsonrpc::Error>` takes full control of
+///   its reply: `Ok` is sent verbatim as the result and `Err` verbatim as the error. Its client
+///   function returns the result as a `Value`. The types may also be written as `serde_json::Value`
+///   and `jsonrpc_core::Error`, or as bare `Value` and `Error`, which the macro can't tell apart
+///   from other types of those names.
+/// - A method returning `Box<dyn erased_serde::Serialize>` gets a client function generic over
+///   the type its result is deserialized into.
+/// - A method returning a reference, e.g. `&'static str` for constant data, serializes it without
+///   cloning. Its client function returns the owned type, e.g. `String`, `Vec<T>` for `&[T]` or
+///   `T` for `&T`.
+/// - `#[rpc(client_only)]` generates a client function for the method but leaves it out of the
+///   Handler implementation. `#[rpc(server_only)]` does the opposite.
+/// - `#[rpc(name = "myMethod")]` on a method exposes it under a different name on the wire. The
+///   name must be non-empty, must not contain control characters and must not start with `rpc.`.
+/// - `#[rpc(readonly)]` marks a `&self` method as safe to call while holding only shared access to
+///   the implementation, e.g. through an `RwLock` read guard. A trait with such methods also gets
+///   an `easy_jsonrpc::HandleReadOnly` implementation, making `easy_jsonrpc::ReadOnly<&dyn MyApi>`
+///   a Handler which dispatches only readonly methods.
+/// - `#[rpc(params_struct)]` on a method taking a single argument builds the argument from the
+///   params as a whole, through `easy_jsonrpc::FromParams`. Named params fill the fields of the
+///   argument's type, e.g. `{"name": "x", "size": 2}` for `fn create(&self, req: CreateReq)`. A
+///   single positional param is deserialized as the argument, which is what the client function
+///   sends. An enum tagged with `#[serde(tag = "action")]` lets one method take several kinds of
+///   call, picked by the `action` param, e.g. `{"action": "deposit", "amount": 5}` for
+///   `fn account(&self, action: AccountAction)`. Unknown actions are answered with an invalid
+///   params error listing the known ones.
+/// - `#[rpc(idempotent)]` marks a method as safe to call more than once. Its name is listed in the
+///   helper module's `IDEMPOTENT` constant, which an `easy_jsonrpc::client::RetryPolicy` consults
+///   to decide which calls may be retried.
+/// - `#[rpc(cacheable)]` marks a method whose result depends only on its params, so a client may
+///   reuse it for a while. Its name is listed in the helper module's `CACHEABLE` constant, which an
+///   `easy_jsonrpc::client::CachingClient` consults to decide which results to cache.
+/// - `#[rpc(lenient_params)]` on a method taking a single argument also accepts params which
+///   aren't an array or an object, e.g. `"params": 5`, as that argument. The jsonrpc spec doesn't
+///   allow such params, so other methods answer them with an invalid request error.
+/// - `#[rpc(max_param_bytes = 1048576)]` rejects calls to the method with an invalid params error
+///   when any param, serialized, is larger than the given number of bytes. The check runs before
+///   the params are deserialized into the argument types, so e.g. an oversized `Vec<u8>` is never
+///   allocated.
+/// - `#[rpc(paginate)]` on a method returning `Vec<T>` sends the result a page at a time, as an
+///   `easy_jsonrpc::Page<T>`: `{"items": [..], "next_cursor": 10}`. Callers pass `cursor` and
+///   `limit` params, named or positionally after the method's own, to pick the page. Without a
+///   cursor the first page is sent, without a limit pages hold `easy_jsonrpc::DEFAULT_PAGE_LIMIT`
+///   items. The method itself still returns the whole `Vec` on every call. The client function
+///   takes the cursor and limit as trailing arguments, and a `my_method_pages` client function
+///   returns the `easy_jsonrpc::Pages` which `easy_jsonrpc::client::Client::call_pages` collects
+///   into the whole `Vec`. A call enum variant holds the cursor and limit alongside the arguments.
+/// - `#[rpc(timeout_ms = 500)]` gives the method a timeout, listed by the generated
+///   `my_api_method_timeout` function. Passing that function to `easy_jsonrpc::WithTimeout`
+///   gives each call its method's timeout as a deadline, passed on in its `RequestMeta`.
+/// - `#[rpc(error_code = 1)]` on a method returning `Result<T, E>` with `E: Display` sends `Ok`
+///   as the result and `Err` as a jsonrpc error whose message is the error's `Display`, rather
+///   than serializing the `Result` itself. The error's code is the trait's `error_base` plus the
+///   given offset, or the offset itself without a base. Codes from -32768 to -32000 are reserved
+///   by the jsonrpc spec, so a code landing there fails to compile. The client function returns
+///   `T`, and the error arrives as a `ResponseFail::RpcError`.
+/// - `#[rpc(deprecated = "use foo instead")]` marks the method's client functions
+///   `#[deprecated]`. With the `tracing` feature of easy-jsonrpc, calls to the method are logged as
+///   warnings.
+/// - `#[rpc(base64)]` is like `#[rpc(hex)]`, but uses standard base64. Requires the `base64`
+///   feature of easy-jsonrpc.
+/// - `#[rpc(num_as_str)]` on a method returning an integer sends the result as a decimal string,
+///   e.g. `"18446744073709551615"`, which javascript clients can read without losing precision.
+///   `i128` and `u128` results and arguments are always sent this way, unless given another
+///   codec, since serde_json can't hold most of their values as numbers, but are also accepted as
+///   numbers. This doesn't depend on the `arbitrary_precision` feature. Types wrapping them, e.g.
+///   `Option<u128>`, are sent as serde would send them.
+/// - `#[rpc_arg(hex)]` on a `Vec<u8>` argument sends the argument as a `0x` prefixed hex string.
+///   `#[rpc_arg(base64)]` sends it as base64. `#[rpc_arg(num_as_str)]` on an integer argument
+///   sends it as a decimal string.
+/// - `#[rpc(stringify_keys)]` on a method returning a map, e.g. `HashMap<K, V>`, sends the result
+///   as an object whose keys are formatted with `K: Display`, and the client function parses them
+///   back with `K: FromStr`. serde_json only accepts strings, numbers and a few other types as
+///   object keys; this lifts the restriction for keys implementing those traits. Keys which don't
+///   implement Display fail to compile. `#[rpc_arg(stringify_keys)]` does the same for an
+///   argument.
+/// - `#[rpc_arg(with = "module")]` (de)serializes the argument using `module::serialize` and
+///   `module::deserialize`, like serde's `with` attribute.
+/// - `#[rpc_arg(alias = "old_name")]` also accepts the argument under `old_name` when params are
+///   passed by name, e.g. to keep old clients working after renaming it. The option may be given
+///   more than once. If a call passes both, the current name wins and the alias is ignored.
+/// - `#[rpc_arg(name = "newName")]` expects the argument under `newName` when params are passed
+///   by name.
+/// - `#[rpc_arg(rest)]` on a final `Vec<T>` argument collects any remaining positional params.
+///   Methods with such an argument can't be called with named params.
+/// - Associated types are ignored, but must keep the trait dyn compatible, so they are bounded by
+///   `Self: Sized`. Associated consts can't be bounded that way and are rejected; keep them in a
+///   separate trait or as free consts.
+/// - A method taking `self: Arc<Self>`, e.g. to move a clone of the handler into a spawned task,
+///   is called with a clone of the `Arc` holding the handler. A trait with such methods implements
+///   `easy_jsonrpc::HandleArc` for `dyn MyApi`, making `Arc<dyn MyApi>` the Handler, instead of
+///   implementing Handler for `dyn MyApi`. Its other methods must take `&self`, and none may take
+///   a `#[rpc_ctx]` unless it holds the `common` fields. `self: &Arc<Self>` isn't supported, as
+///   such methods can't be called through `dyn MyApi`.
+/// - A method returning `easy_jsonrpc::Subscription<T>` sends its items to the client as
+///   notifications, one at a time, rather than a result. It is left out of the Handler
+///   implementation and dispatched through `easy_jsonrpc::HandleSubscribe`, implemented alongside,
+///   so it is only called through an `easy_jsonrpc::Subscriptions` wrapping the handler, which
+///   answers with the new subscription's id. The client function returns that id. `wrap_result`
+///   doesn't apply to the items. Requires the `subscriptions` feature of easy-jsonrpc.
+/// - `#[rpc_ctx] ctx: &C` as the first argument after self passes a per-request context to the
+///   method. It is not a jsonrpc param, so the client function doesn't take it. A trait with such
+///   methods gets an `easy_jsonrpc::HandleWithCtx<C>` implementation instead of `Handler`.
+/// - `#[rpc_progress] progress: &dyn Fn(Value)` as the first argument after self and any
+///   `#[rpc_ctx]` lets the method report progress before it returns. It is not a jsonrpc param
+///   either. A trait with such methods implements `easy_jsonrpc::HandleWithSink` alongside
+///   `Handler`. Handled through `easy_jsonrpc::with_sink`, each reported value is passed to the
+///   sink as an `rpc.progress` notification; handled otherwise, reports are discarded. Such a trait
+///   can't have `self: Arc<Self>` methods, or methods taking a `#[rpc_ctx]` unless it holds the
+///   `common` fields.
+///
+/// A method's `#[cfg(...)]` attributes are copied onto its dispatch, client function, mock and
+/// call enum variant, so a method compiled out of the trait is compiled out of those too. Only
+/// the `IDEMPOTENT` and `CACHEABLE` lists keep naming it.
+///
+/// Doc comments and attributes other than `#[rpc]`, `#[rpc_arg]`, `#[rpc_ctx]` and
+/// `#[rpc_progress]` are kept on the trait as written. A method's doc comments are also copied
+/// onto its client functions, above a note saying they are generated.
+///
+/// The trait itself accepts options as arguments to the macro, e.g. `#[rpc(client_trait)]`:
+///
+/// - `client_trait` additionally generates a `MyApiClient` trait whose provided methods return
+///   the same `BoundMethod`s as the helper module. Implement it for your transport type to get
+///   all client methods on one object.
+/// - `async_client` additionally generates a `MyApiRpc` trait, implemented for every
+///   `easy_jsonrpc::client::Client<T: Transport>`, with an async method per client function which
+///   sends the call and returns its parsed result, e.g.
+///   `client.checked_add(1, 2).await: Result<Option<isize>, CallError>`. A paginated method
+///   fetches every page, taking only the page size.
+/// - `request_builder` additionally generates a `MyApiRequest` type with an associated function
+///   per client function, returning an `easy_jsonrpc::RequestBuilder` for the call, e.g.
+///   `MyApiRequest::checked_add(1, 2).with_id(5).build()`. Call `.notification()` on the builder
+///   to send the call as a notification instead.
+/// - `error_base = -31000` sets the code from which the `#[rpc(error_code = ..)]` of each method
+///   is offset, e.g. to give each subsystem of a large api its own range of codes.
+/// - `strict_version` makes the generated Handler's `strict_version` return true, so that every
+///   way of handling a request, also through wrapping handlers, behaves like
+///   `handle_request_strict`: calls must declare `"jsonrpc": "2.0"` exactly.
+/// - `wrap_result = "path::to::fn"` passes every successful result, after serialization, through
+///   `fn(Value) -> Value` and sends what it returns instead. Errors are sent unchanged.
+/// - `client = "my_client"` names the helper enum `my_client` instead of `my_api`. Besides a client
+///   function per method, the helper enum holds an `easy_jsonrpc::MethodInfo` const per method,
+///   named after the method in upper case, e.g. `my_api::CHECKED_ADD`. Methods whose const would
+///   share a name with another, e.g. a method `idempotent`, or `foo_bar` next to `fooBar`, are
+///   rejected.
+/// - `call_enum` additionally generates a `MyApiCall` enum with a variant for each method, e.g.
+///   `MyApiCall::CheckedAdd { a, b }`, holding its arguments. `MyApiCall::try_from((method,
+///   params))` parses a call, so it can be dispatched with an exhaustive `match`. Borrowed
+///   arguments are held owned, e.g. `&str` as `String`. Methods without arguments are matched as
+///   `MyApiCall::Ping {}`.
+/// - `common = CommonFields` takes the fields of the struct `CommonFields`, e.g. an api version,
+///   out of the named params of every call before they are matched to the method's arguments.
+///   Methods receive the struct as their `#[rpc_ctx] common: &CommonFields`, which then comes from
+///   the params rather than from the server, so the trait gets a `Handler` implementation.
+///   `CommonFields` must be a struct deriving `Deserialize`, without `#[serde(flatten)]` fields.
+///   Positional params carry no common fields, so it is deserialized from an empty object for
+///   them. Client functions don't send the common fields.
+/// - `rename_all = "camelCase"` renames every argument, as if each was given
+///   `#[rpc_arg(name = "..")]`, e.g. `max_count` is passed as `maxCount` in named params.
+///   `rename_all_methods = "camelCase"` does the same for method names, as if each method was given
+///   `#[rpc(name = "..")]`. Arguments and methods with a name of their own keep it. The rules are
+///   serde's: `"lowercase"`, `"UPPERCASE"`, `"PascalCase"`, `"camelCase"`, `"snake_case"`,
+///   `"SCREAMING_SNAKE_CASE"`, `"kebab-case"` and `"SCREAMING-KEBAB-CASE"`. Client functions and
+///   positional params are unaffected.
+/// - `strict_sync` fails the build when a method has a client function but isn't dispatched by
+///   the Handler, or the reverse, i.e. is `client_only` or `server_only`, unless the method is
+///   also marked `#[rpc(unsynced)]`. The error points at the offending method.
+/// - `mock` additionally generates a `MyApiMock` struct implementing the trait. Each method calls
+///   the closure set with `on_my_method`, so client code can be tested against canned responses.
 #[proc_macro_attribute]
-pub fn rpc(_: proc_macro::TokenStream, item: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let trait_def = parse_macro_input!(item as ItemTrait);
-    let server_impl = raise_if_err(impl_server(&trait_def));
-    let client_impl = raise_if_err(impl_client(&trait_def));
+pub fn rpc(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let mut trait_def = parse_macro_input!(item as ItemTrait);
+    let attrs = trait_attrs(attr.into())
+        .and_then(|attrs| check_error_codes(&trait_def, &attrs).map(|()| attrs))
+        .inspect(|attrs| apply_rename_rules(&mut trait_def, attrs));
+    let (server_impl, client_impl, mock_impl, call_enum_impl) = match attrs {
+        Ok(attrs) => (
+            raise_if_err(impl_server(&trait_def, &attrs)),
+            raise_if_err(impl_client(&trait_def, &attrs)),
+            if attrs.mock {
+                raise_if_err(impl_mock(&trait_def))
+            } else {
+                quote! {}
+            },
+            if attrs.call_enum {
+                raise_if_err(impl_call_enum(&trait_def, &attrs))
+            } else {
+                quote! {}
+            },
+        ),
+        Err(rej) => (rej.raise(), quote! {}, quote! {}, quote! {}),
+    };
+    strip_rpc_attrs(&mut trait_def);
+    dyn_compatible_items(&mut trait_def);
     proc_macro::TokenStream::from(quote! {
         #trait_def
         #server_impl
         #client_impl
+        #mock_impl
+        #call_enum_impl
     })
 }
 
+/// Implement `From<MyError> for easy_jsonrpc::Error` for an error enum.
+///
+/// Each variant is annotated with `#[rpc_error(code = -32010, message = "Insufficient funds")]`.
+/// The message defaults to the name of the variant. The variant's fields are serialized into the
+/// error's `data`: named fields as an object, a single unnamed field as itself and several
+/// unnamed fields as an array. Fields which fail to serialize are sent as `null`.
+#[proc_macro_derive(RpcError, attributes(rpc_error))]
+pub fn derive_rpc_error(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+    proc_macro::TokenStream::from(raise_if_err(impl_rpc_error(&input)))
+}
+
 // if Ok, return token stream, else report error
 fn raise_if_err(res: Result<TokenStream, Rejections>) -> TokenStream {
     res.unwrap_or_else(|rej| rej.raise())
 }
 
 // generate a Handler implementation for &dyn Trait
-fn impl_server(tr: &ItemTrait) -> Result<TokenStream, Rejections> {
+fn impl_server(tr: &ItemTrait, attrs: &TraitAttrs) -> Result<TokenStream, Rejections> {
     let trait_name = &tr.ident;
-    let methods: Vec<&MethodSig> = trait_methods(&tr)?;
-    let methods_has_mut_self = methods.iter().any(|method| {
-        method.decl.inputs.iter().any(|arg| {
-            matches!(
-                arg,
-                FnArg::SelfRef(ArgSelfRef {
-                    mutability: Some(_),
-                    ..
-                })
-            )
-        })
-    });
-    let make_handler = |deref_self: bool| {
+    let all_methods: Vec<&TraitItemFn> = partition(
+        trait_methods(tr)?
+            .into_iter()
+            .map(|method| Ok((method, method_attrs(method)?.client_only))),
+    )?
+    .into_iter()
+    .filter(|(_, client_only)| !client_only)
+    .map(|(method, _)| method)
+    .collect();
+    let methods_has_mut_self = all_methods
+        .iter()
+        .any(|method| mut_receiver(&method.sig).is_some());
+    // Subscriptions are started through HandleSubscribe rather than called through Handler.
+    let (subscription_methods, methods): (Vec<&TraitItemFn>, Vec<&TraitItemFn>) = all_methods
+        .iter()
+        .partition(|method| is_subscription(method));
+    let readonly_methods: Vec<&TraitItemFn> = partition(
+        methods
+            .iter()
+            .map(|method| Ok((*method, method_attrs(method)?.readonly))),
+    )?
+    .into_iter()
+    .filter(|(_, readonly)| *readonly)
+    .map(|(method, _)| method)
+    .collect();
+    let make_handler = |methods: &[&TraitItemFn], receiver: TokenStream, raw: bool| {
         let handlers = methods.iter().map(move |method| {
-            let method_literal = method.ident.to_string();
-            let method_return_type_span = return_type_span(&method);
-            let handler = add_handler(trait_name, method, deref_self)?;
-            let try_serialize = quote_spanned! {
-                method_return_type_span =>
-                    easy_jsonrpc::try_serialize(&result)
+            let method_return_type_span = return_type_span(&method.sig);
+            let handler = if raw {
+                add_raw_handler(trait_name, method, &receiver)?
+            } else {
+                add_handler(trait_name, method, &receiver)?
+            };
+            let try_serialize = match method_attrs(method)?.codec {
+                None if method_attrs(method)?.paginate => quote_spanned! {
+                    method_return_type_span =>
+                        easy_jsonrpc::util::page(&result, cursor, limit)
+                },
+                None if is_type_raw_result(&return_type(&method.sig)) => quote! { result },
+                Some(codec) => {
+                    let codec = codec.path();
+                    quote_spanned! {
+                        method_return_type_span =>
+                            easy_jsonrpc::try_serialize_with(&result, #codec::serialize)
+                    }
+                }
+                None => quote_spanned! {
+                    method_return_type_span =>
+                        easy_jsonrpc::try_serialize(&result)
+                },
             };
-            Ok(quote! { #method_literal => {
-                let result = #handler;
+            // errors, including failure to serialize, bypass the wrapper
+            let try_serialize = match &attrs.wrap_result {
+                Some(wrap) => quote! { (#try_serialize).map(#wrap) },
+                None => try_serialize,
+            };
+            let try_serialize = match error_code(method, attrs)? {
+                Some(code) => quote_spanned! { method_return_type_span =>
+                    match result {
+                        Ok(result) => #try_serialize,
+                        Err(err) => Err(easy_jsonrpc::util::coded_error(#code, &err)),
+                    }
+                },
+                None => try_serialize,
+            };
+            let warn_deprecated = warn_deprecated(method)?;
+            Ok(quote! {{
+                #warn_deprecated
+                #handler
                 #try_serialize
             }})
         });
-        handlers
+        dispatch(methods, partition(handlers)?)
+    };
+
+    let method_params = impl_method_params(tr, &all_methods)?;
+    let method_timeout = impl_method_timeout(tr, &all_methods)?;
+    let method_schema = if cfg!(feature = "schema") {
+        impl_method_schema(tr, &all_methods)?
+    } else {
+        quote! {}
+    };
+    let validate_request = impl_validate_request(tr, attrs, &all_methods)?;
+    let accepts_bare_param = |methods: &[&TraitItemFn]| {
+        let lenient_methods: Vec<&TraitItemFn> = partition(
+            methods
+                .iter()
+                .map(|method| Ok((*method, method_attrs(method)?.lenient_params))),
+        )?
+        .into_iter()
+        .filter(|(_, lenient)| *lenient)
+        .map(|(method, _)| method)
+        .collect();
+        if lenient_methods.is_empty() {
+            return Ok(quote! {});
+        }
+        let is_lenient_method = is_method(&lenient_methods)?;
+        Ok::<_, Rejections>(quote! {
+            fn accepts_bare_param(&self, method: &str) -> bool {
+                #is_lenient_method
+            }
+        })
+    };
+    let method_exists = |methods: &[&TraitItemFn]| {
+        let is_known_method = is_method(methods)?;
+        let accepts_bare_param = accepts_bare_param(methods)?;
+        Ok::<_, Rejections>(quote! {
+            fn method_exists(&self, method: &str) -> bool {
+                #is_known_method
+            }
+
+            #accepts_bare_param
+        })
     };
 
-    Ok(if methods_has_mut_self {
-        let handlers = make_handler(false);
-        let handlers: Vec<TokenStream> = partition(handlers)?;
+    let impl_strict_version = if attrs.strict_version {
         quote! {
-            impl easy_jsonrpc::Handler for (dyn #trait_name + '_) {
-                fn handle(&mut self, method: &str, params: easy_jsonrpc::Params)
-                          -> Result<easy_jsonrpc::Value, easy_jsonrpc::Error> {
-                    match method {
-                        #(#handlers,)*
-                        _ => Err(easy_jsonrpc::Error::method_not_found()),
+            fn strict_version(&self) -> bool {
+                true
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // Traits whose methods take a context implement HandleWithCtx instead of Handler, unless the
+    // context is the common fields taken out of the params.
+    let ctx_type = trait_ctx_type(&methods)?;
+    let extract_common = extract_common(attrs);
+    let no_progress = no_progress(&all_methods);
+    let impl_handler = |target: TokenStream, methods: &[&TraitItemFn], receiver: TokenStream| {
+        let handlers = make_handler(methods, receiver, false)?;
+        let method_exists = method_exists(methods)?;
+        Ok::<_, Rejections>(match ctx_type.filter(|_| attrs.common.is_none()) {
+            None => quote! {
+                impl easy_jsonrpc::Handler for #target {
+                    fn handle(&mut self, method: &str, params: easy_jsonrpc::Params)
+                              -> Result<easy_jsonrpc::Value, easy_jsonrpc::Error> {
+                        #extract_common
+                        #no_progress
+                        #handlers
+                    }
+
+                    #method_exists
+
+                    #impl_strict_version
+                }
+            },
+            Some(ctx_type) => quote! {
+                impl easy_jsonrpc::HandleWithCtx<#ctx_type> for #target {
+                    fn handle_with_ctx(
+                        &mut self,
+                        ctx: &#ctx_type,
+                        method: &str,
+                        params: easy_jsonrpc::Params,
+                    ) -> Result<easy_jsonrpc::Value, easy_jsonrpc::Error> {
+                        #handlers
                     }
+
+                    #method_exists
+                }
+            },
+        })
+    };
+
+    // Readonly methods take &self, so they can be called through a read guard.
+    let server_impl_readonly = if readonly_methods.is_empty() {
+        quote! {}
+    } else if let (Some(ctx_type), None) = (ctx_type, &attrs.common) {
+        return Err(Rejection::create(ctx_type.span(), Reason::ReadonlyWithCtx).into());
+    } else {
+        let handlers = make_handler(&readonly_methods, quote! { self }, false)?;
+        let is_readonly_method = is_method(&readonly_methods)?;
+        let accepts_bare_param = accepts_bare_param(&readonly_methods)?;
+        let strict_version = attrs.strict_version;
+        quote! {
+            impl easy_jsonrpc::HandleReadOnly for (dyn #trait_name + '_) {
+                const STRICT_VERSION: bool = #strict_version;
+
+                fn handle_readonly(&self, method: &str, params: easy_jsonrpc::Params)
+                                   -> Result<easy_jsonrpc::Value, easy_jsonrpc::Error> {
+                    #extract_common
+                    #no_progress
+                    #handlers
+                }
+
+                fn readonly_method_exists(&self, method: &str) -> bool {
+                    #is_readonly_method
+                }
+
+                #accepts_bare_param
+            }
+        }
+    };
+
+    // HandleSubscribe builds on Handler, which only the plain and common traits implement.
+    if !subscription_methods.is_empty() {
+        let unhandled = match (ctx_type, &attrs.common) {
+            (Some(ctx_type), None) => Some(ctx_type.span()),
+            _ => methods
+                .iter()
+                .find_map(|method| arc_receiver(&method.sig))
+                .map(|arc| arc.span()),
+        };
+        if let Some(span) = unhandled {
+            return Err(Rejection::create(span, Reason::SubscriptionWithoutHandler).into());
+        }
+    }
+    let impl_subscribe = |target: TokenStream, receiver: TokenStream| {
+        if subscription_methods.is_empty() {
+            return Ok(quote! {});
+        }
+        let arms = partition(subscription_methods.iter().map(|method| {
+            let handler = add_handler(trait_name, method, &receiver)?;
+            let warn_deprecated = warn_deprecated(method)?;
+            Ok(quote! {{
+                #warn_deprecated
+                #handler
+                Ok(easy_jsonrpc::Subscription::into_values(result))
+            }})
+        }))?;
+        let subscribe = dispatch(&subscription_methods, arms)?;
+        let is_subscription = is_method(&subscription_methods)?;
+        Ok::<_, Rejections>(quote! {
+            impl easy_jsonrpc::HandleSubscribe for #target {
+                fn is_subscription(&self, method: &str) -> bool {
+                    #is_subscription
+                }
+
+                fn subscribe(&mut self, method: &str, params: easy_jsonrpc::Params)
+                             -> Result<easy_jsonrpc::ValueStream, easy_jsonrpc::Error> {
+                    #extract_common
+                    #no_progress
+                    #subscribe
+                }
+            }
+        })
+    };
+
+    // HandleWithSink builds on Handler too.
+    let progress_arg = methods.iter().find_map(|method| progress_arg(&method.sig));
+    if let Some(progress_arg) = progress_arg {
+        let has_ctx = ctx_type.is_some() && attrs.common.is_none();
+        if has_ctx
+            || methods
+                .iter()
+                .any(|method| arc_receiver(&method.sig).is_some())
+        {
+            return Err(
+                Rejection::create(progress_arg.span(), Reason::ProgressWithoutHandler).into(),
+            );
+        }
+    }
+    let impl_with_sink = |target: TokenStream, receiver: TokenStream| {
+        if progress_arg.is_none() {
+            return Ok(quote! {});
+        }
+        let handlers = make_handler(&methods, receiver, false)?;
+        Ok::<_, Rejections>(quote! {
+            impl easy_jsonrpc::HandleWithSink for #target {
+                fn handle_with_sink(
+                    &mut self,
+                    method: &str,
+                    params: easy_jsonrpc::Params,
+                    sink: &mut dyn FnMut(easy_jsonrpc::Value),
+                ) -> Result<easy_jsonrpc::Value, easy_jsonrpc::Error> {
+                    #extract_common
+                    // methods are given a Fn, which can't call the FnMut sink directly
+                    let sink = std::cell::RefCell::new(sink);
+                    #[allow(unused_variables)]
+                    let progress: &dyn Fn(easy_jsonrpc::Value) = &|value| (*sink.borrow_mut())(value);
+                    #handlers
                 }
             }
+        })
+    };
+
+    // Handler can't be implemented for Arc<dyn MyApi> outside easy-jsonrpc, which implements it
+    // for any Arc<T: HandleArc> instead.
+    if let Some(arc) = methods.iter().find_map(|method| arc_receiver(&method.sig)) {
+        if let Some(receiver) = methods.iter().find_map(|method| mut_receiver(&method.sig)) {
+            return Err(Rejection::create(receiver.span(), Reason::ArcSelfWithMutSelf).into());
         }
+        if let (Some(_), None) = (ctx_type, &attrs.common) {
+            return Err(Rejection::create(arc.span(), Reason::ArcSelfWithCtx).into());
+        }
+        let handlers = make_handler(&methods, quote! { &**this }, false)?;
+        let method_exists = method_exists(&methods)?;
+        let strict_version = attrs.strict_version;
+        return Ok(quote! {
+            impl easy_jsonrpc::HandleArc for (dyn #trait_name + '_) {
+                const STRICT_VERSION: bool = #strict_version;
+
+                fn handle_arc(
+                    this: &std::sync::Arc<Self>,
+                    method: &str,
+                    params: easy_jsonrpc::Params,
+                ) -> Result<easy_jsonrpc::Value, easy_jsonrpc::Error> {
+                    #extract_common
+                    #handlers
+                }
+
+                #method_exists
+            }
+            #server_impl_readonly
+            #method_params
+            #method_timeout
+            #method_schema
+            #validate_request
+        });
+    }
+
+    let server_impl = impl_handler(quote! { (dyn #trait_name + '_) }, &methods, quote! { self })?;
+    let server_impl_deref = if methods_has_mut_self {
+        quote! {}
     } else {
-        let handlers = make_handler(false);
-        let handlers: Vec<TokenStream> = partition(handlers)?;
-        let handlers_deref = make_handler(true);
-        let handlers_deref: Vec<TokenStream> = partition(handlers_deref)?;
-        quote! {
-            impl easy_jsonrpc::Handler for (dyn #trait_name + '_) {
-                fn handle(&mut self, method: &str, params: easy_jsonrpc::Params)
-                          -> Result<easy_jsonrpc::Value, easy_jsonrpc::Error> {
-                    match method {
-                        #(#handlers,)*
-                        _ => Err(easy_jsonrpc::Error::method_not_found()),
-                    }
+        impl_handler(
+            quote! { &(dyn #trait_name + '_) },
+            &methods,
+            quote! { *self },
+        )?
+    };
+    // Traits taking a context have no Handler implementation for HandleRaw to build on. Common
+    // fields are taken from parsed params.
+    let impl_raw = |target: TokenStream, receiver: TokenStream| {
+        if ctx_type.is_some() || attrs.common.is_some() {
+            return Ok(quote! {});
+        }
+        let handlers = make_handler(&methods, receiver, true)?;
+        Ok::<_, Rejections>(quote! {
+            impl easy_jsonrpc::HandleRaw for #target {
+                fn handle_raw(&mut self, method: &str, params: easy_jsonrpc::RawParams<'_>)
+                              -> Result<easy_jsonrpc::Value, easy_jsonrpc::Error> {
+                    #no_progress
+                    #handlers
                 }
             }
-            impl easy_jsonrpc::Handler for &(dyn #trait_name + '_) {
-                fn handle(&mut self, method: &str, params: easy_jsonrpc::Params)
-                          -> Result<easy_jsonrpc::Value, easy_jsonrpc::Error> {
-                    match method {
-                        #(#handlers_deref,)*
-                        _ => Err(easy_jsonrpc::Error::method_not_found()),
-                    }
+        })
+    };
+    let server_impl_raw = impl_raw(quote! { (dyn #trait_name + '_) }, quote! { self })?;
+    let server_impl_raw_deref = if methods_has_mut_self {
+        quote! {}
+    } else {
+        impl_raw(quote! { &(dyn #trait_name + '_) }, quote! { *self })?
+    };
+    let server_impl_subscribe = impl_subscribe(quote! { (dyn #trait_name + '_) }, quote! { self })?;
+    let server_impl_subscribe_deref = if methods_has_mut_self {
+        quote! {}
+    } else {
+        impl_subscribe(quote! { &(dyn #trait_name + '_) }, quote! { *self })?
+    };
+    let server_impl_with_sink = impl_with_sink(quote! { (dyn #trait_name + '_) }, quote! { self })?;
+    let server_impl_with_sink_deref = if methods_has_mut_self {
+        quote! {}
+    } else {
+        impl_with_sink(quote! { &(dyn #trait_name + '_) }, quote! { *self })?
+    };
+    Ok(quote! {
+        #server_impl
+        #server_impl_deref
+        #server_impl_readonly
+        #server_impl_raw
+        #server_impl_raw_deref
+        #server_impl_subscribe
+        #server_impl_subscribe_deref
+        #server_impl_with_sink
+        #server_impl_with_sink_deref
+        #method_params
+        #method_timeout
+        #method_schema
+        #validate_request
+    })
+}
+
+// Statements taking the common fields out of `params`, for traits with the common option. They are
+// bound to `ctx`, where methods taking a #[rpc_ctx] find them.
+fn extract_common(attrs: &TraitAttrs) -> TokenStream {
+    match &attrs.common {
+        Some(common) => quote! {
+            let (common, params) = easy_jsonrpc::util::extract_common::<#common>(params)?;
+            #[allow(unused_variables)]
+            let ctx = &common;
+        },
+        None => quote! {},
+    }
+}
+
+// A statement binding `progress` to a callback discarding what it is given, for handlers of traits
+// whose methods report progress, outside of HandleWithSink.
+fn no_progress(methods: &[&TraitItemFn]) -> TokenStream {
+    if methods
+        .iter()
+        .all(|method| progress_arg(&method.sig).is_none())
+    {
+        return quote! {};
+    }
+    quote! {
+        #[allow(unused_variables)]
+        let progress: &dyn Fn(easy_jsonrpc::Value) = &|_| {};
+    }
+}
+
+// an expression testing whether `method` names one of methods
+fn is_method(methods: &[&TraitItemFn]) -> Result<TokenStream, Rejections> {
+    let arms = partition(methods.iter().map(|method| {
+        let method_literal = wire_name(method)?;
+        let cfg = cfg_attrs(method);
+        Ok(quote! { #(#cfg)* #method_literal => true })
+    }))?;
+    Ok(quote! {
+        match method {
+            #(#arms,)*
+            _ => false,
+        }
+    })
+}
+
+// Traits with at least this many methods are dispatched by the hash of the method name.
+const HASH_DISPATCH_MIN_METHODS: usize = 16;
+
+// Generate an expression evaluating the body of the method named `method`, or failing with
+// method_not_found. A string match compares the name against each method in turn, so traits with
+// many methods match on a hash of the name instead, which leaves a single name to compare. Traits
+// whose names happen to collide fall back to the string match.
+fn dispatch(methods: &[&TraitItemFn], bodies: Vec<TokenStream>) -> Result<TokenStream, Rejections> {
+    let names = partition(methods.iter().map(|method| wire_name(method)))?;
+    let mut hashes: Vec<u64> = names.iter().map(|name| method_hash(name)).collect();
+    hashes.sort_unstable();
+    hashes.dedup();
+    let by_hash = names.len() >= HASH_DISPATCH_MIN_METHODS && hashes.len() == names.len();
+    let arms = methods
+        .iter()
+        .zip(&names)
+        .zip(bodies)
+        .map(|((method, name), body)| {
+            let cfg = cfg_attrs(method);
+            if by_hash {
+                let hash = method_hash(name);
+                quote! { #(#cfg)* #hash if method == #name => #body }
+            } else {
+                quote! { #(#cfg)* #name => #body }
+            }
+        });
+    let scrutinee = if by_hash {
+        quote! { easy_jsonrpc::util::method_hash(method) }
+    } else {
+        quote! { method }
+    };
+    Ok(quote! {
+        match #scrutinee {
+            #(#arms,)*
+            _ => Err(easy_jsonrpc::Error::method_not_found()),
+        }
+    })
+}
+
+// 64 bit FNV-1a, matching easy_jsonrpc::util::method_hash
+fn method_hash(name: &str) -> u64 {
+    name.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+// The type of the context taken by the trait's methods, if any. All methods taking a context must
+// agree on its type.
+fn trait_ctx_type<'a>(methods: &[&'a TraitItemFn]) -> Result<Option<&'a Type>, Rejections> {
+    let mut ret: Option<&Type> = None;
+    for method in methods {
+        if let Some(ty) = ctx_type(&method.sig)? {
+            match ret {
+                Some(first) if quote!(#first).to_string() != quote!(#ty).to_string() => {
+                    return Err(Rejection::create(ty.span(), Reason::MixedCtxTypes).into());
                 }
+                _ => ret = Some(ty),
+            }
+        }
+    }
+    Ok(ret)
+}
+
+// generate a function listing the params of each handled method and whether they are required
+fn impl_method_params(tr: &ItemTrait, methods: &[&TraitItemFn]) -> Result<TokenStream, Rejections> {
+    let vis = &tr.vis;
+    let fn_name = Ident::new(
+        &format!("{}_method_params", tr.ident.to_string().to_snake_case()),
+        tr.ident.span(),
+    );
+    let arms = partition(methods.iter().map(|method| {
+        let method_literal = wire_name(method)?;
+        let args = get_args(&method.sig)?;
+        let required = required_args(&args);
+        let mut params: Vec<TokenStream> = args
+            .iter()
+            .enumerate()
+            .map(|(index, arg)| {
+                let name = &arg.wire_name;
+                let is_required = index < required;
+                quote! { (#name, #is_required) }
+            })
+            .collect();
+        if method_attrs(method)?.paginate {
+            params.extend(vec![
+                quote! { ("cursor", false) },
+                quote! { ("limit", false) },
+            ]);
+        }
+        let cfg = cfg_attrs(method);
+        Ok(quote! { #(#cfg)* #method_literal => Some(&[#(#params),*]) })
+    }))?;
+    Ok(quote! {
+        /// The params of each method, as (name, required) pairs. Automatically generated by
+        /// easy-jsonrpc.
+        #[allow(dead_code)]
+        #vis fn #fn_name(method: &str) -> Option<&'static [(&'static str, bool)]> {
+            match method {
+                #(#arms,)*
+                _ => None,
             }
         }
     })
 }
 
-fn impl_client(tr: &ItemTrait) -> Result<TokenStream, Rejections> {
-    let trait_name = &tr.ident;
-    let methods: Vec<&MethodSig> = trait_methods(&tr)?;
-    let mod_name = Ident::new(&trait_name.to_string().to_snake_case(), Span::call_site());
-    let method_impls = methods
+// generate a function giving the timeout of each method marked #[rpc(timeout_ms = ..)]
+fn impl_method_timeout(
+    tr: &ItemTrait,
+    methods: &[&TraitItemFn],
+) -> Result<TokenStream, Rejections> {
+    let vis = &tr.vis;
+    let fn_name = Ident::new(
+        &format!("{}_method_timeout", tr.ident.to_string().to_snake_case()),
+        tr.ident.span(),
+    );
+    let arms = partition(methods.iter().map(|method| {
+        let method_literal = wire_name(method)?;
+        let cfg = cfg_attrs(method);
+        Ok(method_attrs(method)?.timeout_ms.map(|ms| {
+            let ms = ms as u64;
+            quote! { #(#cfg)* #method_literal => Some(std::time::Duration::from_millis(#ms)) }
+        }))
+    }))?
+    .into_iter()
+    .flatten();
+    Ok(quote! {
+        /// The timeout of each method marked `#[rpc(timeout_ms = ..)]`, for
+        /// `easy_jsonrpc::WithTimeout`. Automatically generated by easy-jsonrpc.
+        #[allow(dead_code)]
+        #vis fn #fn_name(method: &str) -> Option<std::time::Duration> {
+            match method {
+                #(#arms,)*
+                _ => None,
+            }
+        }
+    })
+}
+
+// generate a function giving JSON Schema (draft 7) documents for each method's params and result
+fn impl_method_schema(tr: &ItemTrait, methods: &[&TraitItemFn]) -> Result<TokenStream, Rejections> {
+    let vis = &tr.vis;
+    let fn_name = Ident::new(
+        &format!("{}_method_schema", tr.ident.to_string().to_snake_case()),
+        tr.ident.span(),
+    );
+    let arms = partition(methods.iter().map(|method| {
+        let method_literal = wire_name(method)?;
+        let params = params_schema(method)?;
+        let result = result_schema(method)?;
+        let cfg = cfg_attrs(method);
+        Ok(quote! {
+            #(#cfg)* #method_literal => Some((with_schema_uri(#params), with_schema_uri(#result)))
+        })
+    }))?;
+    Ok(quote! {
+        /// JSON Schema (draft 7) documents for the params and the result of each method.
+        /// Automatically generated by easy-jsonrpc.
+        #[allow(dead_code)]
+        #vis fn #fn_name(
+            method: &str,
+        ) -> Option<(easy_jsonrpc::Value, easy_jsonrpc::Value)> {
+            // mark schema, an object, as a draft 7 document
+            fn with_schema_uri(mut schema: easy_jsonrpc::Value) -> easy_jsonrpc::Value {
+                schema["$schema"] = "http://json-schema.org/draft-07/schema#".into();
+                schema
+            }
+            match method {
+                #(#arms,)*
+                _ => None,
+            }
+        }
+    })
+}
+
+// An expression building the serde_json::Value written as body in json! syntax. Schemas nest by
+// interpolating one such expression into another, which keeps each json! invocation small.
+fn json(body: TokenStream) -> TokenStream {
+    quote! { easy_jsonrpc::serde_json::json!(#body) }
+}
+
+// Params may be positional or named, so the schema accepts either. Named params aren't accepted by
+// methods with a rest argument, and a params_struct method's argument is the params as a whole.
+// An argument with aliases may be named by any of them, so each is a property, and a required
+// argument must be passed under one of its names.
+fn params_schema(method: &TraitItemFn) -> Result<TokenStream, Rejections> {
+    let attrs = method_attrs(method)?;
+    let args = get_args(&method.sig)?;
+    if attrs.params_struct {
+        return Ok(arg_schema(&args[0]));
+    }
+    let mut params: Vec<(String, Vec<String>, TokenStream)> = args
+        .iter()
+        .filter(|arg| !arg.rest)
+        .map(|arg| {
+            let aliases = arg.aliases.iter().map(LitStr::value).collect();
+            (arg.wire_name.clone(), aliases, arg_schema(arg))
+        })
+        .collect();
+    if attrs.paginate {
+        params.push((
+            "cursor".into(),
+            vec![],
+            json(quote!({"anyOf": [{"type": "integer", "minimum": 0}, {"type": "null"}]})),
+        ));
+        params.push((
+            "limit".into(),
+            vec![],
+            json(quote!({"anyOf": [{"type": "integer", "minimum": 1}, {"type": "null"}]})),
+        ));
+    }
+    let required = required_args(&args);
+    let items = params.iter().map(|(_, _, schema)| schema);
+    let count = params.len();
+    if let Some(rest) = args.last().filter(|arg| arg.rest) {
+        let additional = vec_inner(rest.ty).map_or_else(|| json(quote!({})), type_schema);
+        return Ok(json(quote!({
+            "type": "array",
+            "items": [#(#items),*],
+            "minItems": #required,
+            "additionalItems": #additional
+        })));
+    }
+    let positional = json(quote!({
+        "type": "array",
+        "items": [#(#items),*],
+        "minItems": #required,
+        "maxItems": #count
+    }));
+    let properties = params.iter().flat_map(|(name, aliases, schema)| {
+        std::iter::once(name)
+            .chain(aliases)
+            .map(move |name| quote! { #name: #schema })
+    });
+    let (required_unaliased, required_aliased): (Vec<_>, Vec<_>) = params[..required]
         .iter()
-        .map(|method| impl_client_method(*method))
-        .collect::<Result<Vec<TokenStream>, Rejections>>()?;
+        .partition(|(_, aliases, _)| aliases.is_empty());
+    let required_names = required_unaliased.iter().map(|(name, _, _)| name);
+    let mut named = json(quote!({
+        "type": "object",
+        "properties": {#(#properties),*},
+        "required": [#(#required_names),*],
+        "additionalProperties": false
+    }));
+    if !required_aliased.is_empty() {
+        let one_of_names = required_aliased.iter().map(|(name, aliases, _)| {
+            let names = std::iter::once(name).chain(aliases);
+            json(quote!({"anyOf": [#({"required": [#names]}),*]}))
+        });
+        // a block, which json! would take for an object unless parenthesized
+        named = quote! {({
+            let mut named = #named;
+            named["allOf"] = easy_jsonrpc::serde_json::json!([#(#one_of_names),*]);
+            named
+        })};
+    }
+    Ok(json(quote!({"anyOf": [#positional, #named]})))
+}
+
+fn result_schema(method: &TraitItemFn) -> Result<TokenStream, Rejections> {
+    let attrs = method_attrs(method)?;
+    let ty = result_type(method)?;
+    Ok(match &attrs.codec {
+        Some(codec) => codec_schema(codec),
+        None if is_type_raw_result(&ty) || is_type_erased_serialize(&ty) => json(quote!({})),
+        None if is_subscription(method) => json(quote!({"type": "integer", "minimum": 0})),
+        None if attrs.paginate => {
+            let items = vec_inner(&ty).map_or_else(|| json(quote!({})), type_schema);
+            json(quote!({
+                "type": "object",
+                "properties": {
+                    "items": {"type": "array", "items": #items},
+                    "next_cursor": {"anyOf": [{"type": "integer", "minimum": 0}, {"type": "null"}]}
+                },
+                "required": ["items", "next_cursor"]
+            }))
+        }
+        None => type_schema(&ty),
+    })
+}
+
+fn arg_schema(arg: &Arg) -> TokenStream {
+    match &arg.codec {
+        Some(codec) => codec_schema(codec),
+        None => type_schema(arg.ty),
+    }
+}
+
+fn codec_schema(codec: &Codec) -> TokenStream {
+    json(match codec {
+        Codec::Hex => quote!({"type": "string", "pattern": "^0x([0-9a-fA-F]{2})*$"}),
+        Codec::Base64 => quote!({"type": "string", "contentEncoding": "base64"}),
+        Codec::NumAsStr => quote!({"type": "string", "pattern": "^-?[0-9]+$"}),
+        Codec::Int128 => {
+            quote!({"anyOf": [{"type": "string", "pattern": "^-?[0-9]+$"}, {"type": "integer"}]})
+        }
+        Codec::StringifyKeys => quote!({"type": "object"}),
+        Codec::With(_) => quote!({}),
+    })
+}
+
+// The JSON Schema for values of type ty, as serde serializes them, worked out from how the type is
+// written. Types defined elsewhere can't be looked into, so their schema accepts any value and
+// only names the type.
+fn type_schema(ty: &Type) -> TokenStream {
+    let array = |item: TokenStream| json(quote!({"type": "array", "items": #item}));
+    let path = match ty {
+        Type::Reference(r) if is_type_str(&r.elem) => return json(quote!({"type": "string"})),
+        Type::Reference(r) => return type_schema(&r.elem),
+        Type::Slice(s) => return array(type_schema(&s.elem)),
+        Type::Array(a) => return array(type_schema(&a.elem)),
+        Type::Paren(p) => return type_schema(&p.elem),
+        Type::Group(g) => return type_schema(&g.elem),
+        Type::Tuple(t) if t.elems.is_empty() => return json(quote!({"type": "null"})),
+        Type::Tuple(t) => {
+            let items = t.elems.iter().map(type_schema);
+            let len = t.elems.len();
+            return json(quote!({
+                "type": "array",
+                "items": [#(#items),*],
+                "minItems": #len,
+                "maxItems": #len
+            }));
+        }
+        Type::Path(p) if p.qself.is_none() => &p.path,
+        _ => return json(quote!({})),
+    };
+    let segment = match path.segments.last() {
+        Some(segment) => segment,
+        None => return json(quote!({})),
+    };
+    let params: Vec<&Type> = match &segment.arguments {
+        PathArguments::AngleBracketed(args) => args
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    };
+    let param = |index: usize| {
+        params
+            .get(index)
+            .map_or_else(|| json(quote!({})), |ty| type_schema(ty))
+    };
+    let integer = |min: TokenStream, max: TokenStream| {
+        json(quote!({"type": "integer", "minimum": #min, "maximum": #max}))
+    };
+    match segment.ident.to_string().as_str() {
+        "u8" => integer(quote!(0), quote!(255)),
+        "u16" => integer(quote!(0), quote!(65535)),
+        "u32" => integer(quote!(0), quote!(4294967295u32)),
+        "u64" | "u128" | "usize" => json(quote!({"type": "integer", "minimum": 0})),
+        "i8" => integer(quote!(-128), quote!(127)),
+        "i16" => integer(quote!(-32768), quote!(32767)),
+        "i32" => integer(quote!(-2147483648), quote!(2147483647)),
+        "i64" | "i128" | "isize" => json(quote!({"type": "integer"})),
+        "f32" | "f64" => json(quote!({"type": "number"})),
+        "bool" => json(quote!({"type": "boolean"})),
+        "String" | "str" => json(quote!({"type": "string"})),
+        "char" => json(quote!({"type": "string", "minLength": 1, "maxLength": 1})),
+        "Value" => json(quote!({})),
+        "Option" => {
+            let some = param(0);
+            json(quote!({"anyOf": [#some, {"type": "null"}]}))
+        }
+        "Vec" | "VecDeque" | "LinkedList" => array(param(0)),
+        "HashSet" | "BTreeSet" => {
+            let item = param(0);
+            json(quote!({"type": "array", "items": #item, "uniqueItems": true}))
+        }
+        "HashMap" | "BTreeMap" => {
+            let value = param(1);
+            json(quote!({"type": "object", "additionalProperties": #value}))
+        }
+        "Box" | "Rc" | "Arc" | "Cow" => param(0),
+        // externally tagged, like any enum
+        "Result" => {
+            let (ok, err) = (param(0), param(1));
+            json(quote!({"oneOf": [
+                {
+                    "type": "object",
+                    "properties": {"Ok": #ok},
+                    "required": ["Ok"],
+                    "additionalProperties": false
+                },
+                {
+                    "type": "object",
+                    "properties": {"Err": #err},
+                    "required": ["Err"],
+                    "additionalProperties": false
+                }
+            ]}))
+        }
+        _ => {
+            let title = type_string(ty);
+            json(quote!({"title": #title}))
+        }
+    }
+}
+
+// generate a function checking that a request would be accepted, without calling the method
+fn impl_validate_request(
+    tr: &ItemTrait,
+    attrs: &TraitAttrs,
+    methods: &[&TraitItemFn],
+) -> Result<TokenStream, Rejections> {
+    let extract_common = extract_common(attrs);
+    let vis = &tr.vis;
+    let fn_name = Ident::new(
+        &format!("{}_validate_request", tr.ident.to_string().to_snake_case()),
+        tr.ident.span(),
+    );
+    let arms = partition(methods.iter().map(|method| {
+        let method_literal = wire_name(method)?;
+        let validator = add_validator(method)?;
+        let cfg = cfg_attrs(method);
+        Ok(quote! { #(#cfg)* #method_literal => #validator })
+    }))?;
+    Ok(quote! {
+        /// Check that the method exists and that params can be converted to its arguments, without
+        /// calling it. Returns the error the Handler would return otherwise. Automatically
+        /// generated by easy-jsonrpc.
+        #[allow(dead_code, unused_variables)]
+        #vis fn #fn_name(
+            method: &str,
+            params: &easy_jsonrpc::Params,
+        ) -> Result<(), easy_jsonrpc::Error> {
+            let params = params.clone();
+            #extract_common
+            match method {
+                #(#arms,)*
+                _ => Err(easy_jsonrpc::Error::method_not_found()),
+            }
+        }
+    })
+}
+
+fn impl_client(tr: &ItemTrait, attrs: &TraitAttrs) -> Result<TokenStream, Rejections> {
+    let trait_name = &tr.ident;
+    let vis = &tr.vis;
+    let methods: Vec<&TraitItemFn> = partition(
+        trait_methods(tr)?
+            .into_iter()
+            .map(|method| Ok((method, method_attrs(method)?.server_only))),
+    )?
+    .into_iter()
+    .filter(|(_, server_only)| !server_only)
+    .map(|(method, _)| method)
+    .collect();
+    // generated items take the span of what they were generated from, so tooling can navigate
+    // from the helper to the trait, and from client functions to trait methods
+    let mod_name = match &attrs.client {
+        Some(name) => name.clone(),
+        None => Ident::new(&trait_name.to_string().to_snake_case(), trait_name.span()),
+    };
+    let method_impls = partition(methods.iter().map(|method| impl_client_method(method)))?;
+    check_info_names(&methods)?;
+    let method_infos = partition(methods.iter().map(|method| impl_method_info(method)))?;
+    let idempotent = partition(
+        methods
+            .iter()
+            .map(|method| Ok((wire_name(method)?, method_attrs(method)?.idempotent))),
+    )?
+    .into_iter()
+    .filter(|(_, idempotent)| *idempotent)
+    .map(|(name, _)| name);
+    let cacheable = partition(
+        methods
+            .iter()
+            .map(|method| Ok((wire_name(method)?, method_attrs(method)?.cacheable))),
+    )?
+    .into_iter()
+    .filter(|(_, cacheable)| *cacheable)
+    .map(|(name, _)| name);
+    let client_trait = if attrs.client_trait {
+        impl_client_trait(tr, &methods, &mod_name)?
+    } else {
+        quote! {}
+    };
+    let async_client = if attrs.async_client {
+        impl_async_client(tr, &methods, &mod_name)?
+    } else {
+        quote! {}
+    };
+    let request_builder = if attrs.request_builder {
+        impl_request_builder(tr, &methods, &mod_name)?
+    } else {
+        quote! {}
+    };
+    if attrs.strict_sync {
+        check_sync(tr)?;
+    }
 
     Ok(quote! {
         // We originally used "mod" here. The problem was that modules can't access the
         // namespace of their parent if their parent is a fn.
         /// Helper module for jsonrpc clients. Automatically generated by easy-jsonrpc.
-        pub enum #mod_name {}
+        #[allow(non_camel_case_types)]
+        #vis enum #mod_name {}
+        // a private trait may only be used server side
+        #[allow(dead_code)]
         impl #mod_name {
+            /// Names of the methods marked `#[rpc(idempotent)]`, which may safely be retried.
+            pub const IDEMPOTENT: &[&str] = &[#(#idempotent),*];
+
+            /// Names of the methods marked `#[rpc(cacheable)]`, whose results may be reused.
+            pub const CACHEABLE: &[&str] = &[#(#cacheable),*];
+
+            #(#method_infos)*
+
             #(#method_impls)*
         }
+        #client_trait
+        #async_client
+        #request_builder
     })
 }
 
-fn impl_client_method(method: &MethodSig) -> Result<TokenStream, Rejections> {
-    let method_name = &method.ident;
-    let method_name_literal = &method_name.to_string();
-    let args = get_args(&method.decl)?;
-    let fn_definition_args: &Vec<_> = &args
-        .iter()
-        .enumerate()
-        .map(|(i, (name, typ))| {
-            let arg_num_name = Ident::new(&format!("arg{}", i), name.span());
-            quote! {#arg_num_name: #typ}
-        })
-        .collect();
-    let args_serialize: &Vec<_> = &args
-        .iter()
-        .enumerate()
-        .map(|(i, (name, _))| {
-            let arg_num_name = Ident::new(&format!("arg{}", i), name.span());
-            quote! {
-                easy_jsonrpc::serde_json::to_value(#arg_num_name).map_err(|_| easy_jsonrpc::ArgSerializeError)?
+// For strict_sync, reject the methods on one side only that aren't marked unsynced.
+fn check_sync(tr: &ItemTrait) -> Result<(), Rejections> {
+    partition(trait_methods(tr)?.into_iter().map(|method| {
+        let attrs = method_attrs(method)?;
+        if (attrs.client_only || attrs.server_only) && !attrs.unsynced {
+            Err(Rejection::create(method.sig.ident.span(), Reason::UnsyncedMethod).into())
+        } else {
+            Ok(())
+        }
+    }))?;
+    Ok(())
+}
+
+// generate a trait whose provided methods forward to the helper module
+fn impl_client_trait(
+    tr: &ItemTrait,
+    methods: &[&TraitItemFn],
+    mod_name: &Ident,
+) -> Result<TokenStream, Rejections> {
+    let vis = &tr.vis;
+    let client_trait_name = Ident::new(&format!("{}Client", tr.ident), tr.ident.span());
+    let client_methods = partition(methods.iter().map(|method| {
+        let method_name = &method.sig.ident;
+        let args = get_args(&method.sig)?;
+        let arg_names = args.iter().map(|arg| arg.name);
+        let arg_types = args.iter().map(|arg| arg.ty);
+        let arg_names_call = args.iter().map(|arg| arg.name);
+        let (generics, return_typ) = client_return_type(method)?;
+        let deprecated = deprecated_attr(method)?;
+        let cfg = cfg_attrs(method);
+        let docs = method_docs(method);
+        if let Some(item) = page_item(method)? {
+            let pages_name = pages_fn_name(method);
+            let arg_names_pages = args.iter().map(|arg| arg.name);
+            let arg_types_pages = args.iter().map(|arg| arg.ty);
+            let arg_names_pages_call = args.iter().map(|arg| arg.name);
+            return Ok(quote! {
+                #docs
+                /// Request generator for one page of the result. Automatically generated by
+                /// easy-jsonrpc.
+                #[must_use = "a BoundMethod does nothing unless sent"]
+                #deprecated
+                #(#cfg)*
+                fn #method_name #generics(
+                    &self,
+                    #(#arg_names: #arg_types,)*
+                    cursor: Option<u64>,
+                    limit: Option<usize>,
+                ) -> Result<
+                    easy_jsonrpc::BoundMethod<'static, easy_jsonrpc::Page<#item>>,
+                    easy_jsonrpc::ArgSerializeError,
+                > {
+                    #[allow(deprecated)]
+                    #mod_name::#method_name(#(#arg_names_call,)* cursor, limit)
+                }
+
+                #docs
+                /// Request generator for every page of the result. Automatically generated by
+                /// easy-jsonrpc.
+                #[must_use = "Pages do nothing unless sent"]
+                #deprecated
+                #(#cfg)*
+                fn #pages_name #generics(
+                    &self,
+                    #(#arg_names_pages: #arg_types_pages,)*
+                    limit: Option<usize>,
+                ) -> Result<easy_jsonrpc::Pages<#item>, easy_jsonrpc::ArgSerializeError> {
+                    #[allow(deprecated)]
+                    #mod_name::#pages_name(#(#arg_names_pages_call,)* limit)
+                }
+            });
+        }
+        Ok(quote! {
+            #docs
+            /// Request generator for jsonrpc clients. Automatically generated by easy-jsonrpc.
+            #[must_use = "a BoundMethod does nothing unless sent"]
+            #deprecated
+            #(#cfg)*
+            fn #method_name #generics(&self, #(#arg_names: #arg_types),*)
+                -> Result<easy_jsonrpc::BoundMethod<'static, #return_typ>, easy_jsonrpc::ArgSerializeError> {
+                // only calls from outside the generated code should warn
+                #[allow(deprecated)]
+                #mod_name::#method_name(#(#arg_names_call),*)
             }
         })
-        .collect();
-    let return_typ = return_type(&method);
+    }))?;
 
     Ok(quote! {
-        /// Request generator for jsonrpc clients. Automatically generated by easy-jsonrpc.
-        pub fn #method_name ( #(#fn_definition_args,)* )
-                                 -> Result<easy_jsonrpc::BoundMethod<'static, #return_typ>, easy_jsonrpc::ArgSerializeError> {
-            Ok(easy_jsonrpc::BoundMethod::new(
-                #method_name_literal,
-                vec![ #(#args_serialize),* ],
-            ))
+        /// Client side view of the rpc api. Automatically generated by easy-jsonrpc.
+        #vis trait #client_trait_name {
+            #(#client_methods)*
         }
     })
 }
 
-fn return_type_span(method: &MethodSig) -> Span {
-    let return_type = match &method.decl.output {
-        ReturnType::Default => None,
-        ReturnType::Type(_, typ) => Some(typ),
-    };
-    return_type
-        .map(|typ| typ.span())
-        .unwrap_or_else(|| method.decl.output.span().clone())
+// generate a trait whose methods send their call through a Client and parse the result
+fn impl_async_client(
+    tr: &ItemTrait,
+    methods: &[&TraitItemFn],
+    mod_name: &Ident,
+) -> Result<TokenStream, Rejections> {
+    let vis = &tr.vis;
+    let rpc_trait_name = Ident::new(&format!("{}Rpc", tr.ident), tr.ident.span());
+    let rpc_methods = partition(methods.iter().map(|method| {
+        let method_name = &method.sig.ident;
+        let args = get_args(&method.sig)?;
+        let arg_names: Vec<&Ident> = args.iter().map(|arg| arg.name).collect();
+        let arg_types = args.iter().map(|arg| arg.ty);
+        let (generics, return_typ) = client_return_type(method)?;
+        let deprecated = deprecated_attr(method)?;
+        let cfg = cfg_attrs(method);
+        let docs = method_docs(method);
+        // paginated methods fetch every page
+        let (sig, call) = match page_item(method)? {
+            Some(item) => {
+                let pages_name = pages_fn_name(method);
+                (
+                    quote! {
+                        fn #method_name #generics(&self, #(#arg_names: #arg_types,)* limit: Option<usize>)
+                            -> impl std::future::Future<
+                                Output = Result<Vec<#item>, easy_jsonrpc::client::CallError>,
+                            > + Send
+                    },
+                    quote! {
+                        let pages = #mod_name::#pages_name(#(#arg_names,)* limit)
+                            .map_err(easy_jsonrpc::client::CallError::Args);
+                        async move { self.call_pages(pages?).await }
+                    },
+                )
+            }
+            None => (
+                quote! {
+                    fn #method_name #generics(&self, #(#arg_names: #arg_types),*)
+                        -> impl std::future::Future<
+                            Output = Result<#return_typ, easy_jsonrpc::client::CallError>,
+                        > + Send
+                },
+                quote! {
+                    let method = #mod_name::#method_name(#(#arg_names),*)
+                        .map_err(easy_jsonrpc::client::CallError::Args);
+                    async move { self.call(method?).await }
+                },
+            ),
+        };
+        Ok((
+            quote! {
+                #docs
+                /// Sends the call and parses its result. Automatically generated by easy-jsonrpc.
+                #deprecated
+                #(#cfg)*
+                #sig;
+            },
+            quote! {
+                #(#cfg)*
+                #sig {
+                    // only calls from outside the generated code should warn
+                    #[allow(deprecated)]
+                    #call
+                }
+            },
+        ))
+    }))?;
+    let (declarations, definitions): (Vec<TokenStream>, Vec<TokenStream>) =
+        rpc_methods.into_iter().unzip();
+
+    Ok(quote! {
+        /// Typed async client of the rpc api, implemented for every
+        /// `easy_jsonrpc::client::Client`. Automatically generated by easy-jsonrpc.
+        #vis trait #rpc_trait_name {
+            #(#declarations)*
+        }
+
+        impl<T: easy_jsonrpc::client::Transport + Sync> #rpc_trait_name
+            for easy_jsonrpc::client::Client<T>
+        {
+            #(#definitions)*
+        }
+    })
+}
+
+// generate a type whose associated functions wrap the helper module's in a RequestBuilder
+fn impl_request_builder(
+    tr: &ItemTrait,
+    methods: &[&TraitItemFn],
+    mod_name: &Ident,
+) -> Result<TokenStream, Rejections> {
+    let vis = &tr.vis;
+    let request_name = Ident::new(&format!("{}Request", tr.ident), tr.ident.span());
+    let builders = partition(methods.iter().map(|method| {
+        let method_name = &method.sig.ident;
+        let args = get_args(&method.sig)?;
+        let arg_names: Vec<&Ident> = args.iter().map(|arg| arg.name).collect();
+        let arg_types = args.iter().map(|arg| arg.ty);
+        let (generics, return_typ) = client_return_type(method)?;
+        let deprecated = deprecated_attr(method)?;
+        let cfg = cfg_attrs(method);
+        let docs = method_docs(method);
+        // paginated methods request one page
+        let (page_args, page_names, return_typ) = match page_item(method)? {
+            Some(item) => (
+                quote! { cursor: Option<u64>, limit: Option<usize>, },
+                quote! { cursor, limit, },
+                quote! { easy_jsonrpc::Page<#item> },
+            ),
+            None => (quote! {}, quote! {}, return_typ),
+        };
+        Ok(quote! {
+            #docs
+            /// Request builder for jsonrpc clients. Automatically generated by easy-jsonrpc.
+            #[must_use = "a RequestBuilder does nothing unless built"]
+            #deprecated
+            #(#cfg)*
+            pub fn #method_name #generics(#(#arg_names: #arg_types,)* #page_args)
+                -> easy_jsonrpc::RequestBuilder<#return_typ> {
+                // only calls from outside the generated code should warn
+                #[allow(deprecated)]
+                easy_jsonrpc::RequestBuilder::new(#mod_name::#method_name(#(#arg_names,)* #page_names))
+            }
+        })
+    }))?;
+
+    Ok(quote! {
+        /// Request builders for jsonrpc clients. Automatically generated by easy-jsonrpc.
+        #vis enum #request_name {}
+
+        #[allow(dead_code)]
+        impl #request_name {
+            #(#builders)*
+        }
+    })
+}
+
+// generate MyApiMock, implementing the trait by calling a closure set for each method
+fn impl_mock(tr: &ItemTrait) -> Result<TokenStream, Rejections> {
+    let vis = &tr.vis;
+    let trait_name = &tr.ident;
+    let mock_name = Ident::new(&format!("{}Mock", trait_name), trait_name.span());
+    if let Some(ty) = tr
+        .items
+        .iter()
+        .find(|item| matches!(item, TraitItem::Type(_)))
+    {
+        return Err(Rejection::create(ty.span(), Reason::MockAssociatedType).into());
+    }
+    let methods = trait_methods(tr)?;
+    let mut fields = Vec::new();
+    let mut setters = Vec::new();
+    let mut impls = Vec::new();
+    let mut debug_fields = Vec::new();
+    for method in &methods {
+        let method_name = &method.sig.ident;
+        let cfg = cfg_attrs(method);
+        let setter_name = Ident::new(&format!("on_{}", method_name.unraw()), method_name.span());
+        // every argument after self, including a context
+        let arg_types: Vec<&Type> = method
+            .sig
+            .inputs
+            .iter()
+            .filter_map(|arg| match arg {
+                FnArg::Typed(PatType { ty, .. }) => Some(&**ty),
+                FnArg::Receiver(_) => None,
+            })
+            .collect();
+        let arg_names: Vec<Ident> = (0..arg_types.len())
+            .map(|index| Ident::new(&format!("arg{}", index), Span::call_site()))
+            .collect();
+        let return_type = return_type(&method.sig);
+        let lifetimes: Vec<&Lifetime> = method
+            .sig
+            .generics
+            .lifetimes()
+            .map(|def| &def.lifetime)
+            .collect();
+        let (receiver, closure_trait) = match mut_receiver(&method.sig) {
+            Some(_) => (quote! { &mut self }, quote! { FnMut }),
+            None if arc_receiver(&method.sig).is_some() => {
+                (quote! { self: std::sync::Arc<Self> }, quote! { Fn })
+            }
+            None => (quote! { &self }, quote! { Fn }),
+        };
+        let generics = &method.sig.generics;
+        let message = format!(
+            "{}::{} called without a response set",
+            mock_name,
+            method_name.unraw()
+        );
+        fields.push(quote! {
+            #(#cfg)*
+            #method_name: Option<Box<
+                dyn for<#(#lifetimes),*> #closure_trait(#(#arg_types),*) -> #return_type + Send + Sync
+            >>
+        });
+        setters.push(quote! {
+            /// Respond to calls of this method using f.
+            #(#cfg)*
+            #vis fn #setter_name(
+                &mut self,
+                f: impl for<#(#lifetimes),*> #closure_trait(#(#arg_types),*) -> #return_type
+                    + Send + Sync + 'static,
+            ) -> &mut Self {
+                self.#method_name = Some(Box::new(f));
+                self
+            }
+        });
+        let call = match mut_receiver(&method.sig) {
+            Some(_) => quote! { self.#method_name.as_mut() },
+            None => quote! { self.#method_name.as_ref() },
+        };
+        impls.push(quote! {
+            #(#cfg)*
+            fn #method_name #generics(#receiver, #(#arg_names: #arg_types),*) -> #return_type {
+                (#call.expect(#message))(#(#arg_names),*)
+            }
+        });
+        let method_name_literal = method_name.unraw().to_string();
+        debug_fields.push(quote! {
+            #(#cfg)*
+            debug.field(#method_name_literal, &self.#method_name.is_some());
+        });
+    }
+    let doc = format!(
+        "Implements {} by calling closures set for each method, e.g. to test clients. Panics when a \
+         method without a closure is called. Automatically generated by easy-jsonrpc.",
+        trait_name
+    );
+    Ok(quote! {
+        #[doc = #doc]
+        #[derive(Default)]
+        #vis struct #mock_name {
+            #(#fields,)*
+        }
+
+        impl #mock_name {
+            /// A mock with no responses set.
+            #vis fn new() -> Self {
+                Self::default()
+            }
+
+            #(#setters)*
+        }
+
+        impl #trait_name for #mock_name {
+            #(#impls)*
+        }
+
+        impl std::fmt::Debug for #mock_name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                let mut debug = f.debug_struct(stringify!(#mock_name));
+                #(#debug_fields)*
+                debug.finish()
+            }
+        }
+    })
+}
+
+// generate MyApiCall, an enum with a variant holding the arguments of each method
+fn impl_call_enum(tr: &ItemTrait, attrs: &TraitAttrs) -> Result<TokenStream, Rejections> {
+    let extract_common = extract_common(attrs);
+    let vis = &tr.vis;
+    let trait_name = &tr.ident;
+    let enum_name = Ident::new(&format!("{}Call", trait_name), trait_name.span());
+    let methods: Vec<&TraitItemFn> = partition(
+        trait_methods(tr)?
+            .into_iter()
+            .map(|method| Ok((method, method_attrs(method)?.client_only))),
+    )?
+    .into_iter()
+    .filter(|(_, client_only)| !client_only)
+    .map(|(method, _)| method)
+    .collect();
+    let mut variants = Vec::new();
+    let mut arms = Vec::new();
+    for method in methods {
+        let method_literal = wire_name(method)?;
+        let variant = Ident::new(
+            &method.sig.ident.unraw().to_string().to_camel_case(),
+            method.sig.ident.span(),
+        );
+        let args = get_args(&method.sig)?;
+        let names: Vec<&Ident> = args.iter().map(|arg| arg.name).collect();
+        let types: Vec<TokenStream> = args.iter().map(|arg| owned_type(arg.ty)).collect();
+        let doc = format!("A call to `{}`.", method_literal);
+        let cfg = cfg_attrs(method);
+        // the page of a paginated method's result is part of the call
+        let paginate = method_attrs(method)?.paginate;
+        let (page_fields, page_values) = if paginate {
+            (
+                quote! { cursor: Option<u64>, limit: Option<usize>, },
+                quote! { cursor, limit, },
+            )
+        } else {
+            (quote! {}, quote! {})
+        };
+        variants.push(quote! {
+            #[doc = #doc]
+            #(#cfg)*
+            #variant { #(#names: #types,)* #page_fields }
+        });
+        if method_attrs(method)?.params_struct {
+            let ty = &types[0];
+            arms.push(quote! {
+                #(#cfg)*
+                #method_literal => Ok(#enum_name::#variant {
+                    #(#names)*: <#ty as easy_jsonrpc::FromParams>::from_params(params)?,
+                })
+            });
+            continue;
+        }
+        let get_rpc_args = get_rpc_args(&args);
+        let take_page_params = take_page_params(method)?;
+        let fields = args
+            .iter()
+            .zip(&types)
+            .enumerate()
+            .map(|(index, (arg, ty))| {
+                let name = arg.name;
+                let argname_literal = format!("\"{}\"", name.unraw());
+                let expected = type_string(ty);
+                let value = quote! {
+                    ordered_args.next().ok_or_else(|| easy_jsonrpc::util::missing_arg(#index))?
+                };
+                let parse = match &arg.codec {
+                    None if is_type_integer(arg.ty) => quote! {
+                        easy_jsonrpc::util::from_integer_value(#value, #argname_literal, #index, #expected)
+                            .map_err(easy_jsonrpc::Error::from)?
+                    },
+                    Some(Codec::Int128) => quote! {
+                        easy_jsonrpc::util::from_int128_value(#value, #argname_literal, #index, #expected)
+                            .map_err(easy_jsonrpc::Error::from)?
+                    },
+                    codec => {
+                        let deserialize = match codec {
+                            Some(codec) => {
+                                let codec = codec.path();
+                                quote! { #codec::deserialize }
+                            }
+                            None => quote! { easy_jsonrpc::util::from_serde_json_value_ref::<#ty> },
+                        };
+                        quote! {
+                            #deserialize(#value).map_err(|e| {
+                                easy_jsonrpc::util::invalid_arg_value(
+                                    #argname_literal,
+                                    #index,
+                                    #expected,
+                                    e,
+                                )
+                            })?
+                        }
+                    }
+                };
+                quote! { #name: #parse }
+            });
+        arms.push(quote! {
+            #(#cfg)*
+            #method_literal => {
+                #take_page_params
+                let args: Vec<easy_jsonrpc::Value> =
+                    #get_rpc_args.map_err(easy_jsonrpc::Error::from)?;
+                let mut ordered_args = args.iter();
+                Ok(#enum_name::#variant { #(#fields,)* #page_values })
+            }
+        });
+    }
+    let doc = format!(
+        "A call to one of the methods of {}, with its arguments parsed. Automatically generated \
+         by easy-jsonrpc.",
+        trait_name
+    );
+    Ok(quote! {
+        #[doc = #doc]
+        #vis enum #enum_name {
+            #(#variants,)*
+        }
+
+        impl std::convert::TryFrom<(&str, easy_jsonrpc::Params)> for #enum_name {
+            type Error = easy_jsonrpc::Error;
+
+            /// Parse a call, returning the error the Handler would return if it can't be parsed.
+            fn try_from(
+                (method, params): (&str, easy_jsonrpc::Params),
+            ) -> Result<Self, easy_jsonrpc::Error> {
+                #extract_common
+                match method {
+                    #(#arms,)*
+                    _ => Err(easy_jsonrpc::Error::method_not_found()),
+                }
+            }
+        }
+    })
+}
+
+// The type a call enum stores an argument of type ty as. Borrowed arguments are stored owned.
+fn owned_type(ty: &Type) -> TokenStream {
+    let owned = match ty {
+        Type::Reference(r) if is_type_str(&r.elem) => quote! { String },
+        Type::Reference(r) => match &*r.elem {
+            Type::Slice(s) => {
+                let elem = &s.elem;
+                quote! { Vec<#elem> }
+            }
+            elem => quote! { #elem },
+        },
+        ty => quote! { #ty },
+    };
+    replace_lifetimes(owned, &quote! { 'static })
+}
+
+fn impl_client_method(method: &TraitItemFn) -> Result<TokenStream, Rejections> {
+    let method_name = &method.sig.ident;
+    let method_name_literal = &wire_name(method)?;
+    let args = get_args(&method.sig)?;
+    let (fixed, rest) = match args.split_last() {
+        Some((last, fixed)) if last.rest => (fixed, Some(last)),
+        _ => (&args[..], None),
+    };
+    let fn_definition_args: &Vec<_> = &args
+        .iter()
+        .enumerate()
+        .map(|(i, arg)| {
+            let arg_num_name = Ident::new(&format!("arg{}", i), arg.name.span());
+            let typ = arg.ty;
+            quote! {#arg_num_name: #typ}
+        })
+        .collect();
+    let args_serialize: &Vec<_> = &fixed
+        .iter()
+        .enumerate()
+        .map(|(i, arg)| {
+            let arg_num_name = Ident::new(&format!("arg{}", i), arg.name.span());
+            match &arg.codec {
+                Some(codec) => {
+                    let codec = codec.path();
+                    quote! {
+                        #codec::serialize(&#arg_num_name, easy_jsonrpc::serde_json::value::Serializer)
+                            .map_err(|_| easy_jsonrpc::ArgSerializeError)?
+                    }
+                }
+                None => quote! {
+                    easy_jsonrpc::serde_json::to_value(#arg_num_name).map_err(|_| easy_jsonrpc::ArgSerializeError)?
+                },
+            }
+        })
+        .collect();
+    let (generics, return_typ) = client_return_type(method)?;
+    let params = match rest {
+        Some(rest) => {
+            let arg_num_name = Ident::new(&format!("arg{}", fixed.len()), rest.name.span());
+            quote! {{
+                let mut params = vec![ #(#args_serialize),* ];
+                for arg in #arg_num_name {
+                    params.push(
+                        easy_jsonrpc::serde_json::to_value(arg)
+                            .map_err(|_| easy_jsonrpc::ArgSerializeError)?,
+                    );
+                }
+                params
+            }}
+        }
+        None => quote! { vec![ #(#args_serialize),* ] },
+    };
+    let bound_method = quote! {
+        easy_jsonrpc::BoundMethod::new(
+            #method_name_literal,
+            #params,
+        )
+    };
+    let bound_method = match method_attrs(method)?.codec {
+        Some(codec) => {
+            let codec = codec.path();
+            quote! { #bound_method.with_decoder(|value| #codec::deserialize(value)) }
+        }
+        None => bound_method,
+    };
+
+    let deprecated = deprecated_attr(method)?;
+    let cfg = cfg_attrs(method);
+    let docs = method_docs(method);
+    if let Some(item) = page_item(method)? {
+        let pages_name = pages_fn_name(method);
+        return Ok(quote! {
+            #docs
+            /// Request generator for one page of the result. Automatically generated by
+            /// easy-jsonrpc.
+            #[must_use = "a BoundMethod does nothing unless sent"]
+            #deprecated
+            #(#cfg)*
+            pub fn #method_name #generics(
+                #(#fn_definition_args,)*
+                cursor: Option<u64>,
+                limit: Option<usize>,
+            ) -> Result<
+                easy_jsonrpc::BoundMethod<'static, easy_jsonrpc::Page<#item>>,
+                easy_jsonrpc::ArgSerializeError,
+            > {
+                Ok(easy_jsonrpc::Pages::new(#method_name_literal, #params, limit).page(cursor))
+            }
+
+            #docs
+            /// Request generator for every page of the result. Automatically generated by
+            /// easy-jsonrpc.
+            #[must_use = "Pages do nothing unless sent"]
+            #deprecated
+            #(#cfg)*
+            pub fn #pages_name #generics(
+                #(#fn_definition_args,)*
+                limit: Option<usize>,
+            ) -> Result<easy_jsonrpc::Pages<#item>, easy_jsonrpc::ArgSerializeError> {
+                Ok(easy_jsonrpc::Pages::new(#method_name_literal, #params, limit))
+            }
+        });
+    }
+    Ok(quote! {
+        #docs
+        /// Request generator for jsonrpc clients. Automatically generated by easy-jsonrpc.
+        #[must_use = "a BoundMethod does nothing unless sent"]
+        #deprecated
+        #(#cfg)*
+        pub fn #method_name #generics( #(#fn_definition_args,)* )
+                                 -> Result<easy_jsonrpc::BoundMethod<'static, #return_typ>, easy_jsonrpc::ArgSerializeError> {
+            Ok(#bound_method)
+        }
+    })
+}
+
+// The type of the items of a #[rpc(paginate)] method's result, None for other methods.
+fn page_item(method: &TraitItemFn) -> Result<Option<Type>, Rejections> {
+    if !method_attrs(method)?.paginate {
+        return Ok(None);
+    }
+    Ok(vec_inner(&return_type(&method.sig)).cloned())
+}
+
+// The client function requesting every page of a #[rpc(paginate)] method's result.
+fn pages_fn_name(method: &TraitItemFn) -> Ident {
+    let method_name = &method.sig.ident;
+    Ident::new(
+        &format!("{}_pages", method_name.unraw()),
+        method_name.span(),
+    )
+}
+
+// the name of the MethodInfo const describing method, the method's name in upper case
+fn info_name(method: &TraitItemFn) -> Ident {
+    let method_name = &method.sig.ident;
+    Ident::new(
+        &method_name.unraw().to_string().to_shouty_snake_case(),
+        method_name.span(),
+    )
+}
+
+// Reject methods whose MethodInfo const would share a name with another const of the helper
+// enum, e.g. a method named idempotent, or foo_bar next to fooBar.
+fn check_info_names(methods: &[&TraitItemFn]) -> Result<(), Rejections> {
+    let mut taken: Vec<String> = vec!["IDEMPOTENT".to_owned(), "CACHEABLE".to_owned()];
+    partition(methods.iter().map(|method| {
+        let name = info_name(method).to_string();
+        if taken.contains(&name) {
+            return Err(Rejection::create(method.sig.ident.span(), Reason::InfoNameTaken).into());
+        }
+        taken.push(name);
+        Ok(())
+    }))?;
+    Ok(())
+}
+
+// a MethodInfo const describing method, named after the method in upper case
+fn impl_method_info(method: &TraitItemFn) -> Result<TokenStream, Rejections> {
+    let const_name = info_name(method);
+    let method_name_literal = wire_name(method)?;
+    let args = get_args(&method.sig)?;
+    let mut params: Vec<String> = args.iter().map(|arg| arg.wire_name.clone()).collect();
+    let mut types: Vec<String> = args.iter().map(|arg| type_string(arg.ty)).collect();
+    if method_attrs(method)?.paginate {
+        params.extend(vec!["cursor".to_owned(), "limit".to_owned()]);
+        types.extend(vec!["Option<u64>".to_owned(), "Option<usize>".to_owned()]);
+    }
+    let cfg = cfg_attrs(method);
+    let doc = format!("The name and params of `{}`.", method_name_literal);
+    Ok(quote! {
+        #[doc = #doc]
+        #(#cfg)*
+        pub const #const_name: easy_jsonrpc::MethodInfo = easy_jsonrpc::MethodInfo {
+            name: #method_name_literal,
+            params: &[#(#params),*],
+            types: &[#(#types),*],
+        };
+    })
+}
+
+// #[deprecated] for the client functions of a method marked #[rpc(deprecated = "...")]
+fn deprecated_attr(method: &TraitItemFn) -> Result<TokenStream, Rejections> {
+    Ok(match method_attrs(method)?.deprecated {
+        Some(note) => quote! { #[deprecated(note = #note)] },
+        None => quote! {},
+    })
+}
+
+// a statement logging calls to a deprecated method, with the tracing feature of easy-jsonrpc
+fn warn_deprecated(method: &TraitItemFn) -> Result<TokenStream, Rejections> {
+    let method_literal = wire_name(method)?;
+    Ok(match method_attrs(method)?.deprecated {
+        Some(note) => quote! {
+            easy_jsonrpc::util::warn_deprecated(#method_literal, #note);
+        },
+        None => quote! {},
+    })
+}
+
+fn return_type_span(method: &Signature) -> Span {
+    let return_type = match &method.output {
+        ReturnType::Default => None,
+        ReturnType::Type(_, typ) => Some(typ),
+    };
+    return_type
+        .map(|typ| typ.span())
+        .unwrap_or_else(|| method.output.span())
+}
+
+fn return_type(method: &Signature) -> Type {
+    match &method.output {
+        ReturnType::Default => parse_quote_spanned!(method.output.span() => ()),
+        ReturnType::Type(_, typ) => *typ.clone(),
+    }
+}
+
+// The type sent as the result of a call: the Ok type of a method marked #[rpc(error_code)],
+// otherwise the return type.
+fn result_type(method: &TraitItemFn) -> Result<Type, Rejections> {
+    let return_typ = return_type(&method.sig);
+    Ok(match method_attrs(method)?.error_code {
+        Some(_) => result_ok_type(&return_typ).cloned().unwrap_or(return_typ),
+        None => return_typ,
+    })
+}
+
+// If ty is Result<T, E>, return T. A Result alias naming only T doesn't count.
+fn result_ok_type(ty: &Type) -> Option<&Type> {
+    let segment = match ty {
+        Type::Path(p) if p.qself.is_none() => p.path.segments.last()?,
+        _ => return None,
+    };
+    let args = match &segment.arguments {
+        PathArguments::AngleBracketed(args) if segment.ident == "Result" => &args.args,
+        _ => return None,
+    };
+    let types: Vec<&Type> = args
+        .iter()
+        .filter_map(|arg| match arg {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        })
+        .collect();
+    match types[..] {
+        [ok, _] => Some(ok),
+        _ => None,
+    }
+}
+
+// Generic parameters and return type for a client function. A type erased return value can't be
+// deserialized as itself, so the caller picks the type to deserialize into. A borrowed return
+// value, e.g. &'static str, is received owned, as it can't borrow from the response.
+// The method's lifetime parameters are carried over to the client function.
+fn client_return_type(method: &TraitItemFn) -> Result<(TokenStream, TokenStream), Rejections> {
+    let return_typ = result_type(method)?;
+    let lifetimes: Vec<_> = method.sig.generics.lifetimes().collect();
+    Ok(if is_type_erased_serialize(&return_typ) {
+        let span = return_typ.span();
+        (
+            quote_spanned! { span => <#(#lifetimes,)* R: easy_jsonrpc::util::Deserialize<'static>> },
+            quote_spanned! { span => R },
+        )
+    } else if is_type_raw_result(&return_typ) {
+        (quote! {}, quote! { easy_jsonrpc::Value })
+    } else {
+        let return_typ = match return_typ {
+            Type::Reference(_) => owned_type(&return_typ),
+            _ if cfg!(feature = "subscriptions")
+                && generic_inner(&return_typ, "Subscription").is_some() =>
+            {
+                quote! { easy_jsonrpc::SubscriptionId }
+            }
+            _ => return_typ.to_token_stream(),
+        };
+        if lifetimes.is_empty() {
+            (quote! {}, return_typ)
+        } else {
+            (quote! { <#(#lifetimes),*> }, return_typ)
+        }
+    })
+}
+
+// Replace every named lifetime in tokens with '_, so a type mentioning a method's lifetime
+// parameters can be written outside of the method.
+fn erase_lifetimes(tokens: TokenStream) -> TokenStream {
+    replace_lifetimes(tokens, &quote! { '_ })
+}
+
+// Replace every lifetime in tokens, named or '_, with replacement.
+fn replace_lifetimes(tokens: TokenStream, replacement: &TokenStream) -> TokenStream {
+    let mut ret = TokenStream::new();
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            proc_macro2::TokenTree::Punct(p) if p.as_char() == '\'' => {
+                if let Some(proc_macro2::TokenTree::Ident(_)) = tokens.peek() {
+                    tokens.next();
+                    ret.extend(replacement.clone());
+                } else {
+                    ret.extend(Some(proc_macro2::TokenTree::Punct(p)));
+                }
+            }
+            proc_macro2::TokenTree::Group(g) => {
+                let mut group = proc_macro2::Group::new(
+                    g.delimiter(),
+                    replace_lifetimes(g.stream(), replacement),
+                );
+                group.set_span(g.span());
+                ret.extend(Some(proc_macro2::TokenTree::Group(group)));
+            }
+            other => ret.extend(Some(other)),
+        }
+    }
+    ret
+}
+
+// Is ty Box<dyn Serialize>? serde's Serialize is not object safe, so this must be an erased
+// Serialize such as erased_serde::Serialize.
+fn is_type_erased_serialize(ty: &Type) -> bool {
+    let segment = match ty {
+        Type::Path(p) if p.qself.is_none() => p.path.segments.last(),
+        _ => None,
+    };
+    let boxed = match segment {
+        Some(segment) if segment.ident == "Box" => &segment.arguments,
+        _ => return false,
+    };
+    let trait_object = match boxed {
+        PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+            GenericArgument::Type(Type::TraitObject(t)) => Some(t),
+            _ => None,
+        }),
+        _ => None,
+    };
+    trait_object.is_some_and(|t| {
+        t.bounds.iter().any(|bound| match bound {
+            TypeParamBound::Trait(tr) => tr
+                .path
+                .segments
+                .last()
+                .is_some_and(|s| s.ident == "Serialize"),
+            _ => false,
+        })
+    })
+}
+
+// Is ty Result<Value, Error>? Such results are sent as they are, without serialization. Value
+// and Error may be written as paths to easy_jsonrpc's, serde_json's or jsonrpc_core's types. The
+// macro can't see imports, so bare `Value` and `Error` are assumed to name those types.
+fn is_type_raw_result(ty: &Type) -> bool {
+    const VALUE: &[&[&str]] = &[
+        &["Value"],
+        &["easy_jsonrpc", "Value"],
+        &["serde_json", "Value"],
+        &["serde_json", "value", "Value"],
+        &["jsonrpc_core", "Value"],
+    ];
+    const ERROR: &[&[&str]] = &[
+        &["Error"],
+        &["easy_jsonrpc", "Error"],
+        &["jsonrpc_core", "Error"],
+        &["jsonrpc_core", "types", "Error"],
+    ];
+    let segment = match ty {
+        Type::Path(p) if p.qself.is_none() => p.path.segments.last(),
+        _ => None,
+    };
+    let args = match segment {
+        Some(segment) if segment.ident == "Result" => &segment.arguments,
+        _ => return false,
+    };
+    let args = match args {
+        PathArguments::AngleBracketed(args) => &args.args,
+        _ => return false,
+    };
+    let paths: Vec<Vec<String>> = args
+        .iter()
+        .filter_map(|arg| match arg {
+            GenericArgument::Type(Type::Path(p)) if p.qself.is_none() => Some(
+                p.path
+                    .segments
+                    .iter()
+                    .map(|segment| segment.ident.to_string())
+                    .collect(),
+            ),
+            _ => None,
+        })
+        .collect();
+    let is_one_of = |path: &[String], candidates: &[&[&str]]| {
+        candidates.iter().any(|candidate| *candidate == path)
+    };
+    args.len() == 2
+        && paths.len() == 2
+        && is_one_of(&paths[0], VALUE)
+        && is_one_of(&paths[1], ERROR)
+}
+
+// Whether method returns a Subscription<T>, with the subscriptions feature of easy-jsonrpc.
+fn is_subscription(method: &TraitItemFn) -> bool {
+    cfg!(feature = "subscriptions")
+        && generic_inner(&return_type(&method.sig), "Subscription").is_some()
+}
+
+// the method's receiver, if it is &mut self
+fn mut_receiver(method: &Signature) -> Option<&Receiver> {
+    method.inputs.iter().find_map(|arg| match arg {
+        FnArg::Receiver(receiver) if receiver.mutability.is_some() => Some(receiver),
+        _ => None,
+    })
+}
+
+// the method's receiver, if it is self: Arc<Self>
+fn arc_receiver(method: &Signature) -> Option<&Receiver> {
+    method.inputs.iter().find_map(|arg| match arg {
+        FnArg::Receiver(receiver) if is_arc_self(&receiver.ty) => Some(receiver),
+        _ => None,
+    })
+}
+
+// Arc<Self>, however the path to Arc is written
+fn is_arc_self(ty: &Type) -> bool {
+    generic_inner(ty, "Arc").is_some_and(|inner| match inner {
+        Type::Path(p) => p.qself.is_none() && p.path.is_ident("Self"),
+        _ => false,
+    })
+}
+
+// return all methods in the trait, or reject if trait contains an item that is not a method
+fn trait_methods(tr: &ItemTrait) -> Result<Vec<&TraitItemFn>, Rejections> {
+    let methods = partition(tr.items.iter().filter_map(|item| match item {
+        TraitItem::Fn(method) => Some(Ok(method)),
+        // ignored, see dyn_compatible_items
+        TraitItem::Type(_) => None,
+        TraitItem::Const(item) => Some(Err(
+            Rejection::create(item.ident.span(), Reason::AssociatedConst).into(),
+        )),
+        other => Some(Err(
+            Rejection::create(other.span(), Reason::TraitNotStrictlyMethods).into(),
+        )),
+    }))?;
+    partition(methods.iter().map(|method| {
+        let name = wire_name(method)?;
+        let attrs = method_attrs(method)?;
+        let span = match &attrs.name {
+            Some(lit) => lit.span(),
+            None => method.sig.ident.span(),
+        };
+        let generics = &method.sig.generics;
+        if let Some(param) = generics.type_params().next() {
+            Err(Rejection::create(param.span(), Reason::GenericMethod).into())
+        } else if let Some(param) = generics.const_params().next() {
+            Err(Rejection::create(param.span(), Reason::GenericMethod).into())
+        } else if let Some(predicate) = generics
+            .where_clause
+            .iter()
+            .flat_map(|w| &w.predicates)
+            .find(|p| !matches!(p, syn::WherePredicate::Lifetime(_)))
+        {
+            Err(Rejection::create(predicate.span(), Reason::GenericMethod).into())
+        } else if attrs.client_only && attrs.server_only {
+            Err(Rejection::create(method.sig.ident.span(), Reason::ClientAndServerOnly).into())
+        } else if let (true, Some(receiver)) = (attrs.readonly, mut_receiver(&method.sig)) {
+            Err(Rejection::create(receiver.span(), Reason::ReadonlyMutSelf).into())
+        } else if let (true, Some(receiver)) = (attrs.readonly, arc_receiver(&method.sig)) {
+            Err(Rejection::create(receiver.span(), Reason::ReadonlyArcSelf).into())
+        } else if attrs.params_struct && !is_params_struct(&get_args(&method.sig)?) {
+            Err(Rejection::create(method.sig.ident.span(), Reason::InvalidParamsStruct).into())
+        } else if attrs.lenient_params && get_args(&method.sig)?.len() != 1 {
+            Err(Rejection::create(method.sig.ident.span(), Reason::InvalidLenientParams).into())
+        } else if attrs.paginate && !is_paginatable(method, &attrs)? {
+            Err(Rejection::create(method.sig.ident.span(), Reason::InvalidPaginate).into())
+        } else if let (Some((_, span)), false) =
+            (attrs.error_code, has_error_code_result(method, &attrs))
+        {
+            Err(Rejection::create(span, Reason::InvalidErrorCode).into())
+        } else if is_subscription(method)
+            && (attrs.codec.is_some()
+                || attrs.paginate
+                || attrs.readonly
+                || arc_receiver(&method.sig).is_some())
+        {
+            Err(
+                Rejection::create(return_type_span(&method.sig), Reason::InvalidSubscription)
+                    .into(),
+            )
+        } else if name.starts_with("rpc.") {
+            Err(Rejection::create(span, Reason::ReservedMethodPrefix).into())
+        } else if name.is_empty() || name.chars().any(char::is_control) {
+            Err(Rejection::create(span, Reason::InvalidMethodName).into())
+        } else {
+            Ok(())
+        }
+    }))?;
+    Ok(methods)
+}
+
+// An error_code method returns a Result whose Ok is sent as the result and whose Err is sent as
+// an error with the method's code.
+fn has_error_code_result(method: &TraitItemFn, attrs: &MethodAttrs) -> bool {
+    let ty = return_type(&method.sig);
+    result_ok_type(&ty).is_some()
+        && !is_type_raw_result(&ty)
+        && !attrs.paginate
+        && !is_subscription(method)
+}
+
+// The code of the errors sent by a method marked #[rpc(error_code = offset)], offset from the
+// trait's error_base. Codes reserved by the jsonrpc spec, or beyond i64, are rejected.
+fn error_code(method: &TraitItemFn, attrs: &TraitAttrs) -> Result<Option<i64>, Rejections> {
+    let (offset, span) = match method_attrs(method)?.error_code {
+        Some(offset) => offset,
+        None => return Ok(None),
+    };
+    match attrs.error_base.unwrap_or(0).checked_add(offset) {
+        Some(code) if !(-32768..=-32000).contains(&code) => Ok(Some(code)),
+        _ => Err(Rejection::create(span, Reason::ReservedErrorCode).into()),
+    }
+}
+
+// Check the code of every method, including client_only methods, whatever code is generated.
+// Methods with malformed attributes are left to trait_methods to report.
+fn check_error_codes(tr: &ItemTrait, attrs: &TraitAttrs) -> Result<(), Rejections> {
+    let methods = tr.items.iter().filter_map(|item| match item {
+        TraitItem::Fn(method) if method_attrs(method).is_ok() => Some(method),
+        _ => None,
+    });
+    partition(methods.map(|method| error_code(method, attrs)))?;
+    Ok(())
+}
+
+// A params_struct method's one argument is built from the params as a whole.
+fn is_params_struct(args: &[Arg]) -> bool {
+    match args {
+        [arg] => !arg.rest && arg.codec.is_none() && !matches!(arg.ty, Type::Reference(_)),
+        _ => false,
+    }
+}
+
+// A paginated method's Vec result is sliced into pages. The cursor and limit selecting the page
+// are taken from the params after the method's own arguments.
+fn is_paginatable(method: &TraitItemFn, attrs: &MethodAttrs) -> Result<bool, Rejections> {
+    let args = get_args(&method.sig)?;
+    Ok(vec_inner(&return_type(&method.sig)).is_some()
+        && attrs.codec.is_none()
+        && !attrs.params_struct
+        && !args
+            .iter()
+            .any(|arg| arg.rest || arg.wire_name == "cursor" || arg.wire_name == "limit"))
+}
+
+// the name under which method is exposed over jsonrpc
+fn wire_name(method: &TraitItemFn) -> Result<String, Rejections> {
+    Ok(match method_attrs(method)?.name {
+        Some(lit) => lit.value(),
+        // r#type is called "type" on the wire
+        None => method.sig.ident.unraw().to_string(),
+    })
+}
+
+fn is_type_str(ty: &Type) -> bool {
+    match ty {
+        Type::Path(p) => p.path.is_ident("str"),
+        _ => false,
+    }
+}
+
+fn is_type_integer(ty: &Type) -> bool {
+    const INTEGERS: &[&str] = &[
+        "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+    ];
+    match ty {
+        Type::Path(p) => INTEGERS.iter().any(|int| p.path.is_ident(int)),
+        _ => false,
+    }
+}
+
+// Without arbitrary_precision, serde_json can't hold 128 bit integers outside the range of i64
+// and u64 as numbers, so they are sent as decimal strings unless another codec is chosen. The wire
+// format doesn't depend on the feature, so that clients and servers built either way agree. Types
+// wrapping them, e.g. `Option<u128>`, aren't recognized.
+fn is_type_int128(ty: &Type) -> bool {
+    const INT128: &[&str] = &["u128", "i128"];
+    match ty {
+        Type::Path(p) if p.qself.is_none() => {
+            let segments: Vec<String> = p
+                .path
+                .segments
+                .iter()
+                .map(|segment| segment.ident.to_string())
+                .collect();
+            match segments.as_slice() {
+                [int] => INT128.contains(&int.as_str()),
+                [krate, primitive, int] => {
+                    (krate == "std" || krate == "core")
+                        && primitive == "primitive"
+                        && INT128.contains(&int.as_str())
+                }
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+fn is_type_primitive(ty: &Type) -> bool {
+    const PRIMITIVES: &[&str] = &["bool", "f32", "f64"];
+    is_type_integer(ty)
+        || match ty {
+            Type::Path(p) => PRIMITIVES.iter().any(|prim| p.path.is_ident(prim)),
+            _ => false,
+        }
+}
+
+fn is_type_bytes(ty: &Type) -> bool {
+    match ty {
+        Type::Slice(s) => matches!(&*s.elem, Type::Path(p) if p.path.is_ident("u8")),
+        _ => false,
+    }
+}
+
+// If ty is Cow<T>, return T.
+fn cow_inner(ty: &Type) -> Option<&Type> {
+    generic_inner(ty, "Cow")
+}
+
+// If ty is Vec<T>, return T.
+fn vec_inner(ty: &Type) -> Option<&Type> {
+    generic_inner(ty, "Vec")
+}
+
+// If ty is wrapper<T>, return T.
+fn generic_inner<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    let segment = match ty {
+        Type::Path(p) if p.qself.is_none() => p.path.segments.last()?,
+        _ => return None,
+    };
+    if segment.ident != wrapper {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+// self: Arc<Self> methods are called with a clone of the Arc holding the handler. They are only
+// dispatched by HandleArc::handle_arc, where that's `this`.
+fn arc_receiver_call(method: &TraitItemFn, receiver: &TokenStream) -> TokenStream {
+    match arc_receiver(&method.sig) {
+        Some(_) => quote! { std::sync::Arc::clone(this) },
+        None => receiver.clone(),
+    }
+}
+
+// generate code that parses rpc arguments and calls the given method
+fn add_handler(
+    trait_name: &Ident,
+    method: &TraitItemFn,
+    receiver: &TokenStream,
+) -> Result<TokenStream, Rejections> {
+    let method_name = &method.sig.ident;
+    let receiver = arc_receiver_call(method, receiver);
+    let ctx = match ctx_arg(&method.sig) {
+        Some(_) => quote! { ctx, },
+        None => quote! {},
+    };
+    let progress = progress(method);
+    let (get_rpc_args, ordered_args, parse_args) = parse_args(method)?;
+    let size_check = size_check(method)?;
+    let take_page_params = take_page_params(method)?;
+    let assertions = assert_deserialize(method)?;
+
+    // Statements rather than a block, so that args outlives result. The result of a method with
+    // lifetime parameters may borrow from its arguments.
+    Ok(quote! {
+        #assertions
+        #take_page_params
+        let args: Vec<easy_jsonrpc::Value> = #get_rpc_args.map_err(easy_jsonrpc::Error::from)?;
+        #size_check
+        let mut ordered_args = #ordered_args;
+        let result = <dyn #trait_name>::#method_name(#receiver, #ctx #progress #(#parse_args),*); // call the target procedure
+    })
+}
+
+// Report argument types which can't be deserialized at the argument rather than at the macro.
+// Emitted ahead of each function deserializing the arguments, so that rustc reports the one
+// failed assertion, rather than each call deserializing the argument. Borrowed, Cow and codec
+// arguments aren't deserialized as themselves, so they are left to the code parsing them.
+fn assert_deserialize(method: &TraitItemFn) -> Result<TokenStream, Rejections> {
+    if method_attrs(method)?.params_struct {
+        return Ok(quote! {});
+    }
+    let assertions = get_args(&method.sig)?
+        .into_iter()
+        .filter(|arg| {
+            arg.codec.is_none()
+                && !matches!(arg.ty, Type::Reference(_))
+                && cow_inner(arg.ty).is_none()
+        })
+        .map(|arg| {
+            let ty = erase_lifetimes(arg.ty.to_token_stream());
+            quote_spanned! { arg.ty.span() =>
+                easy_jsonrpc::util::assert_deserialize::<#ty>();
+            }
+        });
+    Ok(quote! { #(#assertions)* })
+}
+
+// The progress argument passed to method, if it takes one. Bound to `progress` by no_progress or
+// by the HandleWithSink implementation.
+fn progress(method: &TraitItemFn) -> TokenStream {
+    match progress_arg(&method.sig) {
+        Some(_) => quote! { progress, },
+        None => quote! {},
+    }
+}
+
+// Like add_handler, for HandleRaw. Each argument is deserialized straight from its RawValue,
+// except that methods taking borrowing, rest or aliased arguments, limiting the size of their
+// params or paginating their result parse their params into Values first.
+fn add_raw_handler(
+    trait_name: &Ident,
+    method: &TraitItemFn,
+    receiver: &TokenStream,
+) -> Result<TokenStream, Rejections> {
+    let args = get_args(&method.sig)?;
+    let attrs = method_attrs(method)?;
+    if attrs.params_struct
+        || attrs.max_param_bytes.is_some()
+        || attrs.paginate
+        || args.iter().any(|arg| !arg.aliases.is_empty())
+        || args.iter().any(|arg| arg.rest || is_type_borrowed(arg.ty))
+    {
+        let handler = add_handler(trait_name, method, receiver)?;
+        return Ok(quote! {
+            let params = params.into_params();
+            #handler
+        });
+    }
+    let method_name = &method.sig.ident;
+    let progress = progress(method);
+    let arg_name_literals = args.iter().map(|arg| &arg.wire_name);
+    let required = required_args(&args);
+    let parse_args = args.iter().enumerate().map(|(index, arg)| {
+        let ty = arg.ty;
+        let argname_literal = format!("\"{}\"", arg.name.unraw());
+        let expected = type_string(ty);
+        let raw = quote! { ordered_args.next_arg().map_err(easy_jsonrpc::Error::from)? };
+        match &arg.codec {
+            None if is_type_integer(ty) => quote_spanned! { ty.span() =>
+                easy_jsonrpc::util::from_raw_integer(#raw, #argname_literal, #index, #expected)
+                    .map_err(easy_jsonrpc::Error::from)?
+            },
+            Some(Codec::Int128) => quote_spanned! { ty.span() =>
+                easy_jsonrpc::util::from_raw_int128(#raw, #argname_literal, #index, #expected)
+                    .map_err(easy_jsonrpc::Error::from)?
+            },
+            codec => {
+                let deserialize = match codec {
+                    Some(codec) => {
+                        let codec = codec.path();
+                        quote! {
+                            easy_jsonrpc::util::from_raw_with(
+                                #raw,
+                                |raw| #codec::deserialize(raw),
+                                |value| #codec::deserialize(value),
+                            )
+                        }
+                    }
+                    None => quote_spanned! { ty.span() => easy_jsonrpc::util::from_raw_value(#raw) },
+                };
+                quote_spanned! { ty.span() =>
+                    #deserialize.map_err(|e| {
+                        easy_jsonrpc::util::invalid_arg_value(#argname_literal, #index, #expected, e)
+                    })?
+                }
+            }
+        }
+    });
+    let arg_idents: Vec<Ident> = (0..args.len())
+        .map(|index| Ident::new(&format!("arg{}", index), Span::call_site()))
+        .collect();
+    let assertions = assert_deserialize(method)?;
+    // Each argument is read just before it is deserialized, so nothing after the first bad
+    // argument is read.
+    Ok(quote! {
+        #assertions
+        let mut ordered_args = params
+            .into_rpc_args(&[#(#arg_name_literals),*], #required)
+            .map_err(easy_jsonrpc::Error::from)?;
+        #(let #arg_idents = #parse_args;)*
+        ordered_args.finish().map_err(easy_jsonrpc::Error::from)?;
+        let result = <dyn #trait_name>::#method_name(#receiver, #progress #(#arg_idents),*); // call the target procedure
+    })
+}
+
+// Whether values of type ty may borrow from what they were deserialized from. Such values can't
+// be deserialized from raw json when it contains escape sequences.
+fn is_type_borrowed(ty: &Type) -> bool {
+    fn has_reference_or_lifetime(tokens: TokenStream) -> bool {
+        tokens.into_iter().any(|tt| match tt {
+            proc_macro2::TokenTree::Punct(p) => p.as_char() == '&' || p.as_char() == '\'',
+            proc_macro2::TokenTree::Group(g) => has_reference_or_lifetime(g.stream()),
+            _ => false,
+        })
+    }
+    cow_inner(ty).is_some() || has_reference_or_lifetime(ty.to_token_stream())
+}
+
+// Generate statements rejecting args larger than #[rpc(max_param_bytes = ..)] allows, to follow
+// the splitting of params into args.
+fn size_check(method: &TraitItemFn) -> Result<TokenStream, Rejections> {
+    let attrs = method_attrs(method)?;
+    Ok(match attrs.max_param_bytes {
+        None => quote! {},
+        Some(max) if attrs.params_struct => {
+            let name = get_args(&method.sig)?.remove(0).wire_name;
+            quote! {
+                easy_jsonrpc::util::check_params_bytes(&params, #name, #max)
+                    .map_err(easy_jsonrpc::Error::from)?;
+            }
+        }
+        Some(max) => {
+            let names = get_args(&method.sig)?.into_iter().map(|arg| arg.wire_name);
+            quote! {
+                easy_jsonrpc::util::check_param_bytes(&args, &[#(#names),*], #max)
+                    .map_err(easy_jsonrpc::Error::from)?;
+            }
+        }
+    })
+}
+
+// Generate a statement taking `cursor` and `limit` out of `params`, for a #[rpc(paginate)] method.
+fn take_page_params(method: &TraitItemFn) -> Result<TokenStream, Rejections> {
+    if !method_attrs(method)?.paginate {
+        return Ok(quote! {});
+    }
+    let args = get_args(&method.sig)?.len();
+    Ok(quote! {
+        let (params, cursor, limit) = easy_jsonrpc::util::take_page_params(params, #args)?;
+    })
+}
+
+// generate code that parses rpc arguments exactly as add_handler would, then discards them
+fn add_validator(method: &TraitItemFn) -> Result<TokenStream, Rejections> {
+    let (get_rpc_args, ordered_args, parse_args) = parse_args(method)?;
+    let size_check = size_check(method)?;
+    let take_page_params = take_page_params(method)?;
+    let assertions = assert_deserialize(method)?;
+    let arg_types = get_args(&method.sig)?
+        .into_iter()
+        .map(|arg| erase_lifetimes(arg.ty.to_token_stream()));
+    Ok(quote! {{
+        #assertions
+        #take_page_params
+        let args: Vec<easy_jsonrpc::Value> = #get_rpc_args.map_err(easy_jsonrpc::Error::from)?;
+        #size_check
+        let mut ordered_args = #ordered_args;
+        #(let _: #arg_types = #parse_args;)*
+        Ok(())
+    }})
+}
+
+// Generate an expression splitting `params` into a Vec of arguments `args`, an expression
+// iterating over `args` for `ordered_args`, and an expression for each argument taking it from
+// `ordered_args` and converting it to the argument's type. The argument expressions return early
+// with an error. Unless an argument borrows from its Value, the Values are moved into the
+// arguments rather than copied.
+fn parse_args(
+    method: &TraitItemFn,
+) -> Result<(TokenStream, TokenStream, Vec<TokenStream>), Rejections> {
+    let args = get_args(&method.sig)?;
+    if method_attrs(method)?.params_struct {
+        // no arguments to split out, the one argument takes the params whole
+        let ty = args[0].ty;
+        return Ok((
+            quote! { Ok::<Vec<easy_jsonrpc::Value>, easy_jsonrpc::InvalidArgs>(vec![]) },
+            quote! { args.iter() },
+            vec![quote_spanned! { ty.span() =>
+                <#ty as easy_jsonrpc::FromParams>::from_params(params)?
+            }],
+        ));
+    }
+    let get_rpc_args = get_rpc_args(&args);
+    let owned = !args.iter().any(|arg| is_type_borrowed(arg.ty));
+    let ordered_args = if owned {
+        quote! { args.into_iter() }
+    } else {
+        quote! { args.iter() }
+    };
+    // By the time handle is called, the params have already been parsed into Values.
+    // get_rpc_args only puts those Values into argument order. Converting each Value into its
+    // argument type is the costly step, and each conversion ends in `?`, so nothing after the
+    // first bad argument is deserialized.
+    let parse_args = args.iter().enumerate().map(|(index, arg)| {
+        let ty = arg.ty;
+        let argname_literal = format!("\"{}\"", arg.name.unraw());
+        let expected = type_string(ty);
+        // non-lexical lifetimes make it possible to create a reference to an anonymous owned value
+        let prefix = match ty {
+            Type::Reference(r) if is_type_str(&r.elem) => quote! {},
+            Type::Reference(_) => quote! { & },
+            _ => quote! {},
+        };
+        let value_ref = if owned { quote! { & } } else { quote! {} };
+        // Primitives are read directly from the Value, skipping serde. The serde path is only
+        // taken to explain why an argument was rejected.
+        if arg.codec.is_none() && is_type_primitive(ty) {
+            let slow_path = if is_type_integer(ty) {
+                quote! {
+                    easy_jsonrpc::util::from_integer_value(value, #argname_literal, #index, #expected)
+                        .map_err(easy_jsonrpc::Error::from)?
+                }
+            } else {
+                quote! {
+                    easy_jsonrpc::util::from_serde_json_value_ref(value).map_err(|e| {
+                        easy_jsonrpc::util::invalid_arg_value(#argname_literal, #index, #expected, e)
+                    })?
+                }
+            };
+            return quote_spanned! { ty.span() => {
+                // get_rpc_args checked the number of args
+                let value = #value_ref ordered_args.next().ok_or_else(|| easy_jsonrpc::util::missing_arg(#index))?;
+                match <#ty as easy_jsonrpc::util::FromValueFast>::from_value_fast(value) {
+                    Some(arg) => arg,
+                    None => #slow_path,
+                }
+            }};
+        }
+        if let Some(Codec::Int128) = arg.codec {
+            return quote_spanned! { ty.span() => {
+                // get_rpc_args checked the number of args
+                let value = #value_ref ordered_args.next().ok_or_else(|| easy_jsonrpc::util::missing_arg(#index))?;
+                easy_jsonrpc::util::from_int128_value(value, #argname_literal, #index, #expected)
+                    .map_err(easy_jsonrpc::Error::from)?
+            }};
+        }
+        let deserialize = match &arg.codec {
+            Some(codec) => {
+                let codec = codec.path();
+                quote! { #codec::deserialize }
+            }
+            // Cow's Deserialize implementation always allocates, strings borrow from the Value instead
+            None => match cow_inner(ty) {
+                Some(inner) if is_type_str(inner) => quote! { easy_jsonrpc::util::borrow_cow_str },
+                Some(inner) if is_type_bytes(inner) => {
+                    quote! { easy_jsonrpc::util::borrow_cow_bytes }
+                }
+                _ if owned => quote_spanned! { ty.span() => easy_jsonrpc::util::from_serde_json_value },
+                _ => quote_spanned! { ty.span() => easy_jsonrpc::util::from_serde_json_value_ref },
+            },
+        };
+        quote_spanned! { ty.span() => #prefix
+            // get_rpc_args checked the number of args
+            #deserialize(ordered_args.next().ok_or_else(|| easy_jsonrpc::util::missing_arg(#index))?)
+                .map_err(|e| {
+                easy_jsonrpc::util::invalid_arg_value(#argname_literal, #index, #expected, e)
+            })?
+        }
+    });
+
+    Ok((get_rpc_args, ordered_args, parse_args.collect()))
+}
+
+// Generate an expression splitting `params` into a Vec of arguments, in the order args are
+// declared.
+fn get_rpc_args(args: &[Arg]) -> TokenStream {
+    let arg_name_literals = args.iter().map(|arg| &arg.wire_name);
+    let aliases: Vec<TokenStream> = args
+        .iter()
+        .flat_map(|arg| {
+            let name = &arg.wire_name;
+            arg.aliases
+                .iter()
+                .map(move |alias| quote! { (#alias, #name) })
+        })
+        .collect();
+    let params = if aliases.is_empty() {
+        quote! { params }
+    } else {
+        quote! { easy_jsonrpc::util::resolve_aliases(params, &[#(#aliases),*]) }
+    };
+    let required = required_args(args);
+    if args.last().is_some_and(|arg| arg.rest) {
+        let arg_name_literals = arg_name_literals.take(args.len() - 1);
+        quote! { #params.get_rpc_args_with_rest(&[#(#arg_name_literals),*], #required) }
+    } else if required == args.len() {
+        quote! { #params.get_rpc_args(&[#(#arg_name_literals),*]) }
+    } else {
+        quote! { #params.get_rpc_args_with_optional(&[#(#arg_name_literals),*], #required) }
+    }
+}
+
+// The number of arguments a caller must pass. Trailing Option<T> arguments and rest arguments may
+// be omitted.
+fn required_args(args: &[Arg]) -> usize {
+    args.iter()
+        .rposition(|arg| !arg.rest && !is_type_option(arg.ty))
+        .map_or(0, |last_required| last_required + 1)
 }
 
-fn return_type(method: &MethodSig) -> Type {
-    match &method.decl.output {
-        ReturnType::Default => Type::Tuple(TypeTuple {
-            paren_token: Paren {
-                span: method.decl.output.span(),
-            },
-            elems: Punctuated::new(),
-        }),
-        ReturnType::Type(_, typ) => *typ.clone(),
+// ty as written, e.g. "Vec<u64>", for error messages. stringify! would give "Vec < u64 >".
+fn type_string(ty: &impl ToTokens) -> String {
+    let mut ret = ty.to_token_stream().to_string();
+    for (spaced, tight) in &[
+        (" <", "<"),
+        ("< ", "<"),
+        (" >", ">"),
+        (" ::", "::"),
+        (":: ", "::"),
+        ("& ", "&"),
+        (" ,", ","),
+        (" ;", ";"),
+    ] {
+        ret = ret.replace(spaced, tight);
     }
+    ret
 }
 
-// return all methods in the trait, or reject if trait contains an item that is not a method
-fn trait_methods(tr: &ItemTrait) -> Result<Vec<&MethodSig>, Rejections> {
-    let methods = partition(tr.items.iter().map(|item| match item {
-        TraitItem::Method(method) => Ok(&method.sig),
-        other => Err(Rejection::create(other.span(), Reason::TraitNotStrictlyMethods).into()),
-    }))?;
-    partition(methods.iter().map(|method| {
-        if method.ident.to_string().starts_with("rpc.") {
-            Err(Rejection::create(method.ident.span(), Reason::ReservedMethodPrefix).into())
-        } else {
-            Ok(())
-        }
-    }))?;
-    Ok(methods)
+fn is_type_vec(ty: &Type) -> bool {
+    match ty {
+        Type::Path(p) if p.qself.is_none() => p
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Vec"),
+        _ => false,
+    }
 }
 
-fn is_type_str(ty: &Type) -> bool {
+fn is_type_option(ty: &Type) -> bool {
     match ty {
-        Type::Path(p) => p.path.is_ident("str"),
+        Type::Path(p) if p.qself.is_none() => p
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Option"),
         _ => false,
     }
 }
 
-// generate code that parses rpc arguments and calls the given method
-fn add_handler(
-    trait_name: &Ident,
-    method: &MethodSig,
-    deref_self: bool,
-) -> Result<TokenStream, Rejections> {
-    let method_name = &method.ident;
-    let args = get_args(&method.decl)?;
-    let arg_name_literals = args.iter().map(|(id, _)| id.to_string());
-    let parse_args = args.iter().enumerate().map(|(index, (ident, ty))| {
-        let argname_literal = format!("\"{}\"", ident);
-        // non-lexical lifetimes make it possible to create a reference to an anonymous owned value
-        let prefix = match ty {
-            Type::Reference(r) if is_type_str(&r.elem) => quote! {},
-            Type::Reference(_) => quote! { & },
-            _ => quote! {},
-        };
-        quote_spanned! { ty.span() => #prefix {
-            easy_jsonrpc::util::from_serde_json_value_ref(&ordered_args.next().expect(
-                "RPC method Got too few args. This is a bug." // checked in get_rpc_args
-            )).map_err(|_| {
-                easy_jsonrpc::InvalidArgs::InvalidArgStructure {
-                    name: #argname_literal,
-                    index: #index,
-                }.into()
-            })?
-        }}
-    });
-
-    if deref_self {
-        Ok(quote! {{
-            let mut args: Vec<easy_jsonrpc::Value> =
-                params.get_rpc_args(&[#(#arg_name_literals),*])
-                    .map_err(|a| a.into())?;
-            let mut ordered_args = args.drain(..);
-            let res = <dyn #trait_name>::#method_name(*self, #(#parse_args),*); // call the target procedure
-            debug_assert_eq!(ordered_args.next(), None); // parse_args must consume ordered_args
-            res
-        }})
-    } else {
-        Ok(quote! {{
-            let mut args: Vec<easy_jsonrpc::Value> =
-                params.get_rpc_args(&[#(#arg_name_literals),*])
-                    .map_err(|a| a.into())?;
-            let mut ordered_args = args.drain(..);
-            let res = <dyn #trait_name>::#method_name(self, #(#parse_args),*); // call the target procedure
-            debug_assert_eq!(ordered_args.next(), None); // parse_args must consume ordered_args
-            res
-        }})
-    }
+// A jsonrpc argument extracted from a method signature.
+struct Arg<'a> {
+    name: &'a Ident,
+    // the name of the arg in named params
+    wire_name: String,
+    ty: &'a Type,
+    codec: Option<Codec>,
+    // collects the remaining positional params, always the last arg
+    rest: bool,
+    // other names the arg is accepted under when params are named
+    aliases: Vec<LitStr>,
 }
 
-// Get the name and type of each argument from method. Skip the first argument, which must be &self.
-// If the first argument is not &self, an error will be returned.
-fn get_args(method: &FnDecl) -> Result<Vec<(&Ident, &Type)>, Rejections> {
+// Get the name and type of each argument from method. Skip the first argument, which must be &self,
+// and the context and progress arguments if there are any. If the first argument is not &self, an
+// error will be returned.
+fn get_args(method: &Signature) -> Result<Vec<Arg<'_>>, Rejections> {
     let mut inputs = method.inputs.iter();
     match inputs.next() {
-        Some(FnArg::SelfRef(ArgSelfRef { mutability: _, .. })) => Ok(()),
+        Some(FnArg::Receiver(Receiver {
+            reference: Some(_),
+            colon_token: None,
+            ..
+        })) => Ok(()),
+        Some(FnArg::Receiver(receiver)) if is_arc_self(&receiver.ty) => Ok(()),
+        Some(FnArg::Receiver(receiver)) if matches!(&*receiver.ty, Type::Reference(r) if is_arc_self(&r.elem)) => {
+            Err(Rejection::create(receiver.span(), Reason::RefArcSelf))
+        }
         Some(a) => Err(Rejection::create(a.span(), Reason::FirstArgumentNotSelfRef)),
         None => Err(Rejection::create(
             method.inputs.span(),
             Reason::FirstArgumentNotSelfRef,
         )),
     }?;
-    partition(inputs.map(as_jsonrpc_arg))
+    let skip = usize::from(ctx_arg(method).is_some()) + usize::from(progress_arg(method).is_some());
+    let args = partition(inputs.skip(skip).map(as_jsonrpc_arg))?;
+    partition(args.iter().enumerate().map(|(index, arg)| {
+        let valid =
+            !arg.rest || (index == args.len() - 1 && arg.codec.is_none() && is_type_vec(arg.ty));
+        if valid {
+            Ok(())
+        } else {
+            Err(Rejection::create(arg.name.span(), Reason::InvalidRestArg).into())
+        }
+    }))?;
+    // every name and alias must pick out a single argument
+    let mut names: Vec<String> = args.iter().map(|arg| arg.wire_name.clone()).collect();
+    for alias in args.iter().flat_map(|arg| &arg.aliases) {
+        if names.contains(&alias.value()) {
+            return Err(Rejection::create(alias.span(), Reason::DuplicateAlias).into());
+        }
+        names.push(alias.value());
+    }
+    Ok(args)
+}
+
+// The argument marked #[rpc_ctx], which may only be the first argument after self.
+fn ctx_arg(method: &Signature) -> Option<&PatType> {
+    match method.inputs.iter().nth(1) {
+        Some(FnArg::Typed(typed)) if typed.attrs.iter().any(is_rpc_ctx_attr) => Some(typed),
+        _ => None,
+    }
+}
+
+// The argument marked #[rpc_progress], which may only be the first argument after self and any
+// #[rpc_ctx].
+fn progress_arg(method: &Signature) -> Option<&PatType> {
+    let index = 1 + usize::from(ctx_arg(method).is_some());
+    match method.inputs.iter().nth(index) {
+        Some(FnArg::Typed(typed)) if typed.attrs.iter().any(is_rpc_progress_attr) => Some(typed),
+        _ => None,
+    }
+}
+
+// The type C of a method's `#[rpc_ctx] ctx: &C` argument.
+fn ctx_type(method: &Signature) -> Result<Option<&Type>, Rejections> {
+    match ctx_arg(method).map(|arg| &*arg.ty) {
+        None => Ok(None),
+        Some(Type::Reference(r)) if r.mutability.is_none() => Ok(Some(&r.elem)),
+        Some(ty) => Err(Rejection::create(ty.span(), Reason::CtxNotSharedRef).into()),
+    }
 }
 
 // If all Ok, return Vec of successful values, otherwise return all Rejections.
@@ -326,13 +2894,19 @@ fn partition<K, I: Iterator<Item = Result<K, Rejections>>>(iter: I) -> Result<Ve
 }
 
 // Attempt to extract name and type from arg
-fn as_jsonrpc_arg(arg: &FnArg) -> Result<(&Ident, &Type), Rejections> {
+fn as_jsonrpc_arg(arg: &FnArg) -> Result<Arg<'_>, Rejections> {
     let arg = match arg {
-        FnArg::Captured(captured) => Ok(captured),
+        FnArg::Typed(typed) => Ok(typed),
         a => Err(Rejection::create(a.span(), Reason::ConcreteTypesRequired)),
     }?;
-    let ty = &arg.ty;
-    let pat_ident = match &arg.pat {
+    let PatType { attrs, pat, ty, .. } = arg;
+    if let Some(attr) = attrs.iter().find(|attr| is_rpc_ctx_attr(attr)) {
+        return Err(Rejection::create(attr.span(), Reason::MisplacedCtx).into());
+    }
+    if let Some(attr) = attrs.iter().find(|attr| is_rpc_progress_attr(attr)) {
+        return Err(Rejection::create(attr.span(), Reason::MisplacedProgress).into());
+    }
+    let pat_ident = match &**pat {
         Pat::Ident(pat_ident) => Ok(pat_ident),
         a => Err(Rejection::create(a.span(), Reason::PatternMatchedArg)),
     }?;
@@ -353,9 +2927,639 @@ fn as_jsonrpc_arg(arg: &FnArg) -> Result<(&Ident, &Type), Rejections> {
             by_ref: None,
             mutability: None,
             subpat: None,
+            ..
         } => Ok(ident),
     }?;
-    Ok((&ident, &ty))
+    let ArgAttrs {
+        codec,
+        rest,
+        aliases,
+        name,
+    } = arg_attrs(attrs)?;
+    Ok(Arg {
+        name: ident,
+        // r#type is called "type" on the wire
+        wire_name: name.map_or_else(|| ident.unraw().to_string(), |lit| lit.value()),
+        ty,
+        codec: codec.or_else(|| is_type_int128(ty).then_some(Codec::Int128)),
+        rest,
+        aliases,
+    })
+}
+
+// A serde "with" module used to (de)serialize an argument or return value in place of its own
+// Serialize and Deserialize implementations.
+#[derive(Clone)]
+enum Codec {
+    Hex,
+    Base64,
+    NumAsStr,
+    Int128,
+    StringifyKeys,
+    With(syn::Path),
+}
+
+impl Codec {
+    fn path(&self) -> TokenStream {
+        match self {
+            Codec::Hex => quote! { easy_jsonrpc::codec::hex },
+            Codec::Base64 => quote! { easy_jsonrpc::codec::base64 },
+            Codec::NumAsStr => quote! { easy_jsonrpc::codec::num_as_str },
+            Codec::Int128 => quote! { easy_jsonrpc::codec::int128 },
+            Codec::StringifyKeys => quote! { easy_jsonrpc::codec::stringify_keys },
+            Codec::With(path) => quote! { #path },
+        }
+    }
+}
+
+// options passed to the macro itself, #[rpc(...)] on the trait
+#[derive(Default)]
+struct TraitAttrs {
+    client_trait: bool,
+    async_client: bool,
+    request_builder: bool,
+    strict_version: bool,
+    strict_sync: bool,
+    wrap_result: Option<syn::Path>,
+    mock: bool,
+    call_enum: bool,
+    client: Option<Ident>,
+    common: Option<Type>,
+    rename_all: Option<RenameRule>,
+    rename_all_methods: Option<RenameRule>,
+    error_base: Option<i64>,
+}
+
+// options set through #[rpc(...)] on a trait method
+#[derive(Default)]
+struct MethodAttrs {
+    codec: Option<Codec>,
+    name: Option<LitStr>,
+    client_only: bool,
+    server_only: bool,
+    unsynced: bool,
+    readonly: bool,
+    deprecated: Option<LitStr>,
+    params_struct: bool,
+    idempotent: bool,
+    cacheable: bool,
+    lenient_params: bool,
+    max_param_bytes: Option<usize>,
+    paginate: bool,
+    timeout_ms: Option<usize>,
+    // the offset and where it was written
+    error_code: Option<(i64, Span)>,
+}
+
+// options set through #[rpc_arg(...)] on a method argument
+#[derive(Default)]
+struct ArgAttrs {
+    codec: Option<Codec>,
+    rest: bool,
+    aliases: Vec<LitStr>,
+    name: Option<LitStr>,
+}
+
+// A method's #[cfg(...)] attributes, copied onto everything generated for the method so it is
+// compiled in or out along with the method itself.
+fn cfg_attrs(method: &TraitItemFn) -> Vec<&Attribute> {
+    method
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("cfg"))
+        .collect()
+}
+
+// A method's doc comments, followed by a blank line when there are any, copied onto its client
+// functions so callers see them on hover. The generated functions add a note of their own below.
+fn method_docs(method: &TraitItemFn) -> TokenStream {
+    let docs: Vec<&Attribute> = method
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .collect();
+    if docs.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            #(#docs)*
+            #[doc = ""]
+        }
+    }
+}
+
+fn is_rpc_attr(attr: &Attribute) -> bool {
+    attr.path().is_ident("rpc")
+}
+
+fn is_rpc_arg_attr(attr: &Attribute) -> bool {
+    attr.path().is_ident("rpc_arg")
+}
+
+fn is_rpc_ctx_attr(attr: &Attribute) -> bool {
+    attr.path().is_ident("rpc_ctx")
+}
+
+fn is_rpc_progress_attr(attr: &Attribute) -> bool {
+    attr.path().is_ident("rpc_progress")
+}
+
+// generate From<Enum> for easy_jsonrpc::Error
+fn impl_rpc_error(input: &DeriveInput) -> Result<TokenStream, Rejections> {
+    let name = &input.ident;
+    let variants = match &input.data {
+        Data::Enum(data) => &data.variants,
+        _ => return Err(Rejection::create(name.span(), Reason::RpcErrorNotEnum).into()),
+    };
+    let arms = partition(variants.iter().map(|variant| {
+        let variant_name = &variant.ident;
+        let (code, message) = rpc_error_attrs(variant)?;
+        let message = message.unwrap_or_else(|| variant_name.to_string());
+        let to_value = |field: &Ident| {
+            quote! {
+                easy_jsonrpc::serde_json::to_value(&#field)
+                    .unwrap_or(easy_jsonrpc::Value::Null)
+            }
+        };
+        let (pattern, data) = match &variant.fields {
+            Fields::Unit => (quote! {}, quote! { None }),
+            Fields::Named(fields) => {
+                let names: Vec<&Ident> = fields
+                    .named
+                    .iter()
+                    .filter_map(|f| f.ident.as_ref())
+                    .collect();
+                let keys = names.iter().map(|name| name.to_string());
+                let values = names.iter().map(|name| to_value(name));
+                (
+                    quote! { { #(#names),* } },
+                    quote! {{
+                        let mut data = easy_jsonrpc::serde_json::Map::new();
+                        #(data.insert(#keys.into(), #values);)*
+                        Some(easy_jsonrpc::Value::Object(data))
+                    }},
+                )
+            }
+            Fields::Unnamed(fields) => {
+                let names: Vec<Ident> = (0..fields.unnamed.len())
+                    .map(|i| Ident::new(&format!("field{}", i), Span::call_site()))
+                    .collect();
+                let values = names.iter().map(to_value);
+                let data = if names.len() == 1 {
+                    quote! { Some(#(#values)*) }
+                } else {
+                    quote! { Some(easy_jsonrpc::Value::Array(vec![#(#values),*])) }
+                };
+                (quote! { ( #(#names),* ) }, data)
+            }
+        };
+        Ok(quote! {
+            #name::#variant_name #pattern => easy_jsonrpc::Error {
+                code: easy_jsonrpc::ErrorCode::from((#code) as i64),
+                message: #message.into(),
+                data: #data,
+            }
+        })
+    }))?;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics From<#name #ty_generics> for easy_jsonrpc::Error #where_clause {
+            fn from(err: #name #ty_generics) -> easy_jsonrpc::Error {
+                match err {
+                    #(#arms,)*
+                }
+            }
+        }
+    })
+}
+
+// read the #[rpc_error(...)] attributes attached to an enum variant
+fn rpc_error_attrs(variant: &syn::Variant) -> Result<(Expr, Option<String>), Rejections> {
+    let mut code = None;
+    let mut message = None;
+    for attr in variant
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("rpc_error"))
+    {
+        parse_attr(attr, |name, meta| match name.to_string().as_str() {
+            "code" => {
+                let value = meta.value().and_then(|value| value.parse::<Expr>());
+                code = Some(
+                    value.map_err(|e| Rejection::create(e.span(), Reason::MalformedAttribute))?,
+                );
+                Ok(())
+            }
+            "message" => {
+                message = Some(option_value(meta)?.value());
+                Ok(())
+            }
+            _ => Err(Rejection::create(name.span(), Reason::UnknownAttributeArg).into()),
+        })?;
+    }
+    match code {
+        Some(code) => Ok((code, message)),
+        None => Err(Rejection::create(variant.ident.span(), Reason::MissingErrorCode).into()),
+    }
+}
+
+// read the options passed to the macro
+fn trait_attrs(args: TokenStream) -> Result<TraitAttrs, Rejections> {
+    let mut ret = TraitAttrs::default();
+    parse_options(args, |name, meta| match name.to_string().as_str() {
+        "client_trait" => {
+            ret.client_trait = true;
+            Ok(())
+        }
+        "async_client" => {
+            ret.async_client = true;
+            Ok(())
+        }
+        "request_builder" => {
+            ret.request_builder = true;
+            Ok(())
+        }
+        "strict_version" => {
+            ret.strict_version = true;
+            Ok(())
+        }
+        "strict_sync" => {
+            ret.strict_sync = true;
+            Ok(())
+        }
+        "mock" => {
+            ret.mock = true;
+            Ok(())
+        }
+        "call_enum" => {
+            ret.call_enum = true;
+            Ok(())
+        }
+        "client" => {
+            let lit = option_value(meta)?;
+            ret.client = Some(
+                lit.parse()
+                    .map_err(|e| Rejection::create(e.span(), Reason::MalformedAttribute))?,
+            );
+            Ok(())
+        }
+        "common" => {
+            ret.common = Some(option_type(meta)?);
+            Ok(())
+        }
+        "rename_all" => {
+            ret.rename_all = Some(option_rename_rule(meta)?);
+            Ok(())
+        }
+        "rename_all_methods" => {
+            ret.rename_all_methods = Some(option_rename_rule(meta)?);
+            Ok(())
+        }
+        "error_base" => {
+            ret.error_base = Some(option_i64(meta)?.0);
+            Ok(())
+        }
+        "wrap_result" => {
+            let lit = option_value(meta)?;
+            ret.wrap_result = Some(
+                lit.parse()
+                    .map_err(|e| Rejection::create(e.span(), Reason::MalformedAttribute))?,
+            );
+            Ok(())
+        }
+        _ => Err(Rejection::create(name.span(), Reason::UnknownAttributeArg).into()),
+    })?;
+    Ok(ret)
+}
+
+// read the #[rpc(...)] attributes attached to a trait method
+fn method_attrs(method: &TraitItemFn) -> Result<MethodAttrs, Rejections> {
+    let mut ret = MethodAttrs::default();
+    for attr in method.attrs.iter().filter(|attr| is_rpc_attr(attr)) {
+        parse_attr(attr, |name, meta| match name.to_string().as_str() {
+            "hex" => set_codec(&mut ret.codec, Codec::Hex, name),
+            "base64" => set_codec(&mut ret.codec, Codec::Base64, name),
+            "num_as_str" => set_codec(&mut ret.codec, Codec::NumAsStr, name),
+            "stringify_keys" => set_codec(&mut ret.codec, Codec::StringifyKeys, name),
+            "client_only" => {
+                ret.client_only = true;
+                Ok(())
+            }
+            "server_only" => {
+                ret.server_only = true;
+                Ok(())
+            }
+            "unsynced" => {
+                ret.unsynced = true;
+                Ok(())
+            }
+            "readonly" => {
+                ret.readonly = true;
+                Ok(())
+            }
+            "params_struct" => {
+                ret.params_struct = true;
+                Ok(())
+            }
+            "idempotent" => {
+                ret.idempotent = true;
+                Ok(())
+            }
+            "cacheable" => {
+                ret.cacheable = true;
+                Ok(())
+            }
+            "lenient_params" => {
+                ret.lenient_params = true;
+                Ok(())
+            }
+            "paginate" => {
+                ret.paginate = true;
+                Ok(())
+            }
+            "max_param_bytes" => {
+                ret.max_param_bytes = Some(option_int(meta)?);
+                Ok(())
+            }
+            "timeout_ms" => {
+                ret.timeout_ms = Some(option_int(meta)?);
+                Ok(())
+            }
+            "error_code" => {
+                ret.error_code = Some(option_i64(meta)?);
+                Ok(())
+            }
+            "deprecated" => {
+                ret.deprecated = Some(option_value(meta)?);
+                Ok(())
+            }
+            "name" => {
+                ret.name = Some(option_value(meta)?);
+                Ok(())
+            }
+            _ => Err(Rejection::create(name.span(), Reason::UnknownAttributeArg).into()),
+        })?;
+    }
+    let return_typ = return_type(&method.sig);
+    let result_typ = match ret.error_code {
+        Some(_) => result_ok_type(&return_typ).unwrap_or(&return_typ),
+        None => &return_typ,
+    };
+    if ret.codec.is_none() && is_type_int128(result_typ) {
+        ret.codec = Some(Codec::Int128);
+    }
+    Ok(ret)
+}
+
+// at most one codec may be chosen for a method or argument
+fn set_codec(slot: &mut Option<Codec>, codec: Codec, name: &Ident) -> Result<(), Rejections> {
+    match slot {
+        Some(_) => Err(Rejection::create(name.span(), Reason::ConflictingCodecs).into()),
+        None => {
+            *slot = Some(codec);
+            Ok(())
+        }
+    }
+}
+
+// read the #[rpc_arg(...)] attributes attached to a method argument
+fn arg_attrs(attrs: &[Attribute]) -> Result<ArgAttrs, Rejections> {
+    let mut ret = ArgAttrs::default();
+    for attr in attrs.iter().filter(|attr| is_rpc_arg_attr(attr)) {
+        parse_attr(attr, |name, meta| match name.to_string().as_str() {
+            "hex" => set_codec(&mut ret.codec, Codec::Hex, name),
+            "base64" => set_codec(&mut ret.codec, Codec::Base64, name),
+            "num_as_str" => set_codec(&mut ret.codec, Codec::NumAsStr, name),
+            "stringify_keys" => set_codec(&mut ret.codec, Codec::StringifyKeys, name),
+            "with" => set_codec(&mut ret.codec, with_codec(meta)?, name),
+            "rest" => {
+                ret.rest = true;
+                Ok(())
+            }
+            "alias" => {
+                ret.aliases.push(option_value(meta)?);
+                Ok(())
+            }
+            "name" => {
+                ret.name = Some(option_value(meta)?);
+                Ok(())
+            }
+            _ => Err(Rejection::create(name.span(), Reason::UnknownAttributeArg).into()),
+        })?;
+    }
+    Ok(ret)
+}
+
+// parse the value of an option, e.g. the "b" in option_b = "b"
+fn option_value(meta: &ParseNestedMeta) -> Result<LitStr, Rejections> {
+    meta.value()
+        .and_then(|value| value.parse())
+        .map_err(|e| Rejection::create(e.span(), Reason::MalformedAttribute).into())
+}
+
+// parse the value of an option naming a type, e.g. the T in option_d = T or option_d = "T"
+fn option_type(meta: &ParseNestedMeta) -> Result<Type, Rejections> {
+    let value = meta
+        .value()
+        .map_err(|e| Rejection::create(e.span(), Reason::MalformedAttribute))?;
+    let ty = if value.peek(LitStr) {
+        value.parse::<LitStr>().and_then(|lit| lit.parse())
+    } else {
+        value.parse()
+    };
+    ty.map_err(|e| Rejection::create(e.span(), Reason::MalformedAttribute).into())
+}
+
+// parse the value of an integer option, e.g. the 8 in option_c = 8
+fn option_int(meta: &ParseNestedMeta) -> Result<usize, Rejections> {
+    meta.value()
+        .and_then(|value| value.parse::<LitInt>())
+        .and_then(|lit| lit.base10_parse())
+        .map_err(|e| Rejection::create(e.span(), Reason::MalformedAttribute).into())
+}
+
+// parse the value of a signed integer option, e.g. the -5 in option_e = -5, and its span
+fn option_i64(meta: &ParseNestedMeta) -> Result<(i64, Span), Rejections> {
+    let parse = || {
+        let value = meta.value()?;
+        let negative = value.parse::<Option<syn::Token![-]>>()?.is_some();
+        let lit = value.parse::<LitInt>()?;
+        let n: i64 = lit.base10_parse()?;
+        Ok((if negative { -n } else { n }, lit.span()))
+    };
+    parse().map_err(|e: syn::Error| Rejection::create(e.span(), Reason::MalformedAttribute).into())
+}
+
+// parse rename_all = "camelCase"
+fn option_rename_rule(meta: &ParseNestedMeta) -> Result<RenameRule, Rejections> {
+    let lit = option_value(meta)?;
+    RenameRule::from_str(&lit.value())
+        .ok_or_else(|| Rejection::create(lit.span(), Reason::UnknownRenameRule).into())
+}
+
+// parse with = "path::to::module"
+fn with_codec(meta: &ParseNestedMeta) -> Result<Codec, Rejections> {
+    let lit = option_value(meta)?;
+    lit.parse()
+        .map(Codec::With)
+        .map_err(|e| Rejection::create(e.span(), Reason::MalformedAttribute).into())
+}
+
+// Call on_option for each comma separated option in attr, e.g. #[rpc(option_a, option_b = "b")].
+fn parse_attr(
+    attr: &Attribute,
+    on_option: impl FnMut(&Ident, &ParseNestedMeta) -> Result<(), Rejections>,
+) -> Result<(), Rejections> {
+    match &attr.meta {
+        Meta::List(list) => parse_options(list.tokens.clone(), on_option),
+        other => Err(Rejection::create(other.span(), Reason::MalformedAttribute).into()),
+    }
+}
+
+// Call on_option for each comma separated option in tokens, e.g. option_a, option_b = "b".
+fn parse_options(
+    tokens: TokenStream,
+    mut on_option: impl FnMut(&Ident, &ParseNestedMeta) -> Result<(), Rejections>,
+) -> Result<(), Rejections> {
+    let mut results: Vec<Result<(), Rejections>> = Vec::new();
+    meta::parser(|meta| {
+        results.push(match meta.path.get_ident() {
+            Some(name) => on_option(name, &meta),
+            None => Err(Rejection::create(meta.path.span(), Reason::UnknownAttributeArg).into()),
+        });
+        Ok(())
+    })
+    .parse2(tokens)
+    .map_err(|e| Rejection::create(e.span(), Reason::MalformedAttribute))?;
+    partition(results.into_iter()).map(|_| ())
+}
+
+// Give each method and argument without a name of its own the name the trait's rename rules make
+// of its identifier, as if it was set with #[rpc(name = "..")] or #[rpc_arg(name = "..")].
+fn apply_rename_rules(tr: &mut ItemTrait, attrs: &TraitAttrs) {
+    for item in tr.items.iter_mut() {
+        let method = match item {
+            TraitItem::Fn(method) => method,
+            _ => continue,
+        };
+        if let Some(rule) = &attrs.rename_all_methods {
+            // malformed attributes are reported when the method is generated
+            if method_attrs(method).is_ok_and(|attrs| attrs.name.is_none()) {
+                let name = rule.apply(&method.sig.ident.unraw().to_string());
+                let span = method.sig.ident.span();
+                method
+                    .attrs
+                    .push(parse_quote_spanned!(span=> #[rpc(name = #name)]));
+            }
+        }
+        let rule = match &attrs.rename_all {
+            Some(rule) => rule,
+            None => continue,
+        };
+        for input in method.sig.inputs.iter_mut() {
+            let typed = match input {
+                FnArg::Typed(typed)
+                    if !typed
+                        .attrs
+                        .iter()
+                        .any(|attr| is_rpc_ctx_attr(attr) || is_rpc_progress_attr(attr)) =>
+                {
+                    typed
+                }
+                _ => continue,
+            };
+            let ident = match &*typed.pat {
+                Pat::Ident(pat_ident) => &pat_ident.ident,
+                _ => continue,
+            };
+            if arg_attrs(&typed.attrs).is_ok_and(|attrs| attrs.name.is_none()) {
+                let name = rule.apply(&ident.unraw().to_string());
+                let span = ident.span();
+                typed
+                    .attrs
+                    .push(parse_quote_spanned!(span=> #[rpc_arg(name = #name)]));
+            }
+        }
+    }
+}
+
+// A case convention for the names of methods or arguments, named like serde's rename_all rules.
+enum RenameRule {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    fn from_str(rule: &str) -> Option<Self> {
+        Some(match rule {
+            "lowercase" => RenameRule::Lower,
+            "UPPERCASE" => RenameRule::Upper,
+            "PascalCase" => RenameRule::Pascal,
+            "camelCase" => RenameRule::Camel,
+            "snake_case" => RenameRule::Snake,
+            "SCREAMING_SNAKE_CASE" => RenameRule::ScreamingSnake,
+            "kebab-case" => RenameRule::Kebab,
+            "SCREAMING-KEBAB-CASE" => RenameRule::ScreamingKebab,
+            _ => return None,
+        })
+    }
+
+    fn apply(&self, name: &str) -> String {
+        match self {
+            RenameRule::Lower => name.to_lowercase(),
+            RenameRule::Upper => name.to_uppercase(),
+            RenameRule::Pascal => name.to_camel_case(),
+            RenameRule::Camel => name.to_mixed_case(),
+            RenameRule::Snake => name.to_snake_case(),
+            RenameRule::ScreamingSnake => name.to_shouty_snake_case(),
+            RenameRule::Kebab => name.to_kebab_case(),
+            RenameRule::ScreamingKebab => name.to_shouty_kebab_case(),
+        }
+    }
+}
+
+// Remove the attributes consumed by this macro so they don't reach the compiler.
+fn strip_rpc_attrs(tr: &mut ItemTrait) {
+    for item in tr.items.iter_mut() {
+        if let TraitItem::Fn(method) = item {
+            method.attrs.retain(|attr| !is_rpc_attr(attr));
+            for input in method.sig.inputs.iter_mut() {
+                if let FnArg::Typed(typed) = input {
+                    typed.attrs.retain(|attr| {
+                        !is_rpc_arg_attr(attr)
+                            && !is_rpc_ctx_attr(attr)
+                            && !is_rpc_progress_attr(attr)
+                    });
+                }
+            }
+        }
+    }
+}
+
+// Associated types would make the trait dyn incompatible, and everything generated goes through
+// dyn Trait. They are bounded by `Self: Sized`, so they stay in the trait but can't be named
+// through dyn Trait. Consts can't be bounded, and are rejected by trait_methods.
+fn dyn_compatible_items(tr: &mut ItemTrait) {
+    for item in &mut tr.items {
+        if let TraitItem::Type(ty) = item {
+            let sized: WherePredicate = parse_quote!(Self: Sized);
+            let where_clause = ty.generics.make_where_clause();
+            let sized_tokens = sized.to_token_stream().to_string();
+            if !where_clause
+                .predicates
+                .iter()
+                .any(|p| p.to_token_stream().to_string() == sized_tokens)
+            {
+                where_clause.predicates.push(sized);
+            }
+        }
+    }
 }
 
 // returned when macro input is invalid
@@ -372,9 +3576,42 @@ enum Reason {
     PatternMatchedArg,
     ConcreteTypesRequired,
     TraitNotStrictlyMethods,
+    AssociatedConst,
+    MockAssociatedType,
     ReservedMethodPrefix,
+    InvalidMethodName,
+    ClientAndServerOnly,
     ReferenceArg,
     MutableArg,
+    UnknownAttributeArg,
+    MalformedAttribute,
+    UnknownRenameRule,
+    MisplacedCtx,
+    MisplacedProgress,
+    CtxNotSharedRef,
+    MixedCtxTypes,
+    InvalidRestArg,
+    DuplicateAlias,
+    ConflictingCodecs,
+    GenericMethod,
+    ReadonlyMutSelf,
+    ReadonlyWithCtx,
+    ReadonlyArcSelf,
+    RefArcSelf,
+    ArcSelfWithMutSelf,
+    ArcSelfWithCtx,
+    InvalidParamsStruct,
+    InvalidLenientParams,
+    InvalidPaginate,
+    InvalidSubscription,
+    SubscriptionWithoutHandler,
+    ProgressWithoutHandler,
+    UnsyncedMethod,
+    InfoNameTaken,
+    RpcErrorNotEnum,
+    MissingErrorCode,
+    InvalidErrorCode,
+    ReservedErrorCode,
 }
 
 // Rustc often reports whole batches of errors at once. We can do the same by returning lists of
@@ -424,11 +3661,111 @@ impl Rejection {
             Reason::TraitNotStrictlyMethods => {
                 "Macro 'jsonrpc_server' expects trait definition containing methods only."
             }
+            Reason::AssociatedConst => {
+                "Associated consts would make the trait dyn incompatible. Move them to a separate \
+                 trait or out of the trait."
+            }
+            Reason::MockAssociatedType => {
+                "'mock' can't implement a trait with associated types, as it can't choose them."
+            }
             Reason::ReservedMethodPrefix => {
                 "The prefix 'rpc.' is reserved https://www.jsonrpc.org/specification#request_object"
             }
+            Reason::InvalidMethodName => {
+                "jsonrpc method names must be non-empty and must not contain control characters."
+            }
+            Reason::ClientAndServerOnly => {
+                "A jsonrpc method can't be both client_only and server_only."
+            }
             Reason::ReferenceArg => "Reference arguments not supported in jsonrpc macro.",
             Reason::MutableArg => "Mutable arguments not supported in jsonrpc macro.",
+            Reason::UnknownAttributeArg => "Unknown option for jsonrpc attribute.",
+            Reason::MalformedAttribute => "Malformed jsonrpc attribute.",
+            Reason::UnknownRenameRule => {
+                "Unknown rename rule, expected one of \"lowercase\", \"UPPERCASE\", \
+                 \"PascalCase\", \"camelCase\", \"snake_case\", \"SCREAMING_SNAKE_CASE\", \
+                 \"kebab-case\" or \"SCREAMING-KEBAB-CASE\"."
+            }
+            Reason::MisplacedCtx => "#[rpc_ctx] may only mark the first argument after self.",
+            Reason::MisplacedProgress => {
+                "#[rpc_progress] may only mark the first argument after self and any #[rpc_ctx]."
+            }
+            Reason::CtxNotSharedRef => "The #[rpc_ctx] argument must be a shared reference.",
+            Reason::MixedCtxTypes => "All #[rpc_ctx] arguments in a trait must have the same type.",
+            Reason::ConflictingCodecs => {
+                "Only one of hex, base64, num_as_str, stringify_keys and with may be specified."
+            }
+            Reason::GenericMethod => {
+                "jsonrpc methods may have lifetime parameters and bounds, but no type or const parameters."
+            }
+            Reason::ReadonlyMutSelf => "#[rpc(readonly)] methods must take &self, not &mut self.",
+            Reason::ReadonlyArcSelf => {
+                "#[rpc(readonly)] methods must take &self, not self: Arc<Self>."
+            }
+            Reason::RefArcSelf => {
+                "A method taking self: &Arc<Self> can't be called through dyn Trait, which \
+                 jsonrpc methods are dispatched through. Take self: Arc<Self> instead."
+            }
+            Reason::ArcSelfWithMutSelf => {
+                "&mut self methods can't be called through the Arc handling a trait with \
+                 self: Arc<Self> methods."
+            }
+            Reason::ArcSelfWithCtx => {
+                "Traits with self: Arc<Self> methods can't have methods taking a #[rpc_ctx]."
+            }
+            Reason::ReadonlyWithCtx => {
+                "#[rpc(readonly)] is not supported on traits whose methods take a #[rpc_ctx]."
+            }
+            Reason::InvalidParamsStruct => {
+                "#[rpc(params_struct)] methods must take exactly one argument, by value and without options."
+            }
+            Reason::InvalidLenientParams => {
+                "#[rpc(lenient_params)] methods must take exactly one argument."
+            }
+            Reason::InvalidPaginate => {
+                "#[rpc(paginate)] methods must return a Vec without a codec, must not be params_struct \
+                 and must not take a rest argument or arguments named cursor or limit."
+            }
+            Reason::InvalidErrorCode => {
+                "#[rpc(error_code)] methods must return Result<T, E>, other than \
+                 Result<Value, Error>, and must not be paginated or return a subscription."
+            }
+            Reason::ReservedErrorCode => {
+                "Error codes from -32768 to -32000 are reserved by the jsonrpc spec. error_base \
+                 plus error_code must fall outside that range."
+            }
+            Reason::InvalidSubscription => {
+                "Methods returning a Subscription must take &self or &mut self, and can't be \
+                 readonly, paginate or have a codec."
+            }
+            Reason::SubscriptionWithoutHandler => {
+                "Traits with methods returning a Subscription can't have self: Arc<Self> methods, or \
+                 methods taking a #[rpc_ctx] unless it holds the common fields."
+            }
+            Reason::ProgressWithoutHandler => {
+                "Methods taking a #[rpc_progress] are dispatched through Handler, which a trait \
+                 doesn't implement when it has self: Arc<Self> methods, or methods taking a \
+                 #[rpc_ctx] unless it holds the common fields."
+            }
+            Reason::UnsyncedMethod => {
+                "With strict_sync, a client_only or server_only method must also be marked \
+                 #[rpc(unsynced)]."
+            }
+            Reason::InfoNameTaken => {
+                "The helper enum holds a MethodInfo const named after each method in upper case. \
+                 This method's would clash with IDEMPOTENT, CACHEABLE or another method's. Rename \
+                 the method, marking it #[rpc(name = \"..\")] to keep its name on the wire."
+            }
+            Reason::RpcErrorNotEnum => "RpcError can only be derived for enums.",
+            Reason::MissingErrorCode => {
+                "Each variant needs an error code, e.g. #[rpc_error(code = -32000)]."
+            }
+            Reason::DuplicateAlias => {
+                "An #[rpc_arg(alias)] must differ from the names and other aliases of the method's arguments."
+            }
+            Reason::InvalidRestArg => {
+                "#[rpc_arg(rest)] may only mark the last argument, which must be a Vec without other options."
+            }
         };
 
         syn::Error::new(self.span, description).to_compile_error()
@@ -443,3 +3780,55 @@ impl From<Rejection> for Rejections {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use syn::parse_quote;
+
+    #[test]
+    fn client_fn_docs() {
+        let method: TraitItemFn = parse_quote! {
+            /// Adds two numbers.
+            #[rpc(name = "sum")]
+            fn add(&self, a: u8, b: u8) -> u8;
+        };
+        let client_fn: syn::ItemFn =
+            syn::parse2(raise_if_err(impl_client_method(&method))).unwrap();
+        let docs: Vec<String> = client_fn
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("doc"))
+            .map(|attr| attr.to_token_stream().to_string())
+            .collect();
+        assert_eq!(docs[0], quote!(#[doc = r" Adds two numbers."]).to_string());
+        assert_eq!(docs[1], quote!(#[doc = ""]).to_string());
+        assert!(docs[2].contains("Request generator for jsonrpc clients."));
+    }
+
+    #[test]
+    fn strip_keeps_other_attrs() {
+        let mut tr: ItemTrait = parse_quote! {
+            /// An api.
+            pub trait Api {
+                /// Adds two numbers.
+                #[rpc(name = "sum")]
+                #[allow(clippy::too_many_arguments)]
+                fn add(&self, #[rpc_arg(hex)] #[allow(unused)] a: Vec<u8>) -> u8;
+            }
+        };
+        strip_rpc_attrs(&mut tr);
+        let expected: ItemTrait = parse_quote! {
+            /// An api.
+            pub trait Api {
+                /// Adds two numbers.
+                #[allow(clippy::too_many_arguments)]
+                fn add(&self, #[allow(unused)] a: Vec<u8>) -> u8;
+            }
+        };
+        assert_eq!(
+            tr.to_token_stream().to_string(),
+            expected.to_token_stream().to_string()
+        );
+    }
+}
@@ -0,0 +1,82 @@
+use crate::{CallKind, Error, Handler, Params, RequestMeta, Value};
+use std::collections::HashSet;
+
+/// Wraps a handler, dispatching only the listed methods, e.g. to give each api key access to a
+/// different subset of an api. Calls to any other method get a method not found error without
+/// reaching the inner handler.
+///
+/// ```
+/// use easy_jsonrpc::{AllowList, DynHandler, Handler};
+/// use serde_json::json;
+///
+/// let mut inner = DynHandler::new();
+/// inner.register("read", |_| Ok(json!("data")));
+/// inner.register("write", |_| Ok(json!(null)));
+///
+/// let mut handler = AllowList::new(inner, &["read"]);
+/// let response = handler.handle_request(json!({"jsonrpc": "2.0", "method": "write", "id": 1}));
+/// assert_eq!(response.as_option().unwrap()["error"]["code"], json!(-32601));
+/// ```
+pub struct AllowList<H> {
+    handler: H,
+    allowed: HashSet<String>,
+}
+
+impl<H: Handler> AllowList<H> {
+    /// Wrap handler, allowing only methods.
+    pub fn new(handler: H, methods: &[&str]) -> Self {
+        AllowList {
+            handler,
+            allowed: methods.iter().map(|method| (*method).to_owned()).collect(),
+        }
+    }
+
+    /// Whether method is on the list.
+    pub fn is_allowed(&self, method: &str) -> bool {
+        self.allowed.contains(method)
+    }
+
+    /// Unwrap the inner handler.
+    pub fn into_inner(self) -> H {
+        self.handler
+    }
+}
+
+impl<H: Handler> Handler for AllowList<H> {
+    fn handle(&mut self, method: &str, params: Params) -> Result<Value, Error> {
+        self.handle_with_meta(method, params, CallKind::MethodCall)
+    }
+
+    fn handle_with_meta(
+        &mut self,
+        method: &str,
+        params: Params,
+        kind: CallKind,
+    ) -> Result<Value, Error> {
+        self.handle_with_request_meta(method, params, &RequestMeta::new(kind))
+    }
+
+    fn handle_with_request_meta(
+        &mut self,
+        method: &str,
+        params: Params,
+        meta: &RequestMeta,
+    ) -> Result<Value, Error> {
+        if !self.is_allowed(method) {
+            return Err(Error::method_not_found());
+        }
+        self.handler.handle_with_request_meta(method, params, meta)
+    }
+
+    fn method_exists(&self, method: &str) -> bool {
+        self.is_allowed(method) && self.handler.method_exists(method)
+    }
+
+    fn accepts_bare_param(&self, method: &str) -> bool {
+        self.handler.accepts_bare_param(method)
+    }
+
+    fn strict_version(&self) -> bool {
+        self.handler.strict_version()
+    }
+}
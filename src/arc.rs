@@ -0,0 +1,67 @@
+use crate::{Error, Handler, Params, Value};
+use std::sync::Arc;
+
+/// Dispatches the methods of a trait with `self: Arc<Self>` methods, through an `Arc`.
+/// Implemented by the [rpc](../easy_jsonrpc_proc_macro/attr.rpc.html) macro for `dyn MyApi` when
+/// a method of `MyApi` takes `self: Arc<Self>`, making `Arc<dyn MyApi>` a
+/// [Handler](trait.Handler.html).
+///
+/// ```
+/// use easy_jsonrpc::{rpc, Handler};
+/// use serde_json::json;
+/// use std::sync::Arc;
+///
+/// #[rpc]
+/// pub trait Actor {
+///     fn start(self: Arc<Self>) -> bool;
+/// }
+///
+/// struct ActorImpl;
+/// impl Actor for ActorImpl {
+///     fn start(self: Arc<Self>) -> bool {
+///         // typically moved into a spawned task
+///         std::thread::spawn(move || drop(self));
+///         true
+///     }
+/// }
+///
+/// let mut handler: Arc<dyn Actor> = Arc::new(ActorImpl);
+/// let response = handler.handle_request(json!({"jsonrpc": "2.0", "method": "start", "id": 1}));
+/// assert_eq!(response.as_option().unwrap()["result"], json!(true));
+/// ```
+pub trait HandleArc {
+    /// Whether requests must declare `"jsonrpc": "2.0"`, see
+    /// [Handler::handle_request_strict](trait.Handler.html#method.handle_request_strict).
+    const STRICT_VERSION: bool = false;
+
+    /// Like [Handler::handle](trait.Handler.html#tymethod.handle), given the Arc holding the
+    /// handler, which `self: Arc<Self>` methods are called with a clone of.
+    fn handle_arc(this: &Arc<Self>, method: &str, params: Params) -> Result<Value, Error>;
+
+    /// Like [Handler::method_exists](trait.Handler.html#method.method_exists).
+    fn method_exists(&self, method: &str) -> bool;
+
+    /// Like [Handler::accepts_bare_param](trait.Handler.html#method.accepts_bare_param).
+    fn accepts_bare_param(&self, method: &str) -> bool {
+        let _ = method;
+        false
+    }
+}
+
+impl<T: HandleArc + ?Sized> Handler for Arc<T> {
+    fn handle(&mut self, method: &str, params: Params) -> Result<Value, Error> {
+        T::handle_arc(self, method, params)
+    }
+
+    fn method_exists(&self, method: &str) -> bool {
+        HandleArc::method_exists(&**self, method)
+    }
+
+    fn accepts_bare_param(&self, method: &str) -> bool {
+        HandleArc::accepts_bare_param(&**self, method)
+    }
+
+    fn strict_version(&self) -> bool {
+        T::STRICT_VERSION
+    }
+}
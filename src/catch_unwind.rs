@@ -0,0 +1,88 @@
+use crate::{CallKind, Error, Handler, Params, RequestMeta, Value};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// Wraps a handler, turning panics in its methods into internal errors so a single buggy method
+/// can't take down the task serving requests.
+///
+/// The panic message is still reported by the panic hook, stderr by default. The client only
+/// receives a generic message.
+///
+/// Handlers are not required to be `UnwindSafe`. If a method panics while the handler is in an
+/// inconsistent state, for example halfway through updating two fields, later calls will observe
+/// that state. Handlers that guard their state with a `Mutex` see it poisoned instead.
+///
+/// ```
+/// use easy_jsonrpc::{CatchUnwind, DynHandler, Handler};
+/// use serde_json::json;
+///
+/// let mut inner = DynHandler::new();
+/// inner.register("oops", |_| panic!("bug"));
+///
+/// let mut handler = CatchUnwind::new(inner);
+/// let response = handler.handle_request(json!({
+///     "jsonrpc": "2.0",
+///     "method": "oops",
+///     "id": 1
+/// }));
+/// assert_eq!(response.as_option().unwrap()["error"]["code"], json!(-32603));
+/// ```
+pub struct CatchUnwind<H> {
+    handler: H,
+}
+
+impl<H: Handler> CatchUnwind<H> {
+    /// Wrap handler.
+    pub fn new(handler: H) -> Self {
+        CatchUnwind { handler }
+    }
+
+    /// Unwrap the inner handler.
+    pub fn into_inner(self) -> H {
+        self.handler
+    }
+}
+
+impl<H: Handler> Handler for CatchUnwind<H> {
+    fn handle(&mut self, method: &str, params: Params) -> Result<Value, Error> {
+        self.handle_with_meta(method, params, CallKind::MethodCall)
+    }
+
+    fn handle_with_meta(
+        &mut self,
+        method: &str,
+        params: Params,
+        kind: CallKind,
+    ) -> Result<Value, Error> {
+        self.handle_with_request_meta(method, params, &RequestMeta::new(kind))
+    }
+
+    fn handle_with_request_meta(
+        &mut self,
+        method: &str,
+        params: Params,
+        meta: &RequestMeta,
+    ) -> Result<Value, Error> {
+        let handler = &mut self.handler;
+        catch_unwind(AssertUnwindSafe(|| {
+            handler.handle_with_request_meta(method, params, meta)
+        }))
+        .unwrap_or_else(|_| {
+            Err(Error {
+                message: "Internal error: method panicked".into(),
+                ..Error::internal_error()
+            })
+        })
+    }
+
+    fn method_exists(&self, method: &str) -> bool {
+        self.handler.method_exists(method)
+    }
+
+    fn accepts_bare_param(&self, method: &str) -> bool {
+        self.handler.accepts_bare_param(method)
+    }
+
+    fn strict_version(&self) -> bool {
+        self.handler.strict_version()
+    }
+}
@@ -0,0 +1,1202 @@
+//! Send the calls produced by the generated client helpers over an async transport.
+//!
+//! ```
+//! use easy_jsonrpc::client::{Client, Transport, TransportError};
+//! use easy_jsonrpc::{rpc, Handler, Value};
+//!
+//! #[rpc]
+//! pub trait Adder {
+//!     fn add(&self, a: i32, b: i32) -> i32;
+//! }
+//!
+//! struct AdderImpl;
+//! impl Adder for AdderImpl {
+//!     fn add(&self, a: i32, b: i32) -> i32 {
+//!         a + b
+//!     }
+//! }
+//!
+//! // Serves requests in process. A real transport would send them over the network.
+//! struct Local;
+//! impl Transport for Local {
+//!     async fn send(&self, request: Value) -> Result<Value, TransportError> {
+//!         let mut handler = &AdderImpl as &dyn Adder;
+//!         Ok(handler.handle_request(request).as_option().unwrap_or(Value::Null))
+//!     }
+//! }
+//!
+//! # let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+//! # runtime.block_on(async {
+//! let client = Client::new(Local);
+//! assert_eq!(client.call(adder::add(1, 2).unwrap()).await.unwrap(), 3);
+//! # });
+//! ```
+
+use crate::{
+    ArgSerializeError, BoundMethod, DuplicateIds, InvalidResponse, Pages, RequestId, Response,
+    ResponseFail, Value,
+};
+use serde::de::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, Weak};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Sends a json encoded jsonrpc request and returns the server's response.
+pub trait Transport {
+    /// Send request and wait for the response.
+    fn send(&self, request: Value) -> impl Future<Output = Result<Value, TransportError>> + Send;
+}
+
+/// Failure to deliver a request or to receive its response.
+#[derive(Debug)]
+pub struct TransportError(pub Box<dyn std::error::Error + Send + Sync>);
+
+impl<E: std::error::Error + Send + Sync + 'static> From<E> for TransportError {
+    fn from(err: E) -> TransportError {
+        TransportError(Box::new(err))
+    }
+}
+
+impl std::fmt::Display for TransportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "transport error: {}", self.0)
+    }
+}
+
+/// Returned by [Client::call](struct.Client.html#method.call) on error.
+#[derive(Debug)]
+pub enum CallError {
+    /// The request could not be sent, or no response was received.
+    Transport(TransportError),
+    /// The server responded, but did not return a value for the call. Contains the rpc error if
+    /// the server returned one.
+    Response(ResponseFail),
+    /// The arguments of the call could not be serialized, so it was not sent.
+    Args(ArgSerializeError),
+}
+
+impl std::fmt::Display for CallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CallError::Transport(err) => err.fmt(f),
+            CallError::Response(fail) => fail.fmt(f),
+            CallError::Args(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for CallError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CallError::Transport(err) => Some(&*err.0),
+            CallError::Response(fail) => Some(fail),
+            CallError::Args(err) => Some(err),
+        }
+    }
+}
+
+/// When to resend a call whose request failed to reach the server, see
+/// [Client::retry](struct.Client.html#method.retry). Only transport errors are retried, a call the
+/// server answered with an error is not. Only the listed methods are retried, since a call whose
+/// response got lost may have been handled already.
+///
+/// ```
+/// use easy_jsonrpc::client::RetryPolicy;
+/// use std::time::Duration;
+///
+/// #[easy_jsonrpc::rpc]
+/// pub trait Store {
+///     #[rpc(idempotent)]
+///     fn get(&self, key: String) -> Option<String>;
+///     fn append(&mut self, key: String, value: String);
+/// }
+///
+/// let policy = RetryPolicy::new(store::IDEMPOTENT)
+///     .max_retries(5)
+///     .backoff(Duration::from_millis(50));
+/// assert!(policy.retries("get"));
+/// assert!(!policy.retries("append"));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    methods: HashSet<String>,
+    max_retries: u32,
+    backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Retry calls to methods at most 3 times, waiting 100ms before the first retry.
+    pub fn new(methods: &[&str]) -> Self {
+        RetryPolicy {
+            methods: methods.iter().map(|method| (*method).to_owned()).collect(),
+            max_retries: 3,
+            backoff: Duration::from_millis(100),
+        }
+    }
+
+    /// Retry a call at most `retries` times.
+    pub fn max_retries(self, retries: u32) -> Self {
+        RetryPolicy {
+            max_retries: retries,
+            ..self
+        }
+    }
+
+    /// Wait `delay` before the first retry, doubling the wait before each one after.
+    ///
+    /// The client doesn't depend on an async runtime, so each wait is timed by a thread spawned
+    /// for it. Many calls retrying at once hold as many threads, each for the length of its wait.
+    pub fn backoff(self, delay: Duration) -> Self {
+        RetryPolicy {
+            backoff: delay,
+            ..self
+        }
+    }
+
+    /// Whether calls to method are retried.
+    pub fn retries(&self, method: &str) -> bool {
+        self.methods.contains(method)
+    }
+
+    // how long to wait before retry number attempt, counting from 0
+    fn delay(&self, attempt: u32) -> Duration {
+        self.backoff.saturating_mul(2u32.saturating_pow(attempt))
+    }
+}
+
+/// Produces the ids of the calls made by a [Client](struct.Client.html). Responses are matched to
+/// calls by id, so ids must not repeat while calls sharing a connection are in flight.
+pub trait IdGenerator: Send + Sync {
+    /// The id for the next call.
+    fn next_id(&self) -> RequestId;
+}
+
+/// Sequential integer ids, starting at 0. Used by clients unless another generator is set.
+#[derive(Debug, Default)]
+pub struct SequentialIds(AtomicU64);
+
+impl IdGenerator for SequentialIds {
+    fn next_id(&self) -> RequestId {
+        self.0.fetch_add(1, Ordering::Relaxed).into()
+    }
+}
+
+/// Random version 4 uuids, as strings, e.g. for clients which share a connection without sharing
+/// a counter. Requires the `uuid` feature.
+#[cfg(feature = "uuid")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UuidIds;
+
+#[cfg(feature = "uuid")]
+impl IdGenerator for UuidIds {
+    fn next_id(&self) -> RequestId {
+        uuid::Uuid::new_v4().to_string().into()
+    }
+}
+
+/// Makes calls using a [Transport](trait.Transport.html). Calls are given sequential ids unless
+/// another [IdGenerator](trait.IdGenerator.html) is set.
+pub struct Client<T> {
+    transport: T,
+    ids: Box<dyn IdGenerator>,
+    retry: Option<RetryPolicy>,
+}
+
+impl<T: Transport> Client<T> {
+    /// Create a client sending requests through transport.
+    pub fn new(transport: T) -> Self {
+        Client {
+            transport,
+            ids: Box::new(SequentialIds::default()),
+            retry: None,
+        }
+    }
+
+    /// Draw the ids of calls from ids.
+    pub fn ids(self, ids: impl IdGenerator + 'static) -> Self {
+        Client {
+            ids: Box::new(ids),
+            ..self
+        }
+    }
+
+    /// Resend calls which fail to reach the server, as allowed by policy. Without a policy, calls
+    /// are sent once.
+    pub fn retry(self, policy: RetryPolicy) -> Self {
+        Client {
+            retry: Some(policy),
+            ..self
+        }
+    }
+
+    /// Unwrap the transport.
+    pub fn into_inner(self) -> T {
+        self.transport
+    }
+
+    /// Send a call to the server and parse the return value.
+    pub async fn call<R>(&self, method: BoundMethod<'_, R>) -> Result<R, CallError>
+    where
+        R: Deserialize<'static>,
+    {
+        self.send_call(method, None).await
+    }
+
+    /// Like [call](#method.call), also telling the server the time after which the result is no
+    /// longer wanted. The deadline is sent in the call's `meta` member, an extension of the
+    /// jsonrpc spec which servers using this crate read into
+    /// [RequestMeta::deadline](../struct.RequestMeta.html#method.deadline). Other servers may
+    /// reject the call as invalid.
+    pub async fn call_with_deadline<R>(
+        &self,
+        method: BoundMethod<'_, R>,
+        deadline: SystemTime,
+    ) -> Result<R, CallError>
+    where
+        R: Deserialize<'static>,
+    {
+        self.send_call(method, Some(deadline)).await
+    }
+
+    async fn send_call<R>(
+        &self,
+        method: BoundMethod<'_, R>,
+        deadline: Option<SystemTime>,
+    ) -> Result<R, CallError>
+    where
+        R: Deserialize<'static>,
+    {
+        let id = self.ids.next_id();
+        let retry = self
+            .retry
+            .as_ref()
+            .filter(|policy| policy.retries(method.method_name()));
+        let method = method.with_id(id);
+        let (call, tracker) = method.call();
+        let mut request = call.as_request();
+        if let Some(deadline) = deadline {
+            request["meta"] = crate::deadline_meta(deadline);
+        }
+        let mut attempt = 0;
+        let raw_response = loop {
+            match self.transport.send(request.clone()).await {
+                Ok(response) => break response,
+                Err(err) => match retry {
+                    Some(policy) if attempt < policy.max_retries => {
+                        sleep(policy.delay(attempt)).await;
+                        attempt += 1;
+                    }
+                    _ => return Err(CallError::Transport(err)),
+                },
+            }
+        };
+        let mut response = Response::from_json_response(raw_response)
+            .map_err(|_| CallError::Response(ResponseFail::InvalidResponse))?;
+        tracker
+            .get_return(&mut response)
+            .map_err(CallError::Response)
+    }
+
+    /// Collect the whole result of a method marked `#[rpc(paginate)]`, calling it once per page
+    /// and following each page's cursor to the next.
+    pub async fn call_pages<I>(&self, pages: Pages<I>) -> Result<Vec<I>, CallError>
+    where
+        I: Deserialize<'static>,
+    {
+        let mut items = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = self.call(pages.page(cursor)).await?;
+            items.extend(page.items);
+            match page.next_cursor {
+                None => return Ok(items),
+                // a cursor which doesn't move forward would never reach the last page
+                Some(next) if cursor.is_some_and(|cursor| next <= cursor) => {
+                    return Err(CallError::Response(ResponseFail::InvalidResponse))
+                }
+                next => cursor = next,
+            }
+        }
+    }
+}
+
+/// Wraps a [Client](struct.Client.html), reusing the results of calls to the listed methods, e.g.
+/// the helper module's `CACHEABLE` list of methods marked `#[rpc(cacheable)]`. A call with the same
+/// method and params as an earlier one is answered from the cache, without reaching the
+/// transport, until the earlier result is older than the time to live. Errors aren't cached.
+///
+/// ```
+/// use easy_jsonrpc::client::{CachingClient, Client, Transport, TransportError};
+/// use easy_jsonrpc::{rpc, Handler, Value};
+/// use std::time::Duration;
+///
+/// #[rpc]
+/// pub trait Prices {
+///     #[rpc(cacheable)]
+///     fn price(&self, item: String) -> u64;
+/// }
+///
+/// struct PricesImpl;
+/// impl Prices for PricesImpl {
+///     fn price(&self, _item: String) -> u64 {
+///         42
+///     }
+/// }
+///
+/// struct Local;
+/// impl Transport for Local {
+///     async fn send(&self, request: Value) -> Result<Value, TransportError> {
+///         let mut handler = &PricesImpl as &dyn Prices;
+///         Ok(handler.handle_request(request).as_option().unwrap_or(Value::Null))
+///     }
+/// }
+///
+/// # let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// # runtime.block_on(async {
+/// let client = CachingClient::new(Client::new(Local), prices::CACHEABLE, Duration::from_secs(60));
+/// let first = client.call(prices::price("apple".into()).unwrap()).await.unwrap();
+/// // answered from the cache
+/// let second = client.call(prices::price("apple".into()).unwrap()).await.unwrap();
+/// assert_eq!(first, second);
+/// # });
+/// ```
+pub struct CachingClient<T> {
+    client: Client<T>,
+    methods: HashSet<String>,
+    ttl: Duration,
+    // results by method and serialized params, with the time they were received
+    cache: Mutex<HashMap<(String, String), (Instant, Value)>>,
+}
+
+impl<T: Transport> CachingClient<T> {
+    /// Cache the results of calls to methods made through client for ttl. Expired results are
+    /// dropped whenever another result is cached, so the cache holds at most the results received
+    /// within the last ttl.
+    pub fn new(client: Client<T>, methods: &[&str], ttl: Duration) -> Self {
+        CachingClient {
+            client,
+            methods: methods.iter().map(|method| (*method).to_owned()).collect(),
+            ttl,
+            cache: Mutex::default(),
+        }
+    }
+
+    /// Whether results of calls to method are cached.
+    pub fn caches(&self, method: &str) -> bool {
+        self.methods.contains(method)
+    }
+
+    /// Forget every cached result.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Unwrap the client.
+    pub fn into_inner(self) -> Client<T> {
+        self.client
+    }
+
+    /// Send a call to the server and parse the return value, or parse the cached return value of
+    /// an earlier call with the same method and params.
+    pub async fn call<R>(&self, method: BoundMethod<'_, R>) -> Result<R, CallError>
+    where
+        R: Deserialize<'static>,
+    {
+        if !self.caches(method.method_name()) {
+            return self.client.call(method).await;
+        }
+        let key = (method.method_name().to_owned(), method.params().to_string());
+        let decode = method.decode;
+        let decode =
+            |value| decode(value).map_err(|_| CallError::Response(ResponseFail::InvalidResponse));
+        if let Some(value) = self.cached(&key) {
+            return decode(value);
+        }
+        let value = self
+            .client
+            .call(BoundMethod::<Value>::bind(method.method, method.params))
+            .await?;
+        let mut cache = self.lock();
+        // expired entries are only replaced when read, so drop those never read again here
+        cache.retain(|_, (received, _)| received.elapsed() < self.ttl);
+        cache.insert(key, (Instant::now(), value.clone()));
+        drop(cache);
+        decode(value)
+    }
+
+    // the cached result for key, if it's fresh
+    fn cached(&self, key: &(String, String)) -> Option<Value> {
+        let mut cache = self.lock();
+        match cache.get(key) {
+            Some((received, value)) if received.elapsed() < self.ttl => Some(value.clone()),
+            Some(_) => {
+                cache.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    // The cache only holds complete entries, so it is valid even if a thread panicked holding it.
+    fn lock(&self) -> MutexGuard<'_, HashMap<(String, String), (Instant, Value)>> {
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Correlates the calls and responses of one connection, for clients which send requests and
+/// receive responses separately, e.g. over a websocket, rather than through a
+/// [Transport](trait.Transport.html). Calls are given sequential ids, and responses, which may
+/// arrive in any order or batched, are routed to the call with the same id.
+///
+/// ```
+/// use easy_jsonrpc::client::ClientSession;
+/// use easy_jsonrpc::{rpc, Handler};
+///
+/// #[rpc]
+/// pub trait Adder {
+///     fn add(&self, a: i32, b: i32) -> i32;
+/// }
+///
+/// struct AdderImpl;
+/// impl Adder for AdderImpl {
+///     fn add(&self, a: i32, b: i32) -> i32 {
+///         a + b
+///     }
+/// }
+///
+/// let session = ClientSession::new();
+/// let (request, sum) = session.request(adder::add(1, 2).unwrap());
+/// // A real client would write the request to its connection, and pass each response it reads
+/// // to the session.
+/// let response = (&AdderImpl as &dyn Adder).handle_request(request);
+/// session.receive(response.as_option().unwrap()).unwrap();
+/// # let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// # runtime.block_on(async {
+/// assert_eq!(sum.await.unwrap(), 3);
+/// # });
+/// ```
+pub struct ClientSession {
+    ids: SequentialIds,
+    in_flight: Arc<Mutex<HashMap<RequestId, Arc<Mutex<Slot>>>>>,
+}
+
+// where a response is left for the call awaiting it
+#[derive(Default)]
+struct Slot {
+    result: Option<Result<Value, CallError>>,
+    waker: Option<Waker>,
+}
+
+impl Slot {
+    fn fill(&mut self, result: Result<Value, CallError>) {
+        self.result = Some(result);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl ClientSession {
+    /// Create a session without calls in flight.
+    pub fn new() -> Self {
+        ClientSession {
+            ids: SequentialIds::default(),
+            in_flight: Arc::default(),
+        }
+    }
+
+    /// Give the call the next id and return the request to send, along with the future
+    /// resolving to the call's result once its response is [received](#method.receive). Dropping
+    /// the future forgets the call.
+    pub fn request<R>(&self, method: BoundMethod<'_, R>) -> (Value, PendingCall<R>)
+    where
+        R: Deserialize<'static>,
+    {
+        let id = self.ids.next_id();
+        let method = method.with_id(id.clone());
+        let request = method.call().0.as_request();
+        let slot = Arc::new(Mutex::new(Slot::default()));
+        lock(&self.in_flight).insert(id.clone(), slot.clone());
+        let pending = PendingCall {
+            id,
+            slot,
+            in_flight: Arc::downgrade(&self.in_flight),
+            decode: method.decode,
+        };
+        (request, pending)
+    }
+
+    /// Pass a response read from the connection, single or batched, to the calls awaiting it.
+    /// Returns the number of calls answered. Outputs whose id belongs to no call in flight are
+    /// ignored.
+    ///
+    /// The session gives every call its own id, so several outputs with one id can't be told
+    /// apart. The call with that id fails with
+    /// [ResponseFail::InvalidResponse](../enum.ResponseFail.html#variant.InvalidResponse), the
+    /// other outputs are still passed on, and the first such id is returned as
+    /// [InvalidResponse::DuplicateId](../enum.InvalidResponse.html#variant.DuplicateId).
+    pub fn receive(&self, response: Value) -> Result<usize, InvalidResponse> {
+        let response = Response::from_json_response_with(response, DuplicateIds::Positional)?;
+        let mut in_flight = lock(&self.in_flight);
+        let mut answered = 0;
+        let mut duplicate = None;
+        for (id, mut outputs) in response.outputs {
+            let output = match (outputs.len(), outputs.pop_front()) {
+                (1, Some(output)) => {
+                    output.map_err(|err| CallError::Response(ResponseFail::RpcError(err)))
+                }
+                _ => {
+                    duplicate = duplicate.or_else(|| Some(id.clone()));
+                    Err(CallError::Response(ResponseFail::InvalidResponse))
+                }
+            };
+            if let Some(slot) = in_flight.remove(&id) {
+                lock(&slot).fill(output);
+                answered += 1;
+            }
+        }
+        match duplicate {
+            Some(id) => Err(InvalidResponse::DuplicateId(id)),
+            None => Ok(answered),
+        }
+    }
+
+    /// The number of calls awaiting their response.
+    pub fn in_flight(&self) -> usize {
+        lock(&self.in_flight).len()
+    }
+}
+
+impl Default for ClientSession {
+    fn default() -> Self {
+        ClientSession::new()
+    }
+}
+
+impl Drop for ClientSession {
+    // the connection is gone, so calls still in flight will never be answered
+    fn drop(&mut self) {
+        for (_, slot) in lock(&self.in_flight).drain() {
+            let closed =
+                std::io::Error::new(std::io::ErrorKind::ConnectionAborted, "session closed");
+            lock(&slot).fill(Err(CallError::Transport(closed.into())));
+        }
+    }
+}
+
+/// The result of a call made through a [ClientSession](struct.ClientSession.html), available
+/// once the session receives its response.
+pub struct PendingCall<R> {
+    id: RequestId,
+    slot: Arc<Mutex<Slot>>,
+    in_flight: Weak<Mutex<HashMap<RequestId, Arc<Mutex<Slot>>>>>,
+    decode: fn(Value) -> Result<R, serde_json::Error>,
+}
+
+impl<R> PendingCall<R> {
+    /// The id of the call.
+    pub fn id(&self) -> &RequestId {
+        &self.id
+    }
+}
+
+impl<R> Future for PendingCall<R> {
+    type Output = Result<R, CallError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut slot = lock(&self.slot);
+        match slot.result.take() {
+            Some(result) => Poll::Ready(result.and_then(|value| {
+                (self.decode)(value).map_err(|_| CallError::Response(ResponseFail::InvalidResponse))
+            })),
+            None => {
+                slot.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<R> Drop for PendingCall<R> {
+    fn drop(&mut self) {
+        if let Some(in_flight) = self.in_flight.upgrade() {
+            lock(&in_flight).remove(&self.id);
+        }
+    }
+}
+
+// Slots and the map of calls in flight only hold complete entries, so they are valid even if a
+// thread panicked holding them.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+// Waits for duration without depending on an async runtime, by sleeping on a separate thread.
+// That costs a thread spawn per wait, and the thread for as long as the wait, which is acceptable
+// only because it's used for retry backoff, once per failed attempt. A zero duration spawns none.
+async fn sleep(duration: Duration) {
+    if !duration.is_zero() {
+        Sleep {
+            duration,
+            shared: None,
+        }
+        .await
+    }
+}
+
+struct Sleep {
+    duration: Duration,
+    // whether the duration has passed, and the waker to call once it has
+    shared: Option<Arc<Mutex<(bool, Waker)>>>,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if let Some(shared) = &self.shared {
+            let mut shared = shared.lock().unwrap();
+            if shared.0 {
+                return Poll::Ready(());
+            }
+            shared.1 = cx.waker().clone();
+            return Poll::Pending;
+        }
+        let shared = Arc::new(Mutex::new((false, cx.waker().clone())));
+        let timer = shared.clone();
+        let duration = self.duration;
+        std::thread::spawn(move || {
+            std::thread::sleep(duration);
+            let mut timer = timer.lock().unwrap();
+            timer.0 = true;
+            timer.1.wake_by_ref();
+        });
+        self.shared = Some(shared);
+        Poll::Pending
+    }
+}
+
+/// Sends requests as http POST requests using reqwest. Requires the `reqwest` feature.
+///
+/// reqwest is used without its default features, so enable one of its tls features to reach
+/// `https` urls.
+#[cfg(feature = "reqwest")]
+pub struct HttpTransport {
+    client: reqwest::Client,
+    url: String,
+}
+
+#[cfg(feature = "reqwest")]
+impl HttpTransport {
+    /// Post requests to url.
+    pub fn new(url: impl Into<String>) -> Self {
+        HttpTransport::with_client(reqwest::Client::new(), url)
+    }
+
+    /// Post requests to url using an existing reqwest client.
+    pub fn with_client(client: reqwest::Client, url: impl Into<String>) -> Self {
+        HttpTransport {
+            client,
+            url: url.into(),
+        }
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl Transport for HttpTransport {
+    async fn send(&self, request: Value) -> Result<Value, TransportError> {
+        let body = self
+            .client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(request.to_string())
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        CachingClient, CallError, Client, IdGenerator, RetryPolicy, Transport, TransportError,
+    };
+    use crate::{Handler, InvalidResponse, ResponseFail, Value};
+    use jsonrpc_core::ErrorCode;
+    use serde_json::json;
+
+    mod easy_jsonrpc {
+        pub use crate::*;
+    }
+
+    #[easy_jsonrpc::rpc(async_client)]
+    pub trait Calculator {
+        #[rpc(idempotent)]
+        fn div(&self, a: u32, b: u32) -> Option<u32>;
+        #[rpc(cacheable)]
+        fn sub(&self, a: u32, b: u32) -> Option<u32>;
+        #[rpc(paginate)]
+        fn range(&self, n: u32) -> Vec<u32>;
+    }
+
+    struct CalculatorImpl;
+    impl Calculator for CalculatorImpl {
+        fn div(&self, a: u32, b: u32) -> Option<u32> {
+            a.checked_div(b)
+        }
+
+        fn sub(&self, a: u32, b: u32) -> Option<u32> {
+            a.checked_sub(b)
+        }
+
+        fn range(&self, n: u32) -> Vec<u32> {
+            (0..n).collect()
+        }
+    }
+
+    fn run<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    struct Local;
+    impl Transport for Local {
+        async fn send(&self, request: Value) -> Result<Value, TransportError> {
+            // ids are assigned sequentially
+            assert!(request["id"].is_u64());
+            let mut handler = &CalculatorImpl as &dyn Calculator;
+            Ok(handler
+                .handle_request(request)
+                .as_option()
+                .unwrap_or(Value::Null))
+        }
+    }
+
+    struct Broken;
+    impl Transport for Broken {
+        async fn send(&self, _: Value) -> Result<Value, TransportError> {
+            Err(std::io::Error::other("connection refused").into())
+        }
+    }
+
+    #[test]
+    fn call() {
+        run(async {
+            let client = Client::new(Local);
+            assert_eq!(
+                client.call(calculator::div(6, 3).unwrap()).await.unwrap(),
+                Some(2)
+            );
+            assert_eq!(
+                client.call(calculator::div(6, 0).unwrap()).await.unwrap(),
+                None
+            );
+
+            let bad = easy_jsonrpc::BoundMethod::<u32>::new("div", vec![json!(1)]);
+            match client.call(bad).await {
+                Err(CallError::Response(ResponseFail::RpcError(err))) => {
+                    assert_eq!(err.code, ErrorCode::InvalidParams)
+                }
+                other => panic!("{:?}", other),
+            }
+
+            let client = Client::new(Broken);
+            assert!(matches!(
+                client.call(calculator::div(6, 3).unwrap()).await,
+                Err(CallError::Transport(_))
+            ));
+        });
+    }
+
+    #[test]
+    fn call_with_deadline() {
+        use crate::{Error, Params, RequestMeta};
+        use std::time::{Duration, UNIX_EPOCH};
+
+        // answers with the deadline it was given, in milliseconds
+        struct Deadline;
+        impl Handler for Deadline {
+            fn handle(&mut self, _method: &str, _params: Params) -> Result<Value, Error> {
+                Ok(Value::Null)
+            }
+
+            fn handle_with_request_meta(
+                &mut self,
+                _method: &str,
+                _params: Params,
+                meta: &RequestMeta,
+            ) -> Result<Value, Error> {
+                Ok(json!(meta.deadline().map(|deadline| deadline
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap()
+                    .as_millis()
+                    as u64)))
+            }
+        }
+
+        struct ToDeadline;
+        impl Transport for ToDeadline {
+            async fn send(&self, request: Value) -> Result<Value, TransportError> {
+                Ok(Deadline
+                    .handle_request(request)
+                    .as_option()
+                    .unwrap_or(Value::Null))
+            }
+        }
+
+        run(async {
+            let client = Client::new(ToDeadline);
+            let deadline = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+            let method = || crate::BoundMethod::<Option<u64>>::new("deadline", vec![]);
+            assert_eq!(
+                client.call_with_deadline(method(), deadline).await.unwrap(),
+                Some(1_700_000_000_123)
+            );
+            assert_eq!(client.call(method()).await.unwrap(), None);
+        });
+    }
+
+    #[test]
+    fn call_pages() {
+        run(async {
+            let client = Client::new(Local);
+            let page = client
+                .call(calculator::range(25, Some(20), Some(10)).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(page.items, (20..25).collect::<Vec<_>>());
+            assert_eq!(page.next_cursor, None);
+
+            let all = client
+                .call_pages(calculator::range_pages(25, Some(10)).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(all, (0..25).collect::<Vec<_>>());
+        });
+    }
+
+    // fails the first request only
+    struct Flaky(std::sync::atomic::AtomicUsize);
+    impl Transport for Flaky {
+        async fn send(&self, request: Value) -> Result<Value, TransportError> {
+            if self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+                return Err(std::io::Error::other("connection reset").into());
+            }
+            Local.send(request).await
+        }
+    }
+
+    #[test]
+    fn async_client() {
+        fn assert_send<T: Send>(value: T) -> T {
+            value
+        }
+
+        run(async {
+            let client = Client::new(Local);
+            assert_eq!(assert_send(client.div(6, 3)).await.unwrap(), Some(2));
+            assert_eq!(client.sub(1, 2).await.unwrap(), None);
+            // every page is fetched
+            assert_eq!(client.range(5, Some(2)).await.unwrap(), vec![0, 1, 2, 3, 4]);
+
+            let client = Client::new(Broken);
+            assert!(matches!(
+                client.div(6, 3).await,
+                Err(CallError::Transport(_))
+            ));
+        });
+
+        pub struct Unserializable;
+        impl serde::Serialize for Unserializable {
+            fn serialize<S: serde::Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+                Err(serde::ser::Error::custom("no"))
+            }
+        }
+
+        #[easy_jsonrpc::rpc(async_client)]
+        pub trait Sink {
+            #[rpc(client_only)]
+            fn sink(&self, a: Unserializable);
+        }
+
+        // the call isn't sent
+        run(async {
+            let client = Client::new(Broken);
+            assert!(matches!(
+                client.sink(Unserializable).await,
+                Err(CallError::Args(_))
+            ));
+        });
+    }
+
+    #[test]
+    fn retry() {
+        use std::time::Duration;
+
+        assert_eq!(calculator::IDEMPOTENT, ["div"]);
+        let policy = RetryPolicy::new(calculator::IDEMPOTENT).backoff(Duration::from_millis(1));
+        run(async {
+            let client = Client::new(Flaky(Default::default())).retry(policy.clone());
+            assert_eq!(
+                client.call(calculator::div(6, 3).unwrap()).await.unwrap(),
+                Some(2)
+            );
+            assert_eq!(client.into_inner().0.into_inner(), 2);
+
+            // calls to other methods are sent once
+            let client = Client::new(Flaky(Default::default())).retry(policy.clone());
+            assert!(matches!(
+                client.call(calculator::sub(6, 3).unwrap()).await,
+                Err(CallError::Transport(_))
+            ));
+            assert_eq!(client.into_inner().0.into_inner(), 1);
+
+            // application errors aren't retried
+            let client = Client::new(Local).retry(policy.clone());
+            let bad = easy_jsonrpc::BoundMethod::<u32>::new("div", vec![json!(1)]);
+            assert!(matches!(
+                client.call(bad).await,
+                Err(CallError::Response(ResponseFail::RpcError(_)))
+            ));
+
+            let client = Client::new(Broken).retry(policy.max_retries(2));
+            assert!(matches!(
+                client.call(calculator::div(6, 3).unwrap()).await,
+                Err(CallError::Transport(_))
+            ));
+        });
+    }
+
+    // counts the requests it serves
+    #[derive(Default)]
+    struct Counting(std::sync::atomic::AtomicUsize);
+    impl Transport for Counting {
+        async fn send(&self, request: Value) -> Result<Value, TransportError> {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Local.send(request).await
+        }
+    }
+
+    #[test]
+    fn caching() {
+        use std::time::Duration;
+
+        assert_eq!(calculator::CACHEABLE, ["sub"]);
+        let client = CachingClient::new(
+            Client::new(Counting::default()),
+            calculator::CACHEABLE,
+            Duration::from_millis(200),
+        );
+        let sent = |client: &CachingClient<Counting>| {
+            client
+                .client
+                .transport
+                .0
+                .load(std::sync::atomic::Ordering::SeqCst)
+        };
+        run(async {
+            assert_eq!(
+                client.call(calculator::sub(6, 3).unwrap()).await.unwrap(),
+                Some(3)
+            );
+            assert_eq!(sent(&client), 1);
+            // an identical call within the ttl doesn't reach the transport
+            assert_eq!(
+                client.call(calculator::sub(6, 3).unwrap()).await.unwrap(),
+                Some(3)
+            );
+            assert_eq!(sent(&client), 1);
+            // other params are another call
+            assert_eq!(
+                client.call(calculator::sub(3, 6).unwrap()).await.unwrap(),
+                None
+            );
+            assert_eq!(sent(&client), 2);
+            // uncached methods are always sent
+            client.call(calculator::div(6, 3).unwrap()).await.unwrap();
+            client.call(calculator::div(6, 3).unwrap()).await.unwrap();
+            assert_eq!(sent(&client), 4);
+
+            std::thread::sleep(Duration::from_millis(250));
+            client.call(calculator::sub(6, 3).unwrap()).await.unwrap();
+            assert_eq!(sent(&client), 5);
+            // the expired result of sub(3, 6) is dropped when another is cached
+            assert_eq!(client.lock().len(), 1);
+            client.clear();
+            client.call(calculator::sub(6, 3).unwrap()).await.unwrap();
+            assert_eq!(sent(&client), 6);
+        });
+    }
+
+    // records the id of each request it serves
+    #[derive(Default)]
+    struct Recording(std::sync::Mutex<Vec<Value>>);
+    impl Transport for Recording {
+        async fn send(&self, request: Value) -> Result<Value, TransportError> {
+            self.0.lock().unwrap().push(request["id"].clone());
+            // let the other call send its request before this one is answered
+            tokio::task::yield_now().await;
+            let mut handler = &CalculatorImpl as &dyn Calculator;
+            Ok(handler
+                .handle_request(request)
+                .as_option()
+                .unwrap_or(Value::Null))
+        }
+    }
+
+    fn concurrent_calls(client: Client<Recording>) -> Vec<Value> {
+        run(async {
+            let (a, b) = tokio::join!(
+                client.call(calculator::div(6, 3).unwrap()),
+                client.call(calculator::div(8, 2).unwrap())
+            );
+            assert_eq!((a.unwrap(), b.unwrap()), (Some(2), Some(4)));
+        });
+        let ids = client.into_inner().0.into_inner().unwrap();
+        assert_eq!(ids.len(), 2);
+        assert_ne!(ids[0], ids[1]);
+        ids
+    }
+
+    #[test]
+    fn ids() {
+        assert_eq!(
+            concurrent_calls(Client::new(Recording::default())),
+            [json!(0), json!(1)]
+        );
+
+        struct Named(std::sync::atomic::AtomicU64);
+        impl IdGenerator for Named {
+            fn next_id(&self) -> easy_jsonrpc::RequestId {
+                let n = self.0.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                format!("call-{}", n).into()
+            }
+        }
+        let client = Client::new(Recording::default()).ids(Named(Default::default()));
+        assert_eq!(concurrent_calls(client), [json!("call-0"), json!("call-1")]);
+
+        #[cfg(feature = "uuid")]
+        for id in concurrent_calls(Client::new(Recording::default()).ids(super::UuidIds)) {
+            assert_eq!(id.as_str().unwrap().len(), 36);
+        }
+    }
+
+    #[test]
+    fn session() {
+        use super::ClientSession;
+
+        let session = ClientSession::new();
+        let (first, div) = session.request(calculator::div(6, 3).unwrap());
+        let (second, sub) = session.request(calculator::sub(1, 2).unwrap());
+        assert_eq!(first["id"], json!(0));
+        assert_eq!(second["id"], json!(1));
+        assert_eq!(session.in_flight(), 2);
+
+        let handle = |request| {
+            let mut handler = &CalculatorImpl as &dyn Calculator;
+            handler.handle_request(request).as_option().unwrap()
+        };
+        run(async {
+            let div = tokio::spawn(div);
+            let sub = tokio::spawn(sub);
+            tokio::task::yield_now().await;
+            // answered in reverse order
+            assert_eq!(session.receive(handle(second)).unwrap(), 1);
+            assert_eq!(session.receive(handle(first)).unwrap(), 1);
+            assert_eq!(div.await.unwrap().unwrap(), Some(2));
+            assert_eq!(sub.await.unwrap().unwrap(), None);
+        });
+        assert_eq!(session.in_flight(), 0);
+
+        // batched responses, errors, and responses to no call in flight
+        let (first, div) = session.request(calculator::div(6, 3).unwrap());
+        let (second, bad) = session.request(easy_jsonrpc::BoundMethod::<u32>::new("div", vec![]));
+        let response = handle(json!([second, first]));
+        assert_eq!(session.receive(response.clone()).unwrap(), 2);
+        assert_eq!(session.receive(response).unwrap(), 0);
+        assert!(session.receive(json!({"jsonrpc": "2.0"})).is_err());
+        run(async {
+            assert_eq!(div.await.unwrap(), Some(2));
+            match bad.await {
+                Err(CallError::Response(ResponseFail::RpcError(err))) => {
+                    assert_eq!(err.code, ErrorCode::InvalidParams)
+                }
+                other => panic!("{:?}", other),
+            }
+        });
+
+        // an id answered twice fails its call, without holding back the other outputs
+        let (first, div) = session.request(calculator::div(6, 3).unwrap());
+        let (second, sub) = session.request(calculator::sub(3, 1).unwrap());
+        let answer = |request: &Value, result| json!({"jsonrpc": "2.0", "result": result, "id": request["id"]});
+        let response = json!([
+            answer(&first, json!(2)),
+            answer(&second, json!(2)),
+            answer(&first, json!(3)),
+        ]);
+        assert_eq!(
+            session.receive(response),
+            Err(InvalidResponse::DuplicateId(
+                easy_jsonrpc::RequestId::from_value(&first["id"]).unwrap()
+            ))
+        );
+        assert_eq!(session.in_flight(), 0);
+        run(async {
+            assert_eq!(sub.await.unwrap(), Some(2));
+            match div.await {
+                Err(CallError::Response(ResponseFail::InvalidResponse)) => {}
+                other => panic!("{:?}", other),
+            }
+        });
+
+        // dropped calls are forgotten, and calls left in flight fail with the session
+        let (_, dropped) = session.request(calculator::div(6, 3).unwrap());
+        let (_, orphan) = session.request(calculator::div(6, 3).unwrap());
+        drop(dropped);
+        assert_eq!(session.in_flight(), 1);
+        drop(session);
+        assert!(matches!(run(orphan), Err(CallError::Transport(_))));
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[test]
+    fn http() {
+        use super::HttpTransport;
+        use warp::Filter;
+
+        run(async {
+            let responder = warp::post()
+                .and(warp::path::end())
+                .and(warp::body::json::<Value>())
+                .map(|request| {
+                    let mut handler = &CalculatorImpl as &dyn Calculator;
+                    let response = handler.handle_request(request).as_option();
+                    warp::reply::json(&response.unwrap_or(Value::Null))
+                });
+            let (addr, server) = warp::serve(responder).bind_ephemeral(([127, 0, 0, 1], 0));
+            tokio::spawn(server);
+
+            let client = Client::new(HttpTransport::new(format!("http://{}", addr)));
+            assert_eq!(
+                client.call(calculator::div(9, 3).unwrap()).await.unwrap(),
+                Some(3)
+            );
+
+            let client = Client::new(HttpTransport::new(format!("http://{}/missing", addr)));
+            assert!(matches!(
+                client.call(calculator::div(9, 3).unwrap()).await,
+                Err(CallError::Transport(_))
+            ));
+        });
+    }
+}
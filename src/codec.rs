@@ -0,0 +1,226 @@
+//! Alternate wire encodings for arguments and return values.
+//!
+//! Each submodule is a serde "with" module, exposing `serialize` and `deserialize` functions.
+//! The [rpc](../../easy_jsonrpc_proc_macro/attr.rpc.html) macro uses them when a method or
+//! argument is annotated with the matching option, e.g. `#[rpc_arg(hex)]`.
+
+pub mod hex {
+    //! Encode bytes as a `0x` prefixed, lowercase hex string, the convention used by many rpc
+    //! apis. Decoding accepts upper or lowercase digits, with or without the `0x` prefix.
+
+    use serde::de::{Deserialize, Deserializer, Error};
+    use serde::ser::Serializer;
+
+    /// Serialize bytes as a hex string.
+    pub fn serialize<T, S>(bytes: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: AsRef<[u8]>,
+        S: Serializer,
+    {
+        serializer.serialize_str(&encode(bytes.as_ref()))
+    }
+
+    /// Deserialize bytes from a hex string.
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let hex = String::deserialize(deserializer)?;
+        decode(&hex).ok_or_else(|| D::Error::custom(format!("invalid hex string {:?}", hex)))
+    }
+
+    fn encode(bytes: &[u8]) -> String {
+        const DIGITS: &[u8; 16] = b"0123456789abcdef";
+        let mut ret = String::with_capacity(2 + bytes.len() * 2);
+        ret.push_str("0x");
+        for byte in bytes {
+            ret.push(DIGITS[(byte >> 4) as usize] as char);
+            ret.push(DIGITS[(byte & 0xf) as usize] as char);
+        }
+        ret
+    }
+
+    fn decode(hex: &str) -> Option<Vec<u8>> {
+        let hex = hex.strip_prefix("0x").unwrap_or(hex).as_bytes();
+        if !hex.len().is_multiple_of(2) {
+            return None;
+        }
+        hex.chunks(2)
+            .map(|pair| Some(nibble(pair[0])? << 4 | nibble(pair[1])?))
+            .collect()
+    }
+
+    fn nibble(digit: u8) -> Option<u8> {
+        (digit as char).to_digit(16).map(|n| n as u8)
+    }
+}
+
+pub mod num_as_str {
+    //! Encode numbers as decimal strings, as many apis do for integers too large for a javascript
+    //! number. Decoding accepts only strings.
+
+    use serde::de::{Deserialize, Deserializer, Error};
+    use serde::ser::Serializer;
+    use std::fmt::Display;
+    use std::str::FromStr;
+
+    /// Serialize a number as a decimal string.
+    pub fn serialize<T, S>(num: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Display,
+        S: Serializer,
+    {
+        serializer.collect_str(num)
+    }
+
+    /// Deserialize a number from a decimal string.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        let num = String::deserialize(deserializer)?;
+        num.parse()
+            .map_err(|e| D::Error::custom(format!("invalid number {:?}: {}", num, e)))
+    }
+}
+
+pub mod int128 {
+    //! Encode numbers as decimal strings, like [num_as_str](../num_as_str/index.html), but decode
+    //! either strings or numbers. The [rpc](../../../easy_jsonrpc_proc_macro/attr.rpc.html) macro
+    //! uses this for `i128` and `u128` arguments and results, which serde_json can't hold as
+    //! numbers without its `arbitrary_precision` feature, while still accepting the numbers sent
+    //! by clients which don't know about the strings.
+
+    use serde::de::{Deserialize, Deserializer, Error};
+    use serde::ser::Serializer;
+    use serde_json::Value;
+    use std::fmt::Display;
+    use std::str::FromStr;
+
+    /// Serialize a number as a decimal string.
+    pub fn serialize<T, S>(num: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Display,
+        S: Serializer,
+    {
+        serializer.collect_str(num)
+    }
+
+    /// Deserialize a number from a decimal string or a number.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        let num = match Value::deserialize(deserializer)? {
+            Value::String(num) => num,
+            Value::Number(num) => num.to_string(),
+            other => {
+                return Err(D::Error::custom(format!(
+                    "expected a number or a decimal string, got {}",
+                    other
+                )))
+            }
+        };
+        num.parse()
+            .map_err(|e| D::Error::custom(format!("invalid number {:?}: {}", num, e)))
+    }
+}
+
+pub mod stringify_keys {
+    //! Encode a map as an object whose keys are the map's keys formatted with `Display`, so maps
+    //! keyed by types serde_json can't use as object keys can be sent, e.g. `HashMap<Cell, V>` for
+    //! a `struct Cell(u8, u8)` displayed as `"1:2"`. Decoding parses the keys back with `FromStr`,
+    //! so the key type must implement both.
+
+    use serde::de::{Deserialize, Deserializer, Error, MapAccess, Visitor};
+    use serde::ser::{Serialize, Serializer};
+    use std::fmt::{self, Display};
+    use std::marker::PhantomData;
+    use std::str::FromStr;
+
+    /// Serialize a map, formatting its keys as strings.
+    pub fn serialize<M, K, V, S>(map: &M, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        for<'a> &'a M: IntoIterator<Item = (&'a K, &'a V)>,
+        K: Display,
+        V: Serialize,
+        S: Serializer,
+    {
+        serializer.collect_map(map.into_iter().map(|(key, value)| (key.to_string(), value)))
+    }
+
+    /// Deserialize a map, parsing its keys from strings.
+    pub fn deserialize<'de, M, K, V, D>(deserializer: D) -> Result<M, D::Error>
+    where
+        M: FromIterator<(K, V)>,
+        K: FromStr,
+        K::Err: Display,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        // the entries in order, for any kind of map to be collected from
+        struct MapVisitor<K, V>(PhantomData<fn() -> (K, V)>);
+
+        impl<'de, K, V> Visitor<'de> for MapVisitor<K, V>
+        where
+            K: FromStr,
+            K::Err: Display,
+            V: Deserialize<'de>,
+        {
+            type Value = Vec<(K, V)>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+                let mut entries = Vec::with_capacity(access.size_hint().unwrap_or(0));
+                while let Some((key, value)) = access.next_entry::<String, V>()? {
+                    let key = key
+                        .parse()
+                        .map_err(|e| A::Error::custom(format!("invalid key {:?}: {}", key, e)))?;
+                    entries.push((key, value));
+                }
+                Ok(entries)
+            }
+        }
+
+        let entries = deserializer.deserialize_map(MapVisitor(PhantomData))?;
+        Ok(entries.into_iter().collect())
+    }
+}
+
+#[cfg(feature = "base64")]
+pub mod base64 {
+    //! Encode bytes as a padded base64 string using the standard alphabet. Requires the `base64`
+    //! feature.
+
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use serde::de::{Deserialize, Deserializer, Error};
+    use serde::ser::Serializer;
+
+    /// Serialize bytes as a base64 string.
+    pub fn serialize<T, S>(bytes: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: AsRef<[u8]>,
+        S: Serializer,
+    {
+        serializer.serialize_str(&STANDARD.encode(bytes.as_ref()))
+    }
+
+    /// Deserialize bytes from a base64 string.
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let encoded = String::deserialize(deserializer)?;
+        STANDARD
+            .decode(&encoded)
+            .map_err(|e| D::Error::custom(format!("invalid base64 string {:?}: {}", encoded, e)))
+    }
+}
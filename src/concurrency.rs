@@ -0,0 +1,188 @@
+use crate::{CallKind, Error, ErrorCode, Handler, Params, RequestMeta, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+
+/// The code of the error returned for calls rejected by a saturated
+/// [ConcurrencyLimit](struct.ConcurrencyLimit.html).
+pub const OVERLOADED: i64 = -32005;
+
+/// How many calls to each method may run at once, and how many are running. Methods without a
+/// limit are unrestricted.
+///
+/// Clones share the count of running calls, so a limit holds across every
+/// [ConcurrencyLimit](struct.ConcurrencyLimit.html) given a clone, e.g. one per thread serving
+/// requests.
+#[derive(Clone, Debug, Default)]
+pub struct ConcurrencyLimits {
+    max: Arc<HashMap<String, usize>>,
+    queue: bool,
+    running: Arc<Running>,
+}
+
+#[derive(Debug, Default)]
+struct Running {
+    counts: Mutex<HashMap<String, usize>>,
+    finished: Condvar,
+}
+
+impl ConcurrencyLimits {
+    /// No limits, calls over a limit are rejected.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Let at most max calls to method run at once. Panics if max is 0, which would leave every
+    /// call rejected, or waiting forever if the limits queue.
+    pub fn limit(mut self, method: &str, max: usize) -> Self {
+        assert!(
+            max > 0,
+            "concurrency limit for {} must be at least 1",
+            method
+        );
+        Arc::make_mut(&mut self.max).insert(method.to_owned(), max);
+        self
+    }
+
+    /// Whether calls over a limit wait for a running call to finish, rather than being rejected
+    /// with an [OVERLOADED](constant.OVERLOADED.html) error.
+    pub fn queue(self, queue: bool) -> Self {
+        ConcurrencyLimits { queue, ..self }
+    }
+
+    /// The number of calls to method running right now.
+    pub fn running(&self, method: &str) -> usize {
+        self.lock().get(method).copied().unwrap_or(0)
+    }
+
+    // The counts are only changed while the lock is held, so they are valid even if a thread
+    // panicked holding it.
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, usize>> {
+        self.running
+            .counts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // Take a place among the calls to method, or None if it's limited and there's none to take.
+    fn acquire<'a>(&'a self, method: &'a str) -> Result<Option<Permit<'a>>, Error> {
+        let max = match self.max.get(method) {
+            Some(&max) => max,
+            None => return Ok(None),
+        };
+        let mut counts = self.lock();
+        loop {
+            let count = counts.entry(method.to_owned()).or_default();
+            if *count < max {
+                *count += 1;
+                return Ok(Some(Permit {
+                    limits: self,
+                    method,
+                }));
+            }
+            if !self.queue {
+                return Err(Error {
+                    code: ErrorCode::ServerError(OVERLOADED),
+                    message: "Overloaded".into(),
+                    data: None,
+                });
+            }
+            counts = self
+                .running
+                .finished
+                .wait(counts)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+}
+
+// A running call, giving up its place when dropped, even if the call panics.
+struct Permit<'a> {
+    limits: &'a ConcurrencyLimits,
+    method: &'a str,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if let Some(count) = self.limits.lock().get_mut(self.method) {
+            *count -= 1;
+        }
+        self.limits.running.finished.notify_all();
+    }
+}
+
+/// Wraps a handler, limiting how many calls to a method run at once, e.g. for methods sharing a
+/// small database pool. Calls over the limit are rejected with an
+/// [OVERLOADED](constant.OVERLOADED.html) error, or wait their turn if the limits
+/// [queue](struct.ConcurrencyLimits.html#method.queue).
+///
+/// ```
+/// use easy_jsonrpc::{ConcurrencyLimit, ConcurrencyLimits, DynHandler, Handler};
+/// use serde_json::json;
+///
+/// let limits = ConcurrencyLimits::new().limit("query", 4);
+///
+/// // typically on each thread serving requests
+/// let mut inner = DynHandler::new();
+/// inner.register("query", |_| Ok(json!([])));
+/// let mut handler = ConcurrencyLimit::new(inner, limits.clone());
+/// let response = handler.handle_request(json!({"jsonrpc": "2.0", "method": "query", "id": 1}));
+/// assert_eq!(response.as_option().unwrap()["result"], json!([]));
+/// ```
+pub struct ConcurrencyLimit<H> {
+    handler: H,
+    limits: ConcurrencyLimits,
+}
+
+impl<H: Handler> ConcurrencyLimit<H> {
+    /// Wrap handler, counting its calls against limits.
+    pub fn new(handler: H, limits: ConcurrencyLimits) -> Self {
+        ConcurrencyLimit { handler, limits }
+    }
+
+    /// The limits calls are counted against.
+    pub fn limits(&self) -> &ConcurrencyLimits {
+        &self.limits
+    }
+
+    /// Unwrap the inner handler.
+    pub fn into_inner(self) -> H {
+        self.handler
+    }
+}
+
+impl<H: Handler> Handler for ConcurrencyLimit<H> {
+    fn handle(&mut self, method: &str, params: Params) -> Result<Value, Error> {
+        self.handle_with_meta(method, params, CallKind::MethodCall)
+    }
+
+    fn handle_with_meta(
+        &mut self,
+        method: &str,
+        params: Params,
+        kind: CallKind,
+    ) -> Result<Value, Error> {
+        self.handle_with_request_meta(method, params, &RequestMeta::new(kind))
+    }
+
+    fn handle_with_request_meta(
+        &mut self,
+        method: &str,
+        params: Params,
+        meta: &RequestMeta,
+    ) -> Result<Value, Error> {
+        let _permit = self.limits.acquire(method)?;
+        self.handler.handle_with_request_meta(method, params, meta)
+    }
+
+    fn method_exists(&self, method: &str) -> bool {
+        self.handler.method_exists(method)
+    }
+
+    fn accepts_bare_param(&self, method: &str) -> bool {
+        self.handler.accepts_bare_param(method)
+    }
+
+    fn strict_version(&self) -> bool {
+        self.handler.strict_version()
+    }
+}
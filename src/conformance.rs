@@ -0,0 +1,296 @@
+//! Checks a [Handler](../trait.Handler.html) against the examples in the
+//! [JSON-RPC 2.0 specification](https://www.jsonrpc.org/specification#examples). Requires the
+//! `conformance` feature.
+//!
+//! Examples calling `subtract` are run only when the handler has a method by that name, taking
+//! `minuend` and `subtrahend`. Examples expecting a method not found error are skipped for handlers
+//! which claim to have the missing methods, see
+//! [method_exists](../trait.Handler.html#method.method_exists). Handlers relying on the default
+//! method_exists, which claims every method, run the `subtract` examples and skip the others.
+//!
+//! ```
+//! use easy_jsonrpc::conformance;
+//!
+//! #[easy_jsonrpc::rpc]
+//! pub trait Arith {
+//!     fn subtract(&self, minuend: i64, subtrahend: i64) -> i64 {
+//!         minuend - subtrahend
+//!     }
+//! }
+//!
+//! struct ArithImpl;
+//! impl Arith for ArithImpl {}
+//!
+//! let report = conformance::run(&mut (&ArithImpl as &dyn Arith));
+//! assert!(report.is_conformant(), "{}", report);
+//! ```
+
+use crate::{Handler, Value};
+use serde_json::json;
+use std::fmt;
+
+/// The result of [run](fn.run.html), one [Check](struct.Check.html) per example.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConformanceReport {
+    /// Every example, in the order they were run.
+    pub checks: Vec<Check>,
+}
+
+impl ConformanceReport {
+    /// Whether no check failed.
+    pub fn is_conformant(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    /// The checks which failed.
+    pub fn failures(&self) -> impl Iterator<Item = &Check> {
+        self.checks
+            .iter()
+            .filter(|check| check.outcome == Outcome::Failed)
+    }
+}
+
+impl fmt::Display for ConformanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            writeln!(f, "{:?}: {}", check.outcome, check.name)?;
+            if check.outcome == Outcome::Failed {
+                writeln!(f, "    request:  {}", check.request)?;
+                writeln!(f, "    expected: {}", show(&check.expected))?;
+                writeln!(f, "    actual:   {}", show(&check.actual))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn show(response: &Option<Value>) -> String {
+    match response {
+        Some(response) => response.to_string(),
+        None => "no reply".into(),
+    }
+}
+
+/// A single example request and the handler's reply.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Check {
+    /// What the example exercises.
+    pub name: &'static str,
+    /// The request text, as passed to
+    /// [handle_request_str](../trait.Handler.html#method.handle_request_str).
+    pub request: &'static str,
+    /// The reply given in the specification, or None where it gives no reply.
+    pub expected: Option<Value>,
+    /// The handler's reply. Replies which aren't valid json are kept as a json string.
+    pub actual: Option<Value>,
+    /// Whether the reply matched.
+    pub outcome: Outcome,
+}
+
+/// The outcome of a [Check](struct.Check.html).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Outcome {
+    /// The reply matched the specification. Error messages and data are free form, so only error
+    /// codes are compared. Replies to batches may be in any order.
+    Passed,
+    /// The reply didn't match the specification.
+    Failed,
+    /// The example doesn't apply to the handler, so it wasn't run.
+    Skipped,
+}
+
+enum Requires {
+    Nothing,
+    Method(&'static str),
+    Missing(&'static str),
+}
+
+/// Run every example against handler.
+pub fn run<H: Handler + ?Sized>(handler: &mut H) -> ConformanceReport {
+    let checks = examples()
+        .into_iter()
+        .map(|(name, requires, request, expected)| {
+            let applies = match requires {
+                Requires::Nothing => true,
+                Requires::Method(method) => handler.method_exists(method),
+                Requires::Missing(method) => !handler.method_exists(method),
+            };
+            if !applies {
+                return Check {
+                    name,
+                    request,
+                    expected,
+                    actual: None,
+                    outcome: Outcome::Skipped,
+                };
+            }
+            let actual = handler
+                .handle_request_str(request)
+                .map(|reply| serde_json::from_str(&reply).unwrap_or(Value::String(reply)));
+            let matched = match (&expected, &actual) {
+                (Some(expected), Some(actual)) => matches(expected, actual),
+                (expected, actual) => expected == actual,
+            };
+            let outcome = if matched {
+                Outcome::Passed
+            } else {
+                Outcome::Failed
+            };
+            Check {
+                name,
+                request,
+                expected,
+                actual,
+                outcome,
+            }
+        })
+        .collect();
+    ConformanceReport { checks }
+}
+
+// Compare replies, ignoring the free form parts of errors and the order of batch replies. Objects
+// compare equal regardless of key order, so neither depends on serde_json's preserve_order.
+fn matches(expected: &Value, actual: &Value) -> bool {
+    fn strip(response: &Value) -> Value {
+        let mut response = response.clone();
+        if let Some(Value::Object(error)) = response.get_mut("error") {
+            error.remove("message");
+            error.remove("data");
+        }
+        response
+    }
+    match (expected, actual) {
+        (Value::Array(expected), Value::Array(actual)) => {
+            let mut unmatched: Vec<Value> = actual.iter().map(strip).collect();
+            expected.len() == actual.len()
+                && expected.iter().map(strip).all(|response| {
+                    match unmatched.iter().position(|other| *other == response) {
+                        Some(index) => {
+                            unmatched.swap_remove(index);
+                            true
+                        }
+                        None => false,
+                    }
+                })
+        }
+        (expected, actual) => strip(expected) == strip(actual),
+    }
+}
+
+type Example = (&'static str, Requires, &'static str, Option<Value>);
+
+fn error(code: i64, id: Value) -> Value {
+    json!({"jsonrpc": "2.0", "error": {"code": code, "message": ""}, "id": id})
+}
+
+fn examples() -> Vec<Example> {
+    vec![
+        (
+            "positional params",
+            Requires::Method("subtract"),
+            r#"{"jsonrpc": "2.0", "method": "subtract", "params": [42, 23], "id": 1}"#,
+            Some(json!({"jsonrpc": "2.0", "result": 19, "id": 1})),
+        ),
+        (
+            "positional params, reversed",
+            Requires::Method("subtract"),
+            r#"{"jsonrpc": "2.0", "method": "subtract", "params": [23, 42], "id": 2}"#,
+            Some(json!({"jsonrpc": "2.0", "result": -19, "id": 2})),
+        ),
+        (
+            "named params",
+            Requires::Method("subtract"),
+            r#"{"jsonrpc": "2.0", "method": "subtract", "params": {"subtrahend": 23, "minuend": 42}, "id": 3}"#,
+            Some(json!({"jsonrpc": "2.0", "result": 19, "id": 3})),
+        ),
+        (
+            "named params, reordered",
+            Requires::Method("subtract"),
+            r#"{"jsonrpc": "2.0", "method": "subtract", "params": {"minuend": 42, "subtrahend": 23}, "id": 4}"#,
+            Some(json!({"jsonrpc": "2.0", "result": 19, "id": 4})),
+        ),
+        (
+            "notification",
+            Requires::Nothing,
+            r#"{"jsonrpc": "2.0", "method": "update", "params": [1, 2, 3, 4, 5]}"#,
+            None,
+        ),
+        (
+            "notification to a missing method",
+            Requires::Nothing,
+            r#"{"jsonrpc": "2.0", "method": "foobar"}"#,
+            None,
+        ),
+        (
+            "missing method",
+            Requires::Missing("foobar"),
+            r#"{"jsonrpc": "2.0", "method": "foobar", "id": "1"}"#,
+            Some(error(-32601, json!("1"))),
+        ),
+        (
+            "invalid json",
+            Requires::Nothing,
+            r#"{"jsonrpc": "2.0", "method": "foobar, "params": "bar", "baz]"#,
+            Some(error(-32700, Value::Null)),
+        ),
+        (
+            "invalid request object",
+            Requires::Nothing,
+            r#"{"jsonrpc": "2.0", "method": 1, "params": "bar"}"#,
+            Some(error(-32600, Value::Null)),
+        ),
+        (
+            "batch, invalid json",
+            Requires::Nothing,
+            r#"[
+                {"jsonrpc": "2.0", "method": "sum", "params": [1, 2, 4], "id": "1"},
+                {"jsonrpc": "2.0", "method"
+            ]"#,
+            Some(error(-32700, Value::Null)),
+        ),
+        (
+            "empty batch",
+            Requires::Nothing,
+            r#"[]"#,
+            Some(error(-32600, Value::Null)),
+        ),
+        (
+            "invalid batch",
+            Requires::Nothing,
+            r#"[1]"#,
+            Some(json!([error(-32600, Value::Null)])),
+        ),
+        (
+            "invalid batch, several calls",
+            Requires::Nothing,
+            r#"[1, 2, 3]"#,
+            Some(json!([
+                error(-32600, Value::Null),
+                error(-32600, Value::Null),
+                error(-32600, Value::Null)
+            ])),
+        ),
+        (
+            "batch, mixed",
+            Requires::Missing("foo.get"),
+            r#"[
+                {"jsonrpc": "2.0", "method": "notify_hello", "params": [7]},
+                {"foo": "boo"},
+                {"jsonrpc": "2.0", "method": "foo.get", "params": {"name": "myself"}, "id": "5"}
+            ]"#,
+            Some(json!([
+                error(-32600, Value::Null),
+                error(-32601, json!("5"))
+            ])),
+        ),
+        (
+            "batch, notifications only",
+            Requires::Nothing,
+            r#"[
+                {"jsonrpc": "2.0", "method": "notify_sum", "params": [1, 2, 4]},
+                {"jsonrpc": "2.0", "method": "notify_hello", "params": [7]}
+            ]"#,
+            None,
+        ),
+    ]
+}
@@ -0,0 +1,93 @@
+use crate::{Error, Handler, Params, Value};
+
+/// Handles requests for a trait whose methods take a per-request context, declared as the first
+/// argument after self with `#[rpc_ctx] ctx: &C`. The context is not one of the jsonrpc params.
+/// Implemented by the [rpc](../easy_jsonrpc_proc_macro/attr.rpc.html) macro in place of
+/// [Handler](trait.Handler.html). Use [with_ctx](fn.with_ctx.html) to handle a request.
+pub trait HandleWithCtx<C: ?Sized> {
+    /// Like [Handler::handle](trait.Handler.html#tymethod.handle), passing ctx to methods which
+    /// take it.
+    fn handle_with_ctx(&mut self, ctx: &C, method: &str, params: Params) -> Result<Value, Error>;
+
+    /// See [Handler::method_exists](trait.Handler.html#method.method_exists).
+    fn method_exists(&self, method: &str) -> bool {
+        let _ = method;
+        true
+    }
+
+    /// See [Handler::accepts_bare_param](trait.Handler.html#method.accepts_bare_param).
+    fn accepts_bare_param(&self, method: &str) -> bool {
+        let _ = method;
+        false
+    }
+}
+
+/// Pair handler with the context for a single request, giving a [Handler](trait.Handler.html).
+///
+/// ```
+/// use easy_jsonrpc::{rpc, with_ctx, Handler, MaybeReply};
+/// use serde_json::json;
+///
+/// pub struct Session {
+///     user: String,
+/// }
+///
+/// #[rpc]
+/// pub trait Greeter {
+///     fn greet(&self, #[rpc_ctx] session: &Session, greeting: String) -> String;
+/// }
+///
+/// struct GreeterImpl;
+/// impl Greeter for GreeterImpl {
+///     fn greet(&self, session: &Session, greeting: String) -> String {
+///         format!("{}, {}", greeting, session.user)
+///     }
+/// }
+///
+/// let mut handler = &GreeterImpl as &dyn Greeter;
+/// let session = Session { user: "bob".into() };
+/// assert_eq!(
+///     with_ctx(&mut handler, &session).handle_request(json!({
+///         "jsonrpc": "2.0",
+///         "method": "greet",
+///         "params": ["hello"],
+///         "id": 1
+///     })),
+///     MaybeReply::Reply(json!({
+///         "jsonrpc": "2.0",
+///         "result": "hello, bob",
+///         "id": 1
+///     }))
+/// );
+/// ```
+pub fn with_ctx<'a, H, C>(handler: &'a mut H, ctx: &'a C) -> WithCtx<'a, H, C>
+where
+    H: HandleWithCtx<C> + ?Sized,
+    C: ?Sized,
+{
+    WithCtx { handler, ctx }
+}
+
+/// A handler paired with a context by [with_ctx](fn.with_ctx.html).
+pub struct WithCtx<'a, H: ?Sized, C: ?Sized> {
+    handler: &'a mut H,
+    ctx: &'a C,
+}
+
+impl<H, C> Handler for WithCtx<'_, H, C>
+where
+    H: HandleWithCtx<C> + ?Sized,
+    C: ?Sized,
+{
+    fn handle(&mut self, method: &str, params: Params) -> Result<Value, Error> {
+        self.handler.handle_with_ctx(self.ctx, method, params)
+    }
+
+    fn method_exists(&self, method: &str) -> bool {
+        self.handler.method_exists(method)
+    }
+
+    fn accepts_bare_param(&self, method: &str) -> bool {
+        self.handler.accepts_bare_param(method)
+    }
+}
@@ -0,0 +1,63 @@
+use crate::{Error, Handler, Params, Value};
+use std::collections::HashMap;
+
+type Method = Box<dyn Fn(Params) -> Result<Value, Error>>;
+
+/// A handler whose methods are registered at runtime rather than generated from a trait.
+///
+/// ```
+/// use easy_jsonrpc::{DynHandler, Handler, MaybeReply};
+/// use serde_json::json;
+///
+/// let mut handler = DynHandler::new();
+/// handler.register("double", |params| {
+///     let n: i64 = params.get_positional(0)?;
+///     Ok(json!(n * 2))
+/// });
+///
+/// assert_eq!(
+///     handler.handle_request(json!({
+///         "jsonrpc": "2.0",
+///         "method": "double",
+///         "params": [21],
+///         "id": 1
+///     })),
+///     MaybeReply::Reply(json!({
+///         "jsonrpc": "2.0",
+///         "result": 42,
+///         "id": 1
+///     }))
+/// );
+/// ```
+#[derive(Default)]
+pub struct DynHandler {
+    methods: HashMap<String, Method>,
+}
+
+impl DynHandler {
+    /// Create a handler with no methods.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a method, replacing any existing method with the same name.
+    pub fn register<F>(&mut self, name: &str, f: F)
+    where
+        F: Fn(Params) -> Result<Value, Error> + 'static,
+    {
+        self.methods.insert(name.to_owned(), Box::new(f));
+    }
+}
+
+impl Handler for DynHandler {
+    fn handle(&mut self, method: &str, params: Params) -> Result<Value, Error> {
+        match self.methods.get(method) {
+            Some(f) => f(params),
+            None => Err(Error::method_not_found()),
+        }
+    }
+
+    fn method_exists(&self, method: &str) -> bool {
+        self.methods.contains_key(method)
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{CallKind, Error, ErrorCode, Handler, Params, RequestMeta, Value};
+
+/// Tries one handler, then another, e.g. to serve a new api in front of a legacy one while
+/// methods are migrated. Calls go to the primary handler. Only when it answers with a method not
+/// found error is the call passed to the secondary handler. Any other error, including errors
+/// returned by the primary's methods, is the reply.
+///
+/// ```
+/// use easy_jsonrpc::{DynHandler, Fallback, Handler};
+/// use serde_json::json;
+///
+/// let mut primary = DynHandler::new();
+/// primary.register("new", |_| Ok(json!("from primary")));
+/// let mut secondary = DynHandler::new();
+/// secondary.register("old", |_| Ok(json!("from secondary")));
+///
+/// let mut handler = Fallback::new(primary, secondary);
+/// let response = handler.handle_request(json!({"jsonrpc": "2.0", "method": "old", "id": 1}));
+/// assert_eq!(response.as_option().unwrap()["result"], json!("from secondary"));
+/// ```
+pub struct Fallback<P, S> {
+    primary: P,
+    secondary: S,
+}
+
+impl<P: Handler, S: Handler> Fallback<P, S> {
+    /// Pass calls to primary, falling back to secondary for methods primary doesn't have.
+    pub fn new(primary: P, secondary: S) -> Self {
+        Fallback { primary, secondary }
+    }
+
+    /// Unwrap the two handlers.
+    pub fn into_inner(self) -> (P, S) {
+        (self.primary, self.secondary)
+    }
+}
+
+impl<P: Handler, S: Handler> Handler for Fallback<P, S> {
+    fn handle(&mut self, method: &str, params: Params) -> Result<Value, Error> {
+        self.handle_with_meta(method, params, CallKind::MethodCall)
+    }
+
+    fn handle_with_meta(
+        &mut self,
+        method: &str,
+        params: Params,
+        kind: CallKind,
+    ) -> Result<Value, Error> {
+        self.handle_with_request_meta(method, params, &RequestMeta::new(kind))
+    }
+
+    fn handle_with_request_meta(
+        &mut self,
+        method: &str,
+        params: Params,
+        meta: &RequestMeta,
+    ) -> Result<Value, Error> {
+        // the params are only copied when the primary might have the method
+        if !self.primary.method_exists(method) {
+            return self
+                .secondary
+                .handle_with_request_meta(method, params, meta);
+        }
+        match self
+            .primary
+            .handle_with_request_meta(method, params.clone(), meta)
+        {
+            Err(error) if error.code == ErrorCode::MethodNotFound => self
+                .secondary
+                .handle_with_request_meta(method, params, meta),
+            result => result,
+        }
+    }
+
+    fn method_exists(&self, method: &str) -> bool {
+        self.primary.method_exists(method) || self.secondary.method_exists(method)
+    }
+
+    fn accepts_bare_param(&self, method: &str) -> bool {
+        if self.primary.method_exists(method) {
+            self.primary.accepts_bare_param(method)
+        } else {
+            self.secondary.accepts_bare_param(method)
+        }
+    }
+
+    // a request is checked before it's known which handler has the method, so the stricter wins
+    fn strict_version(&self) -> bool {
+        self.primary.strict_version() || self.secondary.strict_version()
+    }
+}
@@ -0,0 +1,54 @@
+use crate::{Error, InvalidArgs, Params, Value};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+
+/// Builds a value from a call's params as a whole, rather than from a single argument. Methods
+/// marked `#[rpc(params_struct)]` take their one argument this way, see the
+/// [rpc](../easy_jsonrpc_proc_macro/attr.rpc.html) macro.
+///
+/// Implemented for every type which can be deserialized. Named params are deserialized as one
+/// object, so each param fills the field of the same name. A single positional param is
+/// deserialized by itself, which is how client functions send the argument. Passing no params
+/// deserializes an empty object.
+///
+/// ```
+/// use easy_jsonrpc::{FromParams, Params};
+/// use serde::Deserialize;
+/// use serde_json::json;
+///
+/// #[derive(Deserialize, Debug, PartialEq)]
+/// struct Transfer {
+///     to: String,
+///     amount: u64,
+/// }
+///
+/// let named = Params::Named(json!({"to": "bob", "amount": 3}).as_object().unwrap().clone());
+/// let positional = Params::Positional(vec![json!({"to": "bob", "amount": 3})]);
+/// assert_eq!(Transfer::from_params(named), Transfer::from_params(positional));
+/// ```
+pub trait FromParams: Sized {
+    /// Build Self from params, returning an invalid params error if they don't fit.
+    fn from_params(params: Params) -> Result<Self, Error>;
+}
+
+impl<T: DeserializeOwned> FromParams for T {
+    fn from_params(params: Params) -> Result<Self, Error> {
+        let value = match params {
+            Params::Named(map) => Value::Object(map),
+            Params::Absent => json!({}),
+            Params::Positional(ar) if ar.is_empty() => json!({}),
+            Params::Positional(mut ar) if ar.len() == 1 => ar.remove(0),
+            Params::Positional(ar) => {
+                return Err(InvalidArgs::WrongNumberOfArgs {
+                    expected: 1,
+                    actual: ar.len(),
+                }
+                .into())
+            }
+        };
+        serde_json::from_value(value).map_err(|e| Error {
+            data: Some(json!({ "error": e.to_string() })),
+            ..InvalidArgs::InvalidParamsStructure.into()
+        })
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{CallKind, Error, Handler, Params, RequestMeta, Value};
+use std::collections::HashSet;
+
+/// Wraps a handler, letting individual methods be switched off at runtime, e.g. to back out a
+/// misbehaving method without redeploying. Calls to a disabled method get a method not found
+/// error without reaching the inner handler. Every method starts out enabled.
+///
+/// ```
+/// use easy_jsonrpc::{DynHandler, GatedHandler, Handler};
+/// use serde_json::json;
+///
+/// let mut inner = DynHandler::new();
+/// inner.register("ping", |_| Ok(json!("pong")));
+///
+/// let mut handler = GatedHandler::new(inner);
+/// handler.set_enabled("ping", false);
+/// let response = handler.handle_request(json!({"jsonrpc": "2.0", "method": "ping", "id": 1}));
+/// assert_eq!(response.as_option().unwrap()["error"]["code"], json!(-32601));
+/// ```
+pub struct GatedHandler<H> {
+    handler: H,
+    disabled: HashSet<String>,
+}
+
+impl<H: Handler> GatedHandler<H> {
+    /// Wrap handler, with every method enabled.
+    pub fn new(handler: H) -> Self {
+        GatedHandler {
+            handler,
+            disabled: HashSet::new(),
+        }
+    }
+
+    /// Enable or disable method.
+    pub fn set_enabled(&mut self, method: &str, enabled: bool) {
+        if enabled {
+            self.disabled.remove(method);
+        } else {
+            self.disabled.insert(method.to_owned());
+        }
+    }
+
+    /// Whether method is enabled. Methods the inner handler doesn't have count as enabled.
+    pub fn is_enabled(&self, method: &str) -> bool {
+        !self.disabled.contains(method)
+    }
+
+    /// Unwrap the inner handler.
+    pub fn into_inner(self) -> H {
+        self.handler
+    }
+}
+
+impl<H: Handler> Handler for GatedHandler<H> {
+    fn handle(&mut self, method: &str, params: Params) -> Result<Value, Error> {
+        self.handle_with_meta(method, params, CallKind::MethodCall)
+    }
+
+    fn handle_with_meta(
+        &mut self,
+        method: &str,
+        params: Params,
+        kind: CallKind,
+    ) -> Result<Value, Error> {
+        self.handle_with_request_meta(method, params, &RequestMeta::new(kind))
+    }
+
+    fn handle_with_request_meta(
+        &mut self,
+        method: &str,
+        params: Params,
+        meta: &RequestMeta,
+    ) -> Result<Value, Error> {
+        if !self.is_enabled(method) {
+            return Err(Error::method_not_found());
+        }
+        self.handler.handle_with_request_meta(method, params, meta)
+    }
+
+    fn method_exists(&self, method: &str) -> bool {
+        self.is_enabled(method) && self.handler.method_exists(method)
+    }
+
+    fn accepts_bare_param(&self, method: &str) -> bool {
+        self.handler.accepts_bare_param(method)
+    }
+
+    fn strict_version(&self) -> bool {
+        self.handler.strict_version()
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{CallKind, Error, Handler, Params, RequestMeta, Value};
+
+/// Wrap handler so that before is called ahead of every method call. Returning an error from
+/// before rejects the call without dispatching it to handler.
+///
+/// Useful for logging, metrics or access control.
+///
+/// ```
+/// use easy_jsonrpc::{layer, DynHandler, Error, ErrorCode, Handler, MaybeReply};
+/// use serde_json::json;
+///
+/// let mut inner = DynHandler::new();
+/// inner.register("shutdown", |_| Ok(json!(null)));
+///
+/// let mut handler = layer(inner, |method, _params| match method {
+///     "shutdown" => Err(Error {
+///         code: ErrorCode::ServerError(-32001),
+///         message: "Unauthorized".into(),
+///         data: None,
+///     }),
+///     _ => Ok(()),
+/// });
+///
+/// let response = handler.handle_request(json!({
+///     "jsonrpc": "2.0",
+///     "method": "shutdown",
+///     "id": 1
+/// }));
+/// assert_eq!(response.as_option().unwrap()["error"]["code"], json!(-32001));
+/// ```
+pub fn layer<H, F>(handler: H, before: F) -> Layer<H, F>
+where
+    H: Handler,
+    F: FnMut(&str, &Params) -> Result<(), Error>,
+{
+    Layer { handler, before }
+}
+
+/// A handler wrapped by [layer](fn.layer.html).
+pub struct Layer<H, F> {
+    handler: H,
+    before: F,
+}
+
+impl<H, F> Layer<H, F> {
+    /// Unwrap the inner handler.
+    pub fn into_inner(self) -> H {
+        self.handler
+    }
+}
+
+impl<H, F> Handler for Layer<H, F>
+where
+    H: Handler,
+    F: FnMut(&str, &Params) -> Result<(), Error>,
+{
+    fn handle(&mut self, method: &str, params: Params) -> Result<Value, Error> {
+        self.handle_with_meta(method, params, CallKind::MethodCall)
+    }
+
+    fn handle_with_meta(
+        &mut self,
+        method: &str,
+        params: Params,
+        kind: CallKind,
+    ) -> Result<Value, Error> {
+        self.handle_with_request_meta(method, params, &RequestMeta::new(kind))
+    }
+
+    fn handle_with_request_meta(
+        &mut self,
+        method: &str,
+        params: Params,
+        meta: &RequestMeta,
+    ) -> Result<Value, Error> {
+        (self.before)(method, &params)?;
+        self.handler.handle_with_request_meta(method, params, meta)
+    }
+
+    fn method_exists(&self, method: &str) -> bool {
+        self.handler.method_exists(method)
+    }
+
+    fn accepts_bare_param(&self, method: &str) -> bool {
+        self.handler.accepts_bare_param(method)
+    }
+
+    fn strict_version(&self) -> bool {
+        self.handler.strict_version()
+    }
+}
@@ -189,6 +189,67 @@ const SERIALZATION_ERROR: i64 = -32000;
 
 pub use easy_jsonrpc_proc_macro::rpc;
 
+/// ```
+/// use easy_jsonrpc::{Error, ErrorCode, RpcError};
+/// use serde_json::json;
+///
+/// #[derive(RpcError)]
+/// enum BankError {
+///     #[rpc_error(code = -32010, message = "Insufficient funds")]
+///     InsufficientFunds { balance: u64 },
+///     #[rpc_error(code = -32011)]
+///     AccountFrozen,
+/// }
+///
+/// let error = Error::from(BankError::InsufficientFunds { balance: 3 });
+/// assert_eq!(error.code, ErrorCode::ServerError(-32010));
+/// assert_eq!(error.message, "Insufficient funds");
+/// assert_eq!(error.data, Some(json!({"balance": 3})));
+/// assert_eq!(Error::from(BankError::AccountFrozen).message, "AccountFrozen");
+/// ```
+pub use easy_jsonrpc_proc_macro::RpcError;
+
+/// Turn a call to a generated client function into a request ready to send, and the tracker
+/// reading its result from the response, in one step.
+///
+/// `call!(my_api::my_method(a, b))` evaluates to `Result<(Value, Tracker<T>), ArgSerializeError>`.
+/// The request is given a random id, which the tracker looks for. An argument which fails to
+/// serialize is returned as the client function returned it.
+///
+/// ```
+/// use easy_jsonrpc::{call, rpc, Handler, Response};
+///
+/// #[rpc]
+/// pub trait Adder {
+///     fn add(&self, a: i32, b: i32) -> i32;
+/// }
+///
+/// struct AdderImpl;
+/// impl Adder for AdderImpl {
+///     fn add(&self, a: i32, b: i32) -> i32 {
+///         a + b
+///     }
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let (request, tracker) = call!(adder::add(1, 2))?;
+/// let mut handler = &AdderImpl as &dyn Adder;
+/// let response = handler.handle_request(request).as_option().unwrap();
+/// let mut response = Response::from_json_response(response)?;
+/// assert_eq!(tracker.get_return(&mut response)?, 3);
+/// # Ok(())
+/// # }
+/// ```
+#[macro_export]
+macro_rules! call {
+    ($bound:expr) => {
+        ($bound).map(|bound| {
+            let (call, tracker) = $crate::BoundMethod::call(&bound);
+            ($crate::Call::as_request(&call), tracker)
+        })
+    };
+}
+
 // used from generated code
 #[doc(hidden)]
 pub use jsonrpc_core::types::{
@@ -199,50 +260,735 @@ use serde::de::Deserialize;
 #[doc(hidden)]
 pub use serde_json::{self, Value};
 
-use rand;
+use ordered::response_string;
 use serde::ser::Serialize;
 use serde_json::json;
-use std::{collections::BTreeMap, marker::PhantomData};
+use std::{
+    collections::{btree_map, hash_map::Entry, BTreeMap, HashMap, VecDeque},
+    marker::PhantomData,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
+mod allow_list;
+mod arc;
+mod catch_unwind;
+pub mod client;
+pub mod codec;
+mod concurrency;
+#[cfg(feature = "conformance")]
+pub mod conformance;
+mod ctx;
+mod dyn_handler;
+mod fallback;
+mod from_params;
+mod gated;
+mod layer;
+mod limits;
+mod metered;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+mod ordered;
+mod page;
+mod pipeline;
+mod positional;
+mod progress;
+mod raw;
+mod readonly;
+mod redactor;
+mod remap;
+mod status;
+mod stub;
+#[cfg(feature = "subscriptions")]
+mod subscription;
+mod timeout;
+pub mod transport;
 #[doc(hidden)]
 pub mod util;
 
+pub use allow_list::AllowList;
+pub use arc::HandleArc;
+pub use catch_unwind::CatchUnwind;
+pub use concurrency::{ConcurrencyLimit, ConcurrencyLimits, OVERLOADED};
+pub use ctx::{with_ctx, HandleWithCtx, WithCtx};
+pub use dyn_handler::DynHandler;
+pub use fallback::Fallback;
+pub use from_params::FromParams;
+pub use gated::GatedHandler;
+pub use layer::{layer, Layer};
+pub use limits::RequestLimits;
+pub use metered::Metered;
+pub use ordered::OrderedResponse;
+pub use page::{Page, Pages, DEFAULT_PAGE_LIMIT};
+pub use pipeline::ResponsePipeline;
+pub use progress::{with_sink, HandleWithSink, WithSink, PROGRESS};
+pub use raw::{HandleRaw, RawArgs, RawParams};
+pub use readonly::{HandleReadOnly, ReadOnly};
+pub use redactor::{redact_internal_errors, Redactor, HIDDEN_DETAIL};
+pub use remap::RemapMethods;
+pub use status::StatusHint;
+pub use stub::StubHandler;
+#[cfg(feature = "subscriptions")]
+pub use subscription::{
+    HandleSubscribe, Subscription, SubscriptionId, Subscriptions, ValueStream, UNSUBSCRIBE,
+};
+pub use timeout::{WithTimeout, TIMED_OUT};
+
+/// Methods may return `Box<dyn erased_serde::Serialize>` to choose the concrete result type at
+/// runtime. The generated client function is then generic over the type to deserialize the result
+/// into. Requires the `erased-serde` feature.
+#[cfg(feature = "erased-serde")]
+pub use erased_serde;
+
 /// Handles jsonrpc requests.
+///
+/// When implementing handle by hand, `?` converts [InvalidArgs](enum.InvalidArgs.html),
+/// [ArgSerializeError](struct.ArgSerializeError.html) and error types deriving
+/// [RpcError](derive.RpcError.html) into jsonrpc errors. `serde_json::Error` and `Error` are both
+/// foreign types, so serde_json errors need an explicit `map_err`.
+///
+/// ```
+/// use easy_jsonrpc::{Error, Handler, Params, Value};
+///
+/// struct Echo;
+///
+/// impl Handler for Echo {
+///     fn handle(&mut self, method: &str, params: Params) -> Result<Value, Error> {
+///         match method {
+///             "echo" => {
+///                 let mut args = params.get_rpc_args(&["value"])?;
+///                 let value: String = serde_json::from_value(args.remove(0))
+///                     .map_err(|e| Error::invalid_params(e.to_string()))?;
+///                 Ok(Value::String(value))
+///             }
+///             _ => Err(Error::method_not_found()),
+///         }
+///     }
+/// }
+/// ```
 pub trait Handler {
     /// Type-check params and call method if method exists. This method is implemented automatically
     /// by the [rpc](../easy_jsonrpc_proc_macro/attr.rpc.html) macro.
     fn handle(&mut self, method: &str, params: Params) -> Result<Value, jsonrpc_core::Error>;
 
+    /// Like [handle](#tymethod.handle), but also told whether the method was invoked by a call or
+    /// a notification. The result of a notification, including any error, is never sent to the
+    /// client. By default this simply calls handle.
+    fn handle_with_meta(
+        &mut self,
+        method: &str,
+        params: Params,
+        kind: CallKind,
+    ) -> Result<Value, jsonrpc_core::Error> {
+        let _ = kind;
+        self.handle(method, params)
+    }
+
+    /// Like [handle_with_meta](#method.handle_with_meta), but told everything known about the
+    /// request, e.g. its deadline, see [RequestMeta](struct.RequestMeta.html). By default this
+    /// calls handle_with_meta with the kind of the call.
+    fn handle_with_request_meta(
+        &mut self,
+        method: &str,
+        params: Params,
+        meta: &RequestMeta,
+    ) -> Result<Value, jsonrpc_core::Error> {
+        self.handle_with_meta(method, params, meta.kind())
+    }
+
+    /// Whether handle knows the method, i.e. whether calling it could return something other than
+    /// a method not found error. Handlers generated by the
+    /// [rpc](../easy_jsonrpc_proc_macro/attr.rpc.html) macro answer precisely. The default
+    /// implementation can't know, so it returns true.
+    fn method_exists(&self, method: &str) -> bool {
+        let _ = method;
+        true
+    }
+
+    /// Whether calls must declare `"jsonrpc": "2.0"`, as for
+    /// [handle_request_strict](#method.handle_request_strict), whichever method handles the
+    /// request. Handlers generated for traits with the `strict_version` option return true, and
+    /// handlers wrapping another return what it does. By default false.
+    fn strict_version(&self) -> bool {
+        false
+    }
+
+    /// Like [handle](#tymethod.handle), classifying the outcome, e.g. so that middleware can count
+    /// calls to missing methods apart from calls which failed. By default the error returned by
+    /// handle is classified by its code, see [HandleOutcome](enum.HandleOutcome.html).
+    ///
+    /// ```
+    /// use easy_jsonrpc::{DynHandler, HandleOutcome, Handler, Params};
+    ///
+    /// let mut handler = DynHandler::new();
+    /// let outcome = handler.handle_detailed("missing", Params::Positional(vec![]));
+    /// assert_eq!(outcome, HandleOutcome::MethodNotFound);
+    /// ```
+    fn handle_detailed(&mut self, method: &str, params: Params) -> HandleOutcome {
+        HandleOutcome::from(self.handle(method, params))
+    }
+
+    /// Whether method accepts params which are neither an array nor an object, e.g.
+    /// `"params": 5`, as its only positional param. The jsonrpc spec doesn't allow such params,
+    /// so this is false unless the method is marked `#[rpc(lenient_params)]`.
+    fn accepts_bare_param(&self, method: &str) -> bool {
+        let _ = method;
+        false
+    }
+
+    /// Parses raw_request as a json encoded jsonrpc request, handles request according to the
+    /// jsonrpc spec. Returns the json encoded response, or None if no reply is necessary.
+    fn handle_request_str(&mut self, raw_request: &str) -> Option<String> {
+        let request: Value = match serde_json::from_str(raw_request) {
+            Ok(request) => request,
+            Err(_) => return Some(response_string(&parse_error())),
+        };
+        self.handle_request(request)
+            .as_option()
+            .map(|response| response_string(&response))
+    }
+
+    /// Handles newline delimited requests, as sent by clients speaking
+    /// [JSON Lines](https://jsonlines.org), returning the responses likewise, each followed by a
+    /// newline. Each line is handled by itself, as by
+    /// [handle_request_str](#method.handle_request_str), so a malformed line gets a parse error
+    /// without affecting the others. Blank lines and notifications produce no output.
+    ///
+    /// ```
+    /// use easy_jsonrpc::{DynHandler, Handler};
+    /// use serde_json::json;
+    ///
+    /// let mut handler = DynHandler::new();
+    /// handler.register("ping", |_| Ok(json!("pong")));
+    /// let input = r#"{"jsonrpc": "2.0", "method": "ping", "id": 1}
+    /// {"jsonrpc": "2.0", "method": "ping", "id": 2}
+    /// "#;
+    /// assert_eq!(
+    ///     handler.handle_lines(input),
+    ///     r#"{"jsonrpc":"2.0","result":"pong","id":1}
+    /// {"jsonrpc":"2.0","result":"pong","id":2}
+    /// "#
+    /// );
+    /// ```
+    fn handle_lines(&mut self, input: &str) -> String {
+        let mut output = String::new();
+        for line in input.lines().filter(|line| !line.trim().is_empty()) {
+            if let Some(response) = self.handle_request_str(line) {
+                output.push_str(&response);
+                output.push('\n');
+            }
+        }
+        output
+    }
+
+    /// Like [handle_request_str](#method.handle_request_str), but for requests read from an
+    /// untrusted source. Requests exceeding limits get an invalid request error and input which
+    /// isn't valid utf-8 gets a parse error. Never panics unless a method does.
+    fn handle_request_bytes(
+        &mut self,
+        raw_request: &[u8],
+        limits: &RequestLimits,
+    ) -> Option<String> {
+        if !limits.admits(raw_request) {
+            return Some(response_string(&invalid_request()));
+        }
+        let request: Value = match serde_json::from_slice(raw_request) {
+            Ok(request) => request,
+            Err(_) => return Some(response_string(&parse_error())),
+        };
+        self.handle_request(request)
+            .as_option()
+            .map(|response| response_string(&response))
+    }
+
+    /// Like [handle_request_bytes](#method.handle_request_bytes), for requests already known to be
+    /// utf-8.
+    fn handle_request_str_limited(
+        &mut self,
+        raw_request: &str,
+        limits: &RequestLimits,
+    ) -> Option<String> {
+        self.handle_request_bytes(raw_request.as_bytes(), limits)
+    }
+
     /// Parses raw_request as a jsonrpc request, handles request according to the jsonrpc spec.
-    fn handle_request(&mut self, raw_request: Value) -> MaybeReply {
-        let request: jsonrpc_core::Request = match serde_json::from_value(raw_request) {
+    ///
+    /// With the `tracing` feature, each call is handled inside an `rpc.dispatch` span with fields
+    /// `method`, `id`, `outcome` (`"ok"` or `"error"`) and `latency_us`.
+    fn handle_request(&mut self, mut raw_request: Value) -> MaybeReply {
+        wrap_bare_params(self, &mut raw_request);
+        let deadlines = take_deadlines(&mut raw_request);
+        let request = match parse_request(&raw_request) {
+            Some(request) => request,
+            None => return MaybeReply::Reply(parse_error()),
+        };
+        reply(self, request, deadlines)
+    }
+
+    /// Like [handle_request](#method.handle_request), but separates the three outcomes for
+    /// transports: `Ok(Some(response))` is a reply to send, including replies reporting errors in
+    /// the request or the called methods, and `Ok(None)` means no reply is necessary. `Err` means
+    /// no response could be produced at all, where handle_request would instead reply with a
+    /// generic serialization error.
+    ///
+    /// ```
+    /// use easy_jsonrpc::{DynHandler, Handler};
+    /// use serde_json::json;
+    ///
+    /// let mut handler = DynHandler::new();
+    /// let reply = handler.try_handle_request(json!({"jsonrpc": "2.0", "method": "x", "id": 1}));
+    /// assert_eq!(reply.unwrap().unwrap()["error"]["code"], json!(-32601));
+    /// let reply = handler.try_handle_request(json!({"jsonrpc": "2.0", "method": "x"}));
+    /// assert!(reply.unwrap().is_none());
+    /// ```
+    fn try_handle_request(&mut self, mut raw_request: Value) -> Result<Option<Value>, FatalError> {
+        wrap_bare_params(self, &mut raw_request);
+        let deadlines = take_deadlines(&mut raw_request);
+        let request = match parse_request(&raw_request) {
+            Some(request) => request,
+            None => return Ok(Some(parse_error())),
+        };
+        try_reply(self, request, deadlines)
+    }
+
+    /// Handles a request which was already parsed, e.g. by a pipeline producing owned requests.
+    /// The params of each call are moved into the method's arguments rather than copied, except
+    /// for arguments borrowing from them, e.g. `&str`. Returns None when no reply is necessary.
+    ///
+    /// ```
+    /// use easy_jsonrpc::{types, DynHandler, Handler};
+    /// use serde_json::json;
+    ///
+    /// let mut handler = DynHandler::new();
+    /// handler.register("len", |params| Ok(json!(params.get_positional::<Vec<u8>>(0)?.len())));
+    ///
+    /// let request: types::Request =
+    ///     serde_json::from_value(json!({"jsonrpc": "2.0", "method": "len", "params": [[1, 2]], "id": 1}))
+    ///         .unwrap();
+    /// let response = handler.handle_request_owned(request).unwrap();
+    /// assert_eq!(serde_json::to_value(response).unwrap()["result"], json!(2));
+    /// ```
+    fn handle_request_owned(&mut self, request: types::Request) -> Option<types::Response> {
+        handle_parsed_request(self, request, vec![])
+    }
+
+    /// Like [handle_request](#method.handle_request), but calls which omit the `"jsonrpc": "2.0"`
+    /// member are rejected as invalid requests instead of being tolerated.
+    ///
+    /// Both variants reject calls containing members other than `jsonrpc`, `method`, `params`, `id`
+    /// and the `meta` extension described at [RequestMeta](struct.RequestMeta.html), as well as
+    /// calls specifying a `jsonrpc` version other than `"2.0"`.
+    fn handle_request_strict(&mut self, mut raw_request: Value) -> MaybeReply {
+        wrap_bare_params(self, &mut raw_request);
+        let deadlines = take_deadlines(&mut raw_request);
+        let request = match parse_request(&raw_request) {
+            Some(request) => request,
+            None => return MaybeReply::Reply(parse_error()),
+        };
+        reply(self, require_version(request), deadlines)
+    }
+
+    /// Handles the calls of a batch in order, but stops at the first call whose method fails with
+    /// an error other than method not found. Returns the responses of the calls handled so far,
+    /// the last of which carries the error. The calls after it are never run.
+    ///
+    /// This is not part of the jsonrpc spec, under which every call of a batch is handled
+    /// independently, so only use it for clients which asked for this behavior, e.g. on a
+    /// separate endpoint. Invalid calls and calls to unknown methods get an error response but
+    /// don't stop the batch. A failing notification stops the batch without a response.
+    ///
+    /// ```
+    /// use easy_jsonrpc::{DynHandler, Error, Handler};
+    /// use serde_json::json;
+    ///
+    /// let mut handler = DynHandler::new();
+    /// handler.register("debit", |_| Err(Error::internal_error()));
+    /// handler.register("credit", |_| Ok(json!(true)));
+    /// let responses = handler.handle_batch_transactional(vec![
+    ///     json!({"jsonrpc": "2.0", "method": "debit", "id": 1}),
+    ///     json!({"jsonrpc": "2.0", "method": "credit", "id": 2}),
+    /// ]);
+    /// assert_eq!(responses.len(), 1);
+    /// assert_eq!(responses[0]["error"]["code"], json!(-32603));
+    /// ```
+    fn handle_batch_transactional(&mut self, requests: Vec<Value>) -> Vec<Value> {
+        let mut responses = Vec::new();
+        for mut raw_call in requests {
+            wrap_bare_params(self, &mut raw_call);
+            let deadline = take_deadline(&mut raw_call);
+            let call = from_value_untagged(&raw_call)
+                .unwrap_or(jsonrpc_core::Call::Invalid { id: Id::Null });
+            let mut failed = false;
+            let output = handle_call_inspect(self, call, deadline, |ret| {
+                failed = matches!(ret, Err(e) if e.code != ErrorCode::MethodNotFound);
+            });
+            if let Some(output) = output {
+                responses.push(serde_json::to_value(output).unwrap_or_else(serialization_error));
+            }
+            if failed {
+                break;
+            }
+        }
+        responses
+    }
+
+    /// Like [handle_request](#method.handle_request), also suggesting an HTTP status for the
+    /// reply. See [StatusHint](enum.StatusHint.html).
+    fn handle_request_with_status(&mut self, raw_request: Value) -> (StatusHint, MaybeReply) {
+        let reply = self.handle_request(raw_request);
+        (StatusHint::for_reply(&reply), reply)
+    }
+
+    /// Like [handle_request_str](#method.handle_request_str), also suggesting an HTTP status for
+    /// the reply. See [StatusHint](enum.StatusHint.html).
+    fn handle_request_str_with_status(
+        &mut self,
+        raw_request: &str,
+    ) -> (StatusHint, Option<String>) {
+        let request: Value = match serde_json::from_str(raw_request) {
             Ok(request) => request,
             Err(_) => {
-                return MaybeReply::Reply(serde_json::json!({
-                    "jsonrpc": "2.0",
-                    "error": {
-                        "code": -32700,
-                        "message": "Parse error"
-                    },
-                    "id": null
-                }));
+                return (
+                    StatusHint::BadRequest,
+                    Some(response_string(&parse_error())),
+                )
             }
         };
-        let response = match handle_parsed_request(self, request) {
-            Some(ret) => ret,
-            None => return MaybeReply::DontReply,
+        let (status, reply) = self.handle_request_with_status(request);
+        (
+            status,
+            reply.as_option().map(|response| response_string(&response)),
+        )
+    }
+}
+
+// Wrap the params of calls to methods accepting a bare param in an array, so they parse as the
+// method's only positional param.
+fn wrap_bare_params<H: Handler + ?Sized>(handler: &H, raw_request: &mut Value) {
+    let calls = match raw_request {
+        Value::Array(calls) => calls.iter_mut().collect(),
+        call => vec![call],
+    };
+    for call in calls {
+        let accepts = match call.get("method") {
+            Some(Value::String(method)) => handler.accepts_bare_param(method),
+            _ => false,
         };
-        MaybeReply::Reply(serde_json::to_value(response).unwrap_or_else(|e| {
-            serde_json::json!({
-                "jsonrpc": "2.0",
-                "error": {
-                    "code": SERIALZATION_ERROR,
-                    "message": "Serialization error",
-                    "data": format!("{}", e),
-                },
-                "id": null
-            })
-        }))
+        match call.get_mut("params") {
+            Some(params)
+                if accepts && !(params.is_array() || params.is_object() || params.is_null()) =>
+            {
+                *params = Value::Array(vec![params.take()]);
+            }
+            _ => {}
+        }
+    }
+}
+
+// Remove the meta member from each call, which jsonrpc_core would reject, returning the deadline
+// each call carries, in the order of the calls.
+fn take_deadlines(raw_request: &mut Value) -> Vec<Option<SystemTime>> {
+    match raw_request {
+        Value::Array(calls) => calls.iter_mut().map(take_deadline).collect(),
+        call => vec![take_deadline(call)],
+    }
+}
+
+// A malformed meta member is dropped along with any deadline it was meant to carry.
+fn take_deadline(call: &mut Value) -> Option<SystemTime> {
+    let meta = call.as_object_mut()?.remove("meta")?;
+    let deadline_ms = meta.get("deadline_ms")?.as_u64()?;
+    UNIX_EPOCH.checked_add(Duration::from_millis(deadline_ms))
+}
+
+// the meta member telling the server a call's deadline, see RequestMeta
+pub(crate) fn deadline_meta(deadline: SystemTime) -> Value {
+    let deadline_ms = deadline
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+    json!({ "deadline_ms": deadline_ms })
+}
+
+fn parse_request(raw_request: &Value) -> Option<jsonrpc_core::Request> {
+    match raw_request {
+        // a batch element which isn't even an object is an invalid request, not a parse error
+        Value::Array(calls) => Some(jsonrpc_core::Request::Batch(
+            calls
+                .iter()
+                .map(|call| {
+                    from_value_untagged(call)
+                        .unwrap_or(jsonrpc_core::Call::Invalid { id: Id::Null })
+                })
+                .collect(),
+        )),
+        _ => from_value_untagged(raw_request).ok(),
+    }
+}
+
+// Deserialize one of the untagged enums from jsonrpc_core. With serde_json's arbitrary_precision
+// feature, numbers outside the range of u64 and i64 can't pass through untagged enums when
+// deserializing from a Value. They can when deserializing from a string.
+fn from_value_untagged<T>(value: &Value) -> Result<T, serde_json::Error>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let parsed = T::deserialize(value);
+    #[cfg(feature = "arbitrary_precision")]
+    let parsed = parsed.or_else(|_| serde_json::from_str(&value.to_string()));
+    parsed
+}
+
+// handle a parsed request and serialize the response
+fn reply<S: ?Sized + Handler>(
+    slef: &mut S,
+    request: jsonrpc_core::Request,
+    deadlines: Vec<Option<SystemTime>>,
+) -> MaybeReply {
+    match try_reply(slef, request, deadlines) {
+        Ok(Some(response)) => MaybeReply::Reply(response),
+        Ok(None) => MaybeReply::DontReply,
+        Err(FatalError::Serialization(e)) => MaybeReply::Reply(serialization_error(e)),
+    }
+}
+
+fn try_reply<S: ?Sized + Handler>(
+    slef: &mut S,
+    request: jsonrpc_core::Request,
+    deadlines: Vec<Option<SystemTime>>,
+) -> Result<Option<Value>, FatalError> {
+    handle_parsed_request(slef, request, deadlines)
+        .map(|response| serde_json::to_value(response).map_err(FatalError::Serialization))
+        .transpose()
+}
+
+fn serialization_error(e: serde_json::Error) -> Value {
+    serde_json::json!({
+        "jsonrpc": "2.0",
+        "error": {
+            "code": SERIALZATION_ERROR,
+            "message": "Serialization error",
+            "data": format!("{}", e),
+        },
+        "id": null
+    })
+}
+
+// mark calls which don't declare their jsonrpc version as invalid
+fn require_version(request: jsonrpc_core::Request) -> jsonrpc_core::Request {
+    match request {
+        jsonrpc_core::Request::Single(call) => {
+            jsonrpc_core::Request::Single(require_call_version(call))
+        }
+        jsonrpc_core::Request::Batch(calls) => {
+            jsonrpc_core::Request::Batch(calls.into_iter().map(require_call_version).collect())
+        }
+    }
+}
+
+fn require_call_version(call: jsonrpc_core::Call) -> jsonrpc_core::Call {
+    match call {
+        jsonrpc_core::Call::MethodCall(MethodCall {
+            jsonrpc: None, id, ..
+        }) => jsonrpc_core::Call::Invalid { id },
+        jsonrpc_core::Call::Notification(Notification { jsonrpc: None, .. }) => {
+            jsonrpc_core::Call::Invalid { id: Id::Null }
+        }
+        call => call,
+    }
+}
+
+// the response to a request which is not valid json, or is not shaped like a jsonrpc request
+fn parse_error() -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "error": {
+            "code": -32700,
+            "message": "Parse error"
+        },
+        "id": null
+    })
+}
+
+fn invalid_request() -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "error": jsonrpc_core::Error::invalid_request(),
+        "id": null
+    })
+}
+
+/// How a method was invoked. Passed to
+/// [Handler::handle_with_meta](trait.Handler.html#method.handle_with_meta), and part of
+/// [RequestMeta](struct.RequestMeta.html).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CallKind {
+    /// The request has an id and expects a response.
+    MethodCall,
+    /// The request has no id. No response is sent, not even on error.
+    Notification,
+}
+
+/// What a handler is told about a call besides its method and params. Passed to
+/// [Handler::handle_with_request_meta](trait.Handler.html#method.handle_with_request_meta).
+///
+/// Besides the members the jsonrpc spec defines, a call may carry a `meta` member, an extension
+/// of this crate, which the server takes out before handling the call:
+/// `{"jsonrpc": "2.0", "method": "report", "id": 1, "meta": {"deadline_ms": 1700000000000}}`.
+/// `deadline_ms` is the time after which the caller no longer wants the result, in milliseconds
+/// since the unix epoch, as sent by
+/// [Client::call_with_deadline](client/struct.Client.html#method.call_with_deadline). Servers
+/// may use it to give up on work no one is waiting for. Comparing it to the server's clock
+/// assumes the clocks of client and server roughly agree.
+///
+/// ```
+/// use easy_jsonrpc::{Error, Handler, Params, RequestMeta, Value};
+/// use serde_json::json;
+/// use std::time::SystemTime;
+///
+/// struct Report;
+/// impl Handler for Report {
+///     fn handle(&mut self, _method: &str, _params: Params) -> Result<Value, Error> {
+///         Ok(json!("full report"))
+///     }
+///
+///     fn handle_with_request_meta(
+///         &mut self,
+///         method: &str,
+///         params: Params,
+///         meta: &RequestMeta,
+///     ) -> Result<Value, Error> {
+///         match meta.deadline() {
+///             Some(deadline) if deadline < SystemTime::now() => Ok(json!("no time")),
+///             _ => self.handle(method, params),
+///         }
+///     }
+/// }
+///
+/// let response = Report.handle_request(json!({
+///     "jsonrpc": "2.0",
+///     "method": "report",
+///     "id": 1,
+///     "meta": {"deadline_ms": 1000},
+/// }));
+/// assert_eq!(response.as_option().unwrap()["result"], json!("no time"));
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RequestMeta {
+    kind: CallKind,
+    deadline: Option<SystemTime>,
+    id: Option<Id>,
+}
+
+impl RequestMeta {
+    /// Meta for a call of the given kind without a deadline or id.
+    pub fn new(kind: CallKind) -> Self {
+        RequestMeta {
+            kind,
+            deadline: None,
+            id: None,
+        }
+    }
+
+    /// Set the time after which the caller no longer wants the result.
+    pub fn with_deadline(self, deadline: SystemTime) -> Self {
+        RequestMeta {
+            deadline: Some(deadline),
+            ..self
+        }
+    }
+
+    /// Whether the method was invoked by a call or a notification.
+    pub fn kind(&self) -> CallKind {
+        self.kind
+    }
+
+    /// The time after which the caller no longer wants the result, if it sent one.
+    pub fn deadline(&self) -> Option<SystemTime> {
+        self.deadline
+    }
+
+    /// Set the id of the call.
+    pub fn with_id(self, id: Id) -> Self {
+        RequestMeta {
+            id: Some(id),
+            ..self
+        }
+    }
+
+    /// The id of the call, which notifications don't have.
+    pub fn id(&self) -> Option<&Id> {
+        self.id.as_ref()
+    }
+}
+
+/// The outcome of a call, as classified by
+/// [Handler::handle_detailed](trait.Handler.html#method.handle_detailed).
+///
+/// Errors are classified by their code alone, not by where they were raised. A method may return
+/// an error with any code, e.g. through `#[rpc(error_code = ..)]` or a type deriving
+/// [RpcError](derive.RpcError.html), or by returning
+/// `Error::invalid_params` from a hand written handler. Such an error is classified as
+/// `InvalidParams` or `MethodNotFound` when it has their code, just like an error raised before
+/// the method ran.
+#[derive(Clone, PartialEq, Debug)]
+pub enum HandleOutcome {
+    /// The method ran and produced a result.
+    Ok(Value),
+    /// The call failed with an error whose code is not one of those below.
+    AppError(Error),
+    /// The call failed with the method not found code, usually because there is no such method.
+    MethodNotFound,
+    /// The call failed with the invalid params code, usually because the params couldn't be
+    /// converted to the method's arguments. Holds the error sent to the client.
+    InvalidParams(Error),
+}
+
+impl HandleOutcome {
+    /// The result handle would have returned.
+    pub fn into_result(self) -> Result<Value, Error> {
+        match self {
+            HandleOutcome::Ok(value) => Ok(value),
+            HandleOutcome::AppError(error) | HandleOutcome::InvalidParams(error) => Err(error),
+            HandleOutcome::MethodNotFound => Err(Error::method_not_found()),
+        }
+    }
+}
+
+impl From<Result<Value, Error>> for HandleOutcome {
+    fn from(result: Result<Value, Error>) -> Self {
+        match result {
+            Ok(value) => HandleOutcome::Ok(value),
+            Err(error) => match error.code {
+                ErrorCode::MethodNotFound => HandleOutcome::MethodNotFound,
+                ErrorCode::InvalidParams => HandleOutcome::InvalidParams(error),
+                _ => HandleOutcome::AppError(error),
+            },
+        }
+    }
+}
+
+/// Returned by [Handler::try_handle_request](trait.Handler.html#method.try_handle_request) when
+/// no response can be produced. Responses hold only json values, so this is not expected in
+/// practice, but transports can report it as a server failure rather than sending a reply.
+#[derive(Debug)]
+pub enum FatalError {
+    /// The response could not be serialized.
+    Serialization(serde_json::Error),
+}
+
+impl std::fmt::Display for FatalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FatalError::Serialization(e) => write!(f, "failed to serialize response: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for FatalError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FatalError::Serialization(e) => Some(e),
+        }
     }
 }
 
@@ -265,11 +1011,74 @@ impl MaybeReply {
     }
 }
 
+/// Write responses to writer as a json array, one at a time, without first collecting them into a
+/// single buffer. The output is identical to serializing a `Vec` of the responses.
+///
+/// Combined with handling the calls of a batch one by one, a large batch can be answered without
+/// holding every response in memory.
+///
+/// ```
+/// use easy_jsonrpc::{serialize_batch_to, DynHandler, Handler};
+/// use serde_json::{json, Value};
+///
+/// let mut handler = DynHandler::new();
+/// handler.register("double", |params| Ok(json!(params.get_positional::<i64>(0)? * 2)));
+/// let batch: Vec<Value> = (0..3)
+///     .map(|i| json!({"jsonrpc": "2.0", "method": "double", "params": [i], "id": i}))
+///     .collect();
+/// let mut out = Vec::new();
+/// let responses = batch
+///     .into_iter()
+///     .filter_map(|call| handler.handle_request(call).as_option());
+/// serialize_batch_to(responses, &mut out).unwrap();
+/// assert_eq!(
+///     serde_json::from_slice::<Value>(&out).unwrap()[2],
+///     json!({"jsonrpc": "2.0", "result": 4, "id": 2})
+/// );
+/// ```
+pub fn serialize_batch_to<W, T>(
+    responses: impl IntoIterator<Item = T>,
+    mut writer: W,
+) -> Result<(), serde_json::Error>
+where
+    W: std::io::Write,
+    T: serde::Serialize,
+{
+    writer.write_all(b"[").map_err(serde_json::Error::io)?;
+    for (index, response) in responses.into_iter().enumerate() {
+        if index != 0 {
+            writer.write_all(b",").map_err(serde_json::Error::io)?;
+        }
+        serde_json::to_writer(&mut writer, &response)?;
+    }
+    writer.write_all(b"]").map_err(serde_json::Error::io)
+}
+
 /// extract method name and parameters from call
 /// if call is a normal method call, call `handle` and return result
 /// if call is a notification, call `handle` and return None
 /// if call is invalid return a jsonrpc failure
-fn handle_call<S: ?Sized + Handler>(slef: &mut S, call: jsonrpc_core::Call) -> Option<Output> {
+fn handle_call<S: ?Sized + Handler>(
+    slef: &mut S,
+    call: jsonrpc_core::Call,
+    deadline: Option<SystemTime>,
+) -> Option<Output> {
+    handle_call_inspect(slef, call, deadline, |_| ())
+}
+
+// like handle_call, also passing what the method returned to inspect, if it was called
+fn handle_call_inspect<S: ?Sized + Handler>(
+    slef: &mut S,
+    call: jsonrpc_core::Call,
+    deadline: Option<SystemTime>,
+    inspect: impl FnOnce(&Result<Value, Error>),
+) -> Option<Output> {
+    // every path handling a parsed call passes through here, so strictness holds for all of them
+    let call = if slef.strict_version() {
+        require_call_version(call)
+    } else {
+        call
+    };
     let (method, params, maybe_id, version): (
         String,
         jsonrpc_core::Params,
@@ -277,7 +1086,7 @@ fn handle_call<S: ?Sized + Handler>(slef: &mut S, call: jsonrpc_core::Call) -> O
         Option<Version>,
     ) = match call {
         jsonrpc_core::Call::Invalid { id } => {
-            return Some(Output::invalid_request(id, None));
+            return Some(Output::invalid_request(id, Some(Version::V2)));
         }
         jsonrpc_core::Call::MethodCall(MethodCall {
             method,
@@ -292,7 +1101,48 @@ fn handle_call<S: ?Sized + Handler>(slef: &mut S, call: jsonrpc_core::Call) -> O
         }) => (method, params, None, jsonrpc),
     };
     let args = Params::from_rc_params(params);
-    let ret = slef.handle(&method, args);
+    let id = maybe_id.clone();
+    dispatch(&method, maybe_id, version, |kind| {
+        let meta = RequestMeta { kind, deadline, id };
+        let ret = slef.handle_with_request_meta(&method, args, &meta);
+        inspect(&ret);
+        ret
+    })
+}
+
+// call a method and wrap what it returns in an output, unless the call is a notification
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+fn dispatch(
+    method: &str,
+    maybe_id: Option<Id>,
+    version: Option<Version>,
+    call: impl FnOnce(CallKind) -> Result<Value, Error>,
+) -> Option<Output> {
+    let kind = match maybe_id {
+        Some(_) => CallKind::MethodCall,
+        None => CallKind::Notification,
+    };
+    #[cfg(feature = "tracing")]
+    let span = tracing::info_span!(
+        "rpc.dispatch",
+        method = %method,
+        id = tracing::field::Empty,
+        outcome = tracing::field::Empty,
+        latency_us = tracing::field::Empty,
+    );
+    #[cfg(feature = "tracing")]
+    if let Some(id) = &maybe_id {
+        // notifications have no id, leave the field empty
+        span.record("id", serde_json::to_string(id).unwrap_or_default());
+    }
+    #[cfg(feature = "tracing")]
+    let (_entered, start) = (span.enter(), std::time::Instant::now());
+    let ret = call(kind);
+    #[cfg(feature = "tracing")]
+    {
+        span.record("outcome", if ret.is_ok() { "ok" } else { "error" });
+        span.record("latency_us", start.elapsed().as_micros() as u64);
+    }
     let id = maybe_id?;
     Some(match ret {
         Ok(ok) => Output::Success(Success {
@@ -313,15 +1163,22 @@ fn handle_call<S: ?Sized + Handler>(slef: &mut S, call: jsonrpc_core::Call) -> O
 fn handle_parsed_request<S: ?Sized + Handler>(
     slef: &mut S,
     request: jsonrpc_core::Request,
+    deadlines: Vec<Option<SystemTime>>,
 ) -> Option<jsonrpc_core::Response> {
+    // a request which isn't an array has one deadline, possibly None
+    let mut deadlines = deadlines.into_iter();
     match request {
         jsonrpc_core::Request::Single(call) => {
-            handle_call(slef, call).map(jsonrpc_core::Response::Single)
+            handle_call(slef, call, deadlines.next().flatten()).map(jsonrpc_core::Response::Single)
         }
+        // the spec asks for a single error in response to an empty batch
+        jsonrpc_core::Request::Batch(calls) if calls.is_empty() => Some(
+            jsonrpc_core::Response::Single(Output::invalid_request(Id::Null, Some(Version::V2))),
+        ),
         jsonrpc_core::Request::Batch(mut calls) => {
             let outputs = calls
                 .drain(..)
-                .filter_map(|call| handle_call(slef, call))
+                .filter_map(|call| handle_call(slef, call, deadlines.next().flatten()))
                 .collect::<Vec<_>>();
             if outputs.is_empty() {
                 None
@@ -332,20 +1189,115 @@ fn handle_parsed_request<S: ?Sized + Handler>(
     }
 }
 
+// Take named args in the order of names, filling in omitted optional args. Shared by Params and
+// RawParams, which check for extra args themselves.
+fn order_named<V>(
+    mut take: impl FnMut(&str) -> Option<V>,
+    names: &[&'static str],
+    required: usize,
+    null: impl Fn() -> V,
+) -> Result<Vec<V>, InvalidArgs> {
+    let mut ar: Vec<V> = Vec::with_capacity(names.len());
+    for (index, name) in names.iter().enumerate() {
+        match take(name) {
+            Some(value) => ar.push(value),
+            None if index >= required => ar.push(null()),
+            None => {
+                return Err(InvalidArgs::MissingNamedParameter {
+                    name: name.to_string(),
+                })
+            }
+        }
+    }
+    Ok(ar)
+}
+
+// Check the number of positional args, filling in omitted optional args.
+fn check_arity<V>(
+    mut ar: Vec<V>,
+    names: &[&'static str],
+    required: usize,
+    null: impl Fn() -> V,
+) -> Result<Vec<V>, InvalidArgs> {
+    if ar.len() < required || ar.len() > names.len() {
+        Err(InvalidArgs::WrongNumberOfArgs {
+            expected: if ar.len() < required {
+                required
+            } else {
+                names.len()
+            },
+            actual: ar.len(),
+        })
+    } else {
+        ar.resize_with(names.len(), null);
+        Ok(ar)
+    }
+}
+
 #[doc(hidden)]
 #[derive(
     Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, serde::Serialize, serde::Deserialize,
 )]
 pub enum InvalidArgs {
-    WrongNumberOfArgs { expected: usize, actual: usize },
-    ExtraNamedParameter { name: String },
-    MissingNamedParameter { name: &'static str },
-    InvalidArgStructure { name: &'static str, index: usize },
+    WrongNumberOfArgs {
+        expected: usize,
+        actual: usize,
+    },
+    ExtraNamedParameter {
+        name: String,
+    },
+    MissingNamedParameter {
+        name: String,
+    },
+    InvalidArgStructure {
+        name: &'static str,
+        index: usize,
+    },
+    MissingPositionalParameter {
+        index: usize,
+    },
+    InvalidNamedArg {
+        name: String,
+    },
+    InvalidPositionalArg {
+        index: usize,
+    },
+    InvalidParamsStructure,
+    IntegerOutOfRange {
+        name: &'static str,
+        index: usize,
+        ty: &'static str,
+    },
+    NotAnInteger {
+        name: &'static str,
+        index: usize,
+    },
+    RestArgsRequirePositional,
+    ParamTooLarge {
+        name: &'static str,
+        index: usize,
+        max: usize,
+    },
+    InvalidArgValue {
+        name: &'static str,
+        index: usize,
+        expected: &'static str,
+        error: String,
+    },
 }
 
-impl Into<Error> for InvalidArgs {
-    fn into(self) -> Error {
-        match self {
+// described by the message of the invalid params error it becomes
+impl std::fmt::Display for InvalidArgs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&Error::from(self.clone()).message)
+    }
+}
+
+impl std::error::Error for InvalidArgs {}
+
+impl From<InvalidArgs> for Error {
+    fn from(invalid: InvalidArgs) -> Error {
+        match invalid {
             InvalidArgs::WrongNumberOfArgs { expected, actual } => Error::invalid_params(format!(
                 "WrongNumberOfArgs. Expected {}. Actual {}",
                 expected, actual
@@ -360,29 +1312,75 @@ impl Into<Error> for InvalidArgs {
                 "InvalidArgStructure {} at position {}.",
                 name, index
             )),
+            InvalidArgs::MissingPositionalParameter { index } => {
+                Error::invalid_params(format!("MissingPositionalParameter at position {}", index))
+            }
+            InvalidArgs::InvalidNamedArg { name } => {
+                Error::invalid_params(format!("InvalidNamedArg {}", name))
+            }
+            InvalidArgs::InvalidPositionalArg { index } => {
+                Error::invalid_params(format!("InvalidPositionalArg at position {}", index))
+            }
+            InvalidArgs::InvalidParamsStructure => Error::invalid_params("InvalidParamsStructure"),
+            InvalidArgs::IntegerOutOfRange { name, index, ty } => Error::invalid_params(format!(
+                "IntegerOutOfRange {} at position {}. Number out of range for {}.",
+                name, index, ty
+            )),
+            InvalidArgs::NotAnInteger { name, index } => Error::invalid_params(format!(
+                "NotAnInteger {} at position {}. Expected integer, got float.",
+                name, index
+            )),
+            InvalidArgs::RestArgsRequirePositional => Error::invalid_params(
+                "RestArgsRequirePositional. Variadic methods must be called with positional params.",
+            ),
+            InvalidArgs::ParamTooLarge { name, index, max } => Error::invalid_params(format!(
+                "ParamTooLarge {} at position {}. Exceeds {} bytes.",
+                name, index, max
+            )),
+            // worded like InvalidArgStructure, which it replaces, so existing clients aren't broken
+            InvalidArgs::InvalidArgValue {
+                name,
+                index,
+                expected,
+                error,
+            } => Error {
+                data: Some(json!({
+                    "name": name.trim_matches('"'),
+                    "expected": expected,
+                    "error": error,
+                })),
+                ..Error::invalid_params(format!(
+                    "InvalidArgStructure {} at position {}.",
+                    name, index
+                ))
+            },
         }
     }
 }
 
-/// Represetaion of jsonrpc arguments. Passing no arguments is assumed to be semantically equivalent
-/// to passing 0 positional args, or passing a map with zero entries.
+/// Represetaion of jsonrpc arguments. Passing no arguments, or passing `null`, is assumed to be
+/// semantically equivalent to passing 0 positional args, or passing a map with zero entries.
 ///
 /// Users of this library will rarely need to deal with this type.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum Params {
-    /// Arguments were either not present (expressed as a length 0 list), or arguments were provided as
-    /// a json list.
+    /// Arguments were provided as a json list.
     Positional(Vec<Value>),
     /// Arguments were provided as a json dictionary.
     Named(serde_json::Map<String, Value>),
+    /// Arguments were not present, or were `null`. Treated as a length 0 list, except by
+    /// [into_raw](#method.into_raw).
+    Absent,
 }
 
 impl Params {
+    // Omitted params and `"params": null` both parse as jsonrpc_core::Params::None, so the two are
+    // indistinguishable here. Either is accepted only by methods taking zero arguments.
     fn from_rc_params(params: jsonrpc_core::Params) -> Self {
         match params {
             jsonrpc_core::Params::Array(arr) => Params::Positional(arr),
             jsonrpc_core::Params::Map(map) => Params::Named(map),
-            jsonrpc_core::Params::None => Params::Positional(vec![]),
+            jsonrpc_core::Params::None => Params::Absent,
         }
     }
 
@@ -394,6 +1392,17 @@ impl Params {
     ///    - No missing args in named parameter object
     ///    - No extra args in named parameter object
     pub fn get_rpc_args(self, names: &[&'static str]) -> Result<Vec<Value>, InvalidArgs> {
+        self.get_rpc_args_with_optional(names, names.len())
+    }
+
+    /// Like [get_rpc_args](#method.get_rpc_args), except only the first `required` names must be
+    /// provided. Omitted args are returned as `Value::Null`.
+    pub fn get_rpc_args_with_optional(
+        self,
+        names: &[&'static str],
+        required: usize,
+    ) -> Result<Vec<Value>, InvalidArgs> {
+        debug_assert!(required <= names.len());
         debug_assert!(
             {
                 fn contains_duplicates(list: &[&str]) -> bool {
@@ -405,36 +1414,262 @@ impl Params {
         );
         let ar: Vec<Value> = match self {
             Params::Positional(ar) => ar,
+            Params::Absent => vec![],
             Params::Named(mut ma) => {
-                let mut ar: Vec<Value> = Vec::with_capacity(names.len());
-                for name in names.iter() {
-                    ar.push(
-                        ma.remove(*name)
-                            .ok_or(InvalidArgs::MissingNamedParameter { name })?,
-                    );
-                }
-                debug_assert_eq!(ar.len(), names.len());
-                match ma.keys().next() {
-                    Some(key) => {
-                        return Err(InvalidArgs::ExtraNamedParameter { name: key.clone() })
-                    }
-                    None => ar,
+                let ar = order_named(|name| ma.remove(name), names, required, || Value::Null)?;
+                // the least key, so the error doesn't depend on whether the map preserves order
+                if let Some(key) = ma.keys().min() {
+                    return Err(InvalidArgs::ExtraNamedParameter { name: key.clone() });
                 }
+                ar
             }
         };
-        if ar.len() != names.len() {
-            Err(InvalidArgs::WrongNumberOfArgs {
-                expected: names.len(),
+        check_arity(ar, names, required, || Value::Null)
+    }
+
+    /// Like [get_rpc_args_with_optional](#method.get_rpc_args_with_optional), except any params
+    /// after the named ones are collected into a trailing array, which is always returned. Params
+    /// must be positional.
+    pub fn get_rpc_args_with_rest(
+        self,
+        names: &[&'static str],
+        required: usize,
+    ) -> Result<Vec<Value>, InvalidArgs> {
+        debug_assert!(required <= names.len());
+        let mut ar: Vec<Value> = match self {
+            Params::Positional(ar) => ar,
+            Params::Absent => vec![],
+            Params::Named(_) => return Err(InvalidArgs::RestArgsRequirePositional),
+        };
+        if ar.len() < required {
+            return Err(InvalidArgs::WrongNumberOfArgs {
+                expected: required,
                 actual: ar.len(),
-            })
-        } else {
-            Ok(ar)
+            });
+        }
+        let rest = ar.split_off(names.len().min(ar.len()));
+        ar.resize(names.len(), Value::Null);
+        ar.push(Value::Array(rest));
+        Ok(ar)
+    }
+
+    /// Deserialize the named parameter `name`. Fails if params were not passed by name, if the
+    /// parameter is missing, or if it can't be interpreted as a T.
+    ///
+    /// ```
+    /// # use easy_jsonrpc::Params;
+    /// # use serde_json::json;
+    /// let params = Params::Named(json!({ "a": 1 }).as_object().unwrap().clone());
+    /// assert_eq!(params.get::<u8>("a"), Ok(1));
+    /// assert!(params.get::<u8>("b").is_err());
+    /// ```
+    pub fn get<'a, T: Deserialize<'a>>(&'a self, name: &str) -> Result<T, InvalidArgs> {
+        let value = match self {
+            Params::Named(ma) => ma.get(name),
+            Params::Positional(_) | Params::Absent => None,
+        }
+        .ok_or_else(|| InvalidArgs::MissingNamedParameter {
+            name: name.to_owned(),
+        })?;
+        util::from_serde_json_value_ref(value).map_err(|_| InvalidArgs::InvalidNamedArg {
+            name: name.to_owned(),
+        })
+    }
+
+    /// Deserialize positional params as a whole, typically into a tuple with one element per
+    /// param. A tuple must be given exactly as many params as it has elements. A param which can't
+    /// be deserialized is reported by position, with serde's explanation in the error's data.
+    /// Fails if params were passed by name.
+    ///
+    /// ```
+    /// # use easy_jsonrpc::Params;
+    /// # use serde_json::json;
+    /// let params = Params::Positional(vec![json!(1), json!("two")]);
+    /// let (a, b): (u64, String) = params.parse_positional().unwrap();
+    /// assert_eq!((a, b.as_str()), (1, "two"));
+    /// ```
+    pub fn parse_positional<T: serde::de::DeserializeOwned>(self) -> Result<T, Error> {
+        match self {
+            Params::Positional(ar) => positional::parse_positional(ar),
+            Params::Absent => positional::parse_positional(vec![]),
+            Params::Named(_) => Err(InvalidArgs::InvalidParamsStructure.into()),
+        }
+    }
+
+    /// The number of params passed by position, or None if they were passed by name. Absent
+    /// params are treated as an empty list, so there are 0 of them.
+    ///
+    /// ```
+    /// # use easy_jsonrpc::Params;
+    /// # use serde_json::json;
+    /// let params = Params::Positional(vec![json!(1), json!("two")]);
+    /// assert_eq!(params.positional_len(), Some(2));
+    ///
+    /// let params = Params::Named(json!({"a": 1}).as_object().unwrap().clone());
+    /// assert_eq!(params.positional_len(), None);
+    ///
+    /// // absent params
+    /// assert_eq!(Params::Absent.positional_len(), Some(0));
+    /// ```
+    pub fn positional_len(&self) -> Option<usize> {
+        match self {
+            Params::Positional(ar) => Some(ar.len()),
+            Params::Absent => Some(0),
+            Params::Named(_) => None,
+        }
+    }
+
+    /// Whether no params were passed, whether by position or by name, or params were absent.
+    ///
+    /// ```
+    /// # use easy_jsonrpc::Params;
+    /// # use serde_json::json;
+    /// assert!(!Params::Positional(vec![json!(1)]).is_empty());
+    ///
+    /// assert!(!Params::Named(json!({"a": 1}).as_object().unwrap().clone()).is_empty());
+    /// assert!(Params::Named(serde_json::Map::new()).is_empty());
+    ///
+    /// // absent params
+    /// assert!(Params::Absent.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Params::Positional(ar) => ar.is_empty(),
+            Params::Named(map) => map.is_empty(),
+            Params::Absent => true,
+        }
+    }
+
+    /// Deserialize the positional parameter at `index`. Fails if params were not passed by
+    /// position, if there is no parameter at `index`, or if it can't be interpreted as a T.
+    ///
+    /// ```
+    /// # use easy_jsonrpc::Params;
+    /// # use serde_json::json;
+    /// let params = Params::Positional(vec![json!(1), json!("two")]);
+    /// assert_eq!(params.get_positional::<String>(1), Ok("two".to_owned()));
+    /// assert!(params.get_positional::<String>(0).is_err());
+    /// ```
+    pub fn get_positional<'a, T: Deserialize<'a>>(
+        &'a self,
+        index: usize,
+    ) -> Result<T, InvalidArgs> {
+        let value = match self {
+            Params::Positional(ar) => ar.get(index),
+            Params::Named(_) | Params::Absent => None,
+        }
+        .ok_or(InvalidArgs::MissingPositionalParameter { index })?;
+        util::from_serde_json_value_ref(value)
+            .map_err(|_| InvalidArgs::InvalidPositionalArg { index })
+    }
+
+    /// Iterate over positional params without copying them, e.g. to handle a variable number of
+    /// arguments. Returns None if params were passed by name. Absent params yield an empty
+    /// iterator.
+    ///
+    /// ```
+    /// # use easy_jsonrpc::Params;
+    /// # use serde_json::json;
+    /// let params = Params::Positional(vec![json!(1), json!(2), json!(3)]);
+    /// let sum: i64 = params
+    ///     .positional_iter()
+    ///     .unwrap()
+    ///     .filter_map(|value| value.as_i64())
+    ///     .sum();
+    /// assert_eq!(sum, 6);
+    ///
+    /// let params = Params::Named(json!({ "a": 1 }).as_object().unwrap().clone());
+    /// assert!(params.positional_iter().is_none());
+    /// ```
+    pub fn positional_iter(&self) -> Option<std::slice::Iter<'_, Value>> {
+        match self {
+            Params::Positional(ar) => Some(ar.iter()),
+            Params::Absent => Some([].iter()),
+            Params::Named(_) => None,
         }
     }
+
+    /// The params as they were received, for handlers that forward params verbatim rather than
+    /// extracting individual arguments: an array, an object, or `null` if params were absent or
+    /// `null`. The params are moved, not copied.
+    ///
+    /// ```
+    /// # use easy_jsonrpc::Params;
+    /// # use serde_json::{json, Value};
+    /// let params = Params::Named(json!({ "a": 1 }).as_object().unwrap().clone());
+    /// assert_eq!(params.into_raw(), json!({ "a": 1 }));
+    /// assert_eq!(Params::Absent.into_raw(), Value::Null);
+    /// ```
+    pub fn into_raw(self) -> Value {
+        match self {
+            Params::Positional(ar) => Value::Array(ar),
+            Params::Named(ma) => Value::Object(ma),
+            Params::Absent => Value::Null,
+        }
+    }
+
+    /// Deserialize the params as a whole. Positional params are presented to T as a sequence,
+    /// named params are presented as a map.
+    ///
+    /// ```
+    /// # use easy_jsonrpc::Params;
+    /// # use serde_json::json;
+    /// #[derive(serde::Deserialize)]
+    /// struct Transfer {
+    ///     to: String,
+    ///     amount: u64,
+    /// }
+    ///
+    /// let params = Params::Named(json!({ "to": "bob", "amount": 3 }).as_object().unwrap().clone());
+    /// let transfer: Transfer = params.parse().unwrap();
+    /// assert_eq!(transfer.amount, 3);
+    ///
+    /// let params = Params::Positional(vec![json!("bob"), json!(3)]);
+    /// let (to, amount): (String, u64) = params.parse().unwrap();
+    /// assert_eq!(to, "bob");
+    /// ```
+    pub fn parse<'a, T: Deserialize<'a>>(&'a self) -> Result<T, InvalidArgs> {
+        let parsed = match self {
+            Params::Positional(ar) => {
+                T::deserialize(serde::de::value::SeqDeserializer::new(ar.iter()))
+            }
+            Params::Absent => T::deserialize(serde::de::value::SeqDeserializer::new(
+                <&[Value]>::default().iter(),
+            )),
+            Params::Named(ma) => T::deserialize(serde::de::value::MapDeserializer::new(
+                ma.iter().map(|(k, v)| (k.as_str(), v)),
+            )),
+        };
+        parsed.map_err(|_: serde_json::Error| InvalidArgs::InvalidParamsStructure)
+    }
+}
+
+/// The name and params of a method, generated for each method by the
+/// [rpc](../easy_jsonrpc_proc_macro/attr.rpc.html) macro as a const in the client helper enum,
+/// e.g. `my_api::CHECKED_ADD`. Lets tools enumerate an api without calling it.
+///
+/// ```
+/// #[easy_jsonrpc::rpc]
+/// pub trait Adder {
+///     fn checked_add(&self, a: u64, b: u64) -> Option<u64>;
+/// }
+///
+/// assert_eq!(adder::CHECKED_ADD.name, "checked_add");
+/// assert_eq!(adder::CHECKED_ADD.params, ["a", "b"]);
+/// assert_eq!(adder::CHECKED_ADD.types, ["u64", "u64"]);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct MethodInfo {
+    /// The method's name on the wire.
+    pub name: &'static str,
+    /// The names of the method's params, in positional order.
+    pub params: &'static [&'static str],
+    /// The type of each param, as written in the trait.
+    pub types: &'static [&'static str],
 }
 
 // Intentionally does not implement Serialize; we don't want users to accidentally send a call by
-// itself. Does not implement clone because Vec<Value> is potentially expensive to clone.
+// itself. Does not implement clone because Value is potentially expensive to clone.
 /// Create a binding of arguments to a method name. Can be turned into either a jsonrpc call using
 /// [call](#method.call), or a jsonrpc notification using [notification](#method.notification).
 #[derive(Debug)]
@@ -443,8 +1678,10 @@ where
     T: Deserialize<'static>,
 {
     method: &'a str,
-    args: Vec<Value>,
-    _spook: PhantomData<*const T>,
+    params: Value,
+    id: Option<RequestId>,
+    decode: fn(Value) -> Result<T, serde_json::Error>,
+    _spook: PhantomData<fn() -> T>,
 }
 
 impl<'a, T> BoundMethod<'a, T>
@@ -454,29 +1691,96 @@ where
     /// Create a binding of arguments to a method name.
     /// You probably don't want to use this method directly.
     /// Try using the rpc macro instead.
-    pub fn new(method: &'a str, args: Vec<Value>) -> BoundMethod<T> {
+    pub fn new(method: &'a str, args: Vec<Value>) -> BoundMethod<'a, T> {
+        BoundMethod::bind(method, Value::Array(args))
+    }
+
+    /// Bind already serialized params to a method name, e.g. to forward a call received from
+    /// elsewhere without deserializing its arguments. Params must be an array or an object, which
+    /// servers would answer with an invalid request error, so anything else is returned as an
+    /// error.
+    ///
+    /// ```
+    /// use easy_jsonrpc::BoundMethod;
+    /// use serde_json::json;
+    ///
+    /// let bind = BoundMethod::<i32>::raw("subtract", json!({"minuend": 3, "subtrahend": 1}))
+    ///     .unwrap();
+    /// let (call, _tracker) = bind.call();
+    /// assert_eq!(call.as_request()["params"], json!({"minuend": 3, "subtrahend": 1}));
+    /// assert!(BoundMethod::<i32>::raw("subtract", json!(3)).is_err());
+    /// ```
+    pub fn raw(method: &'a str, params: Value) -> Result<BoundMethod<'a, T>, InvalidRawParams> {
+        match params {
+            Value::Array(_) | Value::Object(_) => Ok(BoundMethod::bind(method, params)),
+            other => Err(InvalidRawParams(other)),
+        }
+    }
+
+    // params must be an array or an object
+    fn bind(method: &'a str, params: Value) -> BoundMethod<'a, T> {
         BoundMethod {
             method,
-            args,
+            params,
+            id: None,
+            decode: |value| T::deserialize(value),
             _spook: PhantomData,
         }
     }
 
-    /// Create a jsonrpc method call with a random id and a tracker for retrieving the return value.
+    /// Use id, rather than a random number, as the id of calls created by [call](#method.call).
+    /// Responses are matched to calls by id, so calls sent in the same batch must have distinct
+    /// ids.
+    pub fn with_id(self, id: impl Into<RequestId>) -> Self {
+        BoundMethod {
+            id: Some(id.into()),
+            ..self
+        }
+    }
+
+    /// Replace the function used to interpret the return value. By default the return value is
+    /// deserialized using T's Deserialize implementation.
+    pub fn with_decoder(self, decode: fn(Value) -> Result<T, serde_json::Error>) -> Self {
+        BoundMethod { decode, ..self }
+    }
+
+    /// The name of the method to be called, as sent on the wire.
+    pub fn method_name(&self) -> &str {
+        self.method
+    }
+
+    /// The serialized params, an array of arguments in positional order unless the method was
+    /// bound by name using [raw](#method.raw). Use `params().as_array()` for the arguments of
+    /// methods bound positionally.
+    pub fn params(&self) -> &Value {
+        &self.params
+    }
+
+    /// Create a jsonrpc method call and a tracker for retrieving the return value. The call has a
+    /// random id unless one was set using [with_id](#method.with_id).
     pub fn call(&'a self) -> (Call<'a>, Tracker<T>)
     where
         T: Deserialize<'static>,
     {
-        let Self { method, args, .. } = self;
-        let id = rand::random::<u64>();
+        let Self {
+            method,
+            params,
+            id,
+            decode,
+            ..
+        } = self;
+        let id = id
+            .clone()
+            .unwrap_or_else(|| RequestId::from(rand::random::<u64>()));
         (
             Call {
                 method,
-                args,
-                id: Some(id),
+                params,
+                id: Some(id.clone()),
             },
             Tracker {
                 id,
+                decode: *decode,
                 _spook: PhantomData,
             },
         )
@@ -485,41 +1789,144 @@ where
     /// Create a jsonrpc method call with no id. Jsonrpc servers accept notifications silently.
     /// That is to say, they handle the notification, but send to reasponse.
     pub fn notification(&'a self) -> Call<'a> {
-        let Self { method, args, .. } = self;
+        let Self { method, params, .. } = self;
         Call {
             method,
-            args,
+            params,
             id: None,
         }
     }
+
+    /// Convert to a jsonrpc request object with the given id, in place of any set using
+    /// [with_id](#method.with_id), ready to be serialized and sent, e.g. by a hand written
+    /// transport. Unlike [call](#method.call) no tracker is returned, so
+    /// parse the response with [Response](struct.Response.html) and decode the result yourself,
+    /// or use call when the method's decoder matters.
+    ///
+    /// ```
+    /// use easy_jsonrpc::BoundMethod;
+    /// use serde_json::json;
+    ///
+    /// let bind = BoundMethod::<i32>::new("subtract", vec![json!(3), json!(1)]);
+    /// assert_eq!(
+    ///     bind.into_request(7),
+    ///     json!({"jsonrpc": "2.0", "method": "subtract", "params": [3, 1], "id": 7})
+    /// );
+    /// ```
+    pub fn into_request(self, id: impl Into<RequestId>) -> Value {
+        self.with_id(id).call().0.as_request()
+    }
+
+    /// Convert to a jsonrpc notification object, which has no id, ready to be serialized and
+    /// sent. The server sends no response.
+    pub fn into_notification(self) -> Value {
+        self.notification().as_request()
+    }
+}
+
+/// Builds the request object for a call, generated for each method by the
+/// [rpc](../easy_jsonrpc_proc_macro/attr.rpc.html) macro's `request_builder` option, e.g.
+/// `MyApiRequest::checked_add(1, 2)`. An argument which fails to serialize is reported by
+/// [build](#method.build).
+///
+/// ```
+/// use serde_json::json;
+///
+/// #[easy_jsonrpc::rpc(request_builder)]
+/// pub trait Adder {
+///     fn checked_add(&self, a: u64, b: u64) -> Option<u64>;
+/// }
+///
+/// assert_eq!(
+///     AdderRequest::checked_add(1, 2).with_id(5).build().unwrap(),
+///     json!({"jsonrpc": "2.0", "method": "checked_add", "params": [1, 2], "id": 5})
+/// );
+/// assert_eq!(
+///     AdderRequest::checked_add(1, 2).notification().build().unwrap(),
+///     json!({"jsonrpc": "2.0", "method": "checked_add", "params": [1, 2]})
+/// );
+/// ```
+#[derive(Debug)]
+pub struct RequestBuilder<T>
+where
+    T: Deserialize<'static>,
+{
+    bound: Result<BoundMethod<'static, T>, ArgSerializeError>,
+    notification: bool,
+}
+
+impl<T> RequestBuilder<T>
+where
+    T: Deserialize<'static>,
+{
+    /// Build requests for the call bound by a client function.
+    pub fn new(bound: Result<BoundMethod<'static, T>, ArgSerializeError>) -> Self {
+        RequestBuilder {
+            bound,
+            notification: false,
+        }
+    }
+
+    /// Use id, rather than a random number, as the id of the request. See
+    /// [BoundMethod::with_id](struct.BoundMethod.html#method.with_id).
+    pub fn with_id(self, id: impl Into<RequestId>) -> Self {
+        RequestBuilder {
+            bound: self.bound.map(|bound| bound.with_id(id)),
+            ..self
+        }
+    }
+
+    /// Build a notification, which has no id, rather than a call.
+    pub fn notification(self) -> Self {
+        RequestBuilder {
+            notification: true,
+            ..self
+        }
+    }
+
+    /// The request object, ready to be serialized and sent.
+    pub fn build(self) -> Result<Value, ArgSerializeError> {
+        let bound = self.bound?;
+        Ok(if self.notification {
+            bound.notification().as_request()
+        } else {
+            bound.call().0.as_request()
+        })
+    }
+
+    /// The underlying binding, e.g. to also get a [Tracker](struct.Tracker.html) for the
+    /// response. The choice of a notification is dropped.
+    pub fn into_bound(self) -> Result<BoundMethod<'static, T>, ArgSerializeError> {
+        self.bound
+    }
 }
 
 // Intentionally does not implement Serialize; we don't want users to accidentally send a call by
-// itself. Does not implement clone because Vec<Value> is potentially expensive to clone.
+// itself. Does not implement clone because Value is potentially expensive to clone.
 /// A single rpc method call with arguments. May be sent to the server by itself using
 /// [as_request](#method.as_request), or as a batch, using
 /// [batch_request](#method.batch_request).
 pub struct Call<'a> {
     method: &'a str,
-    args: &'a [Value],
-    id: Option<u64>,
+    params: &'a Value,
+    id: Option<RequestId>,
 }
 
 impl<'a> Call<'a> {
     /// Convert call to a json object which can be serialized and sent to a jsonrpc server.
     pub fn as_request(&self) -> Value {
-        let Self { method, id, args } = self;
+        let Self { method, id, params } = self;
         match id {
             Some(id) => json!({
                 "jsonrpc": "2.0",
                 "method": method,
-                "params": args,
-                "id": id,
+                "params": params,
+                "id": id.to_value(),
             }),
             None => json!({
                 "jsonrpc": "2.0",
                 "method": method,
-                "params": args,
+                "params": params,
             }),
         }
     }
@@ -527,14 +1934,186 @@ impl<'a> Call<'a> {
     /// Convert list of calls to a json object which can be serialized and sent to a jsonrpc server.
     pub fn batch_request(calls: &[Self]) -> Value {
         debug_assert!({
-            fn contains_duplicates(list: &[u64]) -> bool {
+            fn contains_duplicates(list: &[&RequestId]) -> bool {
                 (1..list.len()).any(|i| list[i..].contains(&list[i - 1]))
             }
-            let ids = calls.iter().filter_map(|call| call.id).collect::<Vec<_>>();
+            let ids = calls
+                .iter()
+                .filter_map(|call| call.id.as_ref())
+                .collect::<Vec<_>>();
             !contains_duplicates(ids.as_slice())
         });
         Value::Array(calls.iter().map(Call::as_request).collect())
     }
+
+    /// Like [batch_request](#method.batch_request), sending calls which repeat an earlier call's
+    /// method and params only once. Calls are compared by their serialized params. Pass the
+    /// server's response to [Duplicates::fan_out](struct.Duplicates.html#method.fan_out) before
+    /// retrieving return values, so every tracker finds its output. Notifications are all sent.
+    ///
+    /// ```
+    /// use easy_jsonrpc::{BoundMethod, Call, Response};
+    /// use serde_json::json;
+    ///
+    /// let bind = BoundMethod::<u64>::new("balance", vec![json!("alice")]);
+    /// let (first, first_tracker) = bind.call();
+    /// let (second, second_tracker) = bind.call();
+    /// let (request, duplicates) = Call::batch_request_deduped(&[first, second]);
+    /// assert_eq!(request.as_array().unwrap().len(), 1);
+    ///
+    /// let id = request[0]["id"].clone();
+    /// let mut response =
+    ///     Response::from_json_response(json!([{"jsonrpc": "2.0", "result": 5, "id": id}]))
+    ///         .unwrap();
+    /// duplicates.fan_out(&mut response);
+    /// assert_eq!(first_tracker.get_return(&mut response), Ok(5));
+    /// assert_eq!(second_tracker.get_return(&mut response), Ok(5));
+    /// ```
+    pub fn batch_request_deduped(calls: &[Self]) -> (Value, Duplicates) {
+        let mut sent: HashMap<(&str, String), &RequestId> = HashMap::new();
+        let mut aliases = Vec::new();
+        let mut requests = Vec::new();
+        for call in calls {
+            if let Some(id) = &call.id {
+                match sent.entry((call.method, call.params.to_string())) {
+                    Entry::Occupied(original) => {
+                        aliases.push(((*original.get()).clone(), id.clone()));
+                        continue;
+                    }
+                    Entry::Vacant(slot) => {
+                        slot.insert(id);
+                    }
+                }
+            }
+            requests.push(call);
+        }
+        debug_assert!({
+            let ids = requests
+                .iter()
+                .filter_map(|call| call.id.as_ref())
+                .collect::<std::collections::HashSet<_>>();
+            ids.len() == sent.len()
+        });
+        (
+            Value::Array(requests.into_iter().map(Call::as_request).collect()),
+            Duplicates { aliases },
+        )
+    }
+}
+
+/// The calls left out of a batch by
+/// [batch_request_deduped](struct.Call.html#method.batch_request_deduped), each paired with the
+/// call sent in its place.
+#[derive(Clone, Debug, Default)]
+pub struct Duplicates {
+    // (id of the call sent, id of the call left out)
+    aliases: Vec<(RequestId, RequestId)>,
+}
+
+impl Duplicates {
+    /// Copy the output of each sent call to the calls left out in its favour, so their trackers
+    /// find it in response.
+    pub fn fan_out(&self, response: &mut Response) {
+        for (sent, left_out) in &self.aliases {
+            if let Some(output) = response.get(sent.clone()).cloned() {
+                response
+                    .outputs
+                    .insert(left_out.clone(), VecDeque::from([output]));
+            }
+        }
+    }
+
+    /// Whether no call was left out.
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+}
+
+/// The id of a jsonrpc call. Any integer representable as a u64 or an i64, any string, or null.
+///
+/// ```
+/// # use easy_jsonrpc::RequestId;
+/// assert_eq!(RequestId::from(-1), RequestId::from(-1i64));
+/// assert_eq!(RequestId::from(1i64), RequestId::from(1u64));
+/// assert_ne!(RequestId::from("1"), RequestId::from(1));
+/// ```
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct RequestId(IdRepr);
+
+// Non-negative integers are always Unsigned, so each id has exactly one representation.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+enum IdRepr {
+    Null,
+    Negative(i64),
+    Unsigned(u64),
+    Str(String),
+}
+
+impl RequestId {
+    /// The id `null`.
+    pub fn null() -> Self {
+        RequestId(IdRepr::Null)
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Null => Some(RequestId::null()),
+            Value::Number(n) => n
+                .as_u64()
+                .map(RequestId::from)
+                .or_else(|| n.as_i64().map(RequestId::from)),
+            Value::String(s) => Some(RequestId::from(s.as_str())),
+            _ => None,
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        match &self.0 {
+            IdRepr::Null => Value::Null,
+            IdRepr::Negative(n) => json!(n),
+            IdRepr::Unsigned(n) => json!(n),
+            IdRepr::Str(s) => json!(s),
+        }
+    }
+}
+
+impl From<u64> for RequestId {
+    fn from(id: u64) -> Self {
+        RequestId(IdRepr::Unsigned(id))
+    }
+}
+
+impl From<i64> for RequestId {
+    fn from(id: i64) -> Self {
+        match u64::try_from(id) {
+            Ok(id) => RequestId::from(id),
+            Err(_) => RequestId(IdRepr::Negative(id)),
+        }
+    }
+}
+
+impl From<u32> for RequestId {
+    fn from(id: u32) -> Self {
+        RequestId::from(u64::from(id))
+    }
+}
+
+impl From<i32> for RequestId {
+    fn from(id: i32) -> Self {
+        RequestId::from(i64::from(id))
+    }
+}
+
+impl From<String> for RequestId {
+    fn from(id: String) -> Self {
+        RequestId(IdRepr::Str(id))
+    }
+}
+
+impl From<&str> for RequestId {
+    fn from(id: &str) -> Self {
+        RequestId::from(id.to_owned())
+    }
 }
 
 /// used from generated code
@@ -543,10 +2122,18 @@ pub fn try_serialize<T: Serialize>(t: &T) -> Result<Value, Error> {
     // Serde serde_json::to_value does not perform io. It's still not safe to unwrap the result. For
     // example, the implementation of Serialize for Mutex returns an error if the mutex is poisined.
     // Another example, serialize(&std::Path) returns an error when it encounters invalid utf-8.
-    serde_json::to_value(t).map_err(|e| Error {
-        code: ErrorCode::ServerError(SERIALZATION_ERROR),
-        message: "Serialization error".to_owned(),
+    try_serialize_with(t, Serialize::serialize)
+}
+
+/// used from generated code, serialize using a serde "with" style function
+#[doc(hidden)]
+pub fn try_serialize_with<T: ?Sized, F>(t: &T, serialize: F) -> Result<Value, Error>
+where
+    F: FnOnce(&T, serde_json::value::Serializer) -> Result<Value, serde_json::Error>,
+{
+    serialize(t, serde_json::value::Serializer).map_err(|e| Error {
         data: Some(Value::String(format!("{}", e))),
+        ..Error::internal_error()
     })
 }
 
@@ -561,108 +2148,296 @@ pub enum ResponseFail {
     RpcError(Error),
 }
 
+impl std::fmt::Display for ResponseFail {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResponseFail::ResultNotFound => f.write_str("no result for the call in the response"),
+            ResponseFail::InvalidResponse => f.write_str("malformed result for the call"),
+            ResponseFail::RpcError(err) => write!(f, "call failed: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ResponseFail {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ResponseFail::RpcError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
 /// Thrown when arguments fail to be serialized. Possible causes include, but are not limited to:
 /// - A poisoned mutex
 /// - A cstring containing invalid utf-8
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct ArgSerializeError;
 
+impl std::fmt::Display for ArgSerializeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("failed to serialize arguments")
+    }
+}
+
+impl std::error::Error for ArgSerializeError {}
+
+/// Returned by [BoundMethod::raw](struct.BoundMethod.html#method.raw) when the params are neither
+/// an array nor an object. Holds the rejected params.
+#[derive(Clone, PartialEq, Debug)]
+pub struct InvalidRawParams(pub Value);
+
+impl std::fmt::Display for InvalidRawParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("params must be an array or an object")
+    }
+}
+
+impl std::error::Error for InvalidRawParams {}
+
+impl From<ArgSerializeError> for Error {
+    fn from(_: ArgSerializeError) -> Error {
+        Error {
+            message: "Internal error: failed to serialize arguments".into(),
+            ..Error::internal_error()
+        }
+    }
+}
+
 /// Returned by [from_json_response](struct.Response.html#method.from_json_response) on error.
 #[derive(Clone, PartialEq, Debug)]
+#[non_exhaustive]
 pub enum InvalidResponse {
     /// Response is not a valid jsonrpc response.
     DeserailizeFailure,
-    /// Response contains an id that is not number. The client helpers in easy_jsonrpc never send
-    /// non-number ids, so if the server responds with a non-number id, something is wrong.
+    /// Response contains an id that is not an integer, a string or null. The client helpers in
+    /// easy_jsonrpc never send such ids, so if the server responds with one, something is wrong.
     ContainsNonNumericId,
+    /// Response contains more than one output with the same id, and was parsed with
+    /// [DuplicateIds::Reject](enum.DuplicateIds.html#variant.Reject).
+    DuplicateId(RequestId),
+}
+
+impl std::fmt::Display for InvalidResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidResponse::DeserailizeFailure => f.write_str("not a valid jsonrpc response"),
+            InvalidResponse::ContainsNonNumericId => {
+                f.write_str("response contains an id which is not an integer, a string or null")
+            }
+            InvalidResponse::DuplicateId(id) => {
+                write!(
+                    f,
+                    "response contains more than one output with id {}",
+                    id.to_value()
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for InvalidResponse {}
+
+/// How [from_json_response_with](struct.Response.html#method.from_json_response_with) treats a
+/// batch response holding more than one output with the same id. The jsonrpc spec doesn't forbid
+/// a client from sending calls with the same id, but their outputs can't be told apart by id.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum DuplicateIds {
+    /// Reject the response with
+    /// [InvalidResponse::DuplicateId](enum.InvalidResponse.html#variant.DuplicateId), rather
+    /// than give a call another call's output.
+    #[default]
+    Reject,
+    /// Keep every output, handing out those sharing an id in the order they appear in the
+    /// response: the first [remove](struct.Response.html#method.remove) of the id returns the
+    /// first of them, the next the second, and so on. Read the trackers of calls sharing an id in
+    /// the order the calls were sent, from a server answering batches in order, as servers using
+    /// this crate do.
+    Positional,
 }
 
 /// Special purpose structure for holding a group of responses. Allows for response lookup by id.
-/// Does not support non-number ids.
+#[derive(Default)]
 pub struct Response {
-    /// Mapping from id to output of rpc call.
-    pub outputs: BTreeMap<u64, Result<Value, Error>>,
+    // Mapping from id to the outputs of rpc calls with that id, in the order they appear in the
+    // response. More than one only with DuplicateIds::Positional; never empty.
+    outputs: BTreeMap<RequestId, VecDeque<Result<Value, Error>>>,
 }
 
 impl Response {
-    /// Deserialize response from a jsonrpc server.
+    /// Deserialize response from a jsonrpc server. Responses holding more than one output with
+    /// the same id are rejected, see [DuplicateIds](enum.DuplicateIds.html).
     pub fn from_json_response(raw_jsonrpc_response: Value) -> Result<Self, InvalidResponse> {
-        let response: jsonrpc_core::Response = serde_json::from_value(raw_jsonrpc_response)
-            .map_err(|_| InvalidResponse::DeserailizeFailure)?;
-        let mut calls: Vec<Output> = match response {
-            jsonrpc_core::Response::Single(out) => vec![out],
-            jsonrpc_core::Response::Batch(outs) => outs,
+        Self::from_json_response_with(raw_jsonrpc_response, DuplicateIds::Reject)
+    }
+
+    /// Like [from_json_response](#method.from_json_response), treating outputs with the same id
+    /// according to duplicates.
+    ///
+    /// ```
+    /// use easy_jsonrpc::{DuplicateIds, InvalidResponse, RequestId, Response};
+    /// use serde_json::json;
+    ///
+    /// let raw = json!([
+    ///     {"jsonrpc": "2.0", "result": "first", "id": 1},
+    ///     {"jsonrpc": "2.0", "result": "second", "id": 1},
+    /// ]);
+    /// assert_eq!(
+    ///     Response::from_json_response(raw.clone()).err(),
+    ///     Some(InvalidResponse::DuplicateId(RequestId::from(1)))
+    /// );
+    /// let mut response = Response::from_json_response_with(raw, DuplicateIds::Positional).unwrap();
+    /// assert_eq!(response.remove(1), Some(Ok(json!("first"))));
+    /// assert_eq!(response.remove(1), Some(Ok(json!("second"))));
+    /// assert_eq!(response.remove(1), None);
+    /// ```
+    pub fn from_json_response_with(
+        raw_jsonrpc_response: Value,
+        duplicates: DuplicateIds,
+    ) -> Result<Self, InvalidResponse> {
+        let raw_outputs: Vec<Value> = match raw_jsonrpc_response {
+            Value::Array(outs) => outs,
+            out => vec![out],
         };
-        debug_assert!({
-            fn contains_duplicates(list: &[u64]) -> bool {
-                (1..list.len()).any(|i| list[i..].contains(&list[i - 1]))
+        // jsonrpc_core only accepts non-negative integer ids, so ids are read separately and
+        // replaced with null before parsing the rest of each output.
+        let calls = raw_outputs
+            .into_iter()
+            .map(|mut out| -> Result<(RequestId, Output), InvalidResponse> {
+                let raw_id = out
+                    .as_object_mut()
+                    .and_then(|out| out.insert("id".into(), Value::Null))
+                    .ok_or(InvalidResponse::DeserailizeFailure)?;
+                let out: Output =
+                    from_value_untagged(&out).map_err(|_| InvalidResponse::DeserailizeFailure)?;
+                let id =
+                    RequestId::from_value(&raw_id).ok_or(InvalidResponse::ContainsNonNumericId)?;
+                Ok((id, out))
+            })
+            .collect::<Result<Vec<_>, InvalidResponse>>()?;
+        let mut response = Self::default();
+        for (id, out) in calls {
+            let output = match out {
+                Output::Success(Success { result, .. }) => Ok(result),
+                Output::Failure(Failure { error, .. }) => Err(error),
+            };
+            match response.outputs.entry(id) {
+                btree_map::Entry::Vacant(entry) => {
+                    entry.insert(VecDeque::from([output]));
+                }
+                btree_map::Entry::Occupied(mut entry) if duplicates == DuplicateIds::Positional => {
+                    entry.get_mut().push_back(output);
+                }
+                btree_map::Entry::Occupied(entry) => {
+                    return Err(InvalidResponse::DuplicateId(entry.key().clone()))
+                }
             }
-            let ids = calls
-                .iter()
-                .filter_map(|out| match out {
-                    Output::Success(Success {
-                        id: Id::Num(id), ..
-                    })
-                    | Output::Failure(Failure {
-                        id: Id::Num(id), ..
-                    }) => Some(*id),
-                    _ => None,
-                })
-                .collect::<Vec<_>>();
-            !contains_duplicates(ids.as_slice())
-        });
-        let outputs = calls
-            .drain(..)
-            .map(
-                |out| -> Result<(u64, Result<Value, Error>), InvalidResponse> {
-                    match out {
-                        Output::Success(Success {
-                            result,
-                            id: Id::Num(id),
-                            ..
-                        }) => Ok((id, Ok(result))),
-                        Output::Failure(Failure {
-                            error,
-                            id: Id::Num(id),
-                            ..
-                        }) => Ok((id, Err(error))),
-                        _ => Err(InvalidResponse::ContainsNonNumericId),
-                    }
-                },
-            )
-            .collect::<Result<BTreeMap<u64, Result<Value, Error>>, InvalidResponse>>()?;
-        Ok(Self { outputs })
+        }
+        Ok(response)
     }
 
-    /// Retrieve the output with a matching id and return it, return None if no such output exists.
-    pub fn remove(&mut self, id: u64) -> Option<Result<Value, Error>> {
-        self.outputs.remove(&id)
+    /// A response containing a single successful output.
+    pub fn success(id: impl Into<RequestId>, result: Value) -> Self {
+        Self {
+            outputs: std::iter::once((id.into(), VecDeque::from([Ok(result)]))).collect(),
+        }
     }
-}
 
-/// Links a jsonrpc id to a return type.
-/// Trackers can be used to get a typed return value from a json response.
-pub struct Tracker<T>
-where
-    T: Deserialize<'static>,
-{
-    id: u64,
-    _spook: PhantomData<*const T>,
-}
+    /// A response containing a single failed output.
+    pub fn error(id: impl Into<RequestId>, error: Error) -> Self {
+        Self {
+            outputs: std::iter::once((id.into(), VecDeque::from([Err(error)]))).collect(),
+        }
+    }
 
-impl<T> Tracker<T>
-where
-    T: Deserialize<'static>,
-{
-    /// Get typed return value from server response.
-    /// If response contains the return value for this request, remove it from the
-    /// server response and attempt to interpret it as a value with type T.
-    pub fn get_return(&self, response: &mut Response) -> Result<T, ResponseFail> {
-        let result = response
-            .remove(self.id)
-            .ok_or(ResponseFail::ResultNotFound)?;
-        let raw_return = result.map_err(ResponseFail::RpcError)?;
-        <T>::deserialize(raw_return).map_err(|_| ResponseFail::InvalidResponse)
+    /// The output with a matching id, as [remove](#method.remove) would return it, without
+    /// removing it.
+    pub fn get(&self, id: impl Into<RequestId>) -> Option<&Result<Value, Error>> {
+        self.outputs.get(&id.into())?.front()
+    }
+
+    /// Every output, by id. Outputs sharing an id, kept by
+    /// [DuplicateIds::Positional](enum.DuplicateIds.html#variant.Positional), follow each other in
+    /// the order they appear in the response.
+    pub fn outputs(&self) -> impl Iterator<Item = (&RequestId, &Result<Value, Error>)> {
+        self.outputs
+            .iter()
+            .flat_map(|(id, outputs)| outputs.iter().map(move |output| (id, output)))
+    }
+
+    /// The number of outputs, counting each of those sharing an id.
+    pub fn len(&self) -> usize {
+        self.outputs.values().map(VecDeque::len).sum()
+    }
+
+    /// Whether the response holds no outputs.
+    pub fn is_empty(&self) -> bool {
+        self.outputs.is_empty()
+    }
+
+    /// Retrieve the output with a matching id and return it, return None if no such output exists.
+    /// Outputs sharing an id, kept by
+    /// [DuplicateIds::Positional](enum.DuplicateIds.html#variant.Positional), are retrieved one at
+    /// a time.
+    pub fn remove(&mut self, id: impl Into<RequestId>) -> Option<Result<Value, Error>> {
+        let id = id.into();
+        let outputs = self.outputs.get_mut(&id)?;
+        let output = outputs.pop_front();
+        if outputs.is_empty() {
+            self.outputs.remove(&id);
+        }
+        output
+    }
+}
+
+/// Renders the response as jsonrpc. A response with exactly one output is rendered as a single
+/// response object, any other number of outputs as a batch.
+impl std::fmt::Display for Response {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let count = self.len();
+        let mut outputs = self.outputs().map(|(id, output)| match output {
+            Ok(result) => json!({
+                "jsonrpc": "2.0",
+                "result": result,
+                "id": id.to_value(),
+            }),
+            Err(error) => json!({
+                "jsonrpc": "2.0",
+                "error": error,
+                "id": id.to_value(),
+            }),
+        });
+        let response = match (count, outputs.next()) {
+            (1, Some(output)) => output,
+            (_, first) => Value::Array(first.into_iter().chain(outputs).collect()),
+        };
+        write!(f, "{}", response)
+    }
+}
+
+/// Links a jsonrpc id to a return type.
+/// Trackers can be used to get a typed return value from a json response.
+pub struct Tracker<T>
+where
+    T: Deserialize<'static>,
+{
+    id: RequestId,
+    decode: fn(Value) -> Result<T, serde_json::Error>,
+    _spook: PhantomData<fn() -> T>,
+}
+
+impl<T> Tracker<T>
+where
+    T: Deserialize<'static>,
+{
+    /// Get typed return value from server response.
+    /// If response contains the return value for this request, remove it from the
+    /// server response and attempt to interpret it as a value with type T.
+    pub fn get_return(&self, response: &mut Response) -> Result<T, ResponseFail> {
+        let result = response
+            .remove(self.id.clone())
+            .ok_or(ResponseFail::ResultNotFound)?;
+        let raw_return = result.map_err(ResponseFail::RpcError)?;
+        (self.decode)(raw_return).map_err(|_| ResponseFail::InvalidResponse)
     }
 }
 
@@ -672,7 +2447,6 @@ mod test {
         pub use crate::*;
     }
     use super::{Handler, InvalidArgs, MaybeReply, Params};
-    use jsonrpc_core;
     use serde_json::{json, Value};
 
     #[easy_jsonrpc::rpc]
@@ -746,6 +2520,55 @@ mod test {
         }
     }
 
+    // collects the name and fields of every span, and the level and fields of every event
+    #[cfg(feature = "tracing")]
+    #[derive(Clone, Default)]
+    struct Recorder {
+        spans: std::sync::Arc<std::sync::Mutex<Vec<(String, Fields)>>>,
+        events: std::sync::Arc<std::sync::Mutex<Vec<(tracing::Level, Fields)>>>,
+    }
+
+    #[cfg(feature = "tracing")]
+    type Fields = Vec<(String, String)>;
+
+    #[cfg(feature = "tracing")]
+    struct Visitor<'a>(&'a mut Fields);
+
+    #[cfg(feature = "tracing")]
+    impl tracing::field::Visit for Visitor<'_> {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.0.push((field.name().into(), format!("{:?}", value)));
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    impl tracing::Subscriber for Recorder {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            let mut spans = self.spans.lock().unwrap();
+            let mut fields = Vec::new();
+            span.record(&mut Visitor(&mut fields));
+            spans.push((span.metadata().name().into(), fields));
+            tracing::span::Id::from_u64(spans.len() as u64)
+        }
+        fn record(&self, span: &tracing::span::Id, values: &tracing::span::Record<'_>) {
+            let mut spans = self.spans.lock().unwrap();
+            let (_, fields) = &mut spans[span.into_u64() as usize - 1];
+            values.record(&mut Visitor(fields));
+        }
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+        fn event(&self, event: &tracing::Event<'_>) {
+            let mut fields = Vec::new();
+            event.record(&mut Visitor(&mut fields));
+            let level = *event.metadata().level();
+            self.events.lock().unwrap().push((level, fields));
+        }
+        fn enter(&self, _: &tracing::span::Id) {}
+        fn exit(&self, _: &tracing::span::Id) {}
+    }
+
     #[test]
     fn batch() {
         assert_adder_response(
@@ -1210,4 +3033,6180 @@ mod test {
             })
         );
     }
+
+    #[test]
+    fn hex_bytes() {
+        use std::cell::RefCell;
+
+        #[easy_jsonrpc::rpc]
+        trait Store {
+            fn store(&self, #[rpc_arg(hex)] data: Vec<u8>);
+            #[rpc(hex)]
+            fn load(&self) -> Vec<u8>;
+        }
+
+        struct StoreImpl(RefCell<Vec<u8>>);
+        impl Store for StoreImpl {
+            fn store(&self, data: Vec<u8>) {
+                *self.0.borrow_mut() = data;
+            }
+
+            fn load(&self) -> Vec<u8> {
+                self.0.borrow().clone()
+            }
+        }
+
+        let store = StoreImpl(RefCell::new(vec![]));
+        let mut handler = &store as &dyn Store;
+        assert_eq!(
+            handler.handle_request(json!({
+                "jsonrpc": "2.0",
+                "method": "store",
+                "params": ["0xdeadbeef"],
+                "id": 1
+            })),
+            MaybeReply::Reply(json!({
+                "jsonrpc": "2.0",
+                "result": null,
+                "id": 1
+            }))
+        );
+        assert_eq!(*store.0.borrow(), vec![0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(
+            handler.handle_request(json!({
+                "jsonrpc": "2.0",
+                "method": "load",
+                "id": 1
+            })),
+            MaybeReply::Reply(json!({
+                "jsonrpc": "2.0",
+                "result": "0xdeadbeef",
+                "id": 1
+            }))
+        );
+
+        // client helpers encode and decode hex too
+        let bind = store::store(vec![0x01, 0xff]).unwrap();
+        let (call, _) = bind.call();
+        assert_eq!(call.as_request()["params"], json!(["0x01ff"]));
+        handler.handle_request(call.as_request());
+        let bind = store::load().unwrap();
+        let (call, tracker) = bind.call();
+        let raw_response = handler
+            .handle_request(call.as_request())
+            .as_option()
+            .unwrap();
+        let mut response = easy_jsonrpc::Response::from_json_response(raw_response).unwrap();
+        assert_eq!(tracker.get_return(&mut response).unwrap(), vec![0x01, 0xff]);
+
+        for malformed in &["0xdeadbee", "0xnothex!", "[1, 2]"] {
+            let raw_response = handler
+                .handle_request(json!({
+                    "jsonrpc": "2.0",
+                    "method": "store",
+                    "params": [malformed],
+                    "id": 1
+                }))
+                .as_option()
+                .unwrap();
+            assert_eq!(raw_response["error"]["code"], json!(-32602));
+        }
+    }
+
+    #[test]
+    fn params_typed_access() {
+        let named = Params::Named(json!({ "a": 1, "b": "two" }).as_object().unwrap().clone());
+        assert_eq!(named.get::<u8>("a"), Ok(1));
+        assert_eq!(named.get::<&str>("b"), Ok("two"));
+        assert_eq!(
+            named.get::<u8>("b"),
+            Err(InvalidArgs::InvalidNamedArg { name: "b".into() })
+        );
+        assert_eq!(
+            named.get::<u8>("c"),
+            Err(InvalidArgs::MissingNamedParameter { name: "c".into() })
+        );
+        assert_eq!(
+            named.get_positional::<u8>(0),
+            Err(InvalidArgs::MissingPositionalParameter { index: 0 })
+        );
+
+        let positional = Params::Positional(vec![json!(1), json!("two")]);
+        assert_eq!(positional.get_positional::<u8>(0), Ok(1));
+        assert_eq!(
+            positional.get_positional::<u8>(1),
+            Err(InvalidArgs::InvalidPositionalArg { index: 1 })
+        );
+        assert_eq!(
+            positional.get_positional::<u8>(2),
+            Err(InvalidArgs::MissingPositionalParameter { index: 2 })
+        );
+        assert_eq!(
+            positional.get::<u8>("a"),
+            Err(InvalidArgs::MissingNamedParameter { name: "a".into() })
+        );
+        assert_eq!(positional.parse::<(u8, String)>(), Ok((1, "two".into())));
+        assert_eq!(
+            positional.parse::<(u8,)>(),
+            Err(InvalidArgs::InvalidParamsStructure)
+        );
+        assert_eq!(
+            positional.parse::<(u8, u8)>(),
+            Err(InvalidArgs::InvalidParamsStructure)
+        );
+    }
+
+    #[test]
+    fn dyn_handler() {
+        let mut handler = easy_jsonrpc::DynHandler::new();
+        handler.register("greet", |_| Ok(json!("hello")));
+        handler.register("add", |params| {
+            let (a, b): (i64, i64) = params.parse()?;
+            Ok(json!(a + b))
+        });
+
+        assert_eq!(
+            handler.handle("greet", Params::Positional(vec![])),
+            Ok(json!("hello"))
+        );
+        assert_eq!(
+            handler.handle("add", Params::Positional(vec![json!(1), json!(2)])),
+            Ok(json!(3))
+        );
+        assert_eq!(
+            handler
+                .handle("add", Params::Positional(vec![json!(1)]))
+                .unwrap_err()
+                .code,
+            jsonrpc_core::ErrorCode::InvalidParams
+        );
+        assert_eq!(
+            handler.handle("nonexist", Params::Positional(vec![])),
+            Err(jsonrpc_core::Error::method_not_found())
+        );
+
+        // registering under an existing name replaces the method
+        handler.register("greet", |_| Ok(json!("hi")));
+        assert_eq!(
+            handler.handle("greet", Params::Positional(vec![])),
+            Ok(json!("hi"))
+        );
+    }
+
+    #[test]
+    fn result_serialization_failure() {
+        use std::collections::BTreeMap;
+
+        #[easy_jsonrpc::rpc]
+        trait Pairs {
+            fn pairs(&self) -> BTreeMap<(u8, u8), u8>;
+        }
+
+        impl Pairs for () {
+            fn pairs(&self) -> BTreeMap<(u8, u8), u8> {
+                // serde_json only accepts strings and numbers as object keys
+                vec![((1, 2), 3)].into_iter().collect()
+            }
+        }
+
+        assert_eq!(
+            (&() as &dyn Pairs).handle_request(json!({
+                "jsonrpc": "2.0",
+                "method": "pairs",
+                "id": 1
+            })),
+            MaybeReply::Reply(json!({
+                "jsonrpc": "2.0",
+                "error": {
+                    "code": -32603,
+                    "message": "Internal error",
+                    "data": "key must be a string"
+                },
+                "id": 1
+            }))
+        );
+    }
+
+    #[test]
+    fn request_str() {
+        let mut handler = &AdderImpl {} as &dyn Adder;
+        let response = handler
+            .handle_request_str(
+                r#"{"jsonrpc": "2.0", "method": "wrapping_add", "params": [1, 1], "id": 1}"#,
+            )
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&response).unwrap(),
+            json!({"jsonrpc": "2.0", "result": 2, "id": 1})
+        );
+        assert_eq!(
+            handler.handle_request_str(
+                r#"{"jsonrpc": "2.0", "method": "wrapping_add", "params": [1, 1]}"#
+            ),
+            None
+        );
+        let response = handler.handle_request_str("{\"jsonrpc\": ").unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&response).unwrap(),
+            json!({"jsonrpc": "2.0", "error": {"code": -32700, "message": "Parse error"}, "id": null})
+        );
+    }
+
+    #[test]
+    fn cow_args() {
+        use std::borrow::Cow;
+
+        #[easy_jsonrpc::rpc]
+        trait Parse {
+            fn parse(&self, s: Cow<str>) -> (String, bool);
+            fn parse_bytes(&self, b: std::borrow::Cow<'_, [u8]>) -> (Vec<u8>, bool);
+        }
+
+        impl Parse for () {
+            fn parse(&self, s: Cow<str>) -> (String, bool) {
+                (s.to_string(), matches!(s, Cow::Borrowed(_)))
+            }
+
+            fn parse_bytes(&self, b: Cow<[u8]>) -> (Vec<u8>, bool) {
+                (b.to_vec(), matches!(b, Cow::Borrowed(_)))
+            }
+        }
+
+        let mut handler = &() as &dyn Parse;
+        let mut call = |method: &str, params: Value| {
+            handler
+                .handle_request(json!({
+                    "jsonrpc": "2.0",
+                    "method": method,
+                    "params": params,
+                    "id": 1
+                }))
+                .as_option()
+                .unwrap()
+                .clone()
+        };
+        // escapes are resolved while parsing the request, so both forms can be borrowed
+        assert_eq!(
+            call("parse", json!(["plain"]))["result"],
+            json!(["plain", true])
+        );
+        assert_eq!(
+            call("parse", json!(["esc\"aped\n"]))["result"],
+            json!(["esc\"aped\n", true])
+        );
+        assert_eq!(call("parse", json!([1]))["error"]["code"], json!(-32602));
+        assert_eq!(
+            call("parse_bytes", json!([[97, 98]]))["result"],
+            json!([[97, 98], false])
+        );
+        assert_eq!(
+            call("parse_bytes", json!(["ab"]))["error"]["code"],
+            json!(-32602)
+        );
+
+        let bind = parse::parse(Cow::Borrowed("client")).unwrap();
+        let (call, tracker) = bind.call();
+        let raw_response = handler
+            .handle_request(call.as_request())
+            .as_option()
+            .unwrap();
+        let mut response = easy_jsonrpc::Response::from_json_response(raw_response).unwrap();
+        assert_eq!(
+            tracker.get_return(&mut response).unwrap(),
+            ("client".to_owned(), true)
+        );
+    }
+
+    #[test]
+    fn strict_envelope() {
+        let mut handler = &AdderImpl {} as &dyn Adder;
+        let invalid_request = |id: Value| {
+            MaybeReply::Reply(json!({
+                "jsonrpc": "2.0",
+                "error": {
+                    "code": -32600,
+                    "message": "Invalid request"
+                },
+                "id": id
+            }))
+        };
+
+        let stray_field = json!({
+            "jsonrpc": "2.0",
+            "method": "wrapping_add",
+            "params": [1, 1],
+            "id": 1,
+            "foo": 1
+        });
+        assert_eq!(
+            handler.handle_request_strict(stray_field.clone()),
+            invalid_request(json!(1))
+        );
+        assert_eq!(
+            handler.handle_request(stray_field),
+            invalid_request(json!(1))
+        );
+
+        let old_version = json!({
+            "jsonrpc": "1.0",
+            "method": "wrapping_add",
+            "params": [1, 1],
+            "id": 1
+        });
+        assert_eq!(
+            handler.handle_request_strict(old_version),
+            invalid_request(json!(1))
+        );
+
+        let no_version = json!({
+            "method": "wrapping_add",
+            "params": [1, 1],
+            "id": 1
+        });
+        assert_eq!(
+            handler.handle_request_strict(no_version.clone()),
+            invalid_request(json!(1))
+        );
+        assert_eq!(
+            handler.handle_request(no_version),
+            MaybeReply::Reply(json!({
+                "result": 2,
+                "id": 1
+            }))
+        );
+
+        // only the offending members of a batch are rejected
+        assert_eq!(
+            handler.handle_request_strict(json!([
+                {"jsonrpc": "2.0", "method": "wrapping_add", "params": [1, 1], "id": 1},
+                {"method": "wrapping_add", "params": [1, 1], "id": 2},
+                {"method": "wrapping_add", "params": [1, 1]},
+            ])),
+            MaybeReply::Reply(json!([
+                {"jsonrpc": "2.0", "result": 2, "id": 1},
+                {"jsonrpc": "2.0", "error": {"code": -32600, "message": "Invalid request"}, "id": 2},
+                {"jsonrpc": "2.0", "error": {"code": -32600, "message": "Invalid request"}, "id": null},
+            ]))
+        );
+    }
+
+    #[test]
+    fn client_trait() {
+        #[easy_jsonrpc::rpc(client_trait)]
+        trait Adder {
+            fn checked_add(&self, a: usize, b: usize) -> Option<usize> {
+                a.checked_add(b)
+            }
+            fn echo_ref(&self, a: &usize) -> usize {
+                *a
+            }
+        }
+
+        impl Adder for () {}
+
+        struct Loopback;
+        impl AdderClient for Loopback {}
+
+        fn call<C: AdderClient>(client: &C) -> (Option<usize>, usize) {
+            let mut handler = &() as &dyn Adder;
+            let bind0 = client.checked_add(1, 2).unwrap();
+            let (call0, tracker0) = bind0.call();
+            let bind1 = client.echo_ref(&4).unwrap();
+            let (call1, tracker1) = bind1.call();
+            let json_request = easy_jsonrpc::Call::batch_request(&[call0, call1]);
+            let raw_response = handler.handle_request(json_request).as_option().unwrap();
+            let mut response = easy_jsonrpc::Response::from_json_response(raw_response).unwrap();
+            (
+                tracker0.get_return(&mut response).unwrap(),
+                tracker1.get_return(&mut response).unwrap(),
+            )
+        }
+
+        assert_eq!(call(&Loopback), (Some(3), 4));
+        // the helper module is still generated
+        let _ = adder::checked_add(1, 2);
+    }
+
+    #[test]
+    fn null_and_missing_params() {
+        let zero_arg = |params: Option<Value>| {
+            let mut request = json!({"jsonrpc": "2.0", "method": "greet", "id": 1});
+            if let Some(params) = params {
+                request["params"] = params;
+            }
+            request
+        };
+        for request in [
+            zero_arg(None),
+            zero_arg(Some(json!(null))),
+            zero_arg(Some(json!([]))),
+            zero_arg(Some(json!({}))),
+        ] {
+            assert_adder_response(
+                request,
+                json!({"jsonrpc": "2.0", "result": "hello", "id": 1}),
+            );
+        }
+
+        // unexpected params are rejected rather than ignored
+        for request in [
+            zero_arg(Some(json!([1]))),
+            zero_arg(Some(json!([null]))),
+            zero_arg(Some(json!({"a": 1}))),
+        ] {
+            assert_eq!(error_code(request), jsonrpc_core::ErrorCode::InvalidParams);
+        }
+
+        let one_arg = |params: Option<Value>| {
+            let mut request = json!({"jsonrpc": "2.0", "method": "echo_ref", "id": 1});
+            if let Some(params) = params {
+                request["params"] = params;
+            }
+            request
+        };
+        for request in [
+            one_arg(None),
+            one_arg(Some(json!(null))),
+            one_arg(Some(json!([]))),
+        ] {
+            assert_eq!(error_code(request), jsonrpc_core::ErrorCode::InvalidParams);
+        }
+    }
+
+    #[test]
+    fn params_raw() {
+        let mut handler = crate::DynHandler::new();
+        handler.register("forward", |params| Ok(params.into_raw()));
+        for params in [json!([1, "two"]), json!({"a": [1]}), json!([])] {
+            assert_eq!(
+                handler
+                    .handle_request(json!({
+                        "jsonrpc": "2.0",
+                        "method": "forward",
+                        "params": params,
+                        "id": 1
+                    }))
+                    .as_option()
+                    .unwrap(),
+                json!({"jsonrpc": "2.0", "result": params, "id": 1})
+            );
+        }
+        assert_eq!(
+            handler
+                .handle_request(json!({"jsonrpc": "2.0", "method": "forward", "id": 1}))
+                .as_option()
+                .unwrap(),
+            json!({"jsonrpc": "2.0", "result": null, "id": 1})
+        );
+        assert_eq!(
+            handler
+                .handle_request(
+                    json!({"jsonrpc": "2.0", "method": "forward", "params": null, "id": 1})
+                )
+                .as_option()
+                .unwrap(),
+            json!({"jsonrpc": "2.0", "result": null, "id": 1})
+        );
+    }
+
+    #[test]
+    fn renamed_method() {
+        #[easy_jsonrpc::rpc]
+        trait Renamed {
+            #[rpc(name = "getBalance")]
+            fn get_balance(&self) -> u64 {
+                3
+            }
+        }
+        impl Renamed for () {}
+
+        let mut handler = &() as &dyn Renamed;
+        assert_eq!(
+            handler
+                .handle_request(json!({"jsonrpc": "2.0", "method": "getBalance", "id": 1}))
+                .as_option()
+                .unwrap(),
+            json!({"jsonrpc": "2.0", "result": 3, "id": 1})
+        );
+        assert_eq!(
+            handler
+                .handle_request(json!({"jsonrpc": "2.0", "method": "get_balance", "id": 1}))
+                .as_option()
+                .unwrap()["error"]["code"],
+            json!(-32601)
+        );
+        let bound = renamed::get_balance().unwrap();
+        let (call, _) = bound.call();
+        assert_eq!(call.as_request()["method"], json!("getBalance"));
+    }
+
+    #[cfg(feature = "erased-serde")]
+    #[test]
+    fn erased_return() {
+        use easy_jsonrpc::erased_serde;
+
+        #[easy_jsonrpc::rpc(client_trait)]
+        trait Gateway {
+            fn lookup(&self, key: String) -> Box<dyn erased_serde::Serialize> {
+                match key.as_str() {
+                    "count" => Box::new(3u8),
+                    _ => Box::new(vec!["a", "b"]),
+                }
+            }
+        }
+        impl Gateway for () {}
+
+        let mut handler = &() as &dyn Gateway;
+        let count = gateway::lookup::<u8>("count".into()).unwrap();
+        let names = gateway::lookup::<Vec<String>>("names".into()).unwrap();
+        let (call0, tracker0) = count.call();
+        let (call1, tracker1) = names.call();
+        let json_request = easy_jsonrpc::Call::batch_request(&[call0, call1]);
+        let raw_response = handler.handle_request(json_request).as_option().unwrap();
+        let mut response = easy_jsonrpc::Response::from_json_response(raw_response).unwrap();
+        assert_eq!(tracker0.get_return(&mut response).unwrap(), 3);
+        assert_eq!(
+            tracker1.get_return(&mut response).unwrap(),
+            vec!["a".to_owned(), "b".to_owned()]
+        );
+    }
+
+    #[test]
+    fn integer_args() {
+        #[easy_jsonrpc::rpc]
+        trait Ints {
+            fn unsigned(&self, a: u64) -> u64 {
+                a
+            }
+            fn signed(&self, a: i64) -> i64 {
+                a
+            }
+        }
+        impl Ints for () {}
+
+        let call = |method: &str, arg: &str| -> Value {
+            let request = format!(
+                r#"{{"jsonrpc": "2.0", "method": "{}", "params": [{}], "id": 1}}"#,
+                method, arg
+            );
+            let mut handler = &() as &dyn Ints;
+            serde_json::from_str(&handler.handle_request_str(&request).unwrap()).unwrap()
+        };
+        let error = |method: &str, arg: &str| -> Value {
+            let response = call(method, arg);
+            assert_eq!(response["error"]["code"], json!(-32602));
+            response["error"]["message"].clone()
+        };
+
+        assert_eq!(
+            call("unsigned", "9999999999999999999")["result"],
+            json!(9999999999999999999u64)
+        );
+        assert_eq!(
+            error("signed", "9999999999999999999"),
+            json!("IntegerOutOfRange \"a\" at position 0. Number out of range for i64.")
+        );
+        assert_eq!(
+            error("unsigned", "99999999999999999999"),
+            json!("IntegerOutOfRange \"a\" at position 0. Number out of range for u64.")
+        );
+        assert_eq!(
+            error("unsigned", "-1"),
+            json!("IntegerOutOfRange \"a\" at position 0. Number out of range for u64.")
+        );
+        assert_eq!(
+            error("unsigned", "1.5"),
+            json!("NotAnInteger \"a\" at position 0. Expected integer, got float.")
+        );
+        assert_eq!(
+            error("unsigned", "1.0"),
+            json!("NotAnInteger \"a\" at position 0. Expected integer, got float.")
+        );
+        assert_eq!(
+            error("unsigned", "\"1\""),
+            json!("InvalidArgStructure \"a\" at position 0.")
+        );
+    }
+
+    #[test]
+    fn response_constructors() {
+        use easy_jsonrpc::Response;
+
+        let success = Response::success(1, json!([2]));
+        assert_eq!(
+            serde_json::from_str::<Value>(&success.to_string()).unwrap(),
+            json!({"jsonrpc": "2.0", "result": [2], "id": 1})
+        );
+
+        let error = Response::error(2, easy_jsonrpc::Error::method_not_found());
+        assert_eq!(
+            serde_json::from_str::<Value>(&error.to_string()).unwrap(),
+            json!({
+                "jsonrpc": "2.0",
+                "error": {"code": -32601, "message": "Method not found"},
+                "id": 2
+            })
+        );
+
+        let mut batch = Response::from_json_response(json!([
+            {"jsonrpc": "2.0", "result": 1, "id": 1},
+            {"jsonrpc": "2.0", "result": 2, "id": 2},
+        ]))
+        .unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&batch.to_string()).unwrap(),
+            json!([
+                {"jsonrpc": "2.0", "result": 1, "id": 1},
+                {"jsonrpc": "2.0", "result": 2, "id": 2},
+            ])
+        );
+        assert_eq!(batch.remove(2), Some(Ok(json!(2))));
+        assert_eq!(
+            Response::from_json_response(serde_json::from_str(&success.to_string()).unwrap())
+                .unwrap()
+                .remove(1),
+            Some(Ok(json!([2])))
+        );
+    }
+
+    #[test]
+    fn client_and_server_only() {
+        #[easy_jsonrpc::rpc]
+        trait Versioned {
+            // only ever called by older servers
+            #[allow(dead_code)]
+            #[rpc(client_only)]
+            fn old(&self) -> u8 {
+                1
+            }
+            #[rpc(server_only)]
+            fn current(&self) -> u8 {
+                2
+            }
+        }
+        impl Versioned for () {}
+
+        let mut handler = &() as &dyn Versioned;
+        assert_eq!(
+            handler
+                .handle_request(json!({"jsonrpc": "2.0", "method": "old", "id": 1}))
+                .as_option()
+                .unwrap()["error"]["code"],
+            json!(-32601)
+        );
+        assert_eq!(
+            handler
+                .handle_request(json!({"jsonrpc": "2.0", "method": "current", "id": 1}))
+                .as_option()
+                .unwrap(),
+            json!({"jsonrpc": "2.0", "result": 2, "id": 1})
+        );
+        let bound = versioned::old().unwrap();
+        assert_eq!(bound.call().0.as_request()["method"], json!("old"));
+    }
+
+    #[test]
+    fn auth_layer() {
+        let mut inner = easy_jsonrpc::DynHandler::new();
+        inner.register("public", |_| Ok(json!("hello")));
+        inner.register("balance", |params| {
+            Ok(json!(params.get::<String>("account")?.len()))
+        });
+
+        let unauthorized = || jsonrpc_core::Error {
+            code: jsonrpc_core::ErrorCode::ServerError(-32001),
+            message: "Unauthorized".into(),
+            data: None,
+        };
+        let mut handler = easy_jsonrpc::layer(inner, |method, params| match method {
+            "public" => Ok(()),
+            _ if params.get::<&str>("token") == Ok("secret") => Ok(()),
+            _ => Err(unauthorized()),
+        });
+
+        let named = |value: Value| Params::Named(value.as_object().unwrap().clone());
+        assert_eq!(
+            handler.handle("public", Params::Positional(vec![])),
+            Ok(json!("hello"))
+        );
+        assert_eq!(
+            handler.handle("balance", named(json!({"account": "alice"}))),
+            Err(unauthorized())
+        );
+        assert_eq!(
+            handler.handle(
+                "balance",
+                named(json!({"account": "alice", "token": "wrong"}))
+            ),
+            Err(unauthorized())
+        );
+        assert_eq!(
+            handler.handle(
+                "balance",
+                named(json!({"account": "alice", "token": "secret"}))
+            ),
+            Ok(json!(5))
+        );
+        // rejected before dispatch, so unknown methods are rejected too
+        assert_eq!(
+            handler.handle("nonexist", Params::Positional(vec![])),
+            Err(unauthorized())
+        );
+    }
+
+    #[test]
+    fn with_module() {
+        use chrono::{DateTime, Utc};
+
+        // unix timestamps in place of chrono's rfc3339 strings
+        mod unix {
+            use chrono::{DateTime, Utc};
+            use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+            pub fn serialize<S: Serializer>(t: &DateTime<Utc>, s: S) -> Result<S::Ok, S::Error> {
+                s.serialize_i64(t.timestamp())
+            }
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<DateTime<Utc>, D::Error> {
+                let secs = i64::deserialize(d)?;
+                DateTime::from_timestamp(secs, 0).ok_or_else(|| D::Error::custom("out of range"))
+            }
+        }
+
+        #[easy_jsonrpc::rpc]
+        trait Clock {
+            fn year(&self, #[rpc_arg(with = "unix")] t: DateTime<Utc>) -> String {
+                t.format("%Y").to_string()
+            }
+        }
+        impl Clock for () {}
+
+        let mut handler = &() as &dyn Clock;
+        let bound = clock::year(DateTime::from_timestamp(1_000_000_000, 0).unwrap()).unwrap();
+        let (call, tracker) = bound.call();
+        assert_eq!(call.as_request()["params"], json!([1_000_000_000]));
+        let raw_response = handler
+            .handle_request(call.as_request())
+            .as_option()
+            .unwrap();
+        let mut response = easy_jsonrpc::Response::from_json_response(raw_response).unwrap();
+        assert_eq!(tracker.get_return(&mut response).unwrap(), "2001");
+
+        assert_eq!(
+            handler
+                .handle_request(json!({
+                    "jsonrpc": "2.0",
+                    "method": "year",
+                    "params": ["2001-09-09T01:46:40Z"],
+                    "id": 1
+                }))
+                .as_option()
+                .unwrap()["error"]["code"],
+            json!(-32602)
+        );
+    }
+
+    #[test]
+    fn optional_args() {
+        #[easy_jsonrpc::rpc]
+        trait Search {
+            fn find(&self, query: String, limit: Option<u8>, offset: Option<u8>) -> String {
+                format!("{} {:?} {:?}", query, limit, offset)
+            }
+            fn middle(&self, a: Option<u8>, b: u8) -> u8 {
+                a.unwrap_or(0) + b
+            }
+        }
+        impl Search for () {}
+
+        let find = |params: Value| -> Value {
+            let mut handler = &() as &dyn Search;
+            handler
+                .handle_request(
+                    json!({"jsonrpc": "2.0", "method": "find", "params": params, "id": 1}),
+                )
+                .as_option()
+                .unwrap()
+        };
+        assert_eq!(find(json!(["a"]))["result"], json!("a None None"));
+        assert_eq!(find(json!(["a", 1]))["result"], json!("a Some(1) None"));
+        assert_eq!(
+            find(json!(["a", null, 2]))["result"],
+            json!("a None Some(2)")
+        );
+        assert_eq!(
+            find(json!({"query": "a", "offset": 2}))["result"],
+            json!("a None Some(2)")
+        );
+        assert_eq!(find(json!([]))["error"]["code"], json!(-32602));
+        assert_eq!(find(json!({"limit": 1}))["error"]["code"], json!(-32602));
+        assert_eq!(find(json!(["a", 1, 2, 3]))["error"]["code"], json!(-32602));
+
+        assert_eq!(
+            search_method_params("find"),
+            Some(&[("query", true), ("limit", false), ("offset", false)][..])
+        );
+        // only trailing options are optional
+        assert_eq!(
+            search_method_params("middle"),
+            Some(&[("a", true), ("b", true)][..])
+        );
+        assert_eq!(search_method_params("nonexist"), None);
+    }
+
+    #[test]
+    fn catch_unwind() {
+        let mut inner = easy_jsonrpc::DynHandler::new();
+        inner.register("panic", |_| panic!("deliberate panic"));
+        inner.register("greet", |_| Ok(json!("hello")));
+        let mut handler = easy_jsonrpc::CatchUnwind::new(inner);
+
+        let error = handler
+            .handle("panic", Params::Positional(vec![]))
+            .unwrap_err();
+        assert_eq!(error.code, jsonrpc_core::ErrorCode::InternalError);
+        assert!(!error.message.contains("deliberate"));
+
+        // the handler keeps serving after a panic
+        assert_eq!(
+            handler.handle("greet", Params::Positional(vec![])),
+            Ok(json!("hello"))
+        );
+        assert_eq!(
+            handler
+                .handle_request(json!([
+                    {"jsonrpc": "2.0", "method": "panic", "id": 1},
+                    {"jsonrpc": "2.0", "method": "greet", "id": 2}
+                ]))
+                .as_option()
+                .unwrap(),
+            json!([
+                {
+                    "jsonrpc": "2.0",
+                    "error": {"code": -32603, "message": "Internal error: method panicked"},
+                    "id": 1
+                },
+                {"jsonrpc": "2.0", "result": "hello", "id": 2}
+            ])
+        );
+    }
+
+    mod private_api {
+        use super::easy_jsonrpc;
+
+        #[easy_jsonrpc::rpc]
+        trait Private {
+            fn ping(&self) -> bool {
+                true
+            }
+        }
+        impl Private for () {}
+
+        pub fn call() -> bool {
+            let mut handler = &() as &dyn Private;
+            let bound = private::ping().unwrap();
+            let (call, tracker) = bound.call();
+            let raw_response =
+                easy_jsonrpc::Handler::handle_request(&mut handler, call.as_request())
+                    .as_option()
+                    .unwrap();
+            let mut response = easy_jsonrpc::Response::from_json_response(raw_response).unwrap();
+            tracker.get_return(&mut response).unwrap()
+        }
+    }
+
+    mod public_api {
+        use super::easy_jsonrpc;
+
+        #[easy_jsonrpc::rpc]
+        pub trait Public {
+            fn ping(&self) -> bool {
+                true
+            }
+        }
+    }
+
+    #[test]
+    fn helper_visibility() {
+        use public_api::{public, Public};
+
+        assert!(private_api::call());
+
+        struct Server;
+        impl Public for Server {}
+        let bound = public::ping().unwrap();
+        let (call, _) = bound.call();
+        let mut handler = &Server as &dyn Public;
+        assert_eq!(
+            handler
+                .handle_request(call.as_request())
+                .as_option()
+                .unwrap()["result"],
+            json!(true)
+        );
+    }
+
+    #[test]
+    fn custom_ids() {
+        let mut handler = &AdderImpl {} as &dyn Adder;
+
+        let bound0 = adder::wrapping_add(1, 2).unwrap().with_id("first");
+        let bound1 = adder::wrapping_add(3, 4).unwrap().with_id(7);
+        let (call0, tracker0) = bound0.call();
+        let (call1, tracker1) = bound1.call();
+        assert_eq!(call0.as_request()["id"], json!("first"));
+        assert_eq!(call1.as_request()["id"], json!(7));
+
+        let raw_response = handler
+            .handle_request(easy_jsonrpc::Call::batch_request(&[call0, call1]))
+            .as_option()
+            .unwrap();
+        let mut response = easy_jsonrpc::Response::from_json_response(raw_response).unwrap();
+        assert_eq!(tracker1.get_return(&mut response).unwrap(), 7);
+        assert_eq!(tracker0.get_return(&mut response).unwrap(), 3);
+
+        // jsonrpc_core, and so Handler, rejects negative ids, but other servers may accept them
+        let bound = adder::greet().unwrap().with_id(-1);
+        let (call, tracker) = bound.call();
+        assert_eq!(call.as_request()["id"], json!(-1));
+        let mut response = easy_jsonrpc::Response::from_json_response(
+            json!({"jsonrpc": "2.0", "result": "hello", "id": -1}),
+        )
+        .unwrap();
+        assert_eq!(tracker.get_return(&mut response).unwrap(), "hello");
+
+        // a response for a different id doesn't satisfy the tracker
+        let bound = adder::greet().unwrap().with_id("mine");
+        let (_, tracker) = bound.call();
+        let mut response = easy_jsonrpc::Response::from_json_response(
+            json!({"jsonrpc": "2.0", "result": "hello", "id": "theirs"}),
+        )
+        .unwrap();
+        assert_eq!(
+            tracker.get_return(&mut response),
+            Err(easy_jsonrpc::ResponseFail::ResultNotFound)
+        );
+
+        // null ids are rendered, not omitted
+        assert_eq!(
+            serde_json::from_str::<Value>(
+                &easy_jsonrpc::Response::error(
+                    easy_jsonrpc::RequestId::null(),
+                    jsonrpc_core::Error::parse_error()
+                )
+                .to_string()
+            )
+            .unwrap(),
+            json!({
+                "jsonrpc": "2.0",
+                "error": {"code": -32700, "message": "Parse error"},
+                "id": null
+            })
+        );
+    }
+
+    #[test]
+    fn primitive_args() {
+        #[easy_jsonrpc::rpc]
+        trait Prims {
+            fn pick(&self, flag: bool, a: u8, b: f32) -> f32 {
+                if flag {
+                    a.into()
+                } else {
+                    b
+                }
+            }
+        }
+        impl Prims for () {}
+
+        let pick = |params: Value| -> Value {
+            let mut handler = &() as &dyn Prims;
+            handler
+                .handle_request(
+                    json!({"jsonrpc": "2.0", "method": "pick", "params": params, "id": 1}),
+                )
+                .as_option()
+                .unwrap()
+        };
+        assert_eq!(pick(json!([true, 1, 2.5]))["result"], json!(1.0));
+        assert_eq!(pick(json!([false, 1, 2.5]))["result"], json!(2.5));
+        assert_eq!(pick(json!([false, 1, 2]))["result"], json!(2.0));
+        assert_eq!(
+            pick(json!(["true", 1, 2.5]))["error"]["message"],
+            json!("InvalidArgStructure \"flag\" at position 0.")
+        );
+        assert_eq!(
+            pick(json!([true, 256, 2.5]))["error"]["message"],
+            json!("IntegerOutOfRange \"a\" at position 1. Number out of range for u8.")
+        );
+    }
+
+    #[cfg(feature = "arbitrary_precision")]
+    #[test]
+    fn arbitrary_precision() {
+        #[easy_jsonrpc::rpc]
+        trait Big {
+            fn double(&self, a: u128) -> u128 {
+                a * 2
+            }
+            fn negate(&self, a: i128) -> i128 {
+                -a
+            }
+        }
+        impl Big for () {}
+
+        let mut handler = &() as &dyn Big;
+        let large: u128 = 1 << 100;
+
+        let bound = big::double(large).unwrap();
+        let (call, tracker) = bound.call();
+        let raw_request = call.as_request().to_string();
+        assert!(raw_request.contains(&large.to_string()));
+        let raw_response = handler.handle_request_str(&raw_request).unwrap();
+        assert!(raw_response.contains(&(large * 2).to_string()));
+        let mut response = easy_jsonrpc::Response::from_json_response(
+            serde_json::from_str(&raw_response).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(tracker.get_return(&mut response).unwrap(), large * 2);
+
+        let bound = big::negate(-(large as i128)).unwrap();
+        let (call, tracker) = bound.call();
+        let raw_response = handler
+            .handle_request(call.as_request())
+            .as_option()
+            .unwrap();
+        let mut response = easy_jsonrpc::Response::from_json_response(raw_response).unwrap();
+        assert_eq!(tracker.get_return(&mut response).unwrap(), large as i128);
+
+        let raw_response = handler
+            .handle_request_str(&format!(
+                r#"{{"jsonrpc": "2.0", "method": "double", "params": [{}0], "id": 1}}"#,
+                u128::MAX
+            ))
+            .unwrap();
+        let response: Value = serde_json::from_str(&raw_response).unwrap();
+        assert_eq!(
+            response["error"]["message"],
+            json!("IntegerOutOfRange \"a\" at position 0. Number out of range for u128.")
+        );
+    }
+
+    #[test]
+    fn call_kind() {
+        use easy_jsonrpc::CallKind;
+
+        struct Recorder {
+            inner: easy_jsonrpc::DynHandler,
+            seen: Vec<(String, CallKind)>,
+        }
+        impl Handler for Recorder {
+            fn handle(
+                &mut self,
+                method: &str,
+                params: Params,
+            ) -> Result<Value, jsonrpc_core::Error> {
+                self.inner.handle(method, params)
+            }
+
+            fn handle_with_meta(
+                &mut self,
+                method: &str,
+                params: Params,
+                kind: CallKind,
+            ) -> Result<Value, jsonrpc_core::Error> {
+                self.seen.push((method.to_owned(), kind));
+                self.handle(method, params)
+            }
+        }
+
+        let mut inner = easy_jsonrpc::DynHandler::new();
+        inner.register("log", |params| {
+            let _: String = params.get_positional(0)?;
+            Ok(Value::Null)
+        });
+        let mut handler = Recorder {
+            inner,
+            seen: vec![],
+        };
+
+        // notifications get no response, even when their params are malformed
+        assert_eq!(
+            handler.handle_request(json!({"jsonrpc": "2.0", "method": "log", "params": [1]})),
+            MaybeReply::DontReply
+        );
+        assert_eq!(
+            handler.handle_request(json!({"jsonrpc": "2.0", "method": "nonexist"})),
+            MaybeReply::DontReply
+        );
+        assert_eq!(
+            handler.handle_request(json!([
+                {"jsonrpc": "2.0", "method": "log", "params": {"a": 1}},
+                {"jsonrpc": "2.0", "method": "log", "params": ["ok"], "id": 1}
+            ])),
+            MaybeReply::Reply(json!([{"jsonrpc": "2.0", "result": null, "id": 1}]))
+        );
+        assert_eq!(
+            handler.seen,
+            vec![
+                ("log".to_owned(), CallKind::Notification),
+                ("nonexist".to_owned(), CallKind::Notification),
+                ("log".to_owned(), CallKind::Notification),
+                ("log".to_owned(), CallKind::MethodCall),
+            ]
+        );
+    }
+
+    #[test]
+    fn hostile_input() {
+        use easy_jsonrpc::RequestLimits;
+
+        fn error_code(response: Option<String>) -> Value {
+            serde_json::from_str::<Value>(&response.unwrap()).unwrap()["error"]["code"].clone()
+        }
+
+        let handler = (&mut AdderImpl {}) as &mut dyn Adder;
+        let limits = RequestLimits::default().max_size(4096).max_depth(4);
+
+        // nesting far beyond serde_json's recursion limit
+        let deep = "[".repeat(100_000) + &"]".repeat(100_000);
+        assert_eq!(error_code(handler.handle_request_str(&deep)), json!(-32700));
+        assert_eq!(
+            error_code(handler.handle_request_bytes(deep.as_bytes(), &RequestLimits::default())),
+            json!(-32600)
+        );
+
+        // brackets inside strings don't count towards the depth
+        let bracketed =
+            r#"{"jsonrpc": "2.0", "method": "[[[[[[", "params": [[{"a": "{{{{\""}]], "id": 1}"#;
+        assert_eq!(
+            error_code(handler.handle_request_bytes(bracketed.as_bytes(), &limits)),
+            json!(-32601)
+        );
+        let too_deep = r#"{"jsonrpc": "2.0", "method": "x", "params": [[[[0]]]], "id": 1}"#;
+        assert_eq!(
+            error_code(handler.handle_request_bytes(too_deep.as_bytes(), &limits)),
+            json!(-32600)
+        );
+
+        // size
+        let call = json!({"jsonrpc": "2.0", "method": "checked_add", "params": [1, 2], "id": 1});
+        let batch = Value::Array(vec![call; 1000]).to_string();
+        assert_eq!(
+            error_code(handler.handle_request_bytes(batch.as_bytes(), &limits)),
+            json!(-32600)
+        );
+        let response = handler
+            .handle_request_bytes(batch.as_bytes(), &RequestLimits::default())
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&response)
+                .unwrap()
+                .as_array()
+                .unwrap()
+                .len(),
+            1000
+        );
+
+        // an empty batch gets a single error
+        assert_eq!(
+            error_code(handler.handle_request_bytes(b"[]", &limits)),
+            json!(-32600)
+        );
+
+        // garbage
+        for raw in [
+            &b"\xff\xfe"[..],
+            b"",
+            b"[[]]",
+            b"{\"jsonrpc\": \"2.0\", \"method\": \"\xc3\x28\", \"id\": 1}",
+            b"\"\\",
+            b"1e999999",
+            b"{\"id\": 18446744073709551616}",
+        ] {
+            assert!(
+                handler.handle_request_bytes(raw, &limits).is_some(),
+                "{:?}",
+                String::from_utf8_lossy(raw)
+            );
+        }
+    }
+
+    #[test]
+    fn method_exists() {
+        #[easy_jsonrpc::rpc]
+        pub trait Gateway {
+            fn local(&self) -> u8;
+            #[rpc(name = "remote.name")]
+            fn renamed(&self) -> u8;
+            #[rpc(client_only)]
+            fn elsewhere(&self) -> u8;
+        }
+
+        struct GatewayImpl;
+        impl Gateway for GatewayImpl {
+            fn local(&self) -> u8 {
+                0
+            }
+            fn renamed(&self) -> u8 {
+                1
+            }
+            fn elsewhere(&self) -> u8 {
+                2
+            }
+        }
+
+        let handler = &GatewayImpl as &dyn Gateway;
+        assert!(handler.method_exists("local"));
+        assert!(handler.method_exists("remote.name"));
+        assert!(!handler.method_exists("renamed"));
+        assert!(!handler.method_exists("elsewhere"));
+        assert!(!(&GatewayImpl as &dyn Gateway).method_exists("nonexist"));
+
+        let mut dynamic = easy_jsonrpc::DynHandler::new();
+        dynamic.register("local", |_| Ok(Value::Null));
+        let layered = easy_jsonrpc::layer(dynamic, |_, _| Ok(()));
+        assert!(layered.method_exists("local"));
+        assert!(!layered.method_exists("remote.name"));
+
+        struct HandWritten;
+        impl Handler for HandWritten {
+            fn handle(&mut self, _: &str, _: Params) -> Result<Value, jsonrpc_core::Error> {
+                Err(jsonrpc_core::Error::method_not_found())
+            }
+        }
+        assert!(HandWritten.method_exists("anything"));
+    }
+
+    #[test]
+    fn wrap_result() {
+        fn envelope(result: Value) -> Value {
+            json!({ "data": result, "ts": 0 })
+        }
+
+        #[easy_jsonrpc::rpc(wrap_result = "envelope")]
+        pub trait Wrapped {
+            fn double(&self, a: u8) -> u16;
+            fn nothing(&self);
+        }
+
+        struct WrappedImpl;
+        impl Wrapped for WrappedImpl {
+            fn double(&self, a: u8) -> u16 {
+                a as u16 * 2
+            }
+            fn nothing(&self) {}
+        }
+
+        let mut handler = &WrappedImpl as &dyn Wrapped;
+        assert_eq!(
+            handler.handle("double", Params::Positional(vec![json!(4)])),
+            Ok(json!({ "data": 8, "ts": 0 }))
+        );
+        assert_eq!(
+            handler.handle("nothing", Params::Positional(vec![])),
+            Ok(json!({ "data": null, "ts": 0 }))
+        );
+        assert_eq!(
+            handler.handle("double", Params::Positional(vec![json!(256)])),
+            Err(InvalidArgs::IntegerOutOfRange {
+                name: "\"a\"",
+                index: 0,
+                ty: "u8"
+            }
+            .into())
+        );
+        assert_eq!(
+            handler.handle("nonexist", Params::Positional(vec![])),
+            Err(jsonrpc_core::Error::method_not_found())
+        );
+    }
+
+    #[test]
+    fn first_bad_arg_short_circuits() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static DESERIALIZED: AtomicUsize = AtomicUsize::new(0);
+
+        // stands in for an argument which is costly to deserialize
+        pub struct Costly;
+        impl<'de> serde::Deserialize<'de> for Costly {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                DESERIALIZED.fetch_add(1, Ordering::SeqCst);
+                serde::de::IgnoredAny::deserialize(deserializer)?;
+                Ok(Costly)
+            }
+        }
+        impl serde::Serialize for Costly {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_unit()
+            }
+        }
+
+        #[easy_jsonrpc::rpc]
+        pub trait Expensive {
+            fn take(&self, a: u8, b: String, c: Costly);
+        }
+
+        struct ExpensiveImpl;
+        impl Expensive for ExpensiveImpl {
+            fn take(&self, _: u8, _: String, _: Costly) {}
+        }
+
+        let mut handler = &ExpensiveImpl as &dyn Expensive;
+        let costly = json!([[1, 2, 3], { "large": "value" }]);
+        for params in [
+            Params::Positional(vec![json!("not a u8"), json!("b"), costly.clone()]),
+            Params::Positional(vec![json!(1), json!(2), costly.clone()]),
+            Params::Named(
+                json!({ "a": 1, "b": null, "c": costly.clone() })
+                    .as_object()
+                    .unwrap()
+                    .clone(),
+            ),
+        ] {
+            assert!(handler.handle("take", params).is_err());
+        }
+        assert_eq!(DESERIALIZED.load(Ordering::SeqCst), 0);
+
+        assert_eq!(
+            handler.handle(
+                "take",
+                Params::Positional(vec![json!(1), json!("b"), costly.clone()])
+            ),
+            Ok(Value::Null)
+        );
+        assert_eq!(DESERIALIZED.load(Ordering::SeqCst), 1);
+
+        // HandleRaw splits positional args off the params text only as they are taken
+        use easy_jsonrpc::{HandleRaw, RawParams};
+        let mut handle_raw = |params: Value| {
+            let params = serde_json::value::to_raw_value(&params).unwrap();
+            handler.handle_raw("take", RawParams::new(Some(&params)).unwrap())
+        };
+        for params in [
+            json!(["not a u8", "b", costly.clone()]),
+            json!([1, 2, costly.clone()]),
+            json!([1]),
+        ] {
+            assert!(handle_raw(params).is_err());
+        }
+        assert_eq!(DESERIALIZED.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            handle_raw(json!([1, "b", costly.clone(), 4])),
+            Err(InvalidArgs::WrongNumberOfArgs {
+                expected: 3,
+                actual: 4
+            }
+            .into())
+        );
+        assert_eq!(handle_raw(json!([1, "b", costly])), Ok(Value::Null));
+        assert_eq!(DESERIALIZED.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn request_context() {
+        use easy_jsonrpc::{with_ctx, HandleWithCtx};
+        use std::cell::RefCell;
+
+        pub struct Request {
+            user: &'static str,
+            log: RefCell<Vec<String>>,
+        }
+
+        #[easy_jsonrpc::rpc]
+        pub trait Bank {
+            fn balance(&self, #[rpc_ctx] req: &Request) -> u64;
+            fn transfer(&self, #[rpc_ctx] req: &Request, to: String, amount: u64) -> bool;
+            fn version(&self) -> u8;
+        }
+
+        struct BankImpl;
+        impl Bank for BankImpl {
+            fn balance(&self, req: &Request) -> u64 {
+                req.log
+                    .borrow_mut()
+                    .push(format!("balance of {}", req.user));
+                10
+            }
+            fn transfer(&self, req: &Request, to: String, amount: u64) -> bool {
+                req.log
+                    .borrow_mut()
+                    .push(format!("{} sends {} to {}", req.user, amount, to));
+                amount <= 10
+            }
+            fn version(&self) -> u8 {
+                1
+            }
+        }
+
+        let mut handler = &BankImpl as &dyn Bank;
+        let req = Request {
+            user: "alice",
+            log: RefCell::new(vec![]),
+        };
+        assert_eq!(
+            with_ctx(&mut handler, &req).handle_request(json!([
+                {"jsonrpc": "2.0", "method": "balance", "id": 1},
+                {"jsonrpc": "2.0", "method": "transfer", "params": {"to": "bob", "amount": 3}, "id": 2},
+                {"jsonrpc": "2.0", "method": "transfer", "params": ["bob", 3, 4], "id": 3},
+                {"jsonrpc": "2.0", "method": "version", "id": 4},
+            ])),
+            MaybeReply::Reply(json!([
+                {"jsonrpc": "2.0", "result": 10, "id": 1},
+                {"jsonrpc": "2.0", "result": true, "id": 2},
+                {
+                    "jsonrpc": "2.0",
+                    "error": {
+                        "code": -32602,
+                        "message": "WrongNumberOfArgs. Expected 2. Actual 3"
+                    },
+                    "id": 3
+                },
+                {"jsonrpc": "2.0", "result": 1, "id": 4},
+            ]))
+        );
+        assert_eq!(
+            *req.log.borrow(),
+            vec![
+                "balance of alice".to_owned(),
+                "alice sends 3 to bob".to_owned()
+            ]
+        );
+        assert!(HandleWithCtx::<Request>::method_exists(
+            &handler, "transfer"
+        ));
+        assert!(!with_ctx(&mut handler, &req).method_exists("nonexist"));
+
+        // the context is not part of the client's params
+        assert_eq!(
+            bank_method_params("transfer"),
+            Some(&[("to", true), ("amount", true)][..])
+        );
+        let bind = bank::transfer("bob".into(), 3).unwrap();
+        let (call, _) = bind.call();
+        assert_eq!(call.as_request()["params"], json!(["bob", 3]));
+    }
+
+    #[test]
+    fn rest_args() {
+        #[easy_jsonrpc::rpc]
+        pub trait Summer {
+            fn sum(&self, label: String, #[rpc_arg(rest)] nums: Vec<i64>) -> String;
+        }
+
+        struct SummerImpl;
+        impl Summer for SummerImpl {
+            fn sum(&self, label: String, nums: Vec<i64>) -> String {
+                format!("{}: {}", label, nums.iter().sum::<i64>())
+            }
+        }
+
+        let mut handler = &SummerImpl as &dyn Summer;
+        let call = |handler: &mut &dyn Summer, params: Value| {
+            handler.handle(
+                "sum",
+                Params::from_rc_params(serde_json::from_value(params).unwrap()),
+            )
+        };
+        assert_eq!(
+            call(&mut handler, json!(["total", 1, 2, 3, 4])),
+            Ok(json!("total: 10"))
+        );
+        assert_eq!(call(&mut handler, json!(["none"])), Ok(json!("none: 0")));
+        assert_eq!(
+            call(&mut handler, json!([])),
+            Err(InvalidArgs::WrongNumberOfArgs {
+                expected: 1,
+                actual: 0
+            }
+            .into())
+        );
+        assert_eq!(
+            call(&mut handler, json!(["bad", 1, "two"])),
+            Err(InvalidArgs::InvalidArgValue {
+                name: "\"nums\"",
+                index: 1,
+                expected: "Vec<i64>",
+                error: if cfg!(feature = "serde_path") {
+                    "[1]: invalid type: string \"two\", expected i64".into()
+                } else {
+                    // without a path, the argument holding the error is described too
+                    "invalid type: string \"two\", expected i64 (the argument was sequence)".into()
+                },
+            }
+            .into())
+        );
+        assert_eq!(
+            call(&mut handler, json!({"label": "named", "nums": [1]})),
+            Err(InvalidArgs::RestArgsRequirePositional.into())
+        );
+        assert_eq!(
+            summer_method_params("sum"),
+            Some(&[("label", true), ("nums", false)][..])
+        );
+
+        // the client spreads the rest arg into the params
+        let bind = summer::sum("total".into(), vec![1, 2, 3]).unwrap();
+        let (call, tracker) = bind.call();
+        let request = call.as_request();
+        assert_eq!(request["params"], json!(["total", 1, 2, 3]));
+        let raw_response = handler.handle_request(request).as_option().unwrap();
+        let mut response = easy_jsonrpc::Response::from_json_response(raw_response).unwrap();
+        assert_eq!(tracker.get_return(&mut response).unwrap(), "total: 6");
+    }
+
+    #[test]
+    fn derive_rpc_error() {
+        use jsonrpc_core::ErrorCode;
+
+        #[derive(easy_jsonrpc::RpcError)]
+        enum BankError {
+            #[rpc_error(code = -32010, message = "Insufficient funds")]
+            InsufficientFunds { balance: u64, requested: u64 },
+            #[rpc_error(code = -32011)]
+            AccountFrozen,
+            #[rpc_error(code = -32012, message = "Unknown account")]
+            UnknownAccount(String),
+            #[rpc_error(code = -32013)]
+            Conflict(u8, u8),
+        }
+
+        assert_eq!(
+            jsonrpc_core::Error::from(BankError::InsufficientFunds {
+                balance: 1,
+                requested: 2
+            }),
+            jsonrpc_core::Error {
+                code: ErrorCode::ServerError(-32010),
+                message: "Insufficient funds".into(),
+                data: Some(json!({"balance": 1, "requested": 2})),
+            }
+        );
+        assert_eq!(
+            jsonrpc_core::Error::from(BankError::AccountFrozen),
+            jsonrpc_core::Error {
+                code: ErrorCode::ServerError(-32011),
+                message: "AccountFrozen".into(),
+                data: None,
+            }
+        );
+        assert_eq!(
+            jsonrpc_core::Error::from(BankError::UnknownAccount("bob".into())).data,
+            Some(json!("bob"))
+        );
+        assert_eq!(
+            jsonrpc_core::Error::from(BankError::Conflict(1, 2)).data,
+            Some(json!([1, 2]))
+        );
+
+        let mut handler = easy_jsonrpc::DynHandler::new();
+        handler.register("withdraw", |params| {
+            let amount: u64 = params.get_positional(0)?;
+            if amount > 5 {
+                Err(BankError::InsufficientFunds {
+                    balance: 5,
+                    requested: amount,
+                })?;
+            }
+            Ok(json!(5 - amount))
+        });
+        assert_eq!(
+            handler.handle_request(
+                json!({"jsonrpc": "2.0", "method": "withdraw", "params": [7], "id": 1})
+            ),
+            MaybeReply::Reply(json!({
+                "jsonrpc": "2.0",
+                "error": {
+                    "code": -32010,
+                    "message": "Insufficient funds",
+                    "data": {"balance": 5, "requested": 7}
+                },
+                "id": 1
+            }))
+        );
+    }
+
+    #[test]
+    fn strict_version() {
+        #[easy_jsonrpc::rpc(strict_version)]
+        pub trait Strict {
+            fn ping(&self) -> bool;
+        }
+
+        #[easy_jsonrpc::rpc]
+        pub trait Lenient {
+            fn ping(&self) -> bool;
+        }
+
+        struct Impl;
+        impl Strict for Impl {
+            fn ping(&self) -> bool {
+                true
+            }
+        }
+        impl Lenient for Impl {
+            fn ping(&self) -> bool {
+                true
+            }
+        }
+
+        let mut strict = &Impl as &dyn Strict;
+        let mut lenient = &Impl as &dyn Lenient;
+        let request = |version: Option<Value>| {
+            let mut request = json!({"method": "ping", "id": 1});
+            if let Some(version) = version {
+                request["jsonrpc"] = version;
+            }
+            request
+        };
+        let invalid_request = || {
+            MaybeReply::Reply(json!({
+                "jsonrpc": "2.0",
+                "error": {"code": -32600, "message": "Invalid request"},
+                "id": 1
+            }))
+        };
+
+        for version in [
+            None,
+            Some(json!("2")),
+            Some(json!(2.0)),
+            Some(json!("2.0 ")),
+            Some(json!(null)),
+        ] {
+            assert_eq!(strict.handle_request(request(version)), invalid_request());
+        }
+        assert_eq!(
+            strict.handle_request(request(Some(json!("2.0")))),
+            MaybeReply::Reply(json!({"jsonrpc": "2.0", "result": true, "id": 1}))
+        );
+        let raw = request(None).to_string();
+        assert_eq!(
+            serde_json::from_str::<Value>(&strict.handle_request_str(&raw).unwrap()).unwrap(),
+            invalid_request().as_option().unwrap()
+        );
+
+        // lenient mode tolerates an omitted version, but not a malformed one
+        assert_eq!(
+            lenient.handle_request(request(Some(json!("2.0")))),
+            MaybeReply::Reply(json!({"jsonrpc": "2.0", "result": true, "id": 1}))
+        );
+        assert_eq!(
+            lenient.handle_request(request(None)),
+            MaybeReply::Reply(json!({"result": true, "id": 1}))
+        );
+        for version in [Some(json!("2")), Some(json!(2.0))] {
+            assert_eq!(lenient.handle_request(request(version)), invalid_request());
+        }
+
+        // handlers wrapping a strict one are strict too, however the request is handled
+        let mut wrapped = easy_jsonrpc::AllowList::new(strict, &["ping"]);
+        assert!(wrapped.strict_version());
+        assert_eq!(wrapped.handle_request(request(None)), invalid_request());
+        assert_eq!(
+            wrapped.handle_batch_transactional(vec![request(None)]),
+            vec![invalid_request().as_option().unwrap()]
+        );
+        let mut wrapped = easy_jsonrpc::AllowList::new(lenient, &["ping"]);
+        assert!(!wrapped.strict_version());
+        assert_eq!(
+            wrapped.handle_request(request(None)),
+            MaybeReply::Reply(json!({"result": true, "id": 1}))
+        );
+    }
+
+    #[test]
+    fn error_conversions() {
+        use easy_jsonrpc::{ArgSerializeError, Error};
+        use jsonrpc_core::ErrorCode;
+
+        #[derive(easy_jsonrpc::RpcError)]
+        enum AppError {
+            #[rpc_error(code = -32050)]
+            Busy,
+        }
+
+        fn code(result: Result<(), Error>) -> ErrorCode {
+            result.unwrap_err().code
+        }
+
+        assert_eq!(
+            code((|| Err(InvalidArgs::InvalidParamsStructure)?)()),
+            ErrorCode::InvalidParams
+        );
+        assert_eq!(
+            code((|| Err(ArgSerializeError)?)()),
+            ErrorCode::InternalError
+        );
+        assert_eq!(
+            code((|| Err(AppError::Busy)?)()),
+            ErrorCode::ServerError(-32050)
+        );
+    }
+
+    #[test]
+    fn validate_request() {
+        use std::borrow::Cow;
+        use std::cell::Cell;
+
+        #[easy_jsonrpc::rpc]
+        pub trait Store {
+            fn put(&self, key: Cow<str>, value: u8, tags: Option<Vec<String>>);
+            fn get(&self, key: &str) -> Option<u8>;
+        }
+
+        struct StoreImpl {
+            calls: Cell<usize>,
+        }
+        impl Store for StoreImpl {
+            fn put(&self, _: Cow<str>, _: u8, _: Option<Vec<String>>) {
+                self.calls.set(self.calls.get() + 1);
+            }
+            fn get(&self, _: &str) -> Option<u8> {
+                self.calls.set(self.calls.get() + 1);
+                None
+            }
+        }
+
+        let store = StoreImpl {
+            calls: Cell::new(0),
+        };
+        let mut handler = &store as &dyn Store;
+        let params =
+            |params: Value| Params::from_rc_params(serde_json::from_value(params).unwrap());
+        for (method, params) in [
+            ("put", params(json!(["a", 1]))),
+            (
+                "put",
+                params(json!({"key": "a", "value": 1, "tags": ["x"]})),
+            ),
+            ("get", params(json!(["a"]))),
+            ("put", params(json!(["a", 256]))),
+            ("put", params(json!(["a", 1, [2]]))),
+            ("get", params(json!([1]))),
+            ("get", params(json!([]))),
+            ("nonexist", params(json!([]))),
+        ] {
+            let calls = store.calls.get();
+            let validated = store_validate_request(method, &params);
+            assert_eq!(store.calls.get(), calls);
+            let handled = handler.handle(method, params).map(|_| ());
+            assert_eq!(validated, handled);
+        }
+        assert_eq!(store.calls.get(), 3);
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn base64_bytes() {
+        use std::cell::RefCell;
+
+        #[easy_jsonrpc::rpc]
+        trait Blobs {
+            fn put(&self, #[rpc_arg(base64)] data: Vec<u8>);
+            #[rpc(base64)]
+            fn get(&self) -> Vec<u8>;
+        }
+
+        struct BlobsImpl(RefCell<Vec<u8>>);
+        impl Blobs for BlobsImpl {
+            fn put(&self, data: Vec<u8>) {
+                *self.0.borrow_mut() = data;
+            }
+
+            fn get(&self) -> Vec<u8> {
+                self.0.borrow().clone()
+            }
+        }
+
+        let blobs = BlobsImpl(RefCell::new(vec![]));
+        let mut handler = &blobs as &dyn Blobs;
+
+        let bind = blobs::put(vec![0xde, 0xad, 0xbe, 0xef, 0x00]).unwrap();
+        let (call, _) = bind.call();
+        assert_eq!(call.as_request()["params"], json!(["3q2+7wA="]));
+        handler.handle_request(call.as_request());
+        assert_eq!(*blobs.0.borrow(), vec![0xde, 0xad, 0xbe, 0xef, 0x00]);
+
+        let bind = blobs::get().unwrap();
+        let (call, tracker) = bind.call();
+        let raw_response = handler
+            .handle_request(call.as_request())
+            .as_option()
+            .unwrap();
+        assert_eq!(raw_response["result"], json!("3q2+7wA="));
+        let mut response = easy_jsonrpc::Response::from_json_response(raw_response).unwrap();
+        assert_eq!(
+            tracker.get_return(&mut response).unwrap(),
+            vec![0xde, 0xad, 0xbe, 0xef, 0x00]
+        );
+
+        assert_eq!(
+            handler.handle("put", Params::Positional(vec![json!("not base64!")])),
+            Err(InvalidArgs::InvalidArgValue {
+                name: "\"data\"",
+                index: 0,
+                expected: "Vec<u8>",
+                error: "invalid base64 string \"not base64!\": Invalid symbol 32, offset 3.".into(),
+            }
+            .into())
+        );
+    }
+
+    #[test]
+    fn method_lifetimes() {
+        use std::borrow::Cow;
+
+        #[easy_jsonrpc::rpc]
+        pub trait Convert {
+            fn upper<'a>(&self, s: &'a str) -> Cow<'a, str>;
+            fn longest<'a, 'b: 'a>(&self, a: &'a str, b: &'b str) -> &'a str;
+            fn first<'a, 'b>(&self, a: &'a str, b: &'b str) -> &'a str
+            where
+                'b: 'a;
+        }
+
+        struct ConvertImpl;
+        impl Convert for ConvertImpl {
+            fn upper<'a>(&self, s: &'a str) -> Cow<'a, str> {
+                if s.chars().any(char::is_lowercase) {
+                    Cow::Owned(s.to_uppercase())
+                } else {
+                    Cow::Borrowed(s)
+                }
+            }
+            fn longest<'a, 'b: 'a>(&self, a: &'a str, b: &'b str) -> &'a str {
+                if b.len() > a.len() {
+                    b
+                } else {
+                    a
+                }
+            }
+            fn first<'a, 'b>(&self, a: &'a str, _b: &'b str) -> &'a str
+            where
+                'b: 'a,
+            {
+                a
+            }
+        }
+
+        let mut handler = &ConvertImpl as &dyn Convert;
+        let input = String::from("abc");
+        let bind = convert::upper(&input).unwrap();
+        let (call, tracker) = bind.call();
+        let raw_response = handler
+            .handle_request(call.as_request())
+            .as_option()
+            .unwrap();
+        let mut response = easy_jsonrpc::Response::from_json_response(raw_response).unwrap();
+        let upper: Cow<str> = tracker.get_return(&mut response).unwrap();
+        assert_eq!(upper, "ABC");
+
+        let params = Params::Positional(vec![json!("a"), json!("bc")]);
+        assert_eq!(convert_validate_request("longest", &params), Ok(()));
+        assert_eq!(
+            handler.handle_request(
+                json!({"jsonrpc": "2.0", "method": "first", "params": ["a", "bc"], "id": 1})
+            ),
+            MaybeReply::Reply(json!({"jsonrpc": "2.0", "result": "a", "id": 1}))
+        );
+        assert_eq!(handler.handle("longest", params), Ok(json!("bc")));
+    }
+
+    #[test]
+    fn readonly() {
+        #[easy_jsonrpc::rpc]
+        pub trait Store {
+            #[rpc(readonly)]
+            fn get(&self, key: String) -> Option<u64>;
+            #[rpc(readonly, name = "keyCount")]
+            fn key_count(&self) -> usize;
+            fn set(&mut self, key: String, value: u64);
+        }
+
+        struct StoreImpl(std::collections::BTreeMap<String, u64>);
+        impl Store for StoreImpl {
+            fn get(&self, key: String) -> Option<u64> {
+                self.0.get(&key).copied()
+            }
+            fn key_count(&self) -> usize {
+                self.0.len()
+            }
+            fn set(&mut self, key: String, value: u64) {
+                self.0.insert(key, value);
+            }
+        }
+
+        let mut store = StoreImpl(Default::default());
+        (&mut store as &mut dyn Store)
+            .handle_request(json!({"jsonrpc": "2.0", "method": "set", "params": ["a", 1]}));
+
+        let mut readonly = easy_jsonrpc::ReadOnly(&store as &dyn Store);
+        assert!(readonly.method_exists("get"));
+        assert!(readonly.method_exists("keyCount"));
+        assert!(!readonly.method_exists("set"));
+        assert_eq!(
+            readonly.handle_request(
+                json!({"jsonrpc": "2.0", "method": "get", "params": ["a"], "id": 1})
+            ),
+            MaybeReply::Reply(json!({"jsonrpc": "2.0", "result": 1, "id": 1}))
+        );
+        assert_eq!(
+            readonly.handle("set", Params::Positional(vec![json!("b"), json!(2)])),
+            Err(easy_jsonrpc::Error::method_not_found())
+        );
+
+        let get = json!({"jsonrpc": "2.0", "method": "get", "params": ["a"], "id": 1});
+        let count = json!({"jsonrpc": "2.0", "method": "keyCount", "id": 2});
+        let set = json!({"jsonrpc": "2.0", "method": "set", "params": ["b", 2]});
+        assert!(readonly.admits(&get));
+        assert!(readonly.admits(&json!([get, count])));
+        assert!(!readonly.admits(&set));
+        assert!(!readonly.admits(&json!([get, set])));
+        assert!(readonly.admits(&json!({"jsonrpc": "2.0", "id": 3})));
+        assert!(readonly.admits(&json!([])));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn tracing_spans() {
+        let recorder = Recorder::default();
+        tracing::subscriber::with_default(recorder.clone(), || {
+            let mut handler = &AdderImpl {} as &dyn Adder;
+            handler.handle_request(json!([
+                {"jsonrpc": "2.0", "method": "wrapping_add", "params": [1, 2], "id": 1},
+                {"jsonrpc": "2.0", "method": "nonexistent", "params": [], "id": "a"},
+                {"jsonrpc": "2.0", "method": "wrapping_add", "params": [1, 2]},
+            ]));
+        });
+
+        let spans = recorder.spans.lock().unwrap();
+        assert_eq!(spans.len(), 3);
+        for (name, fields) in spans.iter() {
+            assert_eq!(name, "rpc.dispatch");
+            assert!(fields.iter().any(|(field, _)| field == "latency_us"));
+        }
+        let field = |span: usize, name: &str| {
+            spans[span]
+                .1
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value.clone())
+        };
+        assert_eq!(field(0, "method").as_deref(), Some("wrapping_add"));
+        assert_eq!(field(0, "id").as_deref(), Some("\"1\""));
+        assert_eq!(field(0, "outcome").as_deref(), Some("\"ok\""));
+        assert_eq!(field(1, "method").as_deref(), Some("nonexistent"));
+        assert_eq!(field(1, "id").as_deref(), Some("\"\\\"a\\\"\""));
+        assert_eq!(field(1, "outcome").as_deref(), Some("\"error\""));
+        assert_eq!(field(2, "id"), None);
+    }
+
+    #[test]
+    fn raw_params() {
+        use easy_jsonrpc::HandleRaw;
+
+        #[easy_jsonrpc::rpc]
+        pub trait Raw {
+            fn add(&self, a: u8, b: u8) -> u8;
+            fn concat(&self, a: String, b: Option<String>) -> String;
+            fn len(&self, s: &str) -> usize;
+            #[rpc(hex)]
+            fn bytes(&self, #[rpc_arg(hex)] b: Vec<u8>) -> Vec<u8>;
+            fn count(&self, first: u8, #[rpc_arg(rest)] rest: Vec<u8>) -> usize;
+        }
+
+        struct RawImpl;
+        impl Raw for RawImpl {
+            fn add(&self, a: u8, b: u8) -> u8 {
+                a.wrapping_add(b)
+            }
+            fn concat(&self, a: String, b: Option<String>) -> String {
+                a + &b.unwrap_or_default()
+            }
+            fn len(&self, s: &str) -> usize {
+                s.len()
+            }
+            fn bytes(&self, b: Vec<u8>) -> Vec<u8> {
+                b.into_iter().rev().collect()
+            }
+            fn count(&self, _first: u8, rest: Vec<u8>) -> usize {
+                rest.len() + 1
+            }
+        }
+
+        let mut handler = &RawImpl as &dyn Raw;
+        let requests = [
+            r#"{"jsonrpc": "2.0", "method": "add", "params": [1, 2], "id": 1}"#,
+            r#"{"jsonrpc": "2.0", "method": "add", "params": {"a": 1, "b": 2}, "id": 1}"#,
+            r#"{"jsonrpc": "2.0", "method": "add", "params": {"a": 1}, "id": 1}"#,
+            r#"{"jsonrpc": "2.0", "method": "add", "params": {"a": 1, "b": 2, "c": 3}, "id": 1}"#,
+            r#"{"jsonrpc": "2.0", "method": "add", "params": [1, 256], "id": 1}"#,
+            r#"{"jsonrpc": "2.0", "method": "add", "params": [1, 2.5], "id": 1}"#,
+            r#"{"jsonrpc": "2.0", "method": "add", "params": [1, "2"], "id": 1}"#,
+            r#"{"jsonrpc": "2.0", "method": "add", "params": [1], "id": 1}"#,
+            r#"{"jsonrpc": "2.0", "method": "add", "params": 1, "id": 1}"#,
+            r#"{"jsonrpc": "2.0", "method": "add", "params": [1, 2], "id": null}"#,
+            r#"{"jsonrpc": "2.0", "method": "add", "params": [1, 2]}"#,
+            r#"{"method": "add", "params": [1, 2], "id": 1}"#,
+            r#"{"jsonrpc": "2.0", "method": "concat", "params": ["a\n", "b"], "id": "x"}"#,
+            r#"{"jsonrpc": "2.0", "method": "concat", "params": ["a"], "id": 1}"#,
+            r#"{"jsonrpc": "2.0", "method": "concat", "params": {"a": "a", "b": null}, "id": 1}"#,
+            r#"{"jsonrpc": "2.0", "method": "len", "params": ["\u00e9\n"], "id": 1}"#,
+            r#"{"jsonrpc": "2.0", "method": "bytes", "params": ["0x0102"], "id": 1}"#,
+            r#"{"jsonrpc": "2.0", "method": "count", "params": [1, 2, 3], "id": 1}"#,
+            r#"{"jsonrpc": "2.0", "method": "missing", "params": [], "id": 1}"#,
+            r#"{"jsonrpc": "2.0", "method": "add", "params": [1, 2], "id": 1, "extra": 0}"#,
+            r#"[{"jsonrpc": "2.0", "method": "add", "params": [1, 2], "id": 1},
+                {"jsonrpc": "2.0", "method": "add", "params": [3, 4]},
+                {"jsonrpc": "2.0", "method": "concat", "params": ["a", "b"], "id": 2}]"#,
+            r#"[{"jsonrpc": "2.0", "method": "add", "params": [1, 2]}]"#,
+            r#"[{"jsonrpc": "2.0", "method": "add", "params": [1, 2], "id": 1}, 5]"#,
+            r#"[]"#,
+            r#"{"jsonrpc": "2.0", "method": "add", "params": [1, 2], "id": 1"#,
+        ];
+        for request in requests {
+            let parse = |response: Option<String>| {
+                response.map(|response| serde_json::from_str::<Value>(&response).unwrap())
+            };
+            assert_eq!(
+                parse(handler.handle_request_raw(request)),
+                parse(handler.handle_request_str(request)),
+                "{}",
+                request
+            );
+        }
+        assert_eq!(
+            handler.handle_request_raw(
+                r#"{"jsonrpc": "2.0", "method": "add", "params": [1, 2], "id": 1}"#
+            ),
+            Some(r#"{"jsonrpc":"2.0","result":3,"id":1}"#.to_owned())
+        );
+
+        let raw = serde_json::value::RawValue::from_string(r#"{"b": 2, "a": 1}"#.into()).unwrap();
+        let params = easy_jsonrpc::RawParams::new(Some(&raw)).unwrap();
+        assert_eq!(handler.handle_raw("add", params), Ok(json!(3)));
+        for (text, args) in [
+            (" [ ] ", vec![]),
+            (
+                "[ 1 ,\n\"a,]\" , [2, {}] ]",
+                vec!["1", "\"a,]\"", "[2, {}]"],
+            ),
+        ] {
+            let raw = serde_json::value::RawValue::from_string(text.into()).unwrap();
+            let params = easy_jsonrpc::RawParams::new(Some(&raw)).unwrap();
+            let split = params.get_rpc_args(&["a", "b", "c"][..args.len()]).unwrap();
+            assert_eq!(split.iter().map(|arg| arg.get()).collect::<Vec<_>>(), args);
+        }
+        let raw = serde_json::value::RawValue::from_string("1".into()).unwrap();
+        assert!(easy_jsonrpc::RawParams::new(Some(&raw)).is_none());
+    }
+
+    #[test]
+    fn mock() {
+        use std::borrow::Cow;
+
+        #[easy_jsonrpc::rpc(mock)]
+        pub trait Calculator {
+            fn checked_add(&self, a: isize, b: isize) -> Option<isize>;
+            fn reset(&mut self);
+            fn upper<'a>(&self, s: &'a str) -> Cow<'a, str>;
+            #[rpc(client_only)]
+            fn client_side(&self);
+        }
+
+        let mut mock = CalculatorMock::new();
+        mock.on_checked_add(|a, b| a.checked_add(b))
+            .on_upper(|s| Cow::Owned(s.to_uppercase()));
+        let resets = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = resets.clone();
+        mock.on_reset(move || {
+            counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        });
+
+        let bind = calculator::checked_add(1, 2).unwrap();
+        let (call, tracker) = bind.call();
+        let raw_response = (&mut mock as &mut dyn Calculator)
+            .handle_request(call.as_request())
+            .as_option()
+            .unwrap();
+        let mut response = easy_jsonrpc::Response::from_json_response(raw_response).unwrap();
+        assert_eq!(tracker.get_return(&mut response), Ok(Some(3)));
+
+        let bind = calculator::reset().unwrap();
+        let (call, _) = bind.call();
+        (&mut mock as &mut dyn Calculator).handle_request(call.as_request());
+        assert_eq!(resets.load(std::sync::atomic::Ordering::Relaxed), 1);
+        assert_eq!(mock.upper("a"), "A");
+
+        assert_eq!(
+            format!("{:?}", CalculatorMock::new().on_reset(|| ())),
+            "CalculatorMock { checked_add: false, reset: true, upper: false, client_side: false }"
+        );
+        let unset = std::panic::catch_unwind(|| CalculatorMock::new().client_side());
+        assert!(unset.is_err());
+    }
+
+    #[test]
+    fn raw_identifiers() {
+        #[easy_jsonrpc::rpc(mock)]
+        pub trait Keywords {
+            fn r#type(&self, r#in: u8) -> u8;
+            fn r#match(&self);
+        }
+
+        let mut mock = KeywordsMock::new();
+        mock.on_type(|r#in| r#in + 1).on_match(|| ());
+        let mut handler = &mock as &dyn Keywords;
+        assert!(handler.method_exists("type"));
+        assert!(!handler.method_exists("r#type"));
+        assert_eq!(
+            handler.handle_request(json!({
+                "jsonrpc": "2.0",
+                "method": "type",
+                "params": {"in": 1},
+                "id": 1
+            })),
+            MaybeReply::Reply(json!({"jsonrpc": "2.0", "result": 2, "id": 1}))
+        );
+        assert_eq!(keywords_method_params("type"), Some(&[("in", true)][..]));
+
+        let bind = keywords::r#type(1).unwrap();
+        let (call, tracker) = bind.call();
+        let request = call.as_request();
+        assert_eq!(request["method"], json!("type"));
+        let raw_response = handler.handle_request(request).as_option().unwrap();
+        let mut response = easy_jsonrpc::Response::from_json_response(raw_response).unwrap();
+        assert_eq!(tracker.get_return(&mut response), Ok(2));
+        assert_eq!(
+            keywords::r#match().unwrap().call().0.as_request()["method"],
+            json!("match")
+        );
+    }
+
+    #[test]
+    fn max_batch_size() {
+        use easy_jsonrpc::RequestLimits;
+
+        struct Counter(usize);
+        impl Handler for Counter {
+            fn handle(&mut self, _: &str, _: Params) -> Result<Value, easy_jsonrpc::Error> {
+                self.0 += 1;
+                Ok(Value::Null)
+            }
+        }
+
+        let call = json!({"jsonrpc": "2.0", "method": "a", "params": [[","], {"b": ","}], "id": 1});
+        let batch = |len: usize| Value::Array(vec![call.clone(); len]).to_string();
+        let limits = RequestLimits::default().max_batch_size(3);
+        let mut handler = Counter(0);
+
+        let response = handler
+            .handle_request_bytes(batch(4).as_bytes(), &limits)
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&response).unwrap(),
+            json!({
+                "jsonrpc": "2.0",
+                "error": {"code": -32600, "message": "Invalid request"},
+                "id": null
+            })
+        );
+        assert_eq!(handler.0, 0);
+
+        let response = handler
+            .handle_request_bytes(batch(3).as_bytes(), &limits)
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&response)
+                .unwrap()
+                .as_array()
+                .unwrap()
+                .len(),
+            3
+        );
+        assert_eq!(handler.0, 3);
+
+        // single calls are not batches, whatever their params contain
+        let limits = RequestLimits::default().max_batch_size(0);
+        assert!(handler
+            .handle_request_bytes(call.to_string().as_bytes(), &limits)
+            .is_some());
+        assert_eq!(handler.0, 4);
+        handler.handle_request_bytes(batch(1).as_bytes(), &limits);
+        assert_eq!(handler.0, 4);
+    }
+
+    #[test]
+    fn status_hint() {
+        use easy_jsonrpc::StatusHint;
+
+        let mut handler = &AdderImpl {} as &dyn Adder;
+        let mut status = |request: &str| handler.handle_request_str_with_status(request).0;
+        assert_eq!(
+            status(r#"{"jsonrpc": "2.0", "method": "wrapping_add", "params": [1, 2], "id": 1}"#),
+            StatusHint::Ok
+        );
+        assert_eq!(
+            status(r#"{"jsonrpc": "2.0", "method": "wrapping_add", "params": [1, 2]}"#),
+            StatusHint::NoContent
+        );
+        assert_eq!(
+            status(r#"{"jsonrpc": "2.0", "method": "missing", "id": 1}"#),
+            StatusHint::NotFound
+        );
+        assert_eq!(
+            status(r#"{"jsonrpc": "2.0", "method": "wrapping_add", "params": [1], "id": 1}"#),
+            StatusHint::BadRequest
+        );
+        assert_eq!(status(r#"{"jsonrpc": "2.0", "#), StatusHint::BadRequest);
+        assert_eq!(status(r#"{"jsonrpc": "2.0"}"#), StatusHint::BadRequest);
+        assert_eq!(status("[]"), StatusHint::BadRequest);
+        assert_eq!(
+            status(r#"[{"jsonrpc": "2.0", "method": "missing", "id": 1}]"#),
+            StatusHint::Ok
+        );
+
+        let (hint, reply) = handler.handle_request_with_status(
+            json!({"jsonrpc": "2.0", "method": "wrapping_add", "params": [1, 2], "id": 1}),
+        );
+        assert_eq!(hint.code(), 200);
+        assert_eq!(
+            reply,
+            MaybeReply::Reply(json!({"jsonrpc": "2.0", "result": 3, "id": 1}))
+        );
+
+        let unauthorized = MaybeReply::Reply(json!({
+            "jsonrpc": "2.0",
+            "error": {"code": -32001, "message": "Unauthorized"},
+            "id": 1
+        }));
+        assert_eq!(
+            StatusHint::for_reply(&unauthorized),
+            StatusHint::InternalServerError
+        );
+        let hint = StatusHint::for_reply_with(&unauthorized, |code| match code.code() {
+            -32001 => StatusHint::Unauthorized,
+            _ => StatusHint::from_error_code(code),
+        });
+        assert_eq!(hint.code(), 401);
+    }
+
+    #[test]
+    fn client_name() {
+        #[easy_jsonrpc::rpc(client = "http_client")]
+        pub trait HTTPApi {
+            fn get(&self, url: String) -> u16;
+        }
+
+        #[easy_jsonrpc::rpc]
+        pub trait HTTPApiDefault {
+            fn get(&self, url: String) -> u16;
+        }
+
+        let bind = http_client::get("/".into()).unwrap();
+        assert_eq!(bind.call().0.as_request()["method"], json!("get"));
+        let bind = http_api_default::get("/".into()).unwrap();
+        assert_eq!(bind.call().0.as_request()["params"], json!(["/"]));
+    }
+
+    #[test]
+    fn serialize_batch_to() {
+        let mut handler = &AdderImpl {} as &dyn Adder;
+        let batch: Vec<Value> = (0..100)
+            .map(|i| match i % 3 {
+                0 => json!({"jsonrpc": "2.0", "method": "wrapping_add", "params": [i, 1], "id": i}),
+                1 => json!({"jsonrpc": "2.0", "method": "wrapping_add", "params": [i, 1]}),
+                _ => json!({"jsonrpc": "2.0", "method": "missing", "id": i}),
+            })
+            .collect();
+        let responses: Vec<Value> = batch
+            .iter()
+            .filter_map(|call| handler.handle_request(call.clone()).as_option())
+            .collect();
+        let mut streamed = Vec::new();
+        easy_jsonrpc::serialize_batch_to(&responses, &mut streamed).unwrap();
+        assert_eq!(streamed, serde_json::to_vec(&responses).unwrap());
+        assert_eq!(
+            serde_json::from_slice::<Value>(&streamed).unwrap(),
+            handler
+                .handle_request(Value::Array(batch))
+                .as_option()
+                .unwrap()
+        );
+
+        let mut empty = Vec::new();
+        easy_jsonrpc::serialize_batch_to(Vec::<Value>::new(), &mut empty).unwrap();
+        assert_eq!(empty, b"[]");
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn deprecated_warning() {
+        #[easy_jsonrpc::rpc]
+        pub trait Old {
+            #[rpc(deprecated = "use current instead")]
+            fn old(&self);
+            fn current(&self);
+        }
+        struct OldImpl;
+        impl Old for OldImpl {
+            fn old(&self) {}
+            fn current(&self) {}
+        }
+
+        let recorder = Recorder::default();
+        tracing::subscriber::with_default(recorder.clone(), || {
+            let mut handler = &OldImpl as &dyn Old;
+            handler.handle_request(json!({"jsonrpc": "2.0", "method": "current", "id": 1}));
+            assert!(recorder.events.lock().unwrap().is_empty());
+            handler.handle_request(json!({"jsonrpc": "2.0", "method": "old", "id": 1}));
+        });
+
+        let field = |name: &str, value: &str| (name.to_owned(), value.to_owned());
+        assert_eq!(
+            *recorder.events.lock().unwrap(),
+            vec![(
+                tracing::Level::WARN,
+                vec![
+                    field("message", "call to deprecated method"),
+                    field("method", "\"old\""),
+                    field("note", "\"use current instead\""),
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn metered() {
+        let mut handler = easy_jsonrpc::Metered::new(&AdderImpl {} as &dyn Adder);
+        for request in [
+            json!({"jsonrpc": "2.0", "method": "checked_add", "params": [1, 2], "id": 1}),
+            json!({"jsonrpc": "2.0", "method": "checked_add", "params": [1], "id": 2}),
+            json!({"jsonrpc": "2.0", "method": "checked_add", "params": [3, 4]}),
+            json!({"jsonrpc": "2.0", "method": "wrapping_add", "params": [1, 2], "id": 3}),
+            json!({"jsonrpc": "2.0", "method": "missing", "id": 4}),
+        ] {
+            handler.handle_request(request);
+        }
+        assert_eq!(handler.metrics().len(), 2);
+        assert_eq!(handler.metrics()["checked_add"], (3, 1));
+        assert_eq!(handler.metrics()["wrapping_add"], (1, 0));
+    }
+
+    #[test]
+    fn null_positional_args() {
+        use easy_jsonrpc::HandleRaw;
+
+        #[easy_jsonrpc::rpc]
+        pub trait Nulls {
+            fn f(&self, a: u8, b: Option<u8>, c: u8) -> (u8, Option<u8>, u8);
+        }
+
+        struct NullsImpl;
+        impl Nulls for NullsImpl {
+            fn f(&self, a: u8, b: Option<u8>, c: u8) -> (u8, Option<u8>, u8) {
+                (a, b, c)
+            }
+        }
+
+        let mut handler = &NullsImpl as &dyn Nulls;
+        assert_eq!(
+            handler.handle_request(json!({
+                "jsonrpc": "2.0",
+                "method": "f",
+                "params": [1, null, 3],
+                "id": 1
+            })),
+            MaybeReply::Reply(json!({"jsonrpc": "2.0", "result": [1, null, 3], "id": 1}))
+        );
+        assert_eq!(
+            handler.handle_request(json!({
+                "jsonrpc": "2.0",
+                "method": "f",
+                "params": [1, 2, null],
+                "id": 1
+            })),
+            MaybeReply::Reply(json!({
+                "jsonrpc": "2.0",
+                "error": {
+                    "code": -32602,
+                    "message": "InvalidArgStructure \"c\" at position 2.",
+                    "data": {
+                        "name": "c",
+                        "expected": "u8",
+                        "error": "invalid type: null, expected u8"
+                    }
+                },
+                "id": 1
+            }))
+        );
+        for request in [
+            r#"{"jsonrpc": "2.0", "method": "f", "params": [1, null, 3], "id": 1}"#,
+            r#"{"jsonrpc": "2.0", "method": "f", "params": [null, 2, 3], "id": 1}"#,
+        ] {
+            let parse = |response: Option<String>| serde_json::from_str::<Value>(&response?).ok();
+            assert_eq!(
+                parse(handler.handle_request_raw(request)),
+                parse(handler.handle_request_str(request))
+            );
+        }
+    }
+
+    #[cfg(feature = "conformance")]
+    #[test]
+    fn conformance() {
+        use easy_jsonrpc::conformance::{self, Outcome};
+
+        #[easy_jsonrpc::rpc]
+        pub trait Arith {
+            fn subtract(&self, minuend: i64, subtrahend: i64) -> i64;
+        }
+
+        struct ArithImpl;
+        impl Arith for ArithImpl {
+            fn subtract(&self, minuend: i64, subtrahend: i64) -> i64 {
+                minuend - subtrahend
+            }
+        }
+
+        let report = conformance::run(&mut (&ArithImpl as &dyn Arith));
+        assert!(report.is_conformant(), "{}", report);
+        assert!(report.checks.iter().all(|c| c.outcome == Outcome::Passed));
+
+        let report = conformance::run(&mut easy_jsonrpc::DynHandler::new());
+        assert!(report.is_conformant(), "{}", report);
+        let skipped: Vec<&str> = report
+            .checks
+            .iter()
+            .filter(|c| c.outcome == Outcome::Skipped)
+            .map(|c| c.name)
+            .collect();
+        assert_eq!(skipped.len(), 4);
+        assert!(skipped.iter().all(|name| name.contains("params")));
+
+        // replies to notifications
+        struct Chatty<'a>(&'a dyn Arith);
+        impl Handler for Chatty<'_> {
+            fn handle(
+                &mut self,
+                method: &str,
+                params: easy_jsonrpc::Params,
+            ) -> Result<Value, easy_jsonrpc::Error> {
+                self.0.handle(method, params)
+            }
+            fn method_exists(&self, method: &str) -> bool {
+                self.0.method_exists(method)
+            }
+            fn handle_request(&mut self, raw_request: Value) -> MaybeReply {
+                match self.0.handle_request(raw_request) {
+                    MaybeReply::DontReply => MaybeReply::Reply(json!([])),
+                    reply => reply,
+                }
+            }
+        }
+        let report = conformance::run(&mut Chatty(&ArithImpl));
+        assert!(!report.is_conformant());
+        let failed: Vec<&str> = report.failures().map(|c| c.name).collect();
+        assert_eq!(
+            failed,
+            [
+                "notification",
+                "notification to a missing method",
+                "batch, notifications only"
+            ]
+        );
+        assert_eq!(report.failures().next().unwrap().actual, Some(json!([])));
+    }
+
+    #[test]
+    fn invalid_batch_elements() {
+        let mut handler = &AdderImpl {} as &dyn Adder;
+        assert_eq!(
+            handler.handle_request(json!([
+                1,
+                {"jsonrpc": "2.0", "method": "wrapping_add", "params": [1, 2], "id": 1}
+            ])),
+            MaybeReply::Reply(json!([
+                {"jsonrpc": "2.0", "error": {"code": -32600, "message": "Invalid request"}, "id": null},
+                {"jsonrpc": "2.0", "result": 3, "id": 1}
+            ]))
+        );
+    }
+
+    #[test]
+    fn num_as_str() {
+        use easy_jsonrpc::HandleRaw;
+
+        #[easy_jsonrpc::rpc]
+        trait Ledger {
+            #[rpc(num_as_str)]
+            fn next(&self, #[rpc_arg(num_as_str)] balance: u64) -> u64;
+        }
+
+        struct LedgerImpl;
+        impl Ledger for LedgerImpl {
+            fn next(&self, balance: u64) -> u64 {
+                balance.wrapping_add(1)
+            }
+        }
+
+        let mut handler = &LedgerImpl as &dyn Ledger;
+        assert_eq!(
+            handler.handle_request(json!({
+                "jsonrpc": "2.0",
+                "method": "next",
+                "params": ["18446744073709551614"],
+                "id": 1
+            })),
+            MaybeReply::Reply(json!({
+                "jsonrpc": "2.0",
+                "result": "18446744073709551615",
+                "id": 1
+            }))
+        );
+
+        // client helpers encode and decode strings too
+        let bind = ledger::next(u64::MAX).unwrap();
+        let (call, tracker) = bind.call();
+        assert_eq!(call.as_request()["params"], json!(["18446744073709551615"]));
+        let raw_response = handler
+            .handle_request(call.as_request())
+            .as_option()
+            .unwrap();
+        let mut response = easy_jsonrpc::Response::from_json_response(raw_response).unwrap();
+        assert_eq!(tracker.get_return(&mut response).unwrap(), 0);
+
+        for malformed in [
+            json!("18446744073709551616"),
+            json!("-1"),
+            json!("12a"),
+            json!(12),
+        ] {
+            let request = json!({
+                "jsonrpc": "2.0",
+                "method": "next",
+                "params": [malformed],
+                "id": 1
+            });
+            let raw_response = handler.handle_request(request.clone()).as_option().unwrap();
+            assert_eq!(raw_response["error"]["code"], json!(-32602));
+            let raw_path = handler.handle_request_raw(&request.to_string()).unwrap();
+            let raw_path: Value = serde_json::from_str(&raw_path).unwrap();
+            assert_eq!(raw_path, raw_response);
+        }
+    }
+
+    #[test]
+    fn raw_error_parity() {
+        use easy_jsonrpc::HandleRaw;
+
+        #[derive(serde::Serialize, serde::Deserialize)]
+        pub struct Limits {
+            count: u8,
+            label: String,
+        }
+
+        #[easy_jsonrpc::rpc]
+        trait Configure {
+            fn set(&self, id: u64, limits: Limits, flags: Vec<bool>, note: String);
+            fn load(&self, #[rpc_arg(hex)] blob: Vec<u8>);
+        }
+
+        struct ConfigureImpl;
+        impl Configure for ConfigureImpl {
+            fn set(&self, _: u64, _: Limits, _: Vec<bool>, _: String) {}
+            fn load(&self, _: Vec<u8>) {}
+        }
+
+        // rejected params get the same error, data included, whichever path parses them
+        let mut handler = &ConfigureImpl as &dyn Configure;
+        let limits = json!({"count": 1, "label": "x"});
+        for (method, params) in [
+            ("set", json!(["a", limits, [], "s"])),
+            ("set", json!([2.5, limits, [], "s"])),
+            ("set", json!([-1, limits, [], "s"])),
+            ("set", json!([1, {"count": 300, "label": "x"}, [], "s"])),
+            ("set", json!([1, {"count": 1}, [], "s"])),
+            ("set", json!([1, "x", [], "s"])),
+            ("set", json!([1, limits, [1], "s"])),
+            ("set", json!([1, limits, [], 5])),
+            (
+                "set",
+                json!({"id": 1, "limits": limits, "flags": [], "note": 3}),
+            ),
+            ("load", json!(["zz"])),
+            ("load", json!([1])),
+        ] {
+            let request = json!({"jsonrpc": "2.0", "method": method, "params": params, "id": 1});
+            let value_path = handler.handle_request(request.clone()).as_option().unwrap();
+            assert_eq!(value_path["error"]["code"], json!(-32602));
+            let raw_path = handler.handle_request_raw(&request.to_string()).unwrap();
+            let raw_path: Value = serde_json::from_str(&raw_path).unwrap();
+            assert_eq!(raw_path, value_path);
+        }
+    }
+
+    #[test]
+    fn raw_result() {
+        use easy_jsonrpc::{Error, ErrorCode};
+
+        #[easy_jsonrpc::rpc]
+        pub trait Escape {
+            fn lookup(&self, key: String) -> Result<Value, Error>;
+        }
+
+        struct EscapeImpl;
+        impl Escape for EscapeImpl {
+            fn lookup(&self, key: String) -> Result<Value, Error> {
+                match key.as_str() {
+                    "answer" => Ok(json!({"value": 42})),
+                    _ => Err(Error {
+                        code: ErrorCode::ServerError(-32004),
+                        message: "No such key".into(),
+                        data: Some(json!({"key": key})),
+                    }),
+                }
+            }
+        }
+
+        let mut handler = &EscapeImpl as &dyn Escape;
+        assert_eq!(
+            handler.handle_request(json!({
+                "jsonrpc": "2.0",
+                "method": "lookup",
+                "params": ["answer"],
+                "id": 1
+            })),
+            MaybeReply::Reply(json!({
+                "jsonrpc": "2.0",
+                "result": {"value": 42},
+                "id": 1
+            }))
+        );
+        assert_eq!(
+            handler.handle_request(json!({
+                "jsonrpc": "2.0",
+                "method": "lookup",
+                "params": ["question"],
+                "id": 2
+            })),
+            MaybeReply::Reply(json!({
+                "jsonrpc": "2.0",
+                "error": {
+                    "code": -32004,
+                    "message": "No such key",
+                    "data": {"key": "question"}
+                },
+                "id": 2
+            }))
+        );
+
+        // the client receives the result as a Value, and the error as an rpc error
+        let bind = escape::lookup("answer".into()).unwrap();
+        let (call, tracker) = bind.call();
+        let raw_response = handler
+            .handle_request(call.as_request())
+            .as_option()
+            .unwrap();
+        let mut response = easy_jsonrpc::Response::from_json_response(raw_response).unwrap();
+        assert_eq!(tracker.get_return(&mut response), Ok(json!({"value": 42})));
+        let bind = escape::lookup("question".into()).unwrap();
+        let (call, tracker) = bind.call();
+        let raw_response = handler
+            .handle_request(call.as_request())
+            .as_option()
+            .unwrap();
+        let mut response = easy_jsonrpc::Response::from_json_response(raw_response).unwrap();
+        match tracker.get_return(&mut response) {
+            Err(easy_jsonrpc::ResponseFail::RpcError(e)) => assert_eq!(e.message, "No such key"),
+            other => panic!("{:?}", other),
+        }
+
+        // paths to the same types count, an Error of another crate is serialized as any Result
+        mod other {
+            #[derive(serde::Serialize, serde::Deserialize)]
+            pub struct Error {
+                pub reason: String,
+            }
+        }
+
+        #[easy_jsonrpc::rpc]
+        pub trait Paths {
+            fn qualified(&self) -> Result<serde_json::Value, jsonrpc_core::Error>;
+            fn other(&self) -> Result<Value, other::Error>;
+        }
+
+        struct PathsImpl;
+        impl Paths for PathsImpl {
+            fn qualified(&self) -> Result<serde_json::Value, jsonrpc_core::Error> {
+                Err(jsonrpc_core::Error::invalid_request())
+            }
+            fn other(&self) -> Result<Value, other::Error> {
+                Err(other::Error {
+                    reason: "other".into(),
+                })
+            }
+        }
+
+        let mut handler = &PathsImpl as &dyn Paths;
+        let mut call = |method: &str| {
+            handler
+                .handle_request(json!({"jsonrpc": "2.0", "method": method, "id": 1}))
+                .as_option()
+                .unwrap()
+        };
+        assert_eq!(call("qualified")["error"]["code"], json!(-32600));
+        assert_eq!(call("other")["result"], json!({"Err": {"reason": "other"}}));
+    }
+
+    #[test]
+    fn gated() {
+        let mut handler = easy_jsonrpc::GatedHandler::new(&AdderImpl {} as &dyn Adder);
+        let request = json!({
+            "jsonrpc": "2.0",
+            "method": "wrapping_add",
+            "params": [1, 2],
+            "id": 1
+        });
+        let enabled = MaybeReply::Reply(json!({"jsonrpc": "2.0", "result": 3, "id": 1}));
+        assert_eq!(handler.handle_request(request.clone()), enabled);
+
+        handler.set_enabled("wrapping_add", false);
+        assert!(!handler.is_enabled("wrapping_add"));
+        assert!(!handler.method_exists("wrapping_add"));
+        assert!(handler.method_exists("checked_add"));
+        assert_eq!(
+            handler.handle_request(request.clone()),
+            MaybeReply::Reply(json!({
+                "jsonrpc": "2.0",
+                "error": {"code": -32601, "message": "Method not found"},
+                "id": 1
+            }))
+        );
+
+        handler.set_enabled("wrapping_add", true);
+        assert!(handler.method_exists("wrapping_add"));
+        assert_eq!(handler.handle_request(request), enabled);
+    }
+
+    #[test]
+    fn invalid_arg_expected_type() {
+        use easy_jsonrpc::HandleRaw;
+
+        #[easy_jsonrpc::rpc]
+        pub trait Typed {
+            fn fetch(&self, ids: Vec<u64>) -> usize;
+            fn scale(&self, factor: f64) -> f64;
+            fn shift(&self, by: u8) -> u8;
+        }
+
+        struct TypedImpl;
+        impl Typed for TypedImpl {
+            fn fetch(&self, ids: Vec<u64>) -> usize {
+                ids.len()
+            }
+            fn scale(&self, factor: f64) -> f64 {
+                factor
+            }
+            fn shift(&self, by: u8) -> u8 {
+                by
+            }
+        }
+
+        let mut handler = &TypedImpl as &dyn Typed;
+        // with the serde_path feature, the error in ids is prefixed with its index, and otherwise
+        // followed by a description of ids
+        let (path, described) = if cfg!(feature = "serde_path") {
+            ("[1]: ", "")
+        } else {
+            ("", " (the argument was sequence)")
+        };
+        for (method, params, name, expected, message) in [
+            (
+                "fetch",
+                json!([[1, "two"]]),
+                "ids",
+                "Vec<u64>",
+                format!(
+                    "{}invalid type: string \"two\", expected u64{}",
+                    path, described
+                ),
+            ),
+            (
+                "scale",
+                json!(["2"]),
+                "factor",
+                "f64",
+                "invalid type: string \"2\", expected f64".to_owned(),
+            ),
+            (
+                "shift",
+                json!(["2"]),
+                "by",
+                "u8",
+                "invalid type: string \"2\", expected u8".to_owned(),
+            ),
+        ] {
+            let request = json!({"jsonrpc": "2.0", "method": method, "params": params, "id": 1});
+            let response = handler.handle_request(request.clone()).as_option().unwrap();
+            let error = &response["error"];
+            assert_eq!(error["code"], json!(-32602));
+            assert_eq!(error["data"]["name"], json!(name));
+            assert_eq!(error["data"]["expected"], json!(expected));
+            assert_eq!(error["data"]["error"], json!(message));
+            let raw_path = handler.handle_request_raw(&request.to_string()).unwrap();
+            let raw_path: Value = serde_json::from_str(&raw_path).unwrap();
+            assert_eq!(raw_path["error"]["data"]["expected"], json!(expected));
+        }
+    }
+
+    #[test]
+    fn response_pipeline() {
+        use easy_jsonrpc::{Error, Id, ResponsePipeline};
+
+        let mut pipeline = ResponsePipeline::new(Vec::new());
+        let completions = vec![
+            (Id::Num(1), Ok(json!(1))),
+            (Id::Str("two".into()), Err(Error::invalid_params("bad"))),
+            (Id::Num(3), Ok(json!([3]))),
+        ];
+        for (id, result) in completions.into_iter().rev() {
+            pipeline.complete(id, result).unwrap();
+        }
+        let out = String::from_utf8(pipeline.into_inner()).unwrap();
+        let frames: Vec<Value> = out
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(
+            frames,
+            vec![
+                json!({"jsonrpc": "2.0", "result": [3], "id": 3}),
+                json!({
+                    "jsonrpc": "2.0",
+                    "error": {"code": -32602, "message": "bad"},
+                    "id": "two"
+                }),
+                json!({"jsonrpc": "2.0", "result": 1, "id": 1}),
+            ]
+        );
+
+        // each frame is a response a client can read on its own
+        for frame in frames {
+            easy_jsonrpc::Response::from_json_response(frame).unwrap();
+        }
+    }
+
+    #[test]
+    fn params_struct() {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        pub struct CreateReq {
+            name: String,
+            #[serde(default)]
+            size: u32,
+        }
+
+        #[easy_jsonrpc::rpc]
+        pub trait Creator {
+            #[rpc(params_struct)]
+            fn create(&self, req: CreateReq) -> String;
+        }
+
+        struct CreatorImpl;
+        impl Creator for CreatorImpl {
+            fn create(&self, req: CreateReq) -> String {
+                format!("{}:{}", req.name, req.size)
+            }
+        }
+
+        let mut handler = &CreatorImpl as &dyn Creator;
+        let call = |handler: &mut &dyn Creator, params: Value| {
+            handler
+                .handle_request(json!({
+                    "jsonrpc": "2.0",
+                    "method": "create",
+                    "params": params,
+                    "id": 1
+                }))
+                .as_option()
+                .unwrap()
+        };
+        let created = json!({"jsonrpc": "2.0", "result": "x:2", "id": 1});
+        assert_eq!(call(&mut handler, json!({"name": "x", "size": 2})), created);
+        assert_eq!(
+            call(&mut handler, json!([{"name": "x", "size": 2}])),
+            created
+        );
+        assert_eq!(
+            call(&mut handler, json!({"name": "x"}))["result"],
+            json!("x:0")
+        );
+        for invalid in [
+            json!({"size": 2}),
+            json!({"name": 1}),
+            json!([{"name": "x"}, {"name": "y"}]),
+            json!([]),
+        ] {
+            assert_eq!(
+                call(&mut handler, invalid.clone())["error"]["code"],
+                json!(-32602),
+                "{}",
+                invalid
+            );
+        }
+
+        // the raw path agrees, and the client sends the array-wrapped form
+        use easy_jsonrpc::HandleRaw;
+        let request = r#"{"jsonrpc": "2.0", "method": "create", "params": {"name": "x", "size": 2}, "id": 1}"#;
+        assert_eq!(
+            serde_json::from_str::<Value>(&handler.handle_request_raw(request).unwrap()).unwrap(),
+            created
+        );
+        let bind = creator::create(CreateReq {
+            name: "x".into(),
+            size: 2,
+        })
+        .unwrap();
+        let (call, _) = bind.call();
+        assert_eq!(
+            handler
+                .handle_request(call.as_request())
+                .as_option()
+                .unwrap()["result"],
+            json!("x:2")
+        );
+        assert_eq!(
+            creator_validate_request("create", &Params::Positional(vec![json!(1)]))
+                .unwrap_err()
+                .code,
+            easy_jsonrpc::ErrorCode::InvalidParams
+        );
+    }
+
+    #[test]
+    fn preserve_order() {
+        let mut handler = &AdderImpl {} as &dyn Adder;
+
+        // responses hold the same fields whether or not objects keep their insertion order
+        let response = handler
+            .handle_request_str(r#"{"jsonrpc": "2.0", "method": "wrapping_add", "params": {"b": 2, "a": 1}, "id": 1}"#)
+            .unwrap();
+        let response: serde_json::Map<String, Value> = serde_json::from_str(&response).unwrap();
+        let mut keys: Vec<&str> = response.keys().map(String::as_str).collect();
+        #[cfg(feature = "preserve_order")]
+        assert_eq!(keys, ["jsonrpc", "result", "id"]);
+        keys.sort_unstable();
+        assert_eq!(keys, ["id", "jsonrpc", "result"]);
+        assert_eq!(response["result"], json!(3));
+
+        // the reported extra param doesn't depend on the order params were sent in
+        for params in [
+            json!({"a": 1, "b": 2, "z": 0, "y": 0}),
+            json!({"z": 0, "y": 0, "b": 2, "a": 1}),
+        ] {
+            let params = Params::Named(params.as_object().unwrap().clone());
+            assert_eq!(
+                params.get_rpc_args(&["a", "b"]),
+                Err(InvalidArgs::ExtraNamedParameter { name: "y".into() })
+            );
+        }
+    }
+
+    #[test]
+    fn bound_method_introspection() {
+        #[easy_jsonrpc::rpc]
+        pub trait Renamed {
+            #[rpc(name = "user.login")]
+            fn login(&self, user: String, password: String) -> bool;
+        }
+
+        let bind = renamed::login("alice".into(), "hunter2".into()).unwrap();
+        assert_eq!(bind.method_name(), "user.login");
+        assert_eq!(bind.params(), &json!(["alice", "hunter2"]));
+
+        let bind = easy_jsonrpc::BoundMethod::<()>::new("ping", vec![]);
+        assert_eq!(bind.method_name(), "ping");
+        assert_eq!(bind.params(), &json!([]));
+    }
+
+    #[test]
+    fn allow_list() {
+        let mut handler =
+            easy_jsonrpc::AllowList::new(&AdderImpl {} as &dyn Adder, &["checked_add", "greet"]);
+        assert_eq!(
+            handler.handle_request(json!({
+                "jsonrpc": "2.0",
+                "method": "checked_add",
+                "params": [1, 2],
+                "id": 1
+            })),
+            MaybeReply::Reply(json!({"jsonrpc": "2.0", "result": 3, "id": 1}))
+        );
+
+        // supported by the inner handler, but not allowed
+        assert!(!handler.method_exists("wrapping_add"));
+        assert_eq!(
+            handler.handle_request(json!({
+                "jsonrpc": "2.0",
+                "method": "wrapping_add",
+                "params": [1, 2],
+                "id": 1
+            })),
+            MaybeReply::Reply(json!({
+                "jsonrpc": "2.0",
+                "error": {"code": -32601, "message": "Method not found"},
+                "id": 1
+            }))
+        );
+        assert!(handler.is_allowed("greet"));
+        assert!(handler.method_exists("greet"));
+    }
+
+    #[test]
+    fn call_enum() {
+        use std::convert::TryFrom;
+
+        #[easy_jsonrpc::rpc(call_enum)]
+        pub trait Calc {
+            fn checked_add(&self, a: isize, b: isize) -> Option<isize>;
+            fn greet(&self) -> String;
+            fn echo<'a>(&self, s: &'a str, times: Option<u8>) -> &'a str;
+            #[rpc(name = "sum.all")]
+            fn sum(&self, #[rpc_arg(rest)] nums: Vec<i64>) -> i64;
+        }
+
+        let parse = |method: &str, params: Value| {
+            let params = match params {
+                Value::Object(map) => Params::Named(map),
+                Value::Array(ar) => Params::Positional(ar),
+                _ => unreachable!(),
+            };
+            CalcCall::try_from((method, params))
+        };
+        let describe = |call: CalcCall| match call {
+            CalcCall::CheckedAdd { a, b } => format!("{} + {}", a, b),
+            CalcCall::Greet {} => "hello".to_owned(),
+            CalcCall::Echo { s, times } => s.repeat(times.unwrap_or(1).into()),
+            CalcCall::Sum { nums } => format!("sum of {:?}", nums),
+        };
+
+        assert_eq!(
+            describe(parse("checked_add", json!([1, 2])).unwrap()),
+            "1 + 2"
+        );
+        assert_eq!(
+            describe(parse("checked_add", json!({"b": 2, "a": 1})).unwrap()),
+            "1 + 2"
+        );
+        assert_eq!(describe(parse("greet", json!([])).unwrap()), "hello");
+        assert_eq!(describe(parse("echo", json!(["ab", 2])).unwrap()), "abab");
+        assert_eq!(describe(parse("echo", json!(["ab"])).unwrap()), "ab");
+        assert_eq!(
+            describe(parse("sum.all", json!([1, 2, 3])).unwrap()),
+            "sum of [1, 2, 3]"
+        );
+
+        // the same errors as the Handler
+        let error = |method, params| parse(method, params).err().unwrap();
+        assert_eq!(
+            error("missing", json!([])),
+            easy_jsonrpc::Error::method_not_found()
+        );
+        assert_eq!(
+            error("checked_add", json!([1])),
+            InvalidArgs::WrongNumberOfArgs {
+                expected: 2,
+                actual: 1
+            }
+            .into()
+        );
+        assert_eq!(
+            error("greet", json!([1])).code,
+            easy_jsonrpc::ErrorCode::InvalidParams
+        );
+        assert_eq!(
+            error("echo", json!([1])).data.unwrap()["expected"],
+            json!("String")
+        );
+    }
+
+    #[test]
+    fn handle_request_str_limited() {
+        use easy_jsonrpc::RequestLimits;
+
+        let mut handler = &AdderImpl {} as &dyn Adder;
+        let mut code = |raw: &str, limits: RequestLimits| -> Value {
+            let response = handler.handle_request_str_limited(raw, &limits).unwrap();
+            serde_json::from_str::<Value>(&response).unwrap()["error"]["code"].clone()
+        };
+        let call = |params: Value| {
+            json!({"jsonrpc": "2.0", "method": "checked_add", "params": params, "id": 1})
+                .to_string()
+        };
+        let ok = call(json!([1, 2]));
+
+        let limits = RequestLimits::default().max_size(ok.len());
+        assert_eq!(code(&ok, limits), Value::Null);
+        assert_eq!(code(&format!("{} ", ok), limits), json!(-32600));
+
+        let limits = RequestLimits::default().max_depth(2);
+        assert_eq!(code(&ok, limits), Value::Null);
+        assert_eq!(code(&call(json!([[1], 2])), limits), json!(-32600));
+
+        let limits = RequestLimits::default().max_array_len(2);
+        assert_eq!(code(&ok, limits), Value::Null);
+        assert_eq!(code(&call(json!([1, 2, 3])), limits), json!(-32600));
+        assert_eq!(code(&call(json!([[1, 2, 3], 2])), limits), json!(-32600));
+        // commas in strings and objects don't count
+        assert_eq!(
+            code(&call(json!([",,", {"a": 1, "b": 2, "c": 3}])), limits),
+            json!(-32602)
+        );
+        assert_eq!(
+            code(&call(json!([])), RequestLimits::default().max_array_len(0)),
+            json!(-32602)
+        );
+        assert_eq!(
+            code(&call(json!([1])), RequestLimits::default().max_array_len(0)),
+            json!(-32600)
+        );
+    }
+
+    #[test]
+    fn unit_return() {
+        #[easy_jsonrpc::rpc]
+        #[allow(clippy::unused_unit)]
+        pub trait Units {
+            fn implicit(&self, a: u8) {
+                let _ = a;
+            }
+            fn explicit(&self, a: u8) -> () {
+                let _ = a;
+            }
+        }
+
+        struct UnitsImpl;
+        impl Units for UnitsImpl {}
+        let mut handler = &UnitsImpl as &dyn Units;
+
+        let response = |handler: &mut &dyn Units, method: &str, id: Option<Value>| {
+            let mut request = json!({"jsonrpc": "2.0", "method": method, "params": [1]});
+            if let Some(id) = id {
+                request["id"] = id;
+            }
+            (
+                handler.handle_request(request.clone()),
+                handler.handle_request_str(&request.to_string()),
+            )
+        };
+
+        // calls reply with a null result
+        let implicit = response(&mut handler, "implicit", Some(json!(1)));
+        let explicit = response(&mut handler, "explicit", Some(json!(1)));
+        assert_eq!(implicit, explicit);
+        assert_eq!(
+            implicit.0,
+            MaybeReply::Reply(json!({"jsonrpc": "2.0", "result": null, "id": 1}))
+        );
+        assert_eq!(
+            serde_json::from_str::<Value>(&implicit.1.unwrap()).unwrap(),
+            json!({"jsonrpc": "2.0", "result": null, "id": 1})
+        );
+
+        // notifications get no reply at all
+        let implicit = response(&mut handler, "implicit", None);
+        let explicit = response(&mut handler, "explicit", None);
+        assert_eq!(implicit, explicit);
+        assert_eq!(implicit, (MaybeReply::DontReply, None));
+
+        // clients of either read the null result back as ()
+        let implicit = units::implicit(1).unwrap();
+        let explicit = units::explicit(1).unwrap();
+        for (call, tracker) in [implicit.call(), explicit.call()] {
+            let raw_response = handler
+                .handle_request(call.as_request())
+                .as_option()
+                .unwrap();
+            let mut response = easy_jsonrpc::Response::from_json_response(raw_response).unwrap();
+            let () = tracker.get_return(&mut response).unwrap();
+        }
+    }
+
+    #[test]
+    fn raw_bound_method() {
+        let mut handler = &AdderImpl {} as &dyn Adder;
+        let mut send = |bind: &easy_jsonrpc::BoundMethod<Option<isize>>| {
+            let (call, tracker) = bind.call();
+            let raw_response = handler
+                .handle_request(call.as_request())
+                .as_option()
+                .unwrap();
+            let mut response = easy_jsonrpc::Response::from_json_response(raw_response).unwrap();
+            tracker.get_return(&mut response)
+        };
+
+        let positional = easy_jsonrpc::BoundMethod::raw("checked_add", json!([1, 2])).unwrap();
+        assert_eq!(send(&positional).unwrap(), Some(3));
+        let named = easy_jsonrpc::BoundMethod::raw("checked_add", json!({"a": 1, "b": 2})).unwrap();
+        assert_eq!(named.params(), &json!({"a": 1, "b": 2}));
+        assert_eq!(send(&named).unwrap(), Some(3));
+        for invalid in [json!(1), json!("a"), Value::Null] {
+            assert_eq!(
+                easy_jsonrpc::BoundMethod::<Option<isize>>::raw("checked_add", invalid.clone())
+                    .err(),
+                Some(easy_jsonrpc::InvalidRawParams(invalid))
+            );
+        }
+    }
+
+    #[test]
+    fn response_field_order() {
+        let mut handler = &AdderImpl {} as &dyn Adder;
+        assert_eq!(
+            handler
+                .handle_request_str(r#"{"id": 1, "method": "greet", "jsonrpc": "2.0"}"#)
+                .unwrap(),
+            r#"{"jsonrpc":"2.0","result":"hello","id":1}"#
+        );
+        assert_eq!(
+            handler
+                .handle_request_str(r#"{"id": 2, "method": "nonexist", "jsonrpc": "2.0"}"#)
+                .unwrap(),
+            r#"{"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":2}"#
+        );
+        assert_eq!(
+            handler.handle_request_str("{").unwrap(),
+            r#"{"jsonrpc":"2.0","error":{"code":-32700,"message":"Parse error"},"id":null}"#
+        );
+        assert_eq!(
+            handler
+                .handle_request_str(
+                    r#"[{"jsonrpc": "2.0", "method": "greet", "id": 1}, {"jsonrpc": "2.0", "method": "nonexist", "id": 2}]"#
+                )
+                .unwrap(),
+            concat!(
+                r#"[{"jsonrpc":"2.0","result":"hello","id":1},"#,
+                r#"{"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":2}]"#
+            )
+        );
+    }
+
+    #[test]
+    fn error_types() {
+        use easy_jsonrpc::{ArgSerializeError, InvalidResponse, ResponseFail};
+        use std::error::Error as _;
+
+        fn boxed(
+            err: impl std::error::Error + Send + Sync + 'static,
+        ) -> Box<dyn std::error::Error> {
+            Box::new(err)
+        }
+
+        assert_eq!(
+            boxed(ArgSerializeError).to_string(),
+            "failed to serialize arguments"
+        );
+        assert_eq!(
+            boxed(InvalidArgs::WrongNumberOfArgs {
+                expected: 2,
+                actual: 1
+            })
+            .to_string(),
+            "WrongNumberOfArgs. Expected 2. Actual 1"
+        );
+        assert_eq!(
+            boxed(easy_jsonrpc::Error::method_not_found()).to_string(),
+            "Method not found: Method not found"
+        );
+        assert_eq!(
+            boxed(InvalidResponse::DeserailizeFailure).to_string(),
+            "not a valid jsonrpc response"
+        );
+
+        let fail = ResponseFail::RpcError(easy_jsonrpc::Error::method_not_found());
+        assert_eq!(
+            fail.to_string(),
+            "call failed: Method not found: Method not found"
+        );
+        assert_eq!(
+            fail.source().unwrap().to_string(),
+            "Method not found: Method not found"
+        );
+        assert!(ResponseFail::ResultNotFound.source().is_none());
+
+        // composes with ? in functions returning a boxed error
+        fn parse() -> Result<(), Box<dyn std::error::Error>> {
+            Params::Positional(vec![]).get_rpc_args(&["a"])?;
+            Ok(())
+        }
+        assert_eq!(
+            parse().unwrap_err().to_string(),
+            "WrongNumberOfArgs. Expected 1. Actual 0"
+        );
+    }
+
+    #[test]
+    fn cfg_methods() {
+        #[easy_jsonrpc::rpc(client_trait, mock, call_enum)]
+        pub trait Gated {
+            #[cfg(test)]
+            fn enabled(&self, a: u8) -> u8 {
+                a
+            }
+            #[cfg(not(test))]
+            #[rpc(readonly)]
+            fn disabled(&self, a: Missing) -> Missing;
+        }
+
+        struct GatedImpl;
+        impl Gated for GatedImpl {}
+        struct Transport;
+        impl GatedClient for Transport {}
+
+        let mut handler = &GatedImpl as &dyn Gated;
+        assert!(handler.method_exists("enabled"));
+        assert!(!handler.method_exists("disabled"));
+        assert_eq!(
+            handler.handle("enabled", Params::Positional(vec![json!(1)])),
+            Ok(json!(1))
+        );
+        assert_eq!(
+            handler.handle("disabled", Params::Positional(vec![json!(1)])),
+            Err(easy_jsonrpc::Error::method_not_found())
+        );
+        assert_eq!(gated_method_params("disabled"), None);
+
+        assert_eq!(gated::enabled(1).unwrap().method_name(), "enabled");
+        assert_eq!(Transport.enabled(1).unwrap().method_name(), "enabled");
+        let mut mock = GatedMock::new();
+        mock.on_enabled(|a| a + 1);
+        assert_eq!(mock.enabled(1), 2);
+        assert!(matches!(
+            GatedCall::try_from(("enabled", Params::Positional(vec![json!(1)]))),
+            Ok(GatedCall::Enabled { a: 1 })
+        ));
+        assert!(GatedCall::try_from(("disabled", Params::Positional(vec![]))).is_err());
+    }
+
+    #[test]
+    fn try_handle_request() {
+        use easy_jsonrpc::FatalError;
+        use std::error::Error as _;
+
+        let mut handler = &AdderImpl {} as &dyn Adder;
+        let call = |method: &str| json!({"jsonrpc": "2.0", "method": method, "id": 1});
+
+        // no reply
+        let notification = json!({"jsonrpc": "2.0", "method": "greet"});
+        assert_eq!(handler.try_handle_request(notification).unwrap(), None);
+        // replies, whether the call succeeded or not
+        assert_eq!(
+            handler.try_handle_request(call("greet")).unwrap(),
+            Some(json!({"jsonrpc": "2.0", "result": "hello", "id": 1}))
+        );
+        for request in [call("nonexist"), json!(1), json!([])] {
+            assert_eq!(
+                handler.try_handle_request(request.clone()).unwrap(),
+                handler.handle_request(request).as_option()
+            );
+        }
+        // no response at all
+        let fatal = FatalError::Serialization(serde_json::from_str::<Value>("{").unwrap_err());
+        assert!(fatal
+            .to_string()
+            .starts_with("failed to serialize response: EOF"));
+        assert!(fatal.source().is_some());
+
+        #[easy_jsonrpc::rpc(strict_version)]
+        pub trait Strict {
+            fn ping(&self) -> bool {
+                true
+            }
+        }
+        struct StrictImpl;
+        impl Strict for StrictImpl {}
+        let mut handler = &StrictImpl as &dyn Strict;
+        let lax = json!({"method": "ping", "id": 1});
+        assert_eq!(
+            handler.try_handle_request(lax.clone()).unwrap().unwrap()["error"]["code"],
+            json!(-32600)
+        );
+        assert_eq!(
+            handler.try_handle_request(lax.clone()).unwrap(),
+            handler.handle_request(lax).as_option()
+        );
+        assert_eq!(
+            handler.try_handle_request(call("ping")).unwrap().unwrap()["result"],
+            json!(true)
+        );
+    }
+
+    #[test]
+    fn lenient_params() {
+        use easy_jsonrpc::HandleRaw;
+
+        #[easy_jsonrpc::rpc]
+        pub trait Lenient {
+            #[rpc(lenient_params)]
+            fn lenient(&self, a: u8) -> u8 {
+                a
+            }
+            fn strict(&self, a: u8) -> u8 {
+                a
+            }
+        }
+
+        struct LenientImpl;
+        impl Lenient for LenientImpl {}
+        let mut handler = &LenientImpl as &dyn Lenient;
+        let request = |method: &str, params: Value| json!({"jsonrpc": "2.0", "method": method, "params": params, "id": 1});
+
+        assert_eq!(
+            handler.handle_request(request("lenient", json!(5))),
+            MaybeReply::Reply(json!({"jsonrpc": "2.0", "result": 5, "id": 1}))
+        );
+        assert_eq!(
+            handler.handle_request(request("lenient", json!([5]))),
+            MaybeReply::Reply(json!({"jsonrpc": "2.0", "result": 5, "id": 1}))
+        );
+        assert_eq!(
+            handler
+                .handle_request(request("strict", json!(5)))
+                .as_option()
+                .unwrap()["error"]["code"],
+            json!(-32600)
+        );
+        // bare params are wrapped the same way in batches and in the text based entry points
+        let batch = json!([request("lenient", json!(5)), request("strict", json!(5))]);
+        let response: Value =
+            serde_json::from_str(&handler.handle_request_str(&batch.to_string()).unwrap()).unwrap();
+        assert_eq!(response[0]["result"], json!(5));
+        assert_eq!(response[1]["error"]["code"], json!(-32600));
+        let raw = handler
+            .handle_request_raw(&request("lenient", json!(5)).to_string())
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&raw).unwrap()["result"],
+            json!(5)
+        );
+    }
+
+    #[test]
+    fn readonly_lenient_params() {
+        #[easy_jsonrpc::rpc]
+        pub trait Store {
+            #[rpc(readonly, lenient_params)]
+            fn get(&self, key: u8) -> u8 {
+                key
+            }
+            #[rpc(readonly)]
+            fn strict_get(&self, key: u8) -> u8 {
+                key
+            }
+        }
+
+        struct StoreImpl;
+        impl Store for StoreImpl {}
+        let mut handler = easy_jsonrpc::ReadOnly(&StoreImpl as &dyn Store);
+        let request =
+            |method: &str| json!({"jsonrpc": "2.0", "method": method, "params": 5, "id": 1});
+
+        assert_eq!(
+            handler.handle_request(request("get")),
+            MaybeReply::Reply(json!({"jsonrpc": "2.0", "result": 5, "id": 1}))
+        );
+        assert_eq!(
+            handler.try_handle_request(request("get")).unwrap().unwrap()["result"],
+            json!(5)
+        );
+        assert_eq!(
+            handler
+                .handle_request(request("strict_get"))
+                .as_option()
+                .unwrap()["error"]["code"],
+            json!(-32600)
+        );
+    }
+
+    #[test]
+    fn method_info() {
+        #[easy_jsonrpc::rpc]
+        pub trait Described {
+            #[rpc(name = "user.login")]
+            fn login(&self, user: String, remember: Option<bool>) -> bool;
+            fn r#type(&self, r#in: &str, #[rpc_arg(rest)] rest: Vec<u8>);
+            fn ping(&self);
+        }
+
+        assert_eq!(
+            described::LOGIN,
+            easy_jsonrpc::MethodInfo {
+                name: "user.login",
+                params: &["user", "remember"],
+                types: &["String", "Option<bool>"],
+            }
+        );
+        assert_eq!(described::TYPE.name, "type");
+        assert_eq!(described::TYPE.params, ["in", "rest"]);
+        assert_eq!(described::TYPE.types, ["&str", "Vec<u8>"]);
+        assert!(described::PING.params.is_empty());
+    }
+
+    #[test]
+    fn max_param_bytes() {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        pub struct Upload {
+            data: Vec<u8>,
+        }
+
+        #[easy_jsonrpc::rpc]
+        pub trait Store {
+            #[rpc(max_param_bytes = 16)]
+            fn store(&self, key: String, data: Vec<u8>) -> usize {
+                key.len() + data.len()
+            }
+            #[rpc(params_struct, max_param_bytes = 32)]
+            fn upload(&self, upload: Upload) -> usize {
+                upload.data.len()
+            }
+        }
+
+        struct StoreImpl;
+        impl Store for StoreImpl {}
+        let mut handler = &StoreImpl as &dyn Store;
+
+        // [1,2,3,4,5] is 11 bytes
+        let small = json!([1, 2, 3, 4, 5]);
+        let large = json!(vec![1; 16]);
+        let call = |handler: &mut &dyn Store, method: &str, params: Value| {
+            handler
+                .handle_request(
+                    json!({"jsonrpc": "2.0", "method": method, "params": params, "id": 1}),
+                )
+                .as_option()
+                .unwrap()
+                .clone()
+        };
+
+        assert_eq!(
+            call(&mut handler, "store", json!(["k", small]))["result"],
+            json!(6)
+        );
+        let response = call(&mut handler, "store", json!(["k", large]));
+        assert_eq!(
+            response["error"],
+            json!({
+                "code": -32602,
+                "message": "ParamTooLarge data at position 1. Exceeds 16 bytes."
+            })
+        );
+        // named params are measured one by one too
+        let response = call(&mut handler, "store", json!({"key": "k", "data": large}));
+        assert_eq!(response["error"]["code"], json!(-32602));
+        assert!(store_validate_request(
+            "store",
+            &Params::Positional(vec![json!("k"), large.clone()])
+        )
+        .is_err());
+
+        // the raw handler gives the same answer
+        {
+            use easy_jsonrpc::HandleRaw;
+            let request =
+                json!({"jsonrpc": "2.0", "method": "store", "params": ["k", large], "id": 1});
+            let response = handler.handle_request_raw(&request.to_string()).unwrap();
+            let response: Value = serde_json::from_str(&response).unwrap();
+            assert_eq!(response["error"]["code"], json!(-32602));
+        }
+
+        assert_eq!(
+            call(&mut handler, "upload", json!({"data": small}))["result"],
+            json!(5)
+        );
+        let response = call(&mut handler, "upload", json!({"data": large}));
+        assert_eq!(
+            response["error"]["message"],
+            json!("ParamTooLarge upload at position 0. Exceeds 32 bytes.")
+        );
+    }
+
+    #[test]
+    fn fallback() {
+        #[easy_jsonrpc::rpc]
+        pub trait Primary {
+            fn greet(&self) -> String {
+                "hi from primary".into()
+            }
+        }
+        struct PrimaryImpl;
+        impl Primary for PrimaryImpl {}
+
+        let mut handler =
+            easy_jsonrpc::Fallback::new(&PrimaryImpl as &dyn Primary, &AdderImpl {} as &dyn Adder);
+        let call = |handler: &mut dyn Handler, method: &str, params: Value| {
+            handler
+                .handle_request(
+                    json!({"jsonrpc": "2.0", "method": method, "params": params, "id": 1}),
+                )
+                .as_option()
+                .unwrap()
+                .clone()
+        };
+
+        // the primary wins where both have the method
+        assert_eq!(
+            call(&mut handler, "greet", json!([]))["result"],
+            json!("hi from primary")
+        );
+        assert_eq!(
+            call(&mut handler, "wrapping_add", json!([1, 2]))["result"],
+            json!(3)
+        );
+        assert!(handler.method_exists("checked_add"));
+        assert!(!handler.method_exists("nonexist"));
+        assert_eq!(
+            call(&mut handler, "nonexist", json!([]))["error"]["code"],
+            json!(-32601)
+        );
+
+        // other errors from the primary are the reply, even if the secondary has the method
+        let mut primary = easy_jsonrpc::DynHandler::new();
+        primary.register("wrapping_add", |_| {
+            Err(easy_jsonrpc::Error::invalid_params("not here"))
+        });
+        let mut handler = easy_jsonrpc::Fallback::new(primary, &AdderImpl {} as &dyn Adder);
+        assert_eq!(
+            call(&mut handler, "wrapping_add", json!([1, 2]))["error"]["message"],
+            json!("not here")
+        );
+
+        // method not found from the primary's own method falls through too
+        let mut primary = easy_jsonrpc::DynHandler::new();
+        primary.register("wrapping_add", |_| {
+            Err(easy_jsonrpc::Error::method_not_found())
+        });
+        let mut handler = easy_jsonrpc::Fallback::new(primary, &AdderImpl {} as &dyn Adder);
+        assert_eq!(
+            call(&mut handler, "wrapping_add", json!([1, 2]))["result"],
+            json!(3)
+        );
+    }
+
+    #[test]
+    fn redactor() {
+        let mut inner = easy_jsonrpc::DynHandler::new();
+        inner.register("connect", |_| {
+            Err(easy_jsonrpc::Error {
+                code: easy_jsonrpc::ErrorCode::ServerError(-32010),
+                message: "database unavailable".into(),
+                data: Some(json!({"dsn": "postgres://admin:hunter2@db"})),
+            })
+        });
+        inner.register("ping", |_| Ok(json!("pong")));
+
+        let mut redacted = Vec::new();
+        let mut handler = easy_jsonrpc::Redactor::new(inner, |method, error| {
+            redacted.push(method.to_owned());
+            error.data = None;
+        });
+        let response = handler
+            .handle_request(json!({"jsonrpc": "2.0", "method": "connect", "id": 1}))
+            .as_option()
+            .unwrap()
+            .clone();
+        assert_eq!(
+            response["error"],
+            json!({"code": -32010, "message": "database unavailable"})
+        );
+        let response = handler
+            .handle_request(json!({"jsonrpc": "2.0", "method": "ping", "id": 2}))
+            .as_option()
+            .unwrap()
+            .clone();
+        assert_eq!(response["result"], json!("pong"));
+        drop(handler);
+        assert_eq!(redacted, ["connect"]);
+    }
+
+    #[test]
+    fn parse_positional() {
+        let params = |params: Value| match params {
+            Value::Array(ar) => Params::Positional(ar),
+            Value::Object(map) => Params::Named(map),
+            _ => unreachable!(),
+        };
+
+        let (a, b): (u64, String) = params(json!([1, "b"])).parse_positional().unwrap();
+        assert_eq!((a, b.as_str()), (1, "b"));
+        params(json!([])).parse_positional::<()>().unwrap();
+        let all: Vec<u8> = params(json!([1, 2, 3])).parse_positional().unwrap();
+        assert_eq!(all, [1, 2, 3]);
+
+        // too few or too many
+        let error = params(json!([1]))
+            .parse_positional::<(u64, String)>()
+            .unwrap_err();
+        assert_eq!(
+            error,
+            InvalidArgs::WrongNumberOfArgs {
+                expected: 2,
+                actual: 1
+            }
+            .into()
+        );
+        let error = params(json!([1, "b", null]))
+            .parse_positional::<(u64, String)>()
+            .unwrap_err();
+        assert_eq!(error.message, "WrongNumberOfArgs. Expected 2. Actual 3");
+        assert!(params(json!([1])).parse_positional::<()>().is_err());
+
+        // wrong type, reported by position
+        let error = params(json!([1, 2]))
+            .parse_positional::<(u64, String)>()
+            .unwrap_err();
+        assert_eq!(error.code, jsonrpc_core::ErrorCode::InvalidParams);
+        assert_eq!(error.message, "InvalidPositionalArg at position 1");
+        // serde's wording depends on serde_json's features
+        let explanation = error.data.unwrap()["error"].as_str().unwrap().to_owned();
+        assert!(explanation.starts_with("invalid type:"), "{}", explanation);
+        assert!(
+            explanation.ends_with("expected a string"),
+            "{}",
+            explanation
+        );
+        let error = params(json!([[1, "x"], 2]))
+            .parse_positional::<(Vec<u8>, u8)>()
+            .unwrap_err();
+        assert_eq!(error.message, "InvalidPositionalArg at position 0");
+
+        let error = params(json!({"a": 1}))
+            .parse_positional::<(u64,)>()
+            .unwrap_err();
+        assert_eq!(error, InvalidArgs::InvalidParamsStructure.into());
+    }
+
+    #[test]
+    fn batch_request_deduped() {
+        use easy_jsonrpc::{Call, Response};
+
+        let balance = adder::wrapping_add(1, 2).unwrap();
+        let other = adder::wrapping_add(2, 2).unwrap();
+        let (first, first_tracker) = balance.call();
+        let (second, second_tracker) = balance.call();
+        let (third, third_tracker) = other.call();
+        let (request, duplicates) = Call::batch_request_deduped(&[first, second, third]);
+        assert_eq!(request.as_array().unwrap().len(), 2);
+        assert!(!duplicates.is_empty());
+
+        let mut handler = &AdderImpl {} as &dyn Adder;
+        let raw = handler.handle_request(request).as_option().unwrap().clone();
+        let mut response = Response::from_json_response(raw).unwrap();
+        assert_eq!(response.len(), 2);
+        duplicates.fan_out(&mut response);
+        assert_eq!(first_tracker.get_return(&mut response).unwrap(), 3);
+        assert_eq!(second_tracker.get_return(&mut response).unwrap(), 3);
+        assert_eq!(third_tracker.get_return(&mut response).unwrap(), 4);
+
+        // nothing to dedupe
+        let (first, _) = balance.call();
+        let (request, duplicates) = Call::batch_request_deduped(&[first, balance.notification()]);
+        assert_eq!(request.as_array().unwrap().len(), 2);
+        assert!(duplicates.is_empty());
+    }
+
+    #[test]
+    fn concurrency_limit() {
+        use easy_jsonrpc::{ConcurrencyLimit, ConcurrencyLimits};
+        use std::sync::{mpsc, Mutex};
+
+        #[easy_jsonrpc::rpc]
+        pub trait Pool {
+            fn query(&self) -> u32;
+            fn other(&self) -> u32 {
+                2
+            }
+        }
+
+        // query blocks until released
+        struct PoolImpl {
+            entered: Mutex<mpsc::Sender<()>>,
+            release: Mutex<mpsc::Receiver<()>>,
+        }
+        impl Pool for PoolImpl {
+            fn query(&self) -> u32 {
+                self.entered.lock().unwrap().send(()).unwrap();
+                self.release.lock().unwrap().recv().unwrap();
+                1
+            }
+        }
+
+        let call = |pool: &PoolImpl, limits: &ConcurrencyLimits, method: &str| {
+            ConcurrencyLimit::new(pool as &dyn Pool, limits.clone())
+                .handle_request(json!({"jsonrpc": "2.0", "method": method, "id": 1}))
+                .as_option()
+                .unwrap()
+                .clone()
+        };
+
+        for queue in [false, true] {
+            let (entered_tx, entered) = mpsc::channel();
+            let (release, release_rx) = mpsc::channel();
+            let pool = PoolImpl {
+                entered: Mutex::new(entered_tx),
+                release: Mutex::new(release_rx),
+            };
+            let limits = ConcurrencyLimits::new().limit("query", 1).queue(queue);
+
+            std::thread::scope(|scope| {
+                let first = scope.spawn(|| call(&pool, &limits, "query"));
+                entered.recv().unwrap();
+                assert_eq!(limits.running("query"), 1);
+                // other methods are unaffected
+                assert_eq!(call(&pool, &limits, "other")["result"], json!(2));
+                if queue {
+                    let second = scope.spawn(|| call(&pool, &limits, "query"));
+                    release.send(()).unwrap();
+                    release.send(()).unwrap();
+                    assert_eq!(first.join().unwrap()["result"], json!(1));
+                    assert_eq!(second.join().unwrap()["result"], json!(1));
+                    entered.recv().unwrap();
+                } else {
+                    assert_eq!(
+                        call(&pool, &limits, "query")["error"],
+                        json!({"code": easy_jsonrpc::OVERLOADED, "message": "Overloaded"})
+                    );
+                    release.send(()).unwrap();
+                    assert_eq!(first.join().unwrap()["result"], json!(1));
+                }
+            });
+            assert_eq!(limits.running("query"), 0);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "concurrency limit for query must be at least 1")]
+    fn concurrency_limit_zero() {
+        easy_jsonrpc::ConcurrencyLimits::new().limit("query", 0);
+    }
+
+    #[test]
+    fn common_fields() {
+        #[derive(serde::Deserialize)]
+        pub struct CommonFields {
+            #[serde(default)]
+            api_version: u32,
+            #[serde(rename = "requestId")]
+            request_id: Option<String>,
+        }
+
+        #[easy_jsonrpc::rpc(common = CommonFields)]
+        pub trait Bank {
+            fn transfer(&self, #[rpc_ctx] common: &CommonFields, to: String, amount: u64) -> Value {
+                json!([common.api_version, common.request_id, to, amount])
+            }
+            fn ping(&self) -> bool {
+                true
+            }
+        }
+        struct BankImpl;
+        impl Bank for BankImpl {}
+
+        let mut handler = &BankImpl as &dyn Bank;
+        let call = |handler: &mut &dyn Bank, method: &str, params: Value| {
+            handler
+                .handle_request(
+                    json!({"jsonrpc": "2.0", "method": method, "params": params, "id": 1}),
+                )
+                .as_option()
+                .unwrap()
+                .clone()
+        };
+
+        let params = json!({"api_version": 2, "requestId": "r1", "to": "bob", "amount": 3});
+        assert_eq!(
+            call(&mut handler, "transfer", params.clone())["result"],
+            json!([2, "r1", "bob", 3])
+        );
+        let Value::Object(map) = params else {
+            unreachable!()
+        };
+        assert_eq!(
+            bank_validate_request("transfer", &Params::Named(map)),
+            Ok(())
+        );
+        // methods which don't take the common fields still have them removed
+        assert_eq!(
+            call(&mut handler, "ping", json!({"api_version": 2}))["result"],
+            json!(true)
+        );
+        // positional params have no common fields
+        assert_eq!(
+            call(&mut handler, "transfer", json!(["bob", 3]))["result"],
+            json!([0, null, "bob", 3])
+        );
+        // other unknown fields are still rejected
+        assert_eq!(
+            call(
+                &mut handler,
+                "transfer",
+                json!({"to": "bob", "amount": 3, "memo": ""})
+            )["error"]["code"],
+            json!(-32602)
+        );
+        // as are common fields of the wrong type
+        assert_eq!(
+            call(&mut handler, "ping", json!({"api_version": "2"}))["error"]["code"],
+            json!(-32602)
+        );
+    }
+
+    #[test]
+    fn handle_detailed() {
+        use easy_jsonrpc::HandleOutcome;
+
+        #[easy_jsonrpc::rpc]
+        pub trait Detailed {
+            fn double(&self, n: u32) -> u32 {
+                n * 2
+            }
+            fn fails(&self) -> Result<Value, easy_jsonrpc::Error> {
+                Err(easy_jsonrpc::Error::internal_error())
+            }
+        }
+        struct DetailedImpl;
+        impl Detailed for DetailedImpl {}
+        let mut handler = &DetailedImpl as &dyn Detailed;
+
+        let positional = |params: Value| match params {
+            Value::Array(ar) => Params::Positional(ar),
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            handler.handle_detailed("double", positional(json!([2]))),
+            HandleOutcome::Ok(json!(4))
+        );
+        assert_eq!(
+            handler.handle_detailed("fails", positional(json!([]))),
+            HandleOutcome::AppError(easy_jsonrpc::Error::internal_error())
+        );
+        assert_eq!(
+            handler.handle_detailed("nonexist", positional(json!([]))),
+            HandleOutcome::MethodNotFound
+        );
+        let outcome = handler.handle_detailed("double", positional(json!(["two"])));
+        match &outcome {
+            HandleOutcome::InvalidParams(error) => {
+                assert_eq!(error.message, r#"InvalidArgStructure "n" at position 0."#)
+            }
+            other => panic!("{:?}", other),
+        }
+        let outcome = handler.handle_detailed("double", positional(json!([])));
+        assert!(matches!(outcome, HandleOutcome::InvalidParams(_)));
+        assert_eq!(
+            outcome.into_result().unwrap_err(),
+            InvalidArgs::WrongNumberOfArgs {
+                expected: 1,
+                actual: 0
+            }
+            .into()
+        );
+        assert_eq!(
+            HandleOutcome::MethodNotFound.into_result(),
+            Err(easy_jsonrpc::Error::method_not_found())
+        );
+    }
+
+    #[test]
+    fn arg_alias() {
+        #[easy_jsonrpc::rpc]
+        pub trait Renamed {
+            fn transfer(
+                &self,
+                #[rpc_arg(alias = "dest", alias = "to")] recipient: String,
+                amount: Option<u64>,
+            ) -> String {
+                format!("{} {:?}", recipient, amount)
+            }
+        }
+        struct RenamedImpl;
+        impl Renamed for RenamedImpl {}
+        let mut handler = &RenamedImpl as &dyn Renamed;
+
+        let named = |params: Value| match params {
+            Value::Object(map) => Params::Named(map),
+            _ => unreachable!(),
+        };
+        for params in [
+            json!({"recipient": "bob", "amount": 1}),
+            json!({"dest": "bob", "amount": 1}),
+            json!({"to": "bob", "amount": 1}),
+            // the current name wins
+            json!({"dest": "alice", "recipient": "bob", "amount": 1}),
+        ] {
+            assert_eq!(
+                handler.handle("transfer", named(params.clone())),
+                Ok(json!("bob Some(1)")),
+                "{}",
+                params
+            );
+            assert_eq!(renamed_validate_request("transfer", &named(params)), Ok(()));
+        }
+
+        // the raw handler accepts aliases too
+        use easy_jsonrpc::HandleRaw;
+        let response = handler
+            .handle_request_raw(
+                r#"{"jsonrpc": "2.0", "method": "transfer", "params": {"to": "bob"}, "id": 1}"#,
+            )
+            .unwrap();
+        let response: Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(response["result"], json!("bob None"));
+
+        assert_eq!(
+            handler.handle("transfer", named(json!({"recipient": "bob", "from": "x"}))),
+            Err(InvalidArgs::ExtraNamedParameter {
+                name: "from".into()
+            }
+            .into())
+        );
+    }
+
+    #[test]
+    fn static_return() {
+        #[derive(serde::Serialize, serde::Deserialize, PartialEq, Debug)]
+        pub struct Config {
+            name: String,
+            limits: Vec<u32>,
+        }
+
+        #[easy_jsonrpc::rpc]
+        pub trait Constants {
+            fn banner(&self) -> &'static str {
+                "welcome"
+            }
+            fn config(&self) -> &'static Config;
+            fn primes(&self) -> &'static [u8] {
+                &[2, 3, 5]
+            }
+        }
+        struct ConstantsImpl(&'static Config);
+        impl Constants for ConstantsImpl {
+            fn config(&self) -> &'static Config {
+                self.0
+            }
+        }
+
+        let config: &'static Config = Box::leak(Box::new(Config {
+            name: "prod".into(),
+            limits: vec![1, 2],
+        }));
+        let mut handler = &ConstantsImpl(config) as &dyn Constants;
+
+        // the client receives the owned type
+        let bind: easy_jsonrpc::BoundMethod<'static, String> = constants::banner().unwrap();
+        let (call, tracker) = bind.call();
+        let raw = handler.handle_request(call.as_request());
+        let mut response =
+            easy_jsonrpc::Response::from_json_response(raw.as_option().unwrap().clone()).unwrap();
+        assert_eq!(tracker.get_return(&mut response).unwrap(), "welcome");
+
+        let bind = constants::config().unwrap();
+        let (call, tracker) = bind.call();
+        let raw = handler.handle_request(call.as_request());
+        let mut response =
+            easy_jsonrpc::Response::from_json_response(raw.as_option().unwrap().clone()).unwrap();
+        let received: Config = tracker.get_return(&mut response).unwrap();
+        assert_eq!(&received, config);
+
+        let bind: easy_jsonrpc::BoundMethod<'static, Vec<u8>> = constants::primes().unwrap();
+        let (call, tracker) = bind.call();
+        let raw = handler.handle_request(call.as_request());
+        let mut response =
+            easy_jsonrpc::Response::from_json_response(raw.as_option().unwrap().clone()).unwrap();
+        assert_eq!(tracker.get_return(&mut response).unwrap(), [2, 3, 5]);
+    }
+
+    #[test]
+    fn paginate() {
+        #[easy_jsonrpc::rpc(call_enum)]
+        pub trait Listing {
+            #[rpc(paginate)]
+            fn numbers(&self, count: u64) -> Vec<u64>;
+        }
+
+        struct ListingImpl;
+        impl Listing for ListingImpl {
+            fn numbers(&self, count: u64) -> Vec<u64> {
+                (0..count).collect()
+            }
+        }
+
+        let mut handler = &ListingImpl as &dyn Listing;
+        let mut call = |params: Value| {
+            handler
+                .handle_request(json!({
+                    "jsonrpc": "2.0",
+                    "method": "numbers",
+                    "params": params,
+                    "id": 1
+                }))
+                .as_option()
+                .unwrap()
+        };
+
+        // 25 items in pages of 10
+        let mut pages = vec![];
+        let mut cursor = Value::Null;
+        loop {
+            let response = call(json!({"count": 25, "cursor": cursor, "limit": 10}));
+            let page: easy_jsonrpc::Page<u64> =
+                serde_json::from_value(response["result"].clone()).unwrap();
+            pages.push(page.items);
+            match page.next_cursor {
+                Some(next) => cursor = json!(next),
+                None => break,
+            }
+        }
+        assert_eq!(
+            pages,
+            [
+                (0..10).collect::<Vec<_>>(),
+                (10..20).collect(),
+                (20..25).collect()
+            ]
+        );
+
+        // positionally after the method's own args, both optional
+        assert_eq!(
+            call(json!([25, 20, 10]))["result"],
+            json!({"items": [20, 21, 22, 23, 24], "next_cursor": null})
+        );
+        assert_eq!(
+            call(json!([3]))["result"],
+            json!({"items": [0, 1, 2], "next_cursor": null})
+        );
+        assert_eq!(
+            call(json!([150]))["result"]["items"]
+                .as_array()
+                .unwrap()
+                .len(),
+            easy_jsonrpc::DEFAULT_PAGE_LIMIT
+        );
+
+        // an empty page would never reach the next
+        assert_eq!(call(json!([25, null, 0]))["error"]["code"], json!(-32602));
+        assert_eq!(call(json!([25, 0, 10, 1]))["error"]["code"], json!(-32602));
+
+        assert_eq!(
+            listing_method_params("numbers"),
+            Some(&[("count", true), ("cursor", false), ("limit", false)][..])
+        );
+        assert_eq!(
+            listing::numbers(25, Some(10), Some(5))
+                .unwrap()
+                .call()
+                .0
+                .as_request()["params"],
+            json!([25, 10, 5])
+        );
+        match ListingCall::try_from(("numbers", Params::Positional(vec![json!(25), json!(10)]))) {
+            Ok(ListingCall::Numbers {
+                count: 25,
+                cursor: Some(10),
+                limit: None,
+            }) => {}
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn method_timeout() {
+        use std::time::Duration;
+
+        #[easy_jsonrpc::rpc]
+        pub trait Timed {
+            #[rpc(timeout_ms = 5000)]
+            fn report(&self) -> u8;
+            #[rpc(timeout_ms = 10)]
+            fn ping(&self) -> u8;
+            fn unlimited(&self) -> u8;
+        }
+
+        // both methods take longer than ping's timeout, but well within report's
+        struct TimedImpl;
+        impl Timed for TimedImpl {
+            fn report(&self) -> u8 {
+                std::thread::sleep(Duration::from_millis(50));
+                1
+            }
+
+            fn ping(&self) -> u8 {
+                std::thread::sleep(Duration::from_millis(50));
+                2
+            }
+
+            fn unlimited(&self) -> u8 {
+                3
+            }
+        }
+
+        assert_eq!(
+            timed_method_timeout("report"),
+            Some(Duration::from_millis(5000))
+        );
+        assert_eq!(
+            timed_method_timeout("ping"),
+            Some(Duration::from_millis(10))
+        );
+        assert_eq!(timed_method_timeout("unlimited"), None);
+
+        let mut handler =
+            easy_jsonrpc::WithTimeout::new(&TimedImpl {} as &dyn Timed, timed_method_timeout);
+        let mut call = |method: &str| {
+            handler
+                .handle_request(json!({"jsonrpc": "2.0", "method": method, "id": 1}))
+                .as_option()
+                .unwrap()
+        };
+        // ping overruns its timeout, but a call which runs keeps its result
+        assert_eq!(call("report")["result"], json!(1));
+        assert_eq!(call("ping")["result"], json!(2));
+        assert_eq!(call("unlimited")["result"], json!(3));
+
+        // a call whose caller's deadline has passed isn't run
+        let response = handler
+            .handle_request(json!({
+                "jsonrpc": "2.0",
+                "method": "report",
+                "id": 1,
+                "meta": {"deadline_ms": 1000}
+            }))
+            .as_option()
+            .unwrap();
+        assert_eq!(
+            response["error"],
+            json!({"code": easy_jsonrpc::TIMED_OUT, "message": "Timed out"})
+        );
+
+        // methods which check it get the earlier of the timeout and the caller's deadline
+        struct Remaining;
+        impl Handler for Remaining {
+            fn handle(&mut self, _: &str, _: Params) -> Result<Value, easy_jsonrpc::Error> {
+                Ok(Value::Null)
+            }
+
+            fn handle_with_request_meta(
+                &mut self,
+                _: &str,
+                _: Params,
+                meta: &easy_jsonrpc::RequestMeta,
+            ) -> Result<Value, easy_jsonrpc::Error> {
+                Ok(json!(meta.deadline().map(|deadline| {
+                    let remaining = deadline.duration_since(std::time::SystemTime::now());
+                    remaining.unwrap_or_default().as_millis() as u64
+                })))
+            }
+        }
+        let mut handler = easy_jsonrpc::WithTimeout::new(Remaining, timed_method_timeout);
+        let in_an_hour = std::time::SystemTime::now() + Duration::from_secs(3600);
+        let mut remaining = |method: &str, deadline: Option<std::time::SystemTime>| {
+            let mut call = json!({"jsonrpc": "2.0", "method": method, "id": 1});
+            if let Some(deadline) = deadline {
+                call["meta"] = easy_jsonrpc::deadline_meta(deadline);
+            }
+            let response = handler.handle_request(call).as_option().unwrap();
+            response["result"].as_u64()
+        };
+        assert!(remaining("ping", None).unwrap() <= 10);
+        assert!(remaining("report", Some(in_an_hour)).unwrap() <= 5000);
+        assert!(remaining("unlimited", Some(in_an_hour)).unwrap() > 5000);
+        assert_eq!(remaining("unlimited", None), None);
+    }
+
+    #[test]
+    fn untagged_enum_arg() {
+        use easy_jsonrpc::HandleRaw;
+
+        #[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+        #[serde(untagged)]
+        pub enum Id {
+            Num(u64),
+            Text(String),
+        }
+
+        #[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+        #[serde(untagged, expecting = "an id, either a number or a string")]
+        pub enum Described {
+            Num(u64),
+            Text(String),
+        }
+
+        #[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+        #[serde(untagged)]
+        pub enum Limit {
+            Count(u64),
+            Off(bool),
+        }
+
+        #[easy_jsonrpc::rpc]
+        pub trait Lookup {
+            fn find(&self, id: Id) -> String;
+            fn described(&self, id: Described) -> String;
+            fn limit(&self, limit: Limit) -> String;
+        }
+
+        struct LookupImpl;
+        impl Lookup for LookupImpl {
+            fn find(&self, id: Id) -> String {
+                format!("{:?}", id)
+            }
+
+            fn described(&self, id: Described) -> String {
+                format!("{:?}", id)
+            }
+
+            fn limit(&self, limit: Limit) -> String {
+                format!("{:?}", limit)
+            }
+        }
+
+        let mut handler = &LookupImpl {} as &dyn Lookup;
+        let call = |handler: &mut &dyn Lookup, method: &str, params: &str| -> Value {
+            let request = format!(
+                r#"{{"jsonrpc": "2.0", "method": "{}", "params": {}, "id": 1}}"#,
+                method, params
+            );
+            // the raw path buffers the variant's content from the raw json, the other from a Value
+            let raw = handler.handle_request_raw(&request);
+            let parsed = handler.handle_request_str(&request);
+            assert_eq!(raw, parsed, "{}", request);
+            serde_json::from_str(&parsed.unwrap()).unwrap()
+        };
+
+        assert_eq!(call(&mut handler, "find", "[7]")["result"], json!("Num(7)"));
+        // escapes must be resolved, which borrowing from the raw json can't do
+        assert_eq!(
+            call(&mut handler, "find", r#"["a\"bé"]"#)["result"],
+            json!("Text(\"a\\\"bé\")")
+        );
+        assert_eq!(
+            call(&mut handler, "find", r#"{"id": "x"}"#)["result"],
+            json!("Text(\"x\")")
+        );
+
+        let error = &call(&mut handler, "find", "[true]")["error"];
+        assert_eq!(error["code"], json!(-32602));
+        assert_eq!(
+            error["data"],
+            json!({
+                "name": "id",
+                "expected": "Id",
+                "error": "data did not match any variant of untagged enum Id (the argument was boolean `true`)",
+            })
+        );
+        assert_eq!(
+            call(&mut handler, "find", "[[1]]")["error"]["data"]["error"],
+            json!("data did not match any variant of untagged enum Id (the argument was sequence)")
+        );
+        // long strings are cut short
+        assert_eq!(
+            call(&mut handler, "limit", &format!("[\"{}\"]", "x".repeat(40)))["error"]["data"]
+                ["error"],
+            json!(format!(
+                "data did not match any variant of untagged enum Limit (the argument was string \"{}…\")",
+                "x".repeat(32)
+            ))
+        );
+        // while short strings are shown whole
+        assert_eq!(
+            call(&mut handler, "limit", "[\"few\"]")["error"]["data"]["error"],
+            json!("data did not match any variant of untagged enum Limit (the argument was string \"few\")")
+        );
+        assert_eq!(
+            call(&mut handler, "described", "[null]")["error"]["data"]["error"],
+            json!("an id, either a number or a string (the argument was null)")
+        );
+    }
+
+    #[cfg(feature = "schema")]
+    #[test]
+    fn method_schema() {
+        #[easy_jsonrpc::rpc]
+        pub trait Stats {
+            fn mean(&self, samples: Vec<u64>, precision: Option<u8>) -> f64;
+        }
+
+        assert_eq!(
+            stats_method_schema("mean"),
+            Some((
+                json!({
+                    "$schema": "http://json-schema.org/draft-07/schema#",
+                    "anyOf": [
+                        {
+                            "type": "array",
+                            "items": [
+                                {"type": "array", "items": {"type": "integer", "minimum": 0}},
+                                {"anyOf": [
+                                    {"type": "integer", "minimum": 0, "maximum": 255},
+                                    {"type": "null"}
+                                ]}
+                            ],
+                            "minItems": 1,
+                            "maxItems": 2
+                        },
+                        {
+                            "type": "object",
+                            "properties": {
+                                "samples": {
+                                    "type": "array",
+                                    "items": {"type": "integer", "minimum": 0}
+                                },
+                                "precision": {"anyOf": [
+                                    {"type": "integer", "minimum": 0, "maximum": 255},
+                                    {"type": "null"}
+                                ]}
+                            },
+                            "required": ["samples"],
+                            "additionalProperties": false
+                        }
+                    ]
+                }),
+                json!({
+                    "$schema": "http://json-schema.org/draft-07/schema#",
+                    "type": "number"
+                })
+            ))
+        );
+        assert_eq!(stats_method_schema("missing"), None);
+    }
+
+    #[cfg(feature = "schema")]
+    #[test]
+    fn method_schema_aliases() {
+        #[easy_jsonrpc::rpc]
+        pub trait Bank {
+            fn transfer(
+                &self,
+                #[rpc_arg(alias = "dest", alias = "to")] recipient: String,
+                amount: u64,
+                #[rpc_arg(alias = "memo")] note: Option<String>,
+            );
+        }
+
+        let (params, _) = bank_method_schema("transfer").unwrap();
+        let named = &params["anyOf"][1];
+        let string = json!({"type": "string"});
+        let properties = named["properties"].as_object().unwrap();
+        let mut names: Vec<&str> = properties.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        assert_eq!(names, ["amount", "dest", "memo", "note", "recipient", "to"]);
+        for name in ["recipient", "dest", "to"] {
+            assert_eq!(properties[name], string);
+        }
+        assert_eq!(properties["memo"], properties["note"]);
+        assert_eq!(named["additionalProperties"], json!(false));
+        assert_eq!(named["required"], json!(["amount"]));
+        assert_eq!(
+            named["allOf"],
+            json!([{"anyOf": [
+                {"required": ["recipient"]},
+                {"required": ["dest"]},
+                {"required": ["to"]}
+            ]}])
+        );
+    }
+
+    #[cfg(feature = "schema")]
+    #[test]
+    fn method_schema_any_result() {
+        #[easy_jsonrpc::rpc]
+        pub trait Proxy {
+            fn forward(&self, request: Value) -> Value;
+        }
+
+        let any = json!({"$schema": "http://json-schema.org/draft-07/schema#"});
+        let (params, result) = proxy_method_schema("forward").unwrap();
+        assert_eq!(params["anyOf"][0]["items"], json!([{}]));
+        assert_eq!(result, any);
+    }
+
+    #[test]
+    fn u64_max_id() {
+        let mut handler = &AdderImpl {} as &dyn Adder;
+        let request = r#"{"jsonrpc": "2.0", "method": "greet", "id": 18446744073709551615}"#;
+        let expected = r#"{"jsonrpc":"2.0","result":"hello","id":18446744073709551615}"#;
+        assert_eq!(
+            handler.handle_request_str(request),
+            Some(expected.to_owned())
+        );
+        assert_eq!(
+            easy_jsonrpc::HandleRaw::handle_request_raw(&mut handler, request),
+            Some(expected.to_owned())
+        );
+        assert_eq!(
+            handler
+                .handle_request(serde_json::from_str(request).unwrap())
+                .as_option()
+                .unwrap()["id"]
+                .to_string(),
+            "18446744073709551615"
+        );
+
+        // the client matches the echoed id to its call
+        let bind = adder::wrapping_add(1, 2).unwrap().with_id(u64::MAX);
+        let (call, tracker) = bind.call();
+        assert_eq!(call.as_request()["id"].to_string(), "18446744073709551615");
+        let raw_response = handler
+            .handle_request(call.as_request())
+            .as_option()
+            .unwrap();
+        let mut response = easy_jsonrpc::Response::from_json_response(raw_response).unwrap();
+        assert_eq!(tracker.get_return(&mut response), Ok(3));
+    }
+
+    #[test]
+    fn handle_batch_transactional() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        let ran = Rc::new(Cell::new(0));
+        let mut handler = easy_jsonrpc::DynHandler::new();
+        let count = ran.clone();
+        handler.register("step", move |params| {
+            count.set(count.get() + 1);
+            let (ok,): (bool,) = params.parse_positional()?;
+            if ok {
+                Ok(json!(count.get()))
+            } else {
+                Err(easy_jsonrpc::Error::internal_error())
+            }
+        });
+        let responses = handler.handle_batch_transactional(vec![
+            json!({"jsonrpc": "2.0", "method": "step", "params": [true], "id": 1}),
+            json!({"jsonrpc": "2.0", "method": "missing", "id": 2}),
+            json!({"jsonrpc": "2.0", "method": "step", "params": [false], "id": 3}),
+            json!({"jsonrpc": "2.0", "method": "step", "params": [true], "id": 4}),
+        ]);
+        assert_eq!(
+            responses,
+            vec![
+                json!({"jsonrpc": "2.0", "result": 1, "id": 1}),
+                json!({
+                    "jsonrpc": "2.0",
+                    "error": {"code": -32601, "message": "Method not found"},
+                    "id": 2
+                }),
+                json!({
+                    "jsonrpc": "2.0",
+                    "error": {"code": -32603, "message": "Internal error"},
+                    "id": 3
+                }),
+            ]
+        );
+        assert_eq!(ran.get(), 2);
+    }
+
+    #[test]
+    fn rename_all() {
+        #[easy_jsonrpc::rpc(rename_all = "camelCase", rename_all_methods = "camelCase")]
+        pub trait Ledger {
+            fn transfer_funds(
+                &self,
+                from_account: String,
+                amount_cents: u64,
+                #[rpc_arg(name = "memo_text")] memo: Option<String>,
+            ) -> String;
+            #[rpc(name = "get_balance")]
+            fn get_balance(&self, account_id: String) -> u64;
+        }
+
+        struct LedgerImpl;
+        impl Ledger for LedgerImpl {
+            fn transfer_funds(
+                &self,
+                from_account: String,
+                amount_cents: u64,
+                memo: Option<String>,
+            ) -> String {
+                format!("{} {} {:?}", from_account, amount_cents, memo)
+            }
+
+            fn get_balance(&self, account_id: String) -> u64 {
+                account_id.len() as u64
+            }
+        }
+
+        let mut handler = &LedgerImpl as &dyn Ledger;
+        let mut call = |method: &str, params: Value| {
+            handler
+                .handle_request(json!({
+                    "jsonrpc": "2.0",
+                    "method": method,
+                    "params": params,
+                    "id": 1
+                }))
+                .as_option()
+                .unwrap()
+        };
+        assert_eq!(
+            call(
+                "transferFunds",
+                json!({"fromAccount": "alice", "amountCents": 5, "memo_text": "rent"})
+            )["result"],
+            json!("alice 5 Some(\"rent\")")
+        );
+        assert_eq!(
+            call("transferFunds", json!(["bob", 7]))["result"],
+            json!("bob 7 None")
+        );
+        assert_eq!(
+            call("get_balance", json!({"accountId": "carol"}))["result"],
+            json!(5)
+        );
+        // the Rust names no longer bind
+        assert_eq!(
+            call(
+                "transferFunds",
+                json!({"from_account": "alice", "amount_cents": 5})
+            )["error"]["code"],
+            json!(-32602)
+        );
+        assert_eq!(
+            call("transfer_funds", json!(["bob", 7]))["error"]["code"],
+            json!(-32601)
+        );
+        assert_eq!(
+            ledger_method_params("transferFunds"),
+            Some(
+                &[
+                    ("fromAccount", true),
+                    ("amountCents", true),
+                    ("memo_text", false)
+                ][..]
+            )
+        );
+        let bind = ledger::transfer_funds("dave".into(), 1, None).unwrap();
+        let (request, _) = bind.call();
+        assert_eq!(request.as_request()["method"], json!("transferFunds"));
+    }
+
+    #[test]
+    fn stub_handler() {
+        let mut results = std::collections::HashMap::new();
+        results.insert("balance".to_owned(), json!({"amount": 10}));
+        let mut handler = easy_jsonrpc::StubHandler::from_map(results);
+        handler.insert("ping", json!(null));
+
+        let mut call = |method: &str, params: Value| {
+            handler
+                .handle_request(json!({
+                    "jsonrpc": "2.0",
+                    "method": method,
+                    "params": params,
+                    "id": 1
+                }))
+                .as_option()
+                .unwrap()
+        };
+        assert_eq!(
+            call("balance", json!(["alice"])),
+            json!({"jsonrpc": "2.0", "result": {"amount": 10}, "id": 1})
+        );
+        assert_eq!(
+            call("balance", json!({"who": 3}))["result"],
+            json!({"amount": 10})
+        );
+        assert_eq!(call("ping", json!([]))["result"], json!(null));
+        assert_eq!(call("transfer", json!([]))["error"]["code"], json!(-32601));
+    }
+
+    #[test]
+    fn int128_as_str() {
+        use easy_jsonrpc::HandleRaw;
+
+        #[easy_jsonrpc::rpc]
+        trait Big {
+            fn pred(&self, a: u128) -> u128 {
+                a - 1
+            }
+            fn negate(&self, a: core::primitive::i128) -> i128 {
+                -a
+            }
+        }
+        impl Big for () {}
+
+        let mut handler = &() as &dyn Big;
+        let mut call = |method: &str, params: Value| {
+            handler
+                .handle_request(json!({
+                    "jsonrpc": "2.0",
+                    "method": method,
+                    "params": params,
+                    "id": 1
+                }))
+                .as_option()
+                .unwrap()
+        };
+        assert_eq!(
+            call("pred", json!([u128::MAX.to_string()]))["result"],
+            json!((u128::MAX - 1).to_string())
+        );
+        assert_eq!(
+            call("negate", json!({"a": i128::MAX.to_string()}))["result"],
+            json!((-i128::MAX).to_string())
+        );
+        // numbers are accepted too, as they were before 128 bit integers were sent as strings
+        assert_eq!(call("pred", json!([5]))["result"], json!("4"));
+        assert_eq!(call("negate", json!([-5]))["result"], json!("5"));
+        for bad in [
+            json!(["340282366920938463463374607431768211456"]),
+            json!(["1e3"]),
+            json!([1.5]),
+            json!([-1]),
+        ] {
+            assert_eq!(call("pred", bad)["error"]["code"], json!(-32602));
+        }
+
+        let bound = big::pred(u128::MAX - 5).unwrap();
+        let (request, tracker) = bound.call();
+        let request = request.as_request();
+        assert_eq!(request["params"], json!([(u128::MAX - 5).to_string()]));
+        let mut response = easy_jsonrpc::Response::from_json_response(
+            handler.handle_request(request).as_option().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(tracker.get_return(&mut response).unwrap(), u128::MAX - 6);
+
+        // the raw path, and clients reading results, accept numbers as well
+        let raw = handler
+            .handle_request_raw(r#"{"jsonrpc": "2.0", "method": "pred", "params": [5], "id": 1}"#)
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&raw).unwrap()["result"],
+            json!("4")
+        );
+        let read = |value: Value| easy_jsonrpc::codec::int128::deserialize::<u128, _>(value);
+        assert_eq!(read(json!(7)).unwrap(), 7);
+        assert_eq!(read(json!("7")).unwrap(), 7);
+        assert!(read(json!(null)).is_err());
+    }
+
+    #[test]
+    fn into_request() {
+        let request = adder::wrapping_add(1, 2)
+            .unwrap()
+            .into_request(easy_jsonrpc::RequestId::from("req-1"));
+        assert_eq!(
+            request,
+            json!({
+                "jsonrpc": "2.0",
+                "method": "wrapping_add",
+                "params": [1, 2],
+                "id": "req-1"
+            })
+        );
+        assert_eq!(
+            (&AdderImpl {} as &dyn Adder).handle_request(request),
+            MaybeReply::Reply(json!({"jsonrpc": "2.0", "result": 3, "id": "req-1"}))
+        );
+
+        let notification = adder::wrapping_add(1, 2).unwrap().into_notification();
+        assert_eq!(
+            notification,
+            json!({"jsonrpc": "2.0", "method": "wrapping_add", "params": [1, 2]})
+        );
+        assert_eq!(
+            (&AdderImpl {} as &dyn Adder).handle_request(notification),
+            MaybeReply::DontReply
+        );
+    }
+
+    #[test]
+    fn arc_self() {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        use std::sync::Arc;
+
+        #[easy_jsonrpc::rpc(mock)]
+        pub trait Actor {
+            fn spawn_add(self: Arc<Self>, n: u64) -> u64;
+            fn total(&self) -> u64;
+        }
+
+        struct ActorImpl(AtomicU64);
+        impl Actor for ActorImpl {
+            fn spawn_add(self: Arc<Self>, n: u64) -> u64 {
+                std::thread::spawn(move || self.0.fetch_add(n, Ordering::SeqCst) + n)
+                    .join()
+                    .unwrap()
+            }
+
+            fn total(&self) -> u64 {
+                self.0.load(Ordering::SeqCst)
+            }
+        }
+
+        let actor = Arc::new(ActorImpl(AtomicU64::new(1)));
+        let mut handler: Arc<dyn Actor> = actor.clone();
+        let mut call = |method: &str, params: Value| {
+            handler
+                .handle_request(json!({
+                    "jsonrpc": "2.0",
+                    "method": method,
+                    "params": params,
+                    "id": 1
+                }))
+                .as_option()
+                .unwrap()
+        };
+        assert_eq!(call("spawn_add", json!([2]))["result"], json!(3));
+        assert_eq!(call("spawn_add", json!({"n": 4}))["result"], json!(7));
+        assert_eq!(call("total", json!([]))["result"], json!(7));
+        assert_eq!(call("missing", json!([]))["error"]["code"], json!(-32601));
+        // the clones given to spawn_add were dropped with their threads
+        assert_eq!(Arc::strong_count(&actor), 2);
+
+        let mut mock = ActorMock::default();
+        mock.on_spawn_add(|n| n * 10);
+        assert_eq!(Arc::new(mock).spawn_add(5), 50);
+    }
+
+    #[cfg(feature = "subscriptions")]
+    #[test]
+    fn subscription() {
+        use easy_jsonrpc::{Subscription, Subscriptions};
+        use futures_util::{stream, FutureExt, StreamExt};
+
+        #[easy_jsonrpc::rpc]
+        trait Node {
+            fn height(&self) -> u64;
+            fn blocks(&self, from: u64, count: u64) -> Subscription<String>;
+        }
+
+        struct NodeImpl;
+        impl Node for NodeImpl {
+            fn height(&self) -> u64 {
+                9
+            }
+
+            fn blocks(&self, from: u64, count: u64) -> Subscription<String> {
+                Subscription::new(stream::iter(from..from + count).map(|n| format!("block {}", n)))
+            }
+        }
+
+        // subscriptions only start through Subscriptions
+        let mut plain = &NodeImpl as &dyn Node;
+        assert!(!plain.method_exists("blocks"));
+        let bind = node::blocks(1, 2).unwrap();
+        let (call, tracker) = bind.call();
+        assert_eq!(
+            plain.handle_request(call.as_request()).as_option().unwrap()["error"]["code"],
+            json!(-32601)
+        );
+
+        let mut handler = Subscriptions::new(&NodeImpl as &dyn Node);
+        assert!(handler.method_exists("blocks"));
+        assert!(handler.method_exists("unsubscribe"));
+        let raw_response = handler
+            .handle_request(call.as_request())
+            .as_option()
+            .unwrap();
+        let mut response = easy_jsonrpc::Response::from_json_response(raw_response).unwrap();
+        assert_eq!(tracker.get_return(&mut response).unwrap(), 1);
+        let bind = node::blocks(10, 5).unwrap();
+        let (call, _) = bind.call();
+        assert_eq!(
+            handler
+                .handle_request(call.as_request())
+                .as_option()
+                .unwrap()["result"],
+            json!(2)
+        );
+        assert_eq!(handler.active().collect::<Vec<_>>(), vec![1, 2]);
+
+        // a notification can't start a subscription, which it couldn't cancel
+        let bind = node::blocks(20, 1).unwrap();
+        assert_eq!(
+            handler.handle_request(bind.notification().as_request()),
+            MaybeReply::DontReply
+        );
+        assert_eq!(handler.active().collect::<Vec<_>>(), vec![1, 2]);
+
+        // other methods pass through
+        let bind = node::height().unwrap();
+        let (call, _) = bind.call();
+        assert_eq!(
+            handler
+                .handle_request(call.as_request())
+                .as_option()
+                .unwrap()["result"],
+            json!(9)
+        );
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let notifications: Vec<Value> = runtime.block_on(async {
+            let mut notifications = vec![];
+            for _ in 0..3 {
+                notifications.push(handler.next_notification().await);
+            }
+            notifications
+        });
+        // subscriptions take turns
+        assert_eq!(
+            notifications,
+            vec![
+                json!({
+                    "jsonrpc": "2.0",
+                    "method": "blocks",
+                    "params": {"subscription": 1, "result": "block 1"}
+                }),
+                json!({
+                    "jsonrpc": "2.0",
+                    "method": "blocks",
+                    "params": {"subscription": 2, "result": "block 10"}
+                }),
+                json!({
+                    "jsonrpc": "2.0",
+                    "method": "blocks",
+                    "params": {"subscription": 1, "result": "block 2"}
+                }),
+            ]
+        );
+
+        // cancelled subscriptions send nothing more
+        assert_eq!(
+            handler
+                .handle_request(json!({
+                    "jsonrpc": "2.0",
+                    "method": "unsubscribe",
+                    "params": {"subscription": 2},
+                    "id": 1
+                }))
+                .as_option()
+                .unwrap()["result"],
+            json!(true)
+        );
+        assert_eq!(
+            handler
+                .handle_request(
+                    json!({"jsonrpc": "2.0", "method": "unsubscribe", "params": [2], "id": 1})
+                )
+                .as_option()
+                .unwrap()["result"],
+            json!(false)
+        );
+        // subscription 1 ended after its two items
+        assert_eq!(handler.next_notification().now_or_never(), None);
+        assert_eq!(handler.active().count(), 0);
+    }
+
+    #[cfg(feature = "subscriptions")]
+    #[test]
+    fn subscription_serialization_error() {
+        use easy_jsonrpc::{Subscription, Subscriptions};
+        use futures_util::stream;
+        use std::collections::BTreeMap;
+
+        #[easy_jsonrpc::rpc]
+        trait Maps {
+            // maps with non-string keys can't be serialized as json
+            fn maps(&self) -> Subscription<BTreeMap<Vec<u8>, u8>>;
+        }
+
+        struct MapsImpl;
+        impl Maps for MapsImpl {
+            fn maps(&self) -> Subscription<BTreeMap<Vec<u8>, u8>> {
+                Subscription::new(stream::iter(vec![
+                    BTreeMap::new(),
+                    BTreeMap::from([(vec![1], 1)]),
+                ]))
+            }
+        }
+
+        let mut handler = Subscriptions::new(&MapsImpl as &dyn Maps);
+        handler.handle_request(json!({"jsonrpc": "2.0", "method": "maps", "id": 1}));
+        let notifications: Vec<Value> = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(async {
+                vec![
+                    handler.next_notification().await,
+                    handler.next_notification().await,
+                ]
+            });
+        assert_eq!(
+            notifications[0]["params"],
+            json!({"subscription": 1, "result": {}})
+        );
+        assert_eq!(notifications[1]["params"]["subscription"], json!(1));
+        assert_eq!(notifications[1]["params"]["error"]["code"], json!(-32603));
+        assert_eq!(notifications[1]["params"].get("result"), None);
+    }
+
+    #[cfg(feature = "serde_path")]
+    #[test]
+    fn serde_path() {
+        use easy_jsonrpc::HandleRaw;
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Deserialize, Serialize)]
+        pub struct Settings {
+            pub retries: u32,
+        }
+
+        #[derive(Deserialize, Serialize)]
+        pub struct Config {
+            pub name: String,
+            pub settings: Settings,
+        }
+
+        #[easy_jsonrpc::rpc]
+        pub trait Configure {
+            fn configure(&self, config: Config) -> u32;
+            fn configure_all(&self, configs: Vec<Config>) -> usize;
+        }
+
+        struct ConfigureImpl;
+        impl Configure for ConfigureImpl {
+            fn configure(&self, config: Config) -> u32 {
+                config.settings.retries
+            }
+            fn configure_all(&self, configs: Vec<Config>) -> usize {
+                configs.len()
+            }
+        }
+
+        let mut handler = &ConfigureImpl as &dyn Configure;
+        for (method, params, path) in [
+            (
+                "configure",
+                json!([{"name": "a", "settings": {"retries": "3"}}]),
+                "settings.retries: invalid type: string \"3\", expected u32",
+            ),
+            (
+                "configure_all",
+                json!([[{"name": "a", "settings": {"retries": 3}}, {"name": true}]]),
+                "[1].name: invalid type: boolean `true`, expected a string",
+            ),
+        ] {
+            let request = json!({"jsonrpc": "2.0", "method": method, "params": params, "id": 1});
+            let response = handler.handle_request(request.clone()).as_option().unwrap();
+            assert_eq!(response["error"]["code"], json!(-32602));
+            assert_eq!(response["error"]["data"]["error"], json!(path));
+            let raw_path = handler.handle_request_raw(&request.to_string()).unwrap();
+            let raw_path: Value = serde_json::from_str(&raw_path).unwrap();
+            assert_eq!(raw_path["error"]["data"]["error"], json!(path));
+        }
+
+        // errors in the argument itself have no path to report
+        let response = handler
+            .handle_request(
+                json!({"jsonrpc": "2.0", "method": "configure", "params": [true], "id": 1}),
+            )
+            .as_option()
+            .unwrap();
+        assert!(response["error"]["data"]["error"]
+            .as_str()
+            .unwrap()
+            .starts_with("invalid type: boolean `true`"));
+    }
+
+    #[test]
+    fn handle_request_owned() {
+        use easy_jsonrpc::types::Request;
+
+        fn parse(request: Value) -> Request {
+            serde_json::from_value(request).unwrap()
+        }
+
+        let mut handler = &AdderImpl {} as &dyn Adder;
+        let response = handler.handle_request_owned(parse(json!([
+            {"jsonrpc": "2.0", "method": "repeat_list", "params": [[1, 2]], "id": 1},
+            {"jsonrpc": "2.0", "method": "wrapping_add", "params": {"a": 1, "b": "2"}, "id": 2},
+            {"jsonrpc": "2.0", "method": "echo_ref", "params": [3], "id": 3},
+            {"jsonrpc": "2.0", "method": "swallow"},
+        ])));
+        let response = serde_json::to_value(response.unwrap()).unwrap();
+        assert_eq!(response[0]["result"], json!([1, 2, 1, 2]));
+        assert_eq!(response[1]["error"]["code"], json!(-32602));
+        assert_eq!(response[2]["result"], json!(3));
+        assert_eq!(response.as_array().unwrap().len(), 3);
+        assert_eq!(
+            handler.handle_request_owned(parse(json!({"jsonrpc": "2.0", "method": "swallow"}))),
+            None
+        );
+
+        #[easy_jsonrpc::rpc(strict_version)]
+        trait Strict {
+            fn ping(&self) -> bool;
+        }
+
+        struct StrictImpl;
+        impl Strict for StrictImpl {
+            fn ping(&self) -> bool {
+                true
+            }
+        }
+
+        let mut handler = &StrictImpl as &dyn Strict;
+        let response = handler.handle_request_owned(parse(json!({"method": "ping", "id": 1})));
+        assert_eq!(
+            serde_json::to_value(response.unwrap()).unwrap()["error"]["code"],
+            json!(-32600)
+        );
+    }
+
+    #[test]
+    fn wrappers_forward_meta() {
+        use easy_jsonrpc::{
+            AllowList, CatchUnwind, ConcurrencyLimit, ConcurrencyLimits, Fallback, GatedHandler,
+            Metered, Redactor, RemapMethods, RequestMeta, WithTimeout,
+        };
+        use std::time::{Duration, SystemTime};
+
+        // answers with what it's told about the call besides its method and params
+        struct Meta;
+        impl Handler for Meta {
+            fn handle(&mut self, _: &str, _: Params) -> Result<Value, easy_jsonrpc::Error> {
+                Ok(Value::Null)
+            }
+
+            fn handle_with_request_meta(
+                &mut self,
+                _: &str,
+                _: Params,
+                meta: &RequestMeta,
+            ) -> Result<Value, easy_jsonrpc::Error> {
+                Ok(json!([meta.id(), meta.deadline().is_some()]))
+            }
+        }
+
+        let deadline = SystemTime::now() + Duration::from_secs(3600);
+        let request = json!({
+            "jsonrpc": "2.0",
+            "method": "m",
+            "id": 7,
+            "meta": easy_jsonrpc::deadline_meta(deadline),
+        });
+        let check = |mut handler: Box<dyn Handler>| {
+            let response = handler.handle_request(request.clone());
+            assert_eq!(response.as_option().unwrap()["result"], json!([7, true]));
+        };
+        check(Box::new(AllowList::new(Meta, &["m"])));
+        check(Box::new(CatchUnwind::new(Meta)));
+        check(Box::new(ConcurrencyLimit::new(
+            Meta,
+            ConcurrencyLimits::new(),
+        )));
+        check(Box::new(Fallback::new(Meta, Meta)));
+        check(Box::new(GatedHandler::new(Meta)));
+        check(Box::new(easy_jsonrpc::layer(Meta, |_, _| Ok(()))));
+        check(Box::new(Metered::new(Meta)));
+        check(Box::new(Redactor::new(Meta, |_, _| {})));
+        check(Box::new(RemapMethods::with_fn(Meta, |_| None)));
+        check(Box::new(WithTimeout::new(Meta, |_| None)));
+    }
+
+    #[test]
+    fn remap_methods() {
+        use easy_jsonrpc::RemapMethods;
+        use std::collections::HashMap;
+
+        let mut renames = HashMap::new();
+        renames.insert("math.add".to_owned(), "wrapping_add".to_owned());
+        renames.insert("math.sub".to_owned(), "wrapping_sub".to_owned());
+        let renames_for_thread = renames.clone();
+        let mut handler = RemapMethods::new(&AdderImpl {} as &dyn Adder, renames);
+
+        assert!(handler.method_exists("math.add"));
+        assert!(handler.method_exists("wrapping_add"));
+        // renamed to a method the handler doesn't have
+        assert!(!handler.method_exists("math.sub"));
+        for method in ["math.add", "wrapping_add"] {
+            let response = handler
+                .handle_request(
+                    json!({"jsonrpc": "2.0", "method": method, "params": [1, 2], "id": 1}),
+                )
+                .as_option()
+                .unwrap();
+            assert_eq!(response["result"], json!(3));
+        }
+        let response = handler
+            .handle_request(
+                json!({"jsonrpc": "2.0", "method": "math.sub", "params": [1, 2], "id": 1}),
+            )
+            .as_option()
+            .unwrap();
+        assert_eq!(response["error"]["code"], json!(-32601));
+
+        let mut handler = RemapMethods::with_fn(&AdderImpl {} as &dyn Adder, |method| {
+            method.strip_prefix("v1_").map(str::to_owned)
+        });
+        let response = handler
+            .handle_request(json!({"jsonrpc": "2.0", "method": "v1_greet", "id": 1}))
+            .as_option()
+            .unwrap();
+        assert_eq!(response["result"], json!("hello"));
+
+        // the renames move into the wrapper, which can be sent to a thread serving requests
+        struct Echo;
+        impl Handler for Echo {
+            fn handle(&mut self, method: &str, _: Params) -> Result<Value, easy_jsonrpc::Error> {
+                Ok(json!(method))
+            }
+        }
+        let mut handler = RemapMethods::new(Echo, renames_for_thread);
+        let response = std::thread::spawn(move || {
+            handler.handle_request(json!({"jsonrpc": "2.0", "method": "math.add", "id": 1}))
+        })
+        .join()
+        .unwrap();
+        assert_eq!(
+            response.as_option().unwrap()["result"],
+            json!("wrapping_add")
+        );
+    }
+
+    #[test]
+    fn strict_sync() {
+        #[easy_jsonrpc::rpc(strict_sync)]
+        pub trait Synced {
+            fn both(&self) -> u8;
+            #[rpc(name = "adminReset", server_only, unsynced)]
+            fn admin_reset(&self);
+            #[rpc(client_only, unsynced)]
+            fn proxied(&self);
+        }
+
+        struct Impl;
+        impl Synced for Impl {
+            fn both(&self) -> u8 {
+                1
+            }
+            fn admin_reset(&self) {}
+            fn proxied(&self) {}
+        }
+        let handler = &Impl as &dyn Synced;
+        assert!(handler.method_exists("both"));
+        assert!(handler.method_exists("adminReset"));
+        assert!(!handler.method_exists("proxied"));
+    }
+
+    #[test]
+    fn call_macro() {
+        use easy_jsonrpc::{ArgSerializeError, Response};
+
+        fn run() -> Result<(), ArgSerializeError> {
+            let mut handler = &AdderImpl {} as &dyn Adder;
+            let (request, tracker) = call!(adder::checked_add(1, 2))?;
+            assert_eq!(request["method"], json!("checked_add"));
+            assert_eq!(request["params"], json!([1, 2]));
+            let (other_request, other_tracker) = call!(adder::repeat_list(vec![1]))?;
+            assert_ne!(request["id"], other_request["id"]);
+
+            let response = handler
+                .handle_request(json!([request, other_request]))
+                .as_option()
+                .unwrap();
+            let mut response = Response::from_json_response(response).unwrap();
+            assert_eq!(tracker.get_return(&mut response).unwrap(), Some(3));
+            assert_eq!(other_tracker.get_return(&mut response).unwrap(), vec![1, 1]);
+            Ok(())
+        }
+        run().unwrap();
+
+        pub struct Unserializable;
+        impl serde::Serialize for Unserializable {
+            fn serialize<S: serde::Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+                Err(serde::ser::Error::custom("no"))
+            }
+        }
+        #[easy_jsonrpc::rpc]
+        pub trait Sink {
+            #[rpc(client_only)]
+            fn sink(&self, a: Unserializable);
+        }
+        assert!(call!(sink::sink(Unserializable)).is_err());
+    }
+
+    #[test]
+    fn progress() {
+        use crate::{with_sink, PROGRESS};
+        use std::cell::Cell;
+
+        #[easy_jsonrpc::rpc]
+        pub trait Job {
+            fn run(&self, #[rpc_progress] progress: &dyn Fn(Value), steps: u32) -> String;
+            fn quick(&self) -> u32;
+        }
+
+        pub struct JobImpl {
+            runs: Cell<u32>,
+        }
+        impl Job for JobImpl {
+            fn run(&self, progress: &dyn Fn(Value), steps: u32) -> String {
+                self.runs.set(self.runs.get() + 1);
+                for step in 1..=steps {
+                    progress(json!({ "step": step, "of": steps }));
+                }
+                "done".into()
+            }
+            fn quick(&self) -> u32 {
+                1
+            }
+        }
+
+        let job = JobImpl { runs: Cell::new(0) };
+        let mut handler = &job as &dyn Job;
+        let mut notifications = Vec::new();
+        let response = with_sink(&mut handler, &mut |notification| {
+            notifications.push(notification)
+        })
+        .handle_request(json!({
+            "jsonrpc": "2.0",
+            "method": "run",
+            "params": {"steps": 2},
+            "id": 7
+        }));
+        assert_eq!(
+            response,
+            MaybeReply::Reply(json!({"jsonrpc": "2.0", "result": "done", "id": 7}))
+        );
+        assert_eq!(
+            notifications,
+            [
+                json!({
+                    "jsonrpc": "2.0",
+                    "method": PROGRESS,
+                    "params": {"id": 7, "value": {"step": 1, "of": 2}}
+                }),
+                json!({
+                    "jsonrpc": "2.0",
+                    "method": PROGRESS,
+                    "params": {"id": 7, "value": {"step": 2, "of": 2}}
+                }),
+            ]
+        );
+
+        // the plain handler runs the method, discarding its progress, and the raw handler too
+        assert_eq!(
+            handler.handle("run", Params::Positional(vec![json!(3)])),
+            Ok(json!("done"))
+        );
+        assert_eq!(
+            easy_jsonrpc::HandleRaw::handle_request_raw(
+                &mut handler,
+                r#"{"jsonrpc": "2.0", "method": "run", "params": [1], "id": 1}"#
+            ),
+            Some(r#"{"jsonrpc":"2.0","result":"done","id":1}"#.to_owned())
+        );
+        assert_eq!(job.runs.get(), 3);
+
+        // the progress argument isn't a param
+        assert_eq!(
+            job::run(1).unwrap().call().0.as_request()["params"],
+            json!([1])
+        );
+    }
+
+    #[test]
+    fn redact_internal_errors() {
+        pub struct Unserializable;
+        impl serde::Serialize for Unserializable {
+            fn serialize<S: serde::Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+                Err(serde::ser::Error::custom("secret detail"))
+            }
+        }
+
+        #[easy_jsonrpc::rpc]
+        pub trait Store {
+            #[rpc(server_only)]
+            fn load(&self) -> Unserializable;
+            fn missing(&self) -> Result<u8, String>;
+        }
+
+        pub struct StoreImpl;
+        impl Store for StoreImpl {
+            fn load(&self) -> Unserializable {
+                Unserializable
+            }
+            fn missing(&self) -> Result<u8, String> {
+                Err("no such key".into())
+            }
+        }
+
+        let call = |verbose_errors: bool, method: &str| {
+            let mut handler = easy_jsonrpc::Redactor::new(
+                &StoreImpl as &dyn Store,
+                easy_jsonrpc::redact_internal_errors(verbose_errors),
+            );
+            handler
+                .handle_request(json!({"jsonrpc": "2.0", "method": method, "id": 1}))
+                .as_option()
+                .unwrap()
+        };
+
+        // a result failing to serialize is reported as an internal error
+        let verbose = call(true, "load");
+        assert_eq!(
+            verbose["error"],
+            json!({"code": -32603, "message": "Internal error", "data": "secret detail"})
+        );
+        let redacted = call(false, "load");
+        assert_eq!(
+            redacted["error"],
+            json!({
+                "code": -32603,
+                "message": "Internal error",
+                "data": easy_jsonrpc::HIDDEN_DETAIL
+            })
+        );
+
+        // internal errors without data are left alone
+        let mut inner = easy_jsonrpc::DynHandler::new();
+        inner.register("fail", |_| Err(easy_jsonrpc::Error::internal_error()));
+        let mut handler =
+            easy_jsonrpc::Redactor::new(inner, easy_jsonrpc::redact_internal_errors(false));
+        assert_eq!(
+            handler.handle("fail", Params::Positional(vec![])),
+            Err(easy_jsonrpc::Error::internal_error())
+        );
+
+        // as are results, including the method's own errors
+        for verbose_errors in [false, true] {
+            assert_eq!(
+                call(verbose_errors, "missing")["result"],
+                json!({"Err": "no such key"})
+            );
+        }
+    }
+
+    #[test]
+    fn stringify_keys() {
+        use easy_jsonrpc::HandleRaw;
+        use std::collections::{BTreeMap, HashMap};
+
+        // serde_json can't use a struct as an object key
+        #[derive(PartialEq, Eq, PartialOrd, Ord, Debug, serde::Serialize, serde::Deserialize)]
+        pub struct Cell {
+            row: u8,
+            column: u8,
+        }
+
+        impl std::fmt::Display for Cell {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}:{}", self.row, self.column)
+            }
+        }
+
+        impl std::str::FromStr for Cell {
+            type Err = String;
+
+            fn from_str(s: &str) -> Result<Self, String> {
+                let (row, column) = s.split_once(':').ok_or("expected row:column")?;
+                Ok(Cell {
+                    row: row.parse().map_err(|_| "invalid row")?,
+                    column: column.parse().map_err(|_| "invalid column")?,
+                })
+            }
+        }
+
+        #[easy_jsonrpc::rpc]
+        pub trait Sheet {
+            #[rpc(stringify_keys)]
+            fn names(&self) -> HashMap<u64, String>;
+            #[rpc(stringify_keys)]
+            fn cells(
+                &self,
+                #[rpc_arg(stringify_keys)] values: BTreeMap<Cell, i32>,
+            ) -> BTreeMap<Cell, i32>;
+        }
+
+        pub struct SheetImpl;
+        impl Sheet for SheetImpl {
+            fn names(&self) -> HashMap<u64, String> {
+                HashMap::from([(1, "one".into()), (u64::MAX, "max".into())])
+            }
+            fn cells(&self, values: BTreeMap<Cell, i32>) -> BTreeMap<Cell, i32> {
+                values
+                    .into_iter()
+                    .map(|(cell, value)| (cell, -value))
+                    .collect()
+            }
+        }
+
+        fn round_trip<R: serde::Deserialize<'static>>(
+            bind: easy_jsonrpc::BoundMethod<'_, R>,
+        ) -> (Value, R) {
+            let mut handler = &SheetImpl as &dyn Sheet;
+            let (call, tracker) = bind.call();
+            let raw_response = handler
+                .handle_request(call.as_request())
+                .as_option()
+                .unwrap();
+            let result = raw_response["result"].clone();
+            let mut response = easy_jsonrpc::Response::from_json_response(raw_response).unwrap();
+            (result, tracker.get_return(&mut response).unwrap())
+        }
+
+        let (result, names) = round_trip(sheet::names().unwrap());
+        assert_eq!(result, json!({"1": "one", "18446744073709551615": "max"}));
+        assert_eq!(names, SheetImpl.names());
+
+        let values = BTreeMap::from([(Cell { row: 1, column: 2 }, 3)]);
+        let (result, cells) = round_trip(sheet::cells(values).unwrap());
+        assert_eq!(result, json!({"1:2": -3}));
+        assert_eq!(cells, BTreeMap::from([(Cell { row: 1, column: 2 }, -3)]));
+
+        // keys which don't parse are invalid params, on either path
+        let request = json!({
+            "jsonrpc": "2.0",
+            "method": "cells",
+            "params": [{"a1": 1}],
+            "id": 1
+        });
+        let mut handler = &SheetImpl as &dyn Sheet;
+        let response = handler.handle_request(request.clone()).as_option().unwrap();
+        assert_eq!(response["error"]["code"], json!(-32602));
+        let response = handler.handle_request_raw(&request.to_string()).unwrap();
+        assert!(response.contains("-32602"));
+    }
+
+    #[test]
+    #[allow(dead_code)] // the fields of ShapesCall are never read
+    fn malformed_params_never_panic() {
+        use easy_jsonrpc::types::Request;
+        use easy_jsonrpc::HandleRaw;
+        use std::borrow::Cow;
+
+        #[derive(serde::Serialize, serde::Deserialize)]
+        pub struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        #[easy_jsonrpc::rpc(call_enum)]
+        pub trait Shapes {
+            fn none(&self) -> u8;
+            fn primitives(&self, a: u8, b: bool, c: f64) -> u8;
+            fn borrowed(&self, s: &str, c: Cow<'_, str>) -> u8;
+            fn nested(&self, points: Vec<Point>, origin: Option<Point>) -> u8;
+            fn codecs(&self, #[rpc_arg(hex)] bytes: Vec<u8>, big: u128) -> u8;
+            fn rest(&self, first: u8, #[rpc_arg(rest)] rest: Vec<u8>) -> u8;
+            #[rpc(params_struct)]
+            fn params_struct(&self, point: Point) -> u8;
+            #[rpc(lenient_params)]
+            fn lenient(&self, n: u64) -> u8;
+        }
+
+        pub struct ShapesImpl;
+        impl Shapes for ShapesImpl {
+            fn none(&self) -> u8 {
+                0
+            }
+            fn primitives(&self, _: u8, _: bool, _: f64) -> u8 {
+                0
+            }
+            fn borrowed(&self, _: &str, _: Cow<'_, str>) -> u8 {
+                0
+            }
+            fn nested(&self, _: Vec<Point>, _: Option<Point>) -> u8 {
+                0
+            }
+            fn codecs(&self, _: Vec<u8>, _: u128) -> u8 {
+                0
+            }
+            fn rest(&self, _: u8, _: Vec<u8>) -> u8 {
+                0
+            }
+            fn params_struct(&self, _: Point) -> u8 {
+                0
+            }
+            fn lenient(&self, _: u64) -> u8 {
+                0
+            }
+        }
+
+        let methods = [
+            "none",
+            "primitives",
+            "borrowed",
+            "nested",
+            "codecs",
+            "rest",
+            "params_struct",
+            "lenient",
+        ];
+        let malformed = [
+            json!([]),
+            json!([null]),
+            json!([1]),
+            json!([1, 2, 3, 4, 5, 6, 7, 8, 9]),
+            json!([[1], {"x": 1}, "zz", -1.5]),
+            json!(["a\\u0000b", {"x": 1, "y": "2"}, [[]]]),
+            json!({}),
+            json!({"unknown": 1}),
+            json!({"a": null, "b": null, "c": null, "s": 1, "points": {}, "bytes": "0xz"}),
+            json!({"x": 1, "y": 2, "z": 3}),
+            json!({"first": "1", "rest": [1]}),
+            json!("params"),
+            json!(5),
+            json!(true),
+        ];
+        let mut handler = &ShapesImpl as &dyn Shapes;
+        for method in methods {
+            for params in &malformed {
+                let request = json!({
+                    "jsonrpc": "2.0",
+                    "method": method,
+                    "params": params,
+                    "id": 1
+                });
+                let response = handler.handle_request(request.clone()).as_option().unwrap();
+                let code = &response["error"]["code"];
+                assert!(
+                    response.get("result").is_some()
+                        || [-32600, -32602].contains(&code.as_i64().unwrap()),
+                    "{} {}: {}",
+                    method,
+                    params,
+                    response
+                );
+
+                // every other path agrees that the call fails, or that it succeeds
+                let text = request.to_string();
+                let agrees = |other: &Value| other.get("result") == response.get("result");
+                let raw = handler.handle_request_raw(&text).unwrap();
+                assert!(
+                    agrees(&serde_json::from_str(&raw).unwrap()),
+                    "{} {}",
+                    method,
+                    params
+                );
+                // bare params aren't wrapped once the request is parsed, so may be rejected
+                if let Ok(request) = serde_json::from_value::<Request>(request.clone()) {
+                    let owned = handler.handle_request_owned(request).unwrap();
+                    let owned = serde_json::to_value(owned).unwrap();
+                    assert!(agrees(&owned) || owned.get("error").is_some(), "{}", method);
+                }
+                if let Ok(params) = serde_json::from_value::<jsonrpc_core::Params>(params.clone()) {
+                    let params = Params::from_rc_params(params);
+                    let valid = shapes_validate_request(method, &params).is_ok();
+                    assert_eq!(valid, response.get("result").is_some(), "{}", method);
+                    let parsed = ShapesCall::try_from((method, params)).is_ok();
+                    assert_eq!(parsed, valid, "{}", method);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn request_builder() {
+        #[easy_jsonrpc::rpc(request_builder)]
+        pub trait Store {
+            fn put(&self, key: String, value: Option<u64>) -> bool;
+            fn len(&self) -> usize;
+            #[rpc(paginate)]
+            fn keys(&self, prefix: String) -> Vec<String>;
+            fn store(&self, item: Unserializable);
+        }
+
+        #[derive(serde::Deserialize)]
+        pub struct Unserializable;
+        impl serde::Serialize for Unserializable {
+            fn serialize<S: serde::Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+                Err(serde::ser::Error::custom("unserializable"))
+            }
+        }
+
+        assert_eq!(
+            StoreRequest::put("a".into(), Some(1))
+                .with_id("put-1")
+                .build()
+                .unwrap(),
+            json!({"jsonrpc": "2.0", "method": "put", "params": ["a", 1], "id": "put-1"})
+        );
+        assert_eq!(
+            StoreRequest::put("a".into(), None)
+                .notification()
+                .build()
+                .unwrap(),
+            json!({"jsonrpc": "2.0", "method": "put", "params": ["a", null]})
+        );
+        assert_eq!(
+            StoreRequest::keys("a".into(), Some(10), None)
+                .with_id(1)
+                .build()
+                .unwrap(),
+            json!({"jsonrpc": "2.0", "method": "keys", "params": ["a", 10, null], "id": 1})
+        );
+
+        // without an id, calls get a random one
+        let request = StoreRequest::len().build().unwrap();
+        assert!(request["id"].is_u64());
+
+        // the tracker reads the result of the call
+        let bound = StoreRequest::len().with_id(2).into_bound().unwrap();
+        let (call, tracker) = bound.call();
+        assert_eq!(call.as_request()["id"], json!(2));
+        let mut response = easy_jsonrpc::Response::from_json_response(
+            json!({"jsonrpc": "2.0", "result": 3, "id": 2}),
+        )
+        .unwrap();
+        assert_eq!(tracker.get_return(&mut response).unwrap(), 3);
+
+        assert_eq!(
+            StoreRequest::store(Unserializable).with_id(3).build(),
+            Err(easy_jsonrpc::ArgSerializeError)
+        );
+    }
+
+    #[test]
+    fn void_result_is_null() {
+        use easy_jsonrpc::{types::Request, HandleRaw, InvalidResponse, Response, ResponseFail};
+
+        assert_eq!(easy_jsonrpc::try_serialize(&()), Ok(Value::Null));
+
+        // a void method's success is a null result, never an omitted result or an error
+        let assert_null_result = |response: &Value| {
+            let response = response.as_object().unwrap();
+            assert_eq!(response.get("result"), Some(&Value::Null), "{:?}", response);
+            assert!(!response.contains_key("error"), "{:?}", response);
+        };
+        let request = json!({"jsonrpc": "2.0", "method": "swallow", "params": [], "id": 1});
+        let mut handler = &AdderImpl {} as &dyn Adder;
+        assert_null_result(&handler.handle_request(request.clone()).as_option().unwrap());
+        let response = handler.handle_request_str(&request.to_string()).unwrap();
+        assert_null_result(&serde_json::from_str(&response).unwrap());
+        let response = handler.handle_request_raw(&request.to_string()).unwrap();
+        assert_null_result(&serde_json::from_str(&response).unwrap());
+        let owned = serde_json::from_value::<Request>(request.clone()).unwrap();
+        let response = handler.handle_request_owned(owned).unwrap();
+        assert_null_result(&serde_json::to_value(response).unwrap());
+        let response = handler
+            .handle_request(json!([request, {"jsonrpc": "2.0", "method": "greet", "id": 2}]))
+            .as_option()
+            .unwrap();
+        assert_null_result(&response[0]);
+
+        // clients read a null result as success, and its absence as an invalid response
+        let bound = adder::swallow().unwrap().with_id(1);
+        let (call, tracker) = bound.call();
+        let mut response =
+            Response::from_json_response(json!({"jsonrpc": "2.0", "result": null, "id": 1}))
+                .unwrap();
+        assert_eq!(response.get(1), Some(&Ok(Value::Null)));
+        assert_eq!(tracker.get_return(&mut response), Ok(()));
+        assert_eq!(call.as_request()["id"], json!(1));
+        assert_eq!(
+            Response::from_json_response(json!({"jsonrpc": "2.0", "id": 1})).err(),
+            Some(InvalidResponse::DeserailizeFailure)
+        );
+        let mut response = Response::from_json_response(json!({
+            "jsonrpc": "2.0",
+            "error": {"code": -32000, "message": "failed"},
+            "id": 1
+        }))
+        .unwrap();
+        assert!(matches!(
+            tracker.get_return(&mut response),
+            Err(ResponseFail::RpcError(_))
+        ));
+    }
+
+    #[test]
+    fn tagged_enum_params() {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        #[serde(tag = "action", rename_all = "snake_case")]
+        pub enum AccountAction {
+            Deposit { amount: u64 },
+            Rename { name: String },
+        }
+
+        #[easy_jsonrpc::rpc]
+        pub trait Accounts {
+            #[rpc(params_struct)]
+            fn account(&self, action: AccountAction) -> String;
+        }
+
+        struct AccountsImpl;
+        impl Accounts for AccountsImpl {
+            fn account(&self, action: AccountAction) -> String {
+                match action {
+                    AccountAction::Deposit { amount } => format!("deposited {}", amount),
+                    AccountAction::Rename { name } => format!("renamed to {}", name),
+                }
+            }
+        }
+
+        let mut handler = &AccountsImpl as &dyn Accounts;
+        let mut call = |params: Value| {
+            handler
+                .handle_request(json!({
+                    "jsonrpc": "2.0",
+                    "method": "account",
+                    "params": params,
+                    "id": 1
+                }))
+                .as_option()
+                .unwrap()
+        };
+        assert_eq!(
+            call(json!({"action": "deposit", "amount": 5}))["result"],
+            json!("deposited 5")
+        );
+        assert_eq!(
+            call(json!([{"action": "rename", "name": "savings"}]))["result"],
+            json!("renamed to savings")
+        );
+
+        // an unknown or missing action is an invalid params error naming the actions
+        let error = call(json!({"action": "close"}))["error"].clone();
+        assert_eq!(error["code"], json!(-32602));
+        assert!(error["data"]["error"]
+            .as_str()
+            .unwrap()
+            .contains("expected `deposit` or `rename`"));
+        assert_eq!(call(json!({"amount": 5}))["error"]["code"], json!(-32602));
+        assert_eq!(
+            call(json!({"action": "deposit", "amount": "5"}))["error"]["code"],
+            json!(-32602)
+        );
+
+        // the raw path and the client function agree
+        use easy_jsonrpc::HandleRaw;
+        let request = r#"{"jsonrpc": "2.0", "method": "account", "params": {"action": "deposit", "amount": 5}, "id": 1}"#;
+        let response = handler.handle_request_raw(request).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&response).unwrap()["result"],
+            json!("deposited 5")
+        );
+        let bind = accounts::account(AccountAction::Rename {
+            name: "savings".into(),
+        })
+        .unwrap();
+        let (client_call, _) = bind.call();
+        assert_eq!(
+            client_call.as_request()["params"],
+            json!([{"action": "rename", "name": "savings"}])
+        );
+        assert_eq!(
+            handler
+                .handle_request(client_call.as_request())
+                .as_option()
+                .unwrap()["result"],
+            json!("renamed to savings")
+        );
+    }
+
+    #[test]
+    fn handle_lines() {
+        let mut handler = &AdderImpl {} as &dyn Adder;
+        let input = concat!(
+            r#"{"jsonrpc": "2.0", "method": "wrapping_add", "params": [1, 2], "id": 1}"#,
+            "\r\n\n",
+            r#"{"jsonrpc": "2.0", "method": "swallow"}"#,
+            "\n  \n",
+            r#"{"jsonrpc": "2.0", "method": "#,
+            "\n",
+        );
+        let output = handler.handle_lines(input);
+        assert!(output.ends_with('\n'));
+        let responses: Vec<Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(
+            responses,
+            [
+                json!({"jsonrpc": "2.0", "result": 3, "id": 1}),
+                json!({"jsonrpc": "2.0", "error": {"code": -32700, "message": "Parse error"}, "id": null}),
+            ]
+        );
+
+        // batches stay on their line
+        let output = handler.handle_lines(
+            r#"[{"jsonrpc": "2.0", "method": "greet", "id": 1}, {"jsonrpc": "2.0", "method": "greet", "id": 2}]"#,
+        );
+        assert_eq!(output.lines().count(), 1);
+        assert_eq!(
+            serde_json::from_str::<Value>(&output).unwrap()[1]["result"],
+            json!("hello")
+        );
+        assert_eq!(handler.handle_lines(""), "");
+    }
+
+    #[test]
+    fn duplicate_ids() {
+        use easy_jsonrpc::{DuplicateIds, HandleRaw, InvalidResponse, RequestId, Response};
+
+        // servers answer every call, echoing its id
+        let mut handler = &AdderImpl {} as &dyn Adder;
+        let first = adder::wrapping_add(1, 1).unwrap().with_id(1);
+        let second = adder::wrapping_add(2, 2).unwrap().with_id(1);
+        let (first_call, first_tracker) = first.call();
+        let (second_call, second_tracker) = second.call();
+        // batch_request insists on distinct ids
+        let request = json!([first_call.as_request(), second_call.as_request()]);
+        let expected = json!([
+            {"jsonrpc": "2.0", "result": 2, "id": 1},
+            {"jsonrpc": "2.0", "result": 4, "id": 1},
+        ]);
+        let raw = handler.handle_request(request.clone()).as_option().unwrap();
+        assert_eq!(raw, expected);
+        let response = handler.handle_request_raw(&request.to_string()).unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&response).unwrap(), expected);
+
+        // clients reject the response, or hand the outputs out in order
+        assert_eq!(
+            Response::from_json_response(raw.clone()).err(),
+            Some(InvalidResponse::DuplicateId(RequestId::from(1)))
+        );
+        let mut response =
+            Response::from_json_response_with(raw.clone(), DuplicateIds::Positional).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&response.to_string()).unwrap(),
+            expected
+        );
+        assert_eq!(response.len(), 2);
+        assert_eq!(response.get(1), Some(&Ok(json!(2))));
+        let one = RequestId::from(1);
+        assert_eq!(
+            response.outputs().collect::<Vec<_>>(),
+            [(&one, &Ok(json!(2))), (&one, &Ok(json!(4)))]
+        );
+        assert_eq!(first_tracker.get_return(&mut response), Ok(2));
+        assert_eq!(second_tracker.get_return(&mut response), Ok(4));
+        assert_eq!(
+            first_tracker.get_return(&mut response),
+            Err(easy_jsonrpc::ResponseFail::ResultNotFound)
+        );
+
+        // distinct ids parse the same under either policy
+        let distinct = json!([
+            {"jsonrpc": "2.0", "result": 2, "id": 1},
+            {"jsonrpc": "2.0", "result": 4, "id": 2},
+        ]);
+        for policy in [DuplicateIds::Reject, DuplicateIds::Positional] {
+            let response = Response::from_json_response_with(distinct.clone(), policy).unwrap();
+            assert_eq!(response.len(), 2);
+        }
+    }
+
+    #[test]
+    fn error_codes() {
+        use easy_jsonrpc::{HandleRaw, Response, ResponseFail};
+
+        #[derive(Debug)]
+        pub struct Overdrawn(u64);
+        impl std::fmt::Display for Overdrawn {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "balance is only {}", self.0)
+            }
+        }
+
+        #[easy_jsonrpc::rpc(error_base = -31000)]
+        pub trait Bank {
+            #[rpc(error_code = 1)]
+            fn withdraw(&self, amount: u64) -> Result<u64, Overdrawn>;
+            #[rpc(error_code = -2)]
+            fn total(&self) -> Result<u128, String>;
+            // serialized as a whole, as before
+            fn audit(&self) -> Result<u64, String>;
+        }
+
+        struct BankImpl;
+        impl Bank for BankImpl {
+            fn withdraw(&self, amount: u64) -> Result<u64, Overdrawn> {
+                10u64.checked_sub(amount).ok_or(Overdrawn(10))
+            }
+            fn total(&self) -> Result<u128, String> {
+                Ok(u128::MAX)
+            }
+            fn audit(&self) -> Result<u64, String> {
+                Err("closed".into())
+            }
+        }
+
+        let mut handler = &BankImpl as &dyn Bank;
+        let mut call = |method: &str, params: Value| {
+            handler
+                .handle_request(json!({
+                    "jsonrpc": "2.0",
+                    "method": method,
+                    "params": params,
+                    "id": 1
+                }))
+                .as_option()
+                .unwrap()
+        };
+        assert_eq!(call("withdraw", json!([3]))["result"], json!(7));
+        let overdrawn = json!({
+            "jsonrpc": "2.0",
+            "error": {"code": -30999, "message": "balance is only 10"},
+            "id": 1
+        });
+        assert_eq!(call("withdraw", json!([11])), overdrawn);
+        // an i128 or u128 Ok type is sent as a string, as a plain result would be
+        assert_eq!(
+            call("total", json!([]))["result"],
+            json!(u128::MAX.to_string())
+        );
+        assert_eq!(call("audit", json!([]))["result"], json!({"Err": "closed"}));
+
+        // the raw path agrees
+        let response = handler
+            .handle_request_raw(
+                r#"{"jsonrpc": "2.0", "method": "withdraw", "params": [11], "id": 1}"#,
+            )
+            .unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&response).unwrap(), overdrawn);
+
+        // the client function returns the Ok type, and receives the error as an rpc error
+        let bound = bank::withdraw(11).unwrap().with_id(1);
+        let (request, tracker) = bound.call();
+        let response = handler
+            .handle_request(request.as_request())
+            .as_option()
+            .unwrap();
+        match tracker.get_return(&mut Response::from_json_response(response).unwrap()) {
+            Err(ResponseFail::RpcError(err)) => assert_eq!(err.code.code(), -30999),
+            other => panic!("{:?}", other),
+        }
+        let bound = bank::total().unwrap().with_id(1);
+        let (request, tracker) = bound.call();
+        let response = handler
+            .handle_request(request.as_request())
+            .as_option()
+            .unwrap();
+        let total: u128 = tracker
+            .get_return(&mut Response::from_json_response(response).unwrap())
+            .unwrap();
+        assert_eq!(total, u128::MAX);
+    }
+
+    #[test]
+    fn hash_dispatch() {
+        // enough methods to be dispatched by the hash of their names
+        #[easy_jsonrpc::rpc]
+        pub trait Many {
+            fn m00(&self) -> u8 {
+                0
+            }
+            fn m01(&self) -> u8 {
+                1
+            }
+            fn m02(&self) -> u8 {
+                2
+            }
+            fn m03(&self) -> u8 {
+                3
+            }
+            fn m04(&self) -> u8 {
+                4
+            }
+            fn m05(&self) -> u8 {
+                5
+            }
+            fn m06(&self) -> u8 {
+                6
+            }
+            fn m07(&self) -> u8 {
+                7
+            }
+            fn m08(&self) -> u8 {
+                8
+            }
+            fn m09(&self) -> u8 {
+                9
+            }
+            fn m10(&self) -> u8 {
+                10
+            }
+            fn m11(&self) -> u8 {
+                11
+            }
+            fn m12(&self) -> u8 {
+                12
+            }
+            fn m13(&self) -> u8 {
+                13
+            }
+            #[rpc(name = "renamed")]
+            fn m14(&self) -> u8 {
+                14
+            }
+            #[cfg(any())]
+            fn m15(&self) -> u8 {
+                15
+            }
+            fn m16(&self, a: u8) -> u8 {
+                a
+            }
+        }
+
+        struct ManyImpl;
+        impl Many for ManyImpl {}
+        let mut handler = &ManyImpl as &dyn Many;
+        let mut call = |method: &str, params: Value| {
+            handler
+                .handle_request(json!({
+                    "jsonrpc": "2.0",
+                    "method": method,
+                    "params": params,
+                    "id": 1
+                }))
+                .as_option()
+                .unwrap()
+        };
+        assert_eq!(call("m00", json!([]))["result"], json!(0));
+        assert_eq!(call("m13", json!([]))["result"], json!(13));
+        assert_eq!(call("renamed", json!([]))["result"], json!(14));
+        assert_eq!(call("m16", json!([16]))["result"], json!(16));
+        assert_eq!(call("m16", json!([]))["error"]["code"], json!(-32602));
+        for missing in ["m14", "m15", "m17", ""] {
+            assert_eq!(call(missing, json!([]))["error"]["code"], json!(-32601));
+        }
+        assert!(handler.method_exists("m13"));
+        assert!(!handler.method_exists("m15"));
+    }
+
+    #[test]
+    fn associated_items() {
+        #[easy_jsonrpc::rpc]
+        trait Versioned {
+            type Output;
+
+            fn version(&self) -> u32 {
+                2
+            }
+            fn output(&self) -> u32;
+        }
+
+        struct VersionedImpl;
+        impl Versioned for VersionedImpl {
+            type Output = u32;
+
+            fn output(&self) -> u32 {
+                7
+            }
+        }
+
+        let _: <VersionedImpl as Versioned>::Output = 0;
+        let mut handler = &VersionedImpl as &dyn Versioned;
+        let mut call = |method: &str| {
+            handler
+                .handle_request(json!({"jsonrpc": "2.0", "method": method, "id": 1}))
+                .as_option()
+                .unwrap()["result"]
+                .clone()
+        };
+        assert_eq!(call("version"), json!(2));
+        assert_eq!(call("output"), json!(7));
+        let bind = versioned::version().unwrap();
+        assert_eq!(bind.call().0.as_request()["method"], json!("version"));
+    }
 }
@@ -0,0 +1,175 @@
+use crate::Value;
+
+/// Bounds on the size, nesting depth, array length and batch size of a raw request, checked before
+/// the request is parsed, so no [Value](../serde_json/enum.Value.html) is built for a request
+/// exceeding them.
+///
+/// serde_json refuses to parse json nested more than 128 levels deep, so no request can
+/// overflow the stack, but a server exposed to untrusted input may want tighter bounds.
+/// Requests exceeding any limit are answered with an `invalid_request` error.
+///
+/// ```
+/// use easy_jsonrpc::{DynHandler, Handler, RequestLimits};
+/// use serde_json::{json, Value};
+///
+/// let mut handler = DynHandler::new();
+/// let limits = RequestLimits::default().max_size(1024).max_depth(8);
+/// let response = handler.handle_request_bytes(&[b'['; 2048], &limits).unwrap();
+/// let response: Value = serde_json::from_str(&response).unwrap();
+/// assert_eq!(response["error"]["code"], json!(-32600));
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RequestLimits {
+    max_size: usize,
+    max_depth: usize,
+    max_batch_size: usize,
+    max_array_len: usize,
+}
+
+impl Default for RequestLimits {
+    /// At most 16 MiB, nested at most 32 levels deep. Arrays and batches may hold any number of
+    /// elements.
+    fn default() -> Self {
+        RequestLimits {
+            max_size: 16 * 1024 * 1024,
+            max_depth: 32,
+            max_batch_size: usize::MAX,
+            max_array_len: usize::MAX,
+        }
+    }
+}
+
+impl RequestLimits {
+    /// Reject requests longer than `bytes`.
+    pub fn max_size(self, bytes: usize) -> Self {
+        RequestLimits {
+            max_size: bytes,
+            ..self
+        }
+    }
+
+    /// Reject requests with arrays or objects nested more than `depth` levels deep. A batch of
+    /// calls with positional params is nested 3 levels deep.
+    pub fn max_depth(self, depth: usize) -> Self {
+        RequestLimits {
+            max_depth: depth,
+            ..self
+        }
+    }
+
+    /// Reject batches of more than `calls` calls. No handler is called for a rejected batch.
+    pub fn max_batch_size(self, calls: usize) -> Self {
+        RequestLimits {
+            max_batch_size: calls,
+            ..self
+        }
+    }
+
+    /// Reject requests containing an array of more than `len` elements, at any depth. A batch is
+    /// an array too, so this also bounds the number of calls in a batch.
+    pub fn max_array_len(self, len: usize) -> Self {
+        RequestLimits {
+            max_array_len: len,
+            ..self
+        }
+    }
+
+    // The most bytes a request may be, for transports which must refuse to read further.
+    #[cfg_attr(not(feature = "framed"), allow(dead_code))]
+    pub(crate) fn size_limit(&self) -> usize {
+        self.max_size
+    }
+
+    // Whether value, decoded from size bytes of a request in an encoding other than json, is
+    // within the limits.
+    #[cfg_attr(not(feature = "msgpack"), allow(dead_code))]
+    pub(crate) fn admits_decoded(&self, size: usize, value: &Value) -> bool {
+        fn within(limits: &RequestLimits, value: &Value, depth: usize) -> bool {
+            match value {
+                Value::Array(elements) => {
+                    depth < limits.max_depth
+                        && elements.len() <= limits.max_array_len
+                        && elements.iter().all(|v| within(limits, v, depth + 1))
+                }
+                Value::Object(fields) => {
+                    depth < limits.max_depth
+                        && fields.values().all(|v| within(limits, v, depth + 1))
+                }
+                _ => true,
+            }
+        }
+        let batch_size = match value {
+            Value::Array(calls) => calls.len(),
+            _ => 0,
+        };
+        size <= self.max_size && batch_size <= self.max_batch_size && within(self, value, 0)
+    }
+
+    // Whether raw is within the limits. Only the brackets outside of strings are counted, so
+    // malformed input passes as long as it is small and shallow enough, then fails to parse.
+    pub(crate) fn admits(&self, raw: &[u8]) -> bool {
+        if raw.len() > self.max_size {
+            return false;
+        }
+        let mut depth = 0usize;
+        // for each enclosing array, the elements started so far and whether another is expected;
+        // None for objects
+        let mut arrays: Vec<Option<(usize, bool)>> = Vec::new();
+        // a request which is an array is a batch, its calls are separated by commas at depth 1
+        let is_batch = raw.iter().find(|byte| !byte.is_ascii_whitespace()) == Some(&b'[');
+        let mut separators = 0usize;
+        let mut has_call = false;
+        let mut in_string = false;
+        let mut escaped = false;
+        for &byte in raw {
+            if in_string {
+                match byte {
+                    _ if escaped => escaped = false,
+                    b'\\' => escaped = true,
+                    b'"' => in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+            if is_batch && depth == 1 && !byte.is_ascii_whitespace() && byte != b']' {
+                has_call = true;
+            }
+            if let Some(Some((elements, expecting))) = arrays.last_mut() {
+                if *expecting && !byte.is_ascii_whitespace() && byte != b']' {
+                    *elements += 1;
+                    *expecting = false;
+                    if *elements > self.max_array_len {
+                        return false;
+                    }
+                }
+            }
+            match byte {
+                b'"' => in_string = true,
+                b'[' | b'{' => {
+                    depth += 1;
+                    if depth > self.max_depth {
+                        return false;
+                    }
+                    arrays.push(if byte == b'[' { Some((0, true)) } else { None });
+                }
+                b']' | b'}' => {
+                    depth = depth.saturating_sub(1);
+                    arrays.pop();
+                }
+                b',' => {
+                    if let Some(Some((_, expecting))) = arrays.last_mut() {
+                        *expecting = true;
+                    }
+                    if is_batch && depth == 1 {
+                        separators += 1;
+                        if separators + 1 > self.max_batch_size {
+                            return false;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        !has_call || separators < self.max_batch_size
+    }
+}
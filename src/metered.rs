@@ -0,0 +1,91 @@
+use crate::{CallKind, Error, Handler, Params, RequestMeta, Value};
+use std::collections::HashMap;
+
+/// Wraps a handler, counting calls and errors for each method, e.g. to export as metrics.
+///
+/// Calls to methods for which the inner handler's
+/// [method_exists](trait.Handler.html#method.method_exists) returns false are not counted, so
+/// clients can't grow the map by calling made up methods. Handlers which can't tell which methods
+/// exist count every method name they are called with.
+///
+/// ```
+/// use easy_jsonrpc::{DynHandler, Handler, Metered};
+/// use serde_json::json;
+///
+/// let mut inner = DynHandler::new();
+/// inner.register("ping", |_| Ok(json!("pong")));
+///
+/// let mut handler = Metered::new(inner);
+/// handler.handle_request(json!({"jsonrpc": "2.0", "method": "ping", "id": 1}));
+/// handler.handle_request(json!({"jsonrpc": "2.0", "method": "ping", "params": [1], "id": 2}));
+/// assert_eq!(handler.metrics()["ping"], (2, 0));
+/// ```
+pub struct Metered<H> {
+    handler: H,
+    metrics: HashMap<String, (u64, u64)>,
+}
+
+impl<H: Handler> Metered<H> {
+    /// Wrap handler, with every count at zero.
+    pub fn new(handler: H) -> Self {
+        Metered {
+            handler,
+            metrics: HashMap::new(),
+        }
+    }
+
+    /// The number of calls and the number of those which returned an error, by method.
+    pub fn metrics(&self) -> &HashMap<String, (u64, u64)> {
+        &self.metrics
+    }
+
+    /// Unwrap the inner handler.
+    pub fn into_inner(self) -> H {
+        self.handler
+    }
+}
+
+impl<H: Handler> Handler for Metered<H> {
+    fn handle(&mut self, method: &str, params: Params) -> Result<Value, Error> {
+        self.handle_with_meta(method, params, CallKind::MethodCall)
+    }
+
+    fn handle_with_meta(
+        &mut self,
+        method: &str,
+        params: Params,
+        kind: CallKind,
+    ) -> Result<Value, Error> {
+        self.handle_with_request_meta(method, params, &RequestMeta::new(kind))
+    }
+
+    fn handle_with_request_meta(
+        &mut self,
+        method: &str,
+        params: Params,
+        meta: &RequestMeta,
+    ) -> Result<Value, Error> {
+        let result = self.handler.handle_with_request_meta(method, params, meta);
+        if self.handler.method_exists(method) {
+            let (calls, errors) = match self.metrics.get_mut(method) {
+                Some(counts) => counts,
+                None => self.metrics.entry(method.to_owned()).or_default(),
+            };
+            *calls += 1;
+            *errors += u64::from(result.is_err());
+        }
+        result
+    }
+
+    fn method_exists(&self, method: &str) -> bool {
+        self.handler.method_exists(method)
+    }
+
+    fn accepts_bare_param(&self, method: &str) -> bool {
+        self.handler.accepts_bare_param(method)
+    }
+
+    fn strict_version(&self) -> bool {
+        self.handler.strict_version()
+    }
+}
@@ -0,0 +1,199 @@
+//! Carry jsonrpc over [MessagePack](https://msgpack.org) rather than JSON text. Requires the
+//! `msgpack` feature.
+//!
+//! A request is decoded into a [Value](../enum.Value.html) and handled like any other, so
+//! methods are found and their arguments bound exactly as for JSON. Binary data is decoded as an
+//! array of bytes, as serde_json represents a `Vec<u8>`. Maps with keys other than strings, and
+//! extension types, have no JSON counterpart and are rejected.
+//!
+//! ```
+//! use easy_jsonrpc::{msgpack, rpc, RequestLimits};
+//! use serde_json::json;
+//!
+//! #[rpc]
+//! pub trait Adder {
+//!     fn add(&self, a: u64, b: u64) -> u64;
+//! }
+//!
+//! struct AdderImpl;
+//! impl Adder for AdderImpl {
+//!     fn add(&self, a: u64, b: u64) -> u64 {
+//!         a + b
+//!     }
+//! }
+//!
+//! let request = msgpack::encode(&json!({
+//!     "jsonrpc": "2.0",
+//!     "method": "add",
+//!     "params": [1, 2],
+//!     "id": 1
+//! }));
+//! let limits = RequestLimits::default();
+//! let response =
+//!     msgpack::handle_request(&mut (&AdderImpl as &dyn Adder), &request, &limits).unwrap();
+//! assert_eq!(
+//!     msgpack::decode(&response),
+//!     Some(json!({"jsonrpc": "2.0", "result": 3, "id": 1}))
+//! );
+//! ```
+
+use crate::{invalid_request, parse_error, Handler, RequestLimits, Value};
+use rmpv::Value as MsgpackValue;
+use serde_json::{Map, Number};
+
+/// Handle a request encoded in MessagePack, returning the response, if any, encoded likewise.
+/// Requests which can't be decoded get a parse error. Requests exceeding limits get an invalid
+/// request error, though unlike for json they are only checked once decoded.
+pub fn handle_request<H: Handler + ?Sized>(
+    handler: &mut H,
+    request: &[u8],
+    limits: &RequestLimits,
+) -> Option<Vec<u8>> {
+    let response = match decode(request) {
+        Some(decoded) if !limits.admits_decoded(request.len(), &decoded) => invalid_request(),
+        Some(request) => handler.handle_request(request).as_option()?,
+        None => parse_error(),
+    };
+    Some(encode(&response))
+}
+
+/// Encode value in MessagePack.
+pub fn encode(value: &Value) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    rmpv::encode::write_value(&mut bytes, &to_msgpack(value))
+        .expect("writing to a Vec doesn't fail");
+    bytes
+}
+
+/// Decode a single MessagePack value, spanning all of bytes. None if bytes hold anything else, or
+/// a value without a JSON counterpart.
+pub fn decode(mut bytes: &[u8]) -> Option<Value> {
+    let value = rmpv::decode::read_value(&mut bytes).ok()?;
+    if !bytes.is_empty() {
+        return None;
+    }
+    from_msgpack(value)
+}
+
+fn to_msgpack(value: &Value) -> MsgpackValue {
+    match value {
+        Value::Null => MsgpackValue::Nil,
+        Value::Bool(b) => MsgpackValue::Boolean(*b),
+        Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+            (Some(n), _) => MsgpackValue::from(n),
+            (None, Some(n)) => MsgpackValue::from(n),
+            // Every other number is sent as a float: numbers with a fraction or exponent, and with
+            // arbitrary_precision, integers beyond 64 bits, rounded to the nearest f64.
+            (None, None) => MsgpackValue::F64(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        Value::String(s) => MsgpackValue::from(s.as_str()),
+        Value::Array(items) => MsgpackValue::Array(items.iter().map(to_msgpack).collect()),
+        Value::Object(members) => MsgpackValue::Map(
+            members
+                .iter()
+                .map(|(key, value)| (MsgpackValue::from(key.as_str()), to_msgpack(value)))
+                .collect(),
+        ),
+    }
+}
+
+fn from_msgpack(value: MsgpackValue) -> Option<Value> {
+    Some(match value {
+        MsgpackValue::Nil => Value::Null,
+        MsgpackValue::Boolean(b) => Value::Bool(b),
+        MsgpackValue::Integer(n) => match (n.as_u64(), n.as_i64()) {
+            (Some(n), _) => Value::from(n),
+            (None, Some(n)) => Value::from(n),
+            (None, None) => return None,
+        },
+        MsgpackValue::F32(f) => Value::Number(Number::from_f64(f.into())?),
+        MsgpackValue::F64(f) => Value::Number(Number::from_f64(f)?),
+        MsgpackValue::String(s) => Value::String(s.into_str()?),
+        MsgpackValue::Binary(bytes) => Value::Array(bytes.into_iter().map(Value::from).collect()),
+        MsgpackValue::Array(items) => {
+            Value::Array(items.into_iter().map(from_msgpack).collect::<Option<_>>()?)
+        }
+        MsgpackValue::Map(members) => Value::Object(
+            members
+                .into_iter()
+                .map(|(key, value)| Some((key.as_str()?.to_owned(), from_msgpack(value)?)))
+                .collect::<Option<Map<String, Value>>>()?,
+        ),
+        MsgpackValue::Ext(..) => return None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode, encode, handle_request};
+    use crate::{DynHandler, RequestLimits, Value};
+    use serde_json::json;
+
+    #[test]
+    fn round_trip() {
+        let mut handler = DynHandler::new();
+        handler.register("echo", |params| Ok(params.get_positional::<Value>(0)?));
+        handler.register("len", |params| {
+            Ok(json!(params.get_positional::<Vec<u8>>(0)?.len()))
+        });
+
+        let limits = RequestLimits::default();
+        let echoed = json!({"n": -1, "big": u64::MAX, "f": 0.5, "s": "str", "a": [null, true]});
+        let request = encode(&json!({
+            "jsonrpc": "2.0",
+            "method": "echo",
+            "params": [echoed],
+            "id": 1
+        }));
+        let response = handle_request(&mut handler, &request, &limits).unwrap();
+        assert_eq!(
+            decode(&response),
+            Some(json!({"jsonrpc": "2.0", "result": echoed, "id": 1}))
+        );
+
+        // binary data binds to byte arrays
+        let request = rmpv::Value::Map(vec![
+            ("jsonrpc".into(), "2.0".into()),
+            ("method".into(), "len".into()),
+            (
+                "params".into(),
+                vec![rmpv::Value::Binary(vec![1, 2, 3])].into(),
+            ),
+            ("id".into(), 2.into()),
+        ]);
+        let mut bytes = Vec::new();
+        rmpv::encode::write_value(&mut bytes, &request).unwrap();
+        let response = handle_request(&mut handler, &bytes, &limits).unwrap();
+        assert_eq!(decode(&response).unwrap()["result"], json!(3));
+
+        // notifications get no response
+        let request = encode(&json!({"jsonrpc": "2.0", "method": "echo", "params": [1]}));
+        assert_eq!(handle_request(&mut handler, &request, &limits), None);
+
+        // truncated and extension values are parse errors
+        for request in [&request[..request.len() - 1], &[0xd4, 0x01, 0x00]] {
+            let response = handle_request(&mut handler, request, &limits).unwrap();
+            assert_eq!(decode(&response).unwrap()["error"]["code"], json!(-32700));
+        }
+
+        // requests exceeding the limits are invalid, checked once decoded
+        let call = json!({"jsonrpc": "2.0", "method": "echo", "params": [[1, 2, 3]], "id": 3});
+        let batch = encode(&json!([call, call]));
+        for limits in [
+            RequestLimits::default().max_batch_size(1),
+            RequestLimits::default().max_depth(3),
+            RequestLimits::default().max_array_len(2),
+            RequestLimits::default().max_size(batch.len() - 1),
+        ] {
+            let response = handle_request(&mut handler, &batch, &limits).unwrap();
+            assert_eq!(decode(&response).unwrap()["error"]["code"], json!(-32600));
+        }
+        let limits = RequestLimits::default()
+            .max_batch_size(2)
+            .max_depth(4)
+            .max_array_len(3)
+            .max_size(batch.len());
+        let response = handle_request(&mut handler, &batch, &limits).unwrap();
+        assert_eq!(decode(&response).unwrap()[1]["result"], json!([1, 2, 3]));
+    }
+}
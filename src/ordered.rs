@@ -0,0 +1,84 @@
+use crate::Value;
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+/// Serializes a response, or a batch of responses, with the members of each response in the order
+/// the specification lists them: `jsonrpc`, then `result` or `error`, then `id`. Members of an
+/// error are ordered `code`, `message`, `data`. Any other members follow in their usual order.
+///
+/// Serializing a [Value](enum.Value.html) directly orders members alphabetically, or by insertion
+/// with serde_json's `preserve_order` feature, so the exact output depends on how serde_json is
+/// built. [handle_request_str](trait.Handler.html#method.handle_request_str) and the other methods
+/// returning a serialized response use this order, making the output stable enough for snapshot
+/// tests.
+///
+/// ```
+/// use easy_jsonrpc::OrderedResponse;
+/// use serde_json::json;
+///
+/// let response = json!({"id": 1, "result": true, "jsonrpc": "2.0"});
+/// assert_eq!(
+///     serde_json::to_string(&OrderedResponse(&response)).unwrap(),
+///     r#"{"jsonrpc":"2.0","result":true,"id":1}"#
+/// );
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct OrderedResponse<'a>(pub &'a Value);
+
+const RESPONSE_ORDER: &[&str] = &["jsonrpc", "result", "error", "id"];
+const ERROR_ORDER: &[&str] = &["code", "message", "data"];
+
+impl Serialize for OrderedResponse<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            Value::Array(responses) => {
+                serializer.collect_seq(responses.iter().map(OrderedResponse))
+            }
+            response => Ordered {
+                value: response,
+                order: RESPONSE_ORDER,
+            }
+            .serialize(serializer),
+        }
+    }
+}
+
+// an object with the keys in order first, anything else is serialized as is
+struct Ordered<'a> {
+    value: &'a Value,
+    order: &'static [&'static str],
+}
+
+impl Serialize for Ordered<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let members = match self.value {
+            Value::Object(members) => members,
+            other => return other.serialize(serializer),
+        };
+        let mut map = serializer.serialize_map(Some(members.len()))?;
+        for key in self.order {
+            match (*key, members.get(*key)) {
+                ("error", Some(error)) => map.serialize_entry(
+                    key,
+                    &Ordered {
+                        value: error,
+                        order: ERROR_ORDER,
+                    },
+                )?,
+                (_, Some(value)) => map.serialize_entry(key, value)?,
+                (_, None) => {}
+            }
+        }
+        for (key, value) in members {
+            if !self.order.contains(&key.as_str()) {
+                map.serialize_entry(key, value)?;
+            }
+        }
+        map.end()
+    }
+}
+
+// serialize a response the way the Handler methods returning strings do
+pub(crate) fn response_string(response: &Value) -> String {
+    // a Value with string keys always serializes
+    serde_json::to_string(&OrderedResponse(response)).unwrap_or_else(|_| response.to_string())
+}
@@ -0,0 +1,81 @@
+use crate::{BoundMethod, Value};
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+/// The number of items in a page of a `#[rpc(paginate)]` method's result, for calls which don't
+/// pass a `limit`.
+pub const DEFAULT_PAGE_LIMIT: usize = 100;
+
+/// One page of the result of a method marked `#[rpc(paginate)]`, which is what the method returns
+/// on the wire: `{"items": [..], "next_cursor": 10}`.
+///
+/// ```
+/// use easy_jsonrpc::{rpc, Handler, Page};
+/// use serde_json::json;
+///
+/// #[rpc]
+/// pub trait Catalog {
+///     #[rpc(paginate)]
+///     fn products(&self) -> Vec<String>;
+/// }
+///
+/// struct CatalogImpl;
+/// impl Catalog for CatalogImpl {
+///     fn products(&self) -> Vec<String> {
+///         vec!["apple".into(), "banana".into(), "cherry".into()]
+///     }
+/// }
+///
+/// let mut handler = &CatalogImpl as &dyn Catalog;
+/// let response = handler.handle_request(json!({
+///     "jsonrpc": "2.0",
+///     "method": "products",
+///     "params": {"cursor": 1, "limit": 1},
+///     "id": 1,
+/// }));
+/// let page: Page<String> = serde_json::from_value(response.as_option().unwrap()["result"].clone())
+///     .unwrap();
+/// assert_eq!(page.items, ["banana"]);
+/// assert_eq!(page.next_cursor, Some(2));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Page<T> {
+    /// The items of this page, in the order the method returned them.
+    pub items: Vec<T>,
+    /// The cursor to request the next page with, or None if this is the last page.
+    pub next_cursor: Option<u64>,
+}
+
+/// The calls requesting each page of a `#[rpc(paginate)]` method's result, as returned by the
+/// method's `_pages` client function. Pass it to
+/// [Client::call_pages](client/struct.Client.html#method.call_pages) to collect the whole result,
+/// or request pages one at a time with [page](#method.page).
+#[derive(Clone, Debug)]
+pub struct Pages<T> {
+    method: &'static str,
+    args: Vec<Value>,
+    limit: Option<usize>,
+    _spook: PhantomData<fn() -> T>,
+}
+
+impl<T: Deserialize<'static>> Pages<T> {
+    /// Request pages of at most limit items of method's result when called with args.
+    /// You probably don't want to use this method directly.
+    /// Try using the rpc macro instead.
+    pub fn new(method: &'static str, args: Vec<Value>, limit: Option<usize>) -> Self {
+        Pages {
+            method,
+            args,
+            limit,
+            _spook: PhantomData,
+        }
+    }
+
+    /// The call requesting the page starting at cursor, or the first page for None.
+    pub fn page(&self, cursor: Option<u64>) -> BoundMethod<'static, Page<T>> {
+        let mut args = self.args.clone();
+        args.push(cursor.into());
+        args.push(self.limit.into());
+        BoundMethod::new(self.method, args)
+    }
+}
@@ -0,0 +1,58 @@
+use crate::{Error, Failure, Id, Output, Success, Value, Version};
+use std::io::Write;
+
+/// Writes responses to a pipelined connection as they complete, in whatever order that is. Each
+/// response is an independent jsonrpc response followed by a newline, unlike a batch response
+/// which is a single array. Clients match responses to calls by id.
+///
+/// ```
+/// use easy_jsonrpc::{Id, ResponsePipeline};
+/// use serde_json::{json, Value};
+///
+/// let mut pipeline = ResponsePipeline::new(Vec::new());
+/// pipeline.complete(Id::Num(2), Ok(json!("second"))).unwrap();
+/// pipeline.complete(Id::Num(1), Ok(json!("first"))).unwrap();
+/// let out = pipeline.into_inner();
+/// let frames: Vec<Value> = serde_json::Deserializer::from_slice(&out)
+///     .into_iter()
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(frames[0], json!({"jsonrpc": "2.0", "result": "second", "id": 2}));
+/// ```
+pub struct ResponsePipeline<W> {
+    writer: W,
+}
+
+impl<W: Write> ResponsePipeline<W> {
+    /// Write responses to writer.
+    pub fn new(writer: W) -> Self {
+        ResponsePipeline { writer }
+    }
+
+    /// Write the response to the call with id, which returned result, and flush it.
+    pub fn complete(
+        &mut self,
+        id: Id,
+        result: Result<Value, Error>,
+    ) -> Result<(), serde_json::Error> {
+        let jsonrpc = Some(Version::V2);
+        let output = match result {
+            Ok(result) => Output::Success(Success {
+                jsonrpc,
+                result,
+                id,
+            }),
+            Err(error) => Output::Failure(Failure { jsonrpc, error, id }),
+        };
+        serde_json::to_writer(&mut self.writer, &output)?;
+        self.writer
+            .write_all(b"\n")
+            .and_then(|()| self.writer.flush())
+            .map_err(serde_json::Error::io)
+    }
+
+    /// Unwrap the writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{Error, InvalidArgs, Value};
+use serde::de::{self, DeserializeOwned, DeserializeSeed, Deserializer, SeqAccess, Visitor};
+use serde::forward_to_deserialize_any;
+use serde_json::json;
+
+// Deserialize positional args as a whole, e.g. into a tuple. See Params::parse_positional.
+pub(crate) fn parse_positional<T: DeserializeOwned>(args: Vec<Value>) -> Result<T, Error> {
+    let actual = args.len();
+    let mut positional = Positional {
+        args: args.into_iter(),
+        index: 0,
+        failed_at: None,
+        expected: None,
+    };
+    T::deserialize(&mut positional).map_err(|e| match (positional.expected, positional.failed_at) {
+        (Some(expected), _) => InvalidArgs::WrongNumberOfArgs { expected, actual }.into(),
+        (None, Some(index)) => Error {
+            data: Some(json!({ "error": e.to_string() })),
+            ..InvalidArgs::InvalidPositionalArg { index }.into()
+        },
+        (None, None) => Error {
+            data: Some(json!({ "error": e.to_string() })),
+            ..InvalidArgs::InvalidParamsStructure.into()
+        },
+    })
+}
+
+// Hands out args one at a time, remembering which one failed to deserialize and, for a tuple or
+// other sequence of known length, how many were expected.
+struct Positional {
+    args: std::vec::IntoIter<Value>,
+    index: usize,
+    failed_at: Option<usize>,
+    expected: Option<usize>,
+}
+
+impl<'de> Deserializer<'de> for &mut Positional {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let value = visitor.visit_seq(&mut *self)?;
+        if self.args.len() != 0 {
+            // the visitor stopped early, so it wanted as many args as it took
+            self.expected = Some(self.index);
+            return Err(de::Error::invalid_length(
+                self.index + self.args.len(),
+                &"fewer args",
+            ));
+        }
+        Ok(value)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        // checked up front so that too few args isn't reported as a bad arg
+        if self.args.len() != len {
+            self.expected = Some(len);
+            return Err(de::Error::invalid_length(self.args.len(), &visitor));
+        }
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    // () takes no args
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        if self.args.len() != 0 {
+            self.expected = Some(0);
+            return Err(de::Error::invalid_length(self.args.len(), &visitor));
+        }
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit_struct newtype_struct seq map struct enum identifier ignored_any
+    }
+}
+
+impl<'de> SeqAccess<'de> for Positional {
+    type Error = serde_json::Error;
+
+    fn next_element_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, Self::Error> {
+        let arg = match self.args.next() {
+            Some(arg) => arg,
+            None => return Ok(None),
+        };
+        let index = self.index;
+        self.index += 1;
+        seed.deserialize(arg)
+            .map(Some)
+            .inspect_err(|_| self.failed_at = Some(index))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.args.len())
+    }
+}
@@ -0,0 +1,129 @@
+use crate::{Error, Handler, Id, Params, RequestMeta, Value};
+use serde_json::json;
+
+/// The method of the notifications reporting a call's progress. Their params hold the id of the
+/// call and the reported value: `{"id": 1, "value": ..}`. The id is null for notifications.
+pub const PROGRESS: &str = "rpc.progress";
+
+/// Handles requests for a trait whose methods report their progress, through an argument
+/// declared as `#[rpc_progress] progress: &dyn Fn(Value)`. That argument is not one of the
+/// jsonrpc params. Implemented by the [rpc](../easy_jsonrpc_proc_macro/attr.rpc.html) macro
+/// alongside [Handler](trait.Handler.html), whose methods report progress to no one. Use
+/// [with_sink](fn.with_sink.html) to handle a request.
+pub trait HandleWithSink: Handler {
+    /// Like [Handler::handle](trait.Handler.html#tymethod.handle), passing each value reported
+    /// by the method to sink as soon as it is reported.
+    fn handle_with_sink(
+        &mut self,
+        method: &str,
+        params: Params,
+        sink: &mut dyn FnMut(Value),
+    ) -> Result<Value, Error>;
+}
+
+/// Pair handler with the sink for the progress notifications of a request, giving a
+/// [Handler](trait.Handler.html). Each value reported by a method is passed to sink as a
+/// [PROGRESS](constant.PROGRESS.html) notification, before the response is returned.
+///
+/// ```
+/// use easy_jsonrpc::{rpc, with_sink, Handler, Value};
+/// use serde_json::json;
+///
+/// #[rpc]
+/// pub trait Indexer {
+///     fn index(&self, #[rpc_progress] progress: &dyn Fn(Value), files: Vec<String>) -> usize;
+/// }
+///
+/// struct IndexerImpl;
+/// impl Indexer for IndexerImpl {
+///     fn index(&self, progress: &dyn Fn(Value), files: Vec<String>) -> usize {
+///         for file in &files {
+///             progress(json!(file));
+///         }
+///         files.len()
+///     }
+/// }
+///
+/// let mut handler = &IndexerImpl as &dyn Indexer;
+/// let mut notifications = Vec::new();
+/// let response = with_sink(&mut handler, &mut |notification| notifications.push(notification))
+///     .handle_request(json!({
+///         "jsonrpc": "2.0",
+///         "method": "index",
+///         "params": [["a.rs"]],
+///         "id": 1
+///     }));
+/// assert_eq!(response.as_option().unwrap()["result"], json!(1));
+/// assert_eq!(
+///     notifications,
+///     [json!({
+///         "jsonrpc": "2.0",
+///         "method": "rpc.progress",
+///         "params": {"id": 1, "value": "a.rs"}
+///     })]
+/// );
+/// ```
+pub fn with_sink<'a, H>(handler: &'a mut H, sink: &'a mut dyn FnMut(Value)) -> WithSink<'a, H>
+where
+    H: HandleWithSink + ?Sized,
+{
+    WithSink { handler, sink }
+}
+
+/// A handler paired with a sink for progress notifications by [with_sink](fn.with_sink.html).
+pub struct WithSink<'a, H: ?Sized> {
+    handler: &'a mut H,
+    sink: &'a mut dyn FnMut(Value),
+}
+
+impl<H> WithSink<'_, H>
+where
+    H: HandleWithSink + ?Sized,
+{
+    // handle the call with the given id, reporting its progress under that id
+    fn handle_with_id(
+        &mut self,
+        id: Option<&Id>,
+        method: &str,
+        params: Params,
+    ) -> Result<Value, Error> {
+        let sink = &mut *self.sink;
+        self.handler.handle_with_sink(method, params, &mut |value| {
+            sink(json!({
+                "jsonrpc": "2.0",
+                "method": PROGRESS,
+                "params": {"id": id, "value": value},
+            }))
+        })
+    }
+}
+
+impl<H> Handler for WithSink<'_, H>
+where
+    H: HandleWithSink + ?Sized,
+{
+    fn handle(&mut self, method: &str, params: Params) -> Result<Value, Error> {
+        self.handle_with_id(None, method, params)
+    }
+
+    fn handle_with_request_meta(
+        &mut self,
+        method: &str,
+        params: Params,
+        meta: &RequestMeta,
+    ) -> Result<Value, Error> {
+        self.handle_with_id(meta.id(), method, params)
+    }
+
+    fn method_exists(&self, method: &str) -> bool {
+        self.handler.method_exists(method)
+    }
+
+    fn accepts_bare_param(&self, method: &str) -> bool {
+        self.handler.accepts_bare_param(method)
+    }
+
+    fn strict_version(&self) -> bool {
+        self.handler.strict_version()
+    }
+}
@@ -0,0 +1,287 @@
+use crate::{check_arity, dispatch, order_named, response_string, serialization_error};
+use crate::{Error, Handler, Id, InvalidArgs, Output, Params, Value, Version};
+use serde::{Deserialize, Deserializer};
+use serde_json::value::RawValue;
+use std::collections::BTreeMap;
+
+/// jsonrpc params split into arguments, each left unparsed until the method it is passed to
+/// deserializes it. Passing no params, or passing `null`, is equivalent to passing 0 positional
+/// params, as with [Params](enum.Params.html).
+#[derive(Clone, Debug)]
+pub struct RawParams<'a>(Split<'a>);
+
+#[derive(Clone, Debug)]
+enum Split<'a> {
+    // the params array, whose args are split off as they are read
+    Positional(&'a str),
+    Named(BTreeMap<String, &'a RawValue>),
+}
+
+impl<'a> RawParams<'a> {
+    /// Split params into arguments. Returns None unless params is omitted, an array or an object.
+    pub fn new(params: Option<&'a RawValue>) -> Option<Self> {
+        let params = match params {
+            Some(params) => params.get(),
+            None => return Some(RawParams(Split::Positional("[]"))),
+        };
+        let split = match params.trim_start().as_bytes().first() {
+            // a RawValue holds valid json, so this is an array
+            Some(b'[') => Split::Positional(params),
+            Some(b'{') => Split::Named(serde_json::from_str(params).ok()?),
+            _ => return None,
+        };
+        Some(RawParams(split))
+    }
+
+    /// See [Params::get_rpc_args](enum.Params.html#method.get_rpc_args).
+    pub fn get_rpc_args(self, names: &[&'static str]) -> Result<Vec<&'a RawValue>, InvalidArgs> {
+        self.get_rpc_args_with_optional(names, names.len())
+    }
+
+    /// See [Params::get_rpc_args_with_optional](enum.Params.html#method.get_rpc_args_with_optional).
+    /// Omitted args are returned as `null`.
+    pub fn get_rpc_args_with_optional(
+        self,
+        names: &[&'static str],
+        required: usize,
+    ) -> Result<Vec<&'a RawValue>, InvalidArgs> {
+        let ar = match self.0 {
+            Split::Positional(ar) => Elements::new(ar).collect(),
+            Split::Named(mut ma) => {
+                let ar = order_named(|name| ma.remove(name), names, required, || RawValue::NULL)?;
+                if let Some(key) = ma.into_keys().next() {
+                    return Err(InvalidArgs::ExtraNamedParameter { name: key });
+                }
+                ar
+            }
+        };
+        check_arity(ar, names, required, || RawValue::NULL)
+    }
+
+    /// Like [get_rpc_args_with_optional](#method.get_rpc_args_with_optional), except that
+    /// positional args are split off the params one at a time, as they are taken. Arguments after
+    /// one which fails to deserialize are never read. A missing argument is reported when it is
+    /// taken, extra arguments by [RawArgs::finish](struct.RawArgs.html#method.finish).
+    #[doc(hidden)]
+    pub fn into_rpc_args(
+        self,
+        names: &[&'static str],
+        required: usize,
+    ) -> Result<RawArgs<'a>, InvalidArgs> {
+        let args = match self.0 {
+            Split::Positional(ar) => Args::Positional(Elements::new(ar)),
+            named => Args::Named(
+                RawParams(named)
+                    .get_rpc_args_with_optional(names, required)?
+                    .into_iter(),
+            ),
+        };
+        Ok(RawArgs {
+            args,
+            taken: 0,
+            len: names.len(),
+            required,
+        })
+    }
+
+    /// Parse every argument, for methods which can't deserialize their arguments from raw json.
+    pub fn into_params(self) -> Params {
+        // each RawValue holds valid json
+        let parse = |raw: &RawValue| Value::deserialize(raw).unwrap_or(Value::Null);
+        match self.0 {
+            Split::Positional(ar) => Params::Positional(Elements::new(ar).map(parse).collect()),
+            Split::Named(ma) => Params::Named(
+                ma.into_iter()
+                    .map(|(name, raw)| (name, parse(raw)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// The arguments of a call, taken in order. See
+/// [RawParams::into_rpc_args](struct.RawParams.html#method.into_rpc_args).
+#[doc(hidden)]
+#[derive(Clone, Debug)]
+pub struct RawArgs<'a> {
+    args: Args<'a>,
+    taken: usize,
+    len: usize,
+    required: usize,
+}
+
+#[derive(Clone, Debug)]
+enum Args<'a> {
+    Positional(Elements<'a>),
+    Named(std::vec::IntoIter<&'a RawValue>),
+}
+
+impl<'a> RawArgs<'a> {
+    /// Take the next argument. Omitted optional arguments are taken as `null`.
+    pub fn next_arg(&mut self) -> Result<&'a RawValue, InvalidArgs> {
+        let index = self.taken;
+        self.taken += 1;
+        let arg = match &mut self.args {
+            Args::Positional(elements) => elements.next(),
+            Args::Named(ar) => ar.next(),
+        };
+        match arg {
+            Some(arg) if index < self.len => Ok(arg),
+            None if index < self.required => Err(InvalidArgs::WrongNumberOfArgs {
+                expected: self.required,
+                actual: index,
+            }),
+            None if index < self.len => Ok(RawValue::NULL),
+            _ => Err(InvalidArgs::MissingPositionalParameter { index }),
+        }
+    }
+
+    /// Check that no arguments are left once all have been taken.
+    pub fn finish(self) -> Result<(), InvalidArgs> {
+        match self.args {
+            Args::Positional(elements) => match elements.count() {
+                0 => Ok(()),
+                extra => Err(InvalidArgs::WrongNumberOfArgs {
+                    expected: self.len,
+                    actual: self.len + extra,
+                }),
+            },
+            Args::Named(_) => Ok(()),
+        }
+    }
+}
+
+// The elements of a json array, split off the array text one at a time.
+#[derive(Clone, Debug)]
+struct Elements<'a> {
+    rest: &'a str,
+    started: bool,
+}
+
+impl<'a> Elements<'a> {
+    fn new(array: &'a str) -> Self {
+        Elements {
+            rest: array,
+            started: false,
+        }
+    }
+}
+
+impl<'a> Iterator for Elements<'a> {
+    type Item = &'a RawValue;
+
+    fn next(&mut self) -> Option<&'a RawValue> {
+        let separator = if self.started { ',' } else { '[' };
+        self.started = true;
+        let rest = self.rest.trim_start();
+        let rest = match rest.strip_prefix(separator) {
+            Some(rest) if !rest.trim_start().starts_with(']') => rest,
+            _ => {
+                self.rest = "";
+                return None;
+            }
+        };
+        let mut stream = serde_json::Deserializer::from_str(rest).into_iter::<&'a RawValue>();
+        match stream.next() {
+            Some(Ok(element)) => {
+                self.rest = &rest[stream.byte_offset()..];
+                Some(element)
+            }
+            _ => {
+                self.rest = "";
+                None
+            }
+        }
+    }
+}
+
+/// Handles requests without first parsing their params into [Value](enum.Value.html)s, e.g. for
+/// methods receiving large arguments. Implemented by the
+/// [rpc](../easy_jsonrpc_proc_macro/attr.rpc.html) macro alongside
+/// [Handler](trait.Handler.html), except for traits whose methods take a `#[rpc_ctx]`.
+///
+/// Arguments are deserialized straight from the request text. Positional arguments are split off
+/// the params one at a time, so nothing after an argument which fails to deserialize is read.
+/// Arguments of types which borrow, such as `&str`, and `#[rpc_arg(rest)]` arguments are still
+/// parsed into Values first.
+pub trait HandleRaw: Handler {
+    /// Like [Handler::handle](trait.Handler.html#tymethod.handle), taking unparsed params.
+    fn handle_raw(&mut self, method: &str, params: RawParams<'_>) -> Result<Value, Error>;
+
+    /// Like [Handler::handle_request_str](trait.Handler.html#method.handle_request_str), calling
+    /// methods through [handle_raw](#tymethod.handle_raw). Requests containing any call which
+    /// doesn't declare `"jsonrpc": "2.0"`, or is otherwise unusual, are passed to
+    /// handle_request_str instead.
+    fn handle_request_raw(&mut self, raw_request: &str) -> Option<String> {
+        let response = match parse_raw_request(raw_request) {
+            Some(RawRequest::Single(call)) => {
+                jsonrpc_core::Response::Single(dispatch_raw(self, call)?)
+            }
+            Some(RawRequest::Batch(calls)) => {
+                let outputs: Vec<Output> = calls
+                    .into_iter()
+                    .filter_map(|call| dispatch_raw(self, call))
+                    .collect();
+                if outputs.is_empty() {
+                    return None;
+                }
+                jsonrpc_core::Response::Batch(outputs)
+            }
+            None => return self.handle_request_str(raw_request),
+        };
+        Some(
+            serde_json::to_string(&response)
+                .unwrap_or_else(|e| response_string(&serialization_error(e))),
+        )
+    }
+}
+
+// A well formed call. Anything else is left to the Value based path, which knows how to report
+// every kind of invalid request.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawCall<'a> {
+    jsonrpc: Version,
+    method: String,
+    #[serde(borrow, default)]
+    params: Option<&'a RawValue>,
+    // an id of null still makes a method call, only an omitted id makes a notification
+    #[serde(default, deserialize_with = "present")]
+    id: Option<Id>,
+}
+
+fn present<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Id>, D::Error> {
+    Id::deserialize(deserializer).map(Some)
+}
+
+enum RawRequest<'a> {
+    Single((RawCall<'a>, RawParams<'a>)),
+    Batch(Vec<(RawCall<'a>, RawParams<'a>)>),
+}
+
+fn parse_raw_request(raw_request: &str) -> Option<RawRequest<'_>> {
+    fn parse_call(raw_call: &str) -> Option<(RawCall<'_>, RawParams<'_>)> {
+        let call: RawCall = serde_json::from_str(raw_call).ok()?;
+        let params = RawParams::new(call.params)?;
+        Some((call, params))
+    }
+    if raw_request.trim_start().starts_with('[') {
+        let calls: Vec<&RawValue> = serde_json::from_str(raw_request).ok()?;
+        if calls.is_empty() {
+            return None;
+        }
+        let calls = calls.into_iter().map(|call| parse_call(call.get()));
+        Some(RawRequest::Batch(calls.collect::<Option<_>>()?))
+    } else {
+        parse_call(raw_request).map(RawRequest::Single)
+    }
+}
+
+fn dispatch_raw<S: ?Sized + HandleRaw>(
+    slef: &mut S,
+    (call, params): (RawCall<'_>, RawParams<'_>),
+) -> Option<Output> {
+    dispatch(&call.method, call.id, Some(call.jsonrpc), |_| {
+        slef.handle_raw(&call.method, params)
+    })
+}
@@ -0,0 +1,109 @@
+use crate::{Error, Handler, Params, Value};
+
+/// Dispatches the methods of a trait marked `#[rpc(readonly)]`, through a shared reference.
+/// Implemented by the [rpc](../easy_jsonrpc_proc_macro/attr.rpc.html) macro for `dyn MyApi` when
+/// `MyApi` has readonly methods. Use [ReadOnly](struct.ReadOnly.html) to handle a request.
+pub trait HandleReadOnly {
+    /// Whether requests must declare `"jsonrpc": "2.0"`, see
+    /// [Handler::handle_request_strict](trait.Handler.html#method.handle_request_strict).
+    const STRICT_VERSION: bool = false;
+
+    /// Like [Handler::handle](trait.Handler.html#tymethod.handle), for readonly methods only.
+    fn handle_readonly(&self, method: &str, params: Params) -> Result<Value, Error>;
+
+    /// Whether method names a readonly method.
+    fn readonly_method_exists(&self, method: &str) -> bool;
+
+    /// Like [Handler::accepts_bare_param](trait.Handler.html#method.accepts_bare_param).
+    fn accepts_bare_param(&self, method: &str) -> bool {
+        let _ = method;
+        false
+    }
+}
+
+/// Restricts a handler to the methods marked `#[rpc(readonly)]`. `ReadOnly<&dyn MyApi>` is a
+/// [Handler](trait.Handler.html) when `MyApi` has readonly methods. Other methods are answered
+/// with `method_not_found`.
+///
+/// State behind an `RwLock` can then serve readonly requests under a read guard, taking the write
+/// guard only for requests which need it.
+///
+/// ```
+/// use easy_jsonrpc::{rpc, Handler, MaybeReply, ReadOnly};
+/// use serde_json::{json, Value};
+/// use std::sync::RwLock;
+///
+/// #[rpc]
+/// pub trait Counter {
+///     #[rpc(readonly)]
+///     fn get(&self) -> u64;
+///     fn increment(&mut self);
+/// }
+///
+/// struct CounterImpl(u64);
+/// impl Counter for CounterImpl {
+///     fn get(&self) -> u64 {
+///         self.0
+///     }
+///     fn increment(&mut self) {
+///         self.0 += 1;
+///     }
+/// }
+///
+/// fn handle(state: &RwLock<CounterImpl>, request: Value) -> MaybeReply {
+///     {
+///         let guard = state.read().unwrap();
+///         let mut readonly = ReadOnly(&*guard as &dyn Counter);
+///         if readonly.admits(&request) {
+///             return readonly.handle_request(request);
+///         }
+///     }
+///     let mut guard = state.write().unwrap();
+///     (&mut *guard as &mut dyn Counter).handle_request(request)
+/// }
+///
+/// let state = RwLock::new(CounterImpl(0));
+/// handle(&state, json!({"jsonrpc": "2.0", "method": "increment", "params": []}));
+/// assert_eq!(
+///     handle(&state, json!({"jsonrpc": "2.0", "method": "get", "params": [], "id": 1})),
+///     MaybeReply::Reply(json!({"jsonrpc": "2.0", "result": 1, "id": 1}))
+/// );
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct ReadOnly<H>(pub H);
+
+impl<T: HandleReadOnly + ?Sized> Handler for ReadOnly<&T> {
+    fn handle(&mut self, method: &str, params: Params) -> Result<Value, Error> {
+        self.0.handle_readonly(method, params)
+    }
+
+    fn method_exists(&self, method: &str) -> bool {
+        self.0.readonly_method_exists(method)
+    }
+
+    fn accepts_bare_param(&self, method: &str) -> bool {
+        self.0.accepts_bare_param(method)
+    }
+
+    fn strict_version(&self) -> bool {
+        T::STRICT_VERSION
+    }
+}
+
+impl<T: HandleReadOnly + ?Sized> ReadOnly<&T> {
+    /// Whether every call in raw_request is to a method this handler dispatches. Malformed calls
+    /// don't reach any method, so they are admitted too.
+    pub fn admits(&self, raw_request: &Value) -> bool {
+        match raw_request {
+            Value::Array(calls) => calls.iter().all(|call| self.admits_call(call)),
+            call => self.admits_call(call),
+        }
+    }
+
+    fn admits_call(&self, call: &Value) -> bool {
+        match call.get("method") {
+            Some(Value::String(method)) => self.method_exists(method),
+            _ => true,
+        }
+    }
+}
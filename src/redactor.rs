@@ -0,0 +1,128 @@
+use crate::{CallKind, Error, ErrorCode, Handler, Params, RequestMeta, Value};
+
+/// The `data` of internal errors redacted by
+/// [redact_internal_errors](fn.redact_internal_errors.html).
+pub const HIDDEN_DETAIL: &str = "details hidden";
+
+/// Wraps a handler, passing every error it returns through redact before it is sent, e.g. to keep
+/// internal details in an error's `data` from reaching external clients. redact is given the
+/// method called and may change anything about the error, typically clearing `data` or rewording
+/// `message`.
+///
+/// Only errors returned by the inner handler's methods are redacted. Errors about the request
+/// itself, such as a parse error, are produced before any method is called.
+///
+/// ```
+/// use easy_jsonrpc::{DynHandler, Error, Handler, Redactor};
+/// use serde_json::json;
+///
+/// let mut inner = DynHandler::new();
+/// inner.register("login", |_| {
+///     Err(Error {
+///         data: Some(json!({"query": "SELECT * FROM users"})),
+///         ..Error::internal_error()
+///     })
+/// });
+///
+/// let mut handler = Redactor::new(inner, |_method, error| error.data = None);
+/// let response = handler.handle_request(json!({"jsonrpc": "2.0", "method": "login", "id": 1}));
+/// assert_eq!(response.as_option().unwrap()["error"].get("data"), None);
+/// ```
+pub struct Redactor<H, F> {
+    handler: H,
+    redact: F,
+}
+
+impl<H, F> Redactor<H, F>
+where
+    H: Handler,
+    F: FnMut(&str, &mut Error),
+{
+    /// Wrap handler, calling redact on each error it returns.
+    pub fn new(handler: H, redact: F) -> Self {
+        Redactor { handler, redact }
+    }
+
+    /// Unwrap the inner handler.
+    pub fn into_inner(self) -> H {
+        self.handler
+    }
+}
+
+impl<H, F> Handler for Redactor<H, F>
+where
+    H: Handler,
+    F: FnMut(&str, &mut Error),
+{
+    fn handle(&mut self, method: &str, params: Params) -> Result<Value, Error> {
+        self.handle_with_meta(method, params, CallKind::MethodCall)
+    }
+
+    fn handle_with_meta(
+        &mut self,
+        method: &str,
+        params: Params,
+        kind: CallKind,
+    ) -> Result<Value, Error> {
+        self.handle_with_request_meta(method, params, &RequestMeta::new(kind))
+    }
+
+    fn handle_with_request_meta(
+        &mut self,
+        method: &str,
+        params: Params,
+        meta: &RequestMeta,
+    ) -> Result<Value, Error> {
+        self.handler
+            .handle_with_request_meta(method, params, meta)
+            .map_err(|mut error| {
+                (self.redact)(method, &mut error);
+                error
+            })
+    }
+
+    fn method_exists(&self, method: &str) -> bool {
+        self.handler.method_exists(method)
+    }
+
+    fn accepts_bare_param(&self, method: &str) -> bool {
+        self.handler.accepts_bare_param(method)
+    }
+
+    fn strict_version(&self) -> bool {
+        self.handler.strict_version()
+    }
+}
+
+/// A redact function for [Redactor](struct.Redactor.html) which, unless verbose_errors, replaces
+/// the `data` of internal errors with [HIDDEN_DETAIL](constant.HIDDEN_DETAIL.html), keeping their
+/// code and message. That data carries implementation details, such as why a result failed to
+/// serialize. Other errors, and internal errors without data, are left alone. Typically
+/// verbose_errors is on in development only, e.g. `cfg!(debug_assertions)`.
+///
+/// ```
+/// use easy_jsonrpc::{redact_internal_errors, DynHandler, Error, Handler, Redactor};
+/// use serde_json::json;
+///
+/// let mut inner = DynHandler::new();
+/// inner.register("load", |_| {
+///     Err(Error {
+///         data: Some(json!("config.toml: permission denied")),
+///         ..Error::internal_error()
+///     })
+/// });
+///
+/// let mut handler = Redactor::new(inner, redact_internal_errors(false));
+/// let response = handler.handle_request(json!({"jsonrpc": "2.0", "method": "load", "id": 1}));
+/// assert_eq!(
+///     response.as_option().unwrap()["error"],
+///     json!({"code": -32603, "message": "Internal error", "data": "details hidden"})
+/// );
+/// ```
+pub fn redact_internal_errors(verbose_errors: bool) -> impl FnMut(&str, &mut Error) + Clone {
+    move |_method, error| {
+        if !verbose_errors && error.code == ErrorCode::InternalError && error.data.is_some() {
+            error.data = Some(Value::String(HIDDEN_DETAIL.into()));
+        }
+    }
+}
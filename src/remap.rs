@@ -0,0 +1,105 @@
+use crate::{CallKind, Error, Handler, Params, RequestMeta, Value};
+use std::collections::HashMap;
+
+/// Wraps a handler, renaming methods before they reach it, e.g. to keep serving clients which
+/// still call a method by its old name. Methods which aren't renamed are passed through as they
+/// are, so the inner handler's own names keep working.
+///
+/// ```
+/// use easy_jsonrpc::{DynHandler, Handler, RemapMethods};
+/// use serde_json::json;
+/// use std::collections::HashMap;
+///
+/// let mut inner = DynHandler::new();
+/// inner.register("get_balance", |_| Ok(json!(10)));
+///
+/// let mut renames = HashMap::new();
+/// renames.insert("account.balance".to_owned(), "get_balance".to_owned());
+/// let mut handler = RemapMethods::new(inner, renames);
+///
+/// for method in ["account.balance", "get_balance"] {
+///     let response = handler.handle_request(json!({"jsonrpc": "2.0", "method": method, "id": 1}));
+///     assert_eq!(response.as_option().unwrap()["result"], json!(10));
+/// }
+/// ```
+pub struct RemapMethods<H, F> {
+    handler: H,
+    remap: F,
+}
+
+impl<H: Handler> RemapMethods<H, fn(&str) -> Option<String>> {
+    /// Wrap handler, passing calls to each key of renames to the method named by its value.
+    pub fn new(
+        handler: H,
+        renames: HashMap<String, String>,
+    ) -> RemapMethods<H, impl Fn(&str) -> Option<String>> {
+        RemapMethods::with_fn(handler, move |method| renames.get(method).cloned())
+    }
+}
+
+impl<H, F> RemapMethods<H, F>
+where
+    H: Handler,
+    F: Fn(&str) -> Option<String>,
+{
+    /// Wrap handler, passing calls to the method named by remap, or unchanged if it returns None.
+    pub fn with_fn(handler: H, remap: F) -> Self {
+        RemapMethods { handler, remap }
+    }
+
+    /// Unwrap the inner handler.
+    pub fn into_inner(self) -> H {
+        self.handler
+    }
+}
+
+impl<H, F> Handler for RemapMethods<H, F>
+where
+    H: Handler,
+    F: Fn(&str) -> Option<String>,
+{
+    fn handle(&mut self, method: &str, params: Params) -> Result<Value, Error> {
+        self.handle_with_meta(method, params, CallKind::MethodCall)
+    }
+
+    fn handle_with_meta(
+        &mut self,
+        method: &str,
+        params: Params,
+        kind: CallKind,
+    ) -> Result<Value, Error> {
+        self.handle_with_request_meta(method, params, &RequestMeta::new(kind))
+    }
+
+    fn handle_with_request_meta(
+        &mut self,
+        method: &str,
+        params: Params,
+        meta: &RequestMeta,
+    ) -> Result<Value, Error> {
+        match (self.remap)(method) {
+            Some(renamed) => self
+                .handler
+                .handle_with_request_meta(&renamed, params, meta),
+            None => self.handler.handle_with_request_meta(method, params, meta),
+        }
+    }
+
+    fn method_exists(&self, method: &str) -> bool {
+        match (self.remap)(method) {
+            Some(renamed) => self.handler.method_exists(&renamed),
+            None => self.handler.method_exists(method),
+        }
+    }
+
+    fn accepts_bare_param(&self, method: &str) -> bool {
+        match (self.remap)(method) {
+            Some(renamed) => self.handler.accepts_bare_param(&renamed),
+            None => self.handler.accepts_bare_param(method),
+        }
+    }
+
+    fn strict_version(&self) -> bool {
+        self.handler.strict_version()
+    }
+}
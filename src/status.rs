@@ -0,0 +1,86 @@
+use crate::{ErrorCode, MaybeReply};
+
+/// An HTTP status suggested for a reply, for transports serving jsonrpc over HTTP. Returned by
+/// [handle_request_with_status](trait.Handler.html#method.handle_request_with_status).
+///
+/// A reply to a single call which failed gets a status derived from the error code. Successful
+/// calls and batches, whose calls may have failed in different ways, get `Ok`. Requests
+/// consisting solely of notifications get `NoContent`.
+///
+/// ```
+/// use easy_jsonrpc::{DynHandler, Handler, StatusHint};
+/// use serde_json::json;
+///
+/// let mut handler = DynHandler::new();
+/// let (status, _) = handler.handle_request_with_status(json!({
+///     "jsonrpc": "2.0",
+///     "method": "missing",
+///     "id": 1
+/// }));
+/// assert_eq!(status, StatusHint::NotFound);
+/// assert_eq!(status.code(), 404);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum StatusHint {
+    /// 200
+    Ok,
+    /// 204
+    NoContent,
+    /// 400, for parse errors, invalid requests and invalid params
+    BadRequest,
+    /// 401, never chosen by [from_error_code](#method.from_error_code), see
+    /// [for_reply_with](#method.for_reply_with)
+    Unauthorized,
+    /// 404, for calls to methods which don't exist
+    NotFound,
+    /// 500, for internal errors and application defined errors
+    InternalServerError,
+}
+
+impl StatusHint {
+    /// The numeric HTTP status.
+    pub fn code(self) -> u16 {
+        match self {
+            StatusHint::Ok => 200,
+            StatusHint::NoContent => 204,
+            StatusHint::BadRequest => 400,
+            StatusHint::Unauthorized => 401,
+            StatusHint::NotFound => 404,
+            StatusHint::InternalServerError => 500,
+        }
+    }
+
+    /// The status suggested for a single call failing with code.
+    pub fn from_error_code(code: &ErrorCode) -> Self {
+        match code {
+            ErrorCode::ParseError | ErrorCode::InvalidRequest | ErrorCode::InvalidParams => {
+                StatusHint::BadRequest
+            }
+            ErrorCode::MethodNotFound => StatusHint::NotFound,
+            ErrorCode::InternalError | ErrorCode::ServerError(_) => StatusHint::InternalServerError,
+        }
+    }
+
+    /// The status suggested for reply.
+    pub fn for_reply(reply: &MaybeReply) -> Self {
+        Self::for_reply_with(reply, Self::from_error_code)
+    }
+
+    /// Like [for_reply](#method.for_reply), choosing the status for a failed call with
+    /// from_error_code, e.g. to map an application's authentication errors to `Unauthorized`.
+    pub fn for_reply_with(
+        reply: &MaybeReply,
+        from_error_code: impl FnOnce(&ErrorCode) -> StatusHint,
+    ) -> Self {
+        match reply {
+            MaybeReply::DontReply => StatusHint::NoContent,
+            MaybeReply::Reply(value) => match value.get("error").and_then(|e| e.get("code")) {
+                Some(code) => match code.as_i64() {
+                    Some(code) => from_error_code(&ErrorCode::from(code)),
+                    None => StatusHint::InternalServerError,
+                },
+                None => StatusHint::Ok,
+            },
+        }
+    }
+}
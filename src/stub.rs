@@ -0,0 +1,53 @@
+use crate::{Error, Handler, Params, Value};
+use std::collections::HashMap;
+
+/// A handler answering each method with a fixed result, whatever the params, e.g. to develop a
+/// frontend against a static backend or to test a client against recorded responses. Methods
+/// without a result get a method not found error.
+///
+/// ```
+/// use easy_jsonrpc::{Handler, StubHandler};
+/// use serde_json::json;
+/// use std::collections::HashMap;
+///
+/// let mut results = HashMap::new();
+/// results.insert("version".to_owned(), json!("1.2.0"));
+/// let mut handler = StubHandler::from_map(results);
+///
+/// let response = handler.handle_request(json!({
+///     "jsonrpc": "2.0",
+///     "method": "version",
+///     "params": ["ignored"],
+///     "id": 1
+/// }));
+/// assert_eq!(response.as_option().unwrap()["result"], json!("1.2.0"));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct StubHandler {
+    results: HashMap<String, Value>,
+}
+
+impl StubHandler {
+    /// Answer each method in results with its value.
+    pub fn from_map(results: HashMap<String, Value>) -> Self {
+        StubHandler { results }
+    }
+
+    /// Answer method with result, replacing any result it already had.
+    pub fn insert(&mut self, method: &str, result: Value) {
+        self.results.insert(method.to_owned(), result);
+    }
+}
+
+impl Handler for StubHandler {
+    fn handle(&mut self, method: &str, _params: Params) -> Result<Value, Error> {
+        self.results
+            .get(method)
+            .cloned()
+            .ok_or_else(Error::method_not_found)
+    }
+
+    fn method_exists(&self, method: &str) -> bool {
+        self.results.contains_key(method)
+    }
+}
@@ -0,0 +1,239 @@
+use crate::{try_serialize, CallKind, Error, ErrorCode, Handler, Params, RequestMeta, Value};
+use futures_util::future::poll_fn;
+use futures_util::{Stream, StreamExt};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::pin::Pin;
+use std::task::Poll;
+
+/// The method [Subscriptions](struct.Subscriptions.html) cancels subscriptions with, unless
+/// given [another](struct.Subscriptions.html#method.with_unsubscribe_method). It takes the
+/// subscription id, named `subscription` or positionally, and returns whether it was subscribed.
+pub const UNSUBSCRIBE: &str = "unsubscribe";
+
+/// Identifies a subscription in the notifications carrying its items.
+pub type SubscriptionId = u64;
+
+/// The items of a subscription, serialized, or the error serializing them.
+pub type ValueStream = Pin<Box<dyn Stream<Item = Result<Value, Error>> + Send>>;
+
+/// A stream of results, returned by a method to push each item to the client in a notification.
+/// Requires the `subscriptions` feature.
+///
+/// The [rpc](../easy_jsonrpc_proc_macro/attr.rpc.html) macro leaves such methods out of the
+/// Handler it implements. They are called through a [Subscriptions](struct.Subscriptions.html)
+/// wrapping the handler, which answers the call with a
+/// [SubscriptionId](type.SubscriptionId.html). The method's client function returns that id.
+pub struct Subscription<T> {
+    stream: Pin<Box<dyn Stream<Item = T> + Send>>,
+}
+
+impl<T> Subscription<T> {
+    /// Send each item of stream as it becomes ready.
+    pub fn new(stream: impl Stream<Item = T> + Send + 'static) -> Self {
+        Subscription {
+            stream: Box::pin(stream),
+        }
+    }
+}
+
+impl<T: Serialize + 'static> Subscription<T> {
+    #[doc(hidden)]
+    pub fn into_values(self) -> ValueStream {
+        Box::pin(self.stream.map(|item| try_serialize(&item)))
+    }
+}
+
+/// Starts the subscriptions of a handler. Implemented by the
+/// [rpc](../easy_jsonrpc_proc_macro/attr.rpc.html) macro alongside Handler, for traits with
+/// methods returning a [Subscription](struct.Subscription.html).
+pub trait HandleSubscribe: Handler {
+    /// Whether method returns a subscription.
+    fn is_subscription(&self, method: &str) -> bool;
+
+    /// Call method, which returns a subscription, and return its items.
+    fn subscribe(&mut self, method: &str, params: Params) -> Result<ValueStream, Error>;
+}
+
+/// Wraps a handler, answering calls to its subscription methods with a new
+/// [SubscriptionId](type.SubscriptionId.html) and keeping the subscription until it ends or is
+/// cancelled with [UNSUBSCRIBE](constant.UNSUBSCRIBE.html). Other calls are passed through.
+///
+/// Items are pulled with [next_notification](#method.next_notification), typically alongside
+/// reading requests, and sent to the client as a notification named after the subscription
+/// method: `{"jsonrpc": "2.0", "method": "blocks", "params": {"subscription": 1, "result": ..}}`.
+/// An item which fails to serialize is sent as an `"error"` in place of the `"result"`.
+///
+/// Subscription methods invoked by a notification are rejected, as the client wouldn't learn the
+/// id needed to cancel the subscription.
+///
+/// ```
+/// use easy_jsonrpc::{rpc, Handler, Subscription, Subscriptions};
+/// use futures_util::stream;
+/// use serde_json::json;
+///
+/// #[rpc]
+/// pub trait Node {
+///     fn blocks(&self, from: u64) -> Subscription<u64>;
+/// }
+///
+/// struct NodeImpl;
+/// impl Node for NodeImpl {
+///     fn blocks(&self, from: u64) -> Subscription<u64> {
+///         Subscription::new(stream::iter(from..from + 2))
+///     }
+/// }
+///
+/// let mut handler = Subscriptions::new(&NodeImpl as &dyn Node);
+/// let response = handler.handle_request(json!({
+///     "jsonrpc": "2.0",
+///     "method": "blocks",
+///     "params": [7],
+///     "id": 1
+/// }));
+/// assert_eq!(response.as_option().unwrap()["result"], json!(1));
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let notification = handler.next_notification().await;
+/// assert_eq!(notification["params"], json!({"subscription": 1, "result": 7}));
+/// # });
+/// ```
+pub struct Subscriptions<H> {
+    handler: H,
+    unsubscribe: String,
+    last_id: SubscriptionId,
+    // the subscription method and items of each subscription
+    streams: BTreeMap<SubscriptionId, (String, ValueStream)>,
+    // the subscription polled first, so that every subscription gets a turn
+    next_polled: SubscriptionId,
+}
+
+impl<H> Subscriptions<H> {
+    /// Wrap handler, without subscriptions.
+    pub fn new(handler: H) -> Self {
+        Subscriptions {
+            handler,
+            unsubscribe: UNSUBSCRIBE.to_owned(),
+            last_id: 0,
+            streams: BTreeMap::new(),
+            next_polled: 0,
+        }
+    }
+
+    /// Cancel subscriptions with method rather than [UNSUBSCRIBE](constant.UNSUBSCRIBE.html),
+    /// e.g. `"eth_unsubscribe"`.
+    pub fn with_unsubscribe_method(self, method: &str) -> Self {
+        Subscriptions {
+            unsubscribe: method.to_owned(),
+            ..self
+        }
+    }
+
+    /// The ids of the subscriptions which have neither ended nor been cancelled.
+    pub fn active(&self) -> impl Iterator<Item = SubscriptionId> + '_ {
+        self.streams.keys().copied()
+    }
+
+    /// Wait for an item of any subscription, and return the notification sending it. Subscriptions
+    /// are dropped as they end. Never resolves while there are no subscriptions, so it can be
+    /// awaited alongside the next request, e.g. in `tokio::select!`, and dropped when the request
+    /// arrives.
+    pub async fn next_notification(&mut self) -> Value {
+        poll_fn(|cx| {
+            let ids: Vec<SubscriptionId> = self
+                .streams
+                .range(self.next_polled..)
+                .chain(self.streams.range(..self.next_polled))
+                .map(|(id, _)| *id)
+                .collect();
+            for id in ids {
+                let (method, stream) = self.streams.get_mut(&id).expect("id was just listed");
+                match stream.poll_next_unpin(cx) {
+                    Poll::Ready(Some(item)) => {
+                        let params = match item {
+                            Ok(result) => json!({"subscription": id, "result": result}),
+                            Err(error) => json!({"subscription": id, "error": error}),
+                        };
+                        let notification = json!({
+                            "jsonrpc": "2.0",
+                            "method": method,
+                            "params": params,
+                        });
+                        self.next_polled = id + 1;
+                        return Poll::Ready(notification);
+                    }
+                    Poll::Ready(None) => {
+                        self.streams.remove(&id);
+                    }
+                    Poll::Pending => {}
+                }
+            }
+            Poll::Pending
+        })
+        .await
+    }
+
+    /// Retrieve the wrapped handler, dropping the subscriptions.
+    pub fn into_inner(self) -> H {
+        self.handler
+    }
+}
+
+impl<H: HandleSubscribe> Handler for Subscriptions<H> {
+    fn handle(&mut self, method: &str, params: Params) -> Result<Value, Error> {
+        self.handle_with_meta(method, params, CallKind::MethodCall)
+    }
+
+    fn handle_with_meta(
+        &mut self,
+        method: &str,
+        params: Params,
+        kind: CallKind,
+    ) -> Result<Value, Error> {
+        self.handle_with_request_meta(method, params, &RequestMeta::new(kind))
+    }
+
+    fn handle_with_request_meta(
+        &mut self,
+        method: &str,
+        params: Params,
+        meta: &RequestMeta,
+    ) -> Result<Value, Error> {
+        if method == self.unsubscribe {
+            let args = params.get_rpc_args(&["subscription"])?;
+            let id: SubscriptionId = serde_json::from_value(args[0].clone())
+                .map_err(|err| Error::invalid_params(format!("subscription: {}", err)))?;
+            return Ok(Value::Bool(self.streams.remove(&id).is_some()));
+        }
+        if !self.handler.is_subscription(method) {
+            return self.handler.handle_with_request_meta(method, params, meta);
+        }
+        if meta.kind() == CallKind::Notification {
+            return Err(Error {
+                code: ErrorCode::InvalidRequest,
+                message: "Subscriptions can't be started by a notification".into(),
+                data: None,
+            });
+        }
+        let stream = self.handler.subscribe(method, params)?;
+        self.last_id += 1;
+        self.streams
+            .insert(self.last_id, (method.to_owned(), stream));
+        Ok(json!(self.last_id))
+    }
+
+    fn method_exists(&self, method: &str) -> bool {
+        method == self.unsubscribe
+            || self.handler.is_subscription(method)
+            || self.handler.method_exists(method)
+    }
+
+    fn accepts_bare_param(&self, method: &str) -> bool {
+        self.handler.accepts_bare_param(method)
+    }
+
+    fn strict_version(&self) -> bool {
+        self.handler.strict_version()
+    }
+}
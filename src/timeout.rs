@@ -0,0 +1,117 @@
+use crate::{CallKind, Error, ErrorCode, Handler, Params, RequestMeta, Value};
+use std::time::{Duration, SystemTime};
+
+/// The code of the error returned for calls whose deadline passed before
+/// [WithTimeout](struct.WithTimeout.html) dispatched them.
+pub const TIMED_OUT: i64 = -32006;
+
+/// Wraps a handler, giving each call the timeout of its method as a deadline. timeouts gives the
+/// limit for each method, typically the `my_api_method_timeout` function generated for methods
+/// marked `#[rpc(timeout_ms = ..)]`. Methods it gives None for have no limit.
+///
+/// Methods are called synchronously and can't be interrupted, so the limit is enforced only where
+/// a check is possible. The deadline, or the caller's own if that is earlier, is passed on in the
+/// call's [RequestMeta](struct.RequestMeta.html), for methods which can give up early to check.
+/// A call whose deadline has already passed when it is dispatched, e.g. after earlier calls in a
+/// batch ran long, isn't run and gets a [TIMED_OUT](constant.TIMED_OUT.html) error. A call which
+/// does run keeps its result, however long it took.
+///
+/// ```
+/// use easy_jsonrpc::{rpc, Handler, WithTimeout};
+/// use serde_json::json;
+/// use std::time::Duration;
+///
+/// #[rpc]
+/// pub trait Reports {
+///     #[rpc(timeout_ms = 5000)]
+///     fn report(&self) -> String;
+/// }
+///
+/// struct ReportsImpl;
+/// impl Reports for ReportsImpl {
+///     fn report(&self) -> String {
+///         "all good".into()
+///     }
+/// }
+///
+/// assert_eq!(reports_method_timeout("report"), Some(Duration::from_millis(5000)));
+///
+/// let mut handler = WithTimeout::new(&ReportsImpl as &dyn Reports, reports_method_timeout);
+/// let response = handler.handle_request(json!({"jsonrpc": "2.0", "method": "report", "id": 1}));
+/// assert_eq!(response.as_option().unwrap()["result"], json!("all good"));
+/// ```
+pub struct WithTimeout<H, F> {
+    handler: H,
+    timeouts: F,
+}
+
+impl<H, F> WithTimeout<H, F>
+where
+    H: Handler,
+    F: Fn(&str) -> Option<Duration>,
+{
+    /// Wrap handler, giving each call the deadline timeouts gives for its method.
+    pub fn new(handler: H, timeouts: F) -> Self {
+        WithTimeout { handler, timeouts }
+    }
+
+    /// Unwrap the inner handler.
+    pub fn into_inner(self) -> H {
+        self.handler
+    }
+}
+
+impl<H, F> Handler for WithTimeout<H, F>
+where
+    H: Handler,
+    F: Fn(&str) -> Option<Duration>,
+{
+    fn handle(&mut self, method: &str, params: Params) -> Result<Value, Error> {
+        self.handle_with_meta(method, params, CallKind::MethodCall)
+    }
+
+    fn handle_with_meta(
+        &mut self,
+        method: &str,
+        params: Params,
+        kind: CallKind,
+    ) -> Result<Value, Error> {
+        self.handle_with_request_meta(method, params, &RequestMeta::new(kind))
+    }
+
+    fn handle_with_request_meta(
+        &mut self,
+        method: &str,
+        params: Params,
+        meta: &RequestMeta,
+    ) -> Result<Value, Error> {
+        let now = SystemTime::now();
+        let limit = (self.timeouts)(method).and_then(|timeout| now.checked_add(timeout));
+        let deadline = match (meta.deadline(), limit) {
+            (Some(deadline), Some(limit)) => deadline.min(limit),
+            (Some(deadline), None) | (None, Some(deadline)) => deadline,
+            (None, None) => return self.handler.handle_with_request_meta(method, params, meta),
+        };
+        if deadline <= now {
+            return Err(Error {
+                code: ErrorCode::ServerError(TIMED_OUT),
+                message: "Timed out".into(),
+                data: None,
+            });
+        }
+        let meta = meta.clone().with_deadline(deadline);
+        self.handler.handle_with_request_meta(method, params, &meta)
+    }
+
+    fn method_exists(&self, method: &str) -> bool {
+        self.handler.method_exists(method)
+    }
+
+    fn accepts_bare_param(&self, method: &str) -> bool {
+        self.handler.accepts_bare_param(method)
+    }
+
+    fn strict_version(&self) -> bool {
+        self.handler.strict_version()
+    }
+}
@@ -0,0 +1,315 @@
+//! Serve jsonrpc over HTTP with [axum](https://docs.rs/axum). Requires the `axum` feature.
+//!
+//! ```
+//! use easy_jsonrpc::transport::axum::rpc_route;
+//! use easy_jsonrpc::{Error, Handler, Params, RequestLimits, Value};
+//! use std::sync::Arc;
+//!
+//! #[easy_jsonrpc::rpc]
+//! pub trait Calculator {
+//!     fn add(&self, a: u64, b: u64) -> u64 {
+//!         a + b
+//!     }
+//! }
+//!
+//! struct CalculatorImpl;
+//! impl Calculator for CalculatorImpl {}
+//!
+//! // &dyn Calculator isn't Send, so the route is given a handler owning the implementation
+//! #[derive(Clone)]
+//! struct Server(Arc<CalculatorImpl>);
+//!
+//! impl Handler for Server {
+//!     fn handle(&mut self, method: &str, params: Params) -> Result<Value, Error> {
+//!         (&*self.0 as &dyn Calculator).handle(method, params)
+//!     }
+//! }
+//!
+//! let handler = Server(Arc::new(CalculatorImpl));
+//! let app = axum::Router::new().nest("/rpc", rpc_route(handler, &RequestLimits::default()));
+//! ```
+
+use crate::ordered::response_string;
+use crate::{invalid_request, parse_error, Handler, MaybeReply, RequestLimits, StatusHint, Value};
+use axum::body::Bytes;
+use axum::http::{header, StatusCode};
+#[cfg(feature = "gzip")]
+use axum::http::{HeaderMap, HeaderValue};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::Router;
+
+/// A router answering jsonrpc requests POSTed to `/`, typically nested under a path of your
+/// choosing with [Router::nest](https://docs.rs/axum/latest/axum/struct.Router.html#method.nest).
+///
+/// Each request is handled by a clone of handler. Bodies exceeding limits are answered with an
+/// invalid request error without being parsed. The response's HTTP status is chosen by
+/// [StatusHint::for_reply](../../enum.StatusHint.html#method.for_reply), and requests consisting
+/// solely of notifications get an empty `204 No Content` response. Methods are called on the
+/// runtime's worker threads, so long running methods should hand their work off, e.g. with
+/// `tokio::task::spawn_blocking`.
+pub fn rpc_route<H>(handler: H, limits: &RequestLimits) -> Router
+where
+    H: Handler + Clone + Send + Sync + 'static,
+{
+    let limits = *limits;
+    Router::new().route(
+        "/",
+        post(move |body: Bytes| {
+            let mut handler = handler.clone();
+            async move { respond(&mut handler, &body, &limits, None) }
+        }),
+    )
+}
+
+/// Like [rpc_route](fn.rpc_route.html), compressing responses of at least min_bytes with gzip
+/// for clients sending `Accept-Encoding: gzip`. Smaller responses, which gain little, are sent
+/// as they are. Every response carries `Vary: Accept-Encoding`. Requires the `gzip` feature.
+#[cfg(feature = "gzip")]
+pub fn rpc_route_gzip<H>(handler: H, limits: &RequestLimits, min_bytes: usize) -> Router
+where
+    H: Handler + Clone + Send + Sync + 'static,
+{
+    let limits = *limits;
+    Router::new().route(
+        "/",
+        post(move |headers: HeaderMap, body: Bytes| {
+            let mut handler = handler.clone();
+            let gzip_min_bytes = accepts_gzip(&headers).then_some(min_bytes);
+            async move {
+                // whether or not this response is compressed, caches must key it by encoding
+                let mut response = respond(&mut handler, &body, &limits, gzip_min_bytes);
+                response
+                    .headers_mut()
+                    .insert(header::VARY, HeaderValue::from_static("accept-encoding"));
+                response
+            }
+        }),
+    )
+}
+
+// Whether the Accept-Encoding headers allow gzip, by name or as `*`, with a nonzero quality.
+#[cfg(feature = "gzip")]
+fn accepts_gzip(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|coding| {
+            let mut parts = coding.split(';').map(str::trim);
+            let name = parts.next().unwrap_or_default();
+            let quality = parts
+                .find_map(|param| param.strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.parse::<f32>().ok());
+            (name.eq_ignore_ascii_case("gzip") || name == "*") && quality.is_some_and(|q| q > 0.0)
+        })
+}
+
+// The response to body. Replies of at least gzip_min_bytes, if given, are compressed.
+#[cfg_attr(not(feature = "gzip"), allow(unused_variables))]
+fn respond<H: Handler>(
+    handler: &mut H,
+    body: &[u8],
+    limits: &RequestLimits,
+    gzip_min_bytes: Option<usize>,
+) -> Response {
+    let reply = if !limits.admits(body) {
+        MaybeReply::Reply(invalid_request())
+    } else {
+        match serde_json::from_slice::<Value>(body) {
+            Ok(request) => handler.handle_request(request),
+            Err(_) => MaybeReply::Reply(parse_error()),
+        }
+    };
+    let status = StatusCode::from_u16(StatusHint::for_reply(&reply).code())
+        .expect("StatusHint codes are valid");
+    match reply {
+        MaybeReply::Reply(response) => {
+            let response = response_string(&response);
+            #[cfg(feature = "gzip")]
+            if gzip_min_bytes.is_some_and(|min_bytes| response.len() >= min_bytes) {
+                return (
+                    status,
+                    [
+                        (header::CONTENT_TYPE, "application/json"),
+                        (header::CONTENT_ENCODING, "gzip"),
+                    ],
+                    gzip(response.as_bytes()),
+                )
+                    .into_response();
+            }
+            (
+                status,
+                [(header::CONTENT_TYPE, "application/json")],
+                response,
+            )
+                .into_response()
+        }
+        MaybeReply::DontReply => status.into_response(),
+    }
+}
+
+#[cfg(feature = "gzip")]
+fn gzip(bytes: &[u8]) -> Vec<u8> {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(bytes)
+        .expect("writing to a Vec doesn't fail");
+    encoder.finish().expect("writing to a Vec doesn't fail")
+}
+
+#[cfg(test)]
+mod test {
+    use super::rpc_route;
+    use crate::RequestLimits;
+    use serde_json::{json, Value};
+
+    #[derive(Clone)]
+    struct Echo;
+
+    impl crate::Handler for Echo {
+        fn handle(&mut self, method: &str, params: crate::Params) -> Result<Value, crate::Error> {
+            match method {
+                "echo" => Ok(params.get_positional::<Value>(0)?),
+                _ => Err(crate::Error::method_not_found()),
+            }
+        }
+    }
+
+    #[test]
+    fn serve() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}/rpc", listener.local_addr().unwrap());
+            let limits = RequestLimits::default().max_batch_size(1);
+            let app = axum::Router::new().nest("/rpc", rpc_route(Echo, &limits));
+            tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+            let client = reqwest::Client::new();
+            let post = |body: &'static str| client.post(&url).body(body).send();
+
+            let response = post(r#"{"jsonrpc": "2.0", "method": "echo", "params": [1], "id": 1}"#)
+                .await
+                .unwrap();
+            assert_eq!(response.status(), 200);
+            assert_eq!(response.headers()["content-type"], "application/json");
+            assert_eq!(
+                response.json::<Value>().await.unwrap(),
+                json!({"jsonrpc": "2.0", "result": 1, "id": 1})
+            );
+
+            let response = post(r#"{"jsonrpc": "2.0", "method": "missing", "id": 2}"#)
+                .await
+                .unwrap();
+            assert_eq!(response.status(), 404);
+            assert_eq!(
+                response.json::<Value>().await.unwrap()["error"]["code"],
+                json!(-32601)
+            );
+
+            let response = post("{").await.unwrap();
+            assert_eq!(response.status(), 400);
+
+            // batches over the limit are rejected whole
+            let response = post(
+                r#"[
+                    {"jsonrpc": "2.0", "method": "echo", "params": [1], "id": 1},
+                    {"jsonrpc": "2.0", "method": "echo", "params": [2], "id": 2}
+                ]"#,
+            )
+            .await
+            .unwrap();
+            assert_eq!(response.status(), 400);
+            assert_eq!(
+                response.json::<Value>().await.unwrap()["error"]["code"],
+                json!(-32600)
+            );
+
+            let response = post(r#"{"jsonrpc": "2.0", "method": "echo", "params": [1]}"#)
+                .await
+                .unwrap();
+            assert_eq!(response.status(), 204);
+            assert_eq!(response.text().await.unwrap(), "");
+        });
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn serve_gzip() {
+        use super::rpc_route_gzip;
+        use std::io::Read;
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}/rpc", listener.local_addr().unwrap());
+            let app = axum::Router::new().nest(
+                "/rpc",
+                rpc_route_gzip(Echo, &RequestLimits::default(), 1024),
+            );
+            tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+            let client = reqwest::Client::new();
+            let large = "x".repeat(4096);
+            let request = json!({"jsonrpc": "2.0", "method": "echo", "params": [large], "id": 1});
+            let post = |request: &Value, accept_encoding: &'static str| {
+                client
+                    .post(&url)
+                    .header("accept-encoding", accept_encoding)
+                    .body(request.to_string())
+                    .send()
+            };
+
+            let response = post(&request, "br, gzip;q=0.5").await.unwrap();
+            assert_eq!(response.status(), 200);
+            assert_eq!(response.headers()["content-encoding"], "gzip");
+            assert_eq!(response.headers()["vary"], "accept-encoding");
+            let compressed = response.bytes().await.unwrap();
+            assert!(compressed.len() < large.len());
+            let mut decompressed = String::new();
+            flate2::read::GzDecoder::new(&compressed[..])
+                .read_to_string(&mut decompressed)
+                .unwrap();
+            assert_eq!(
+                serde_json::from_str::<Value>(&decompressed).unwrap(),
+                json!({"jsonrpc": "2.0", "result": large, "id": 1})
+            );
+
+            // clients not accepting gzip get the response as it is
+            for accept_encoding in ["identity", "gzip;q=0"] {
+                let response = post(&request, accept_encoding).await.unwrap();
+                assert!(response.headers().get("content-encoding").is_none());
+                assert_eq!(response.headers()["vary"], "accept-encoding");
+                assert_eq!(
+                    response.json::<Value>().await.unwrap()["result"],
+                    json!(large)
+                );
+            }
+
+            // as are responses below the threshold
+            let small = json!({"jsonrpc": "2.0", "method": "echo", "params": [1], "id": 2});
+            let response = post(&small, "gzip").await.unwrap();
+            assert!(response.headers().get("content-encoding").is_none());
+            assert_eq!(response.headers()["vary"], "accept-encoding");
+            assert_eq!(response.json::<Value>().await.unwrap()["result"], json!(1));
+
+            // and notifications, answered with no body
+            let notification = json!({"jsonrpc": "2.0", "method": "echo", "params": [1]});
+            let response = post(&notification, "gzip").await.unwrap();
+            assert_eq!(response.status(), 204);
+            assert_eq!(response.headers()["vary"], "accept-encoding");
+        });
+    }
+}
@@ -0,0 +1,223 @@
+//! Serve jsonrpc over a byte stream, e.g. a Unix domain socket, with length prefixed framing.
+//! Requires the `framed` feature.
+//!
+//! Each frame is a 4 byte big-endian length followed by that many bytes of message. A request
+//! frame holds one jsonrpc request (single or batch), and its response, if any, is sent back as
+//! one frame. Unlike newline delimited messages, frames need no escaping, so any bytes may be
+//! sent.
+//!
+//! ```
+//! use easy_jsonrpc::transport::framed::{read_frame, serve_framed, write_frame};
+//! use easy_jsonrpc::{DynHandler, RequestLimits};
+//! use serde_json::json;
+//! use std::io::Cursor;
+//!
+//! let mut handler = DynHandler::new();
+//! handler.register("ping", |_| Ok(json!("pong")));
+//!
+//! let mut requests = Vec::new();
+//! write_frame(&mut requests, br#"{"jsonrpc": "2.0", "method": "ping", "id": 1}"#).unwrap();
+//! let mut responses = Vec::new();
+//! let limits = RequestLimits::default();
+//! serve_framed(&mut handler, Cursor::new(requests), &mut responses, &limits).unwrap();
+//!
+//! let response = read_frame(&mut Cursor::new(responses), 1024).unwrap().unwrap();
+//! assert_eq!(response, br#"{"jsonrpc":"2.0","result":"pong","id":1}"#);
+//! ```
+
+use crate::{Handler, RequestLimits};
+use std::io::{Error, ErrorKind, Read, Write};
+
+/// Handle the requests framed in reader until it ends, writing each response, framed, to writer.
+///
+/// Frames are interpreted as utf-8 encoded text and handled by
+/// [handle_request_bytes](../../trait.Handler.html#method.handle_request_bytes), so a frame which
+/// isn't valid utf-8 is answered with a parse error, and one exceeding limits with an invalid
+/// request error. Requests consisting solely of notifications get no response frame. Ending in
+/// the middle of a frame, or a frame longer than the size limit, is an error, since the stream
+/// can't be trusted past it.
+pub fn serve_framed<H, R, W>(
+    handler: &mut H,
+    mut reader: R,
+    mut writer: W,
+    limits: &RequestLimits,
+) -> Result<(), Error>
+where
+    H: Handler + ?Sized,
+    R: Read,
+    W: Write,
+{
+    while let Some(request) = read_frame(&mut reader, limits.size_limit())? {
+        if let Some(response) = handler.handle_request_bytes(&request, limits) {
+            write_frame(&mut writer, response.as_bytes())?;
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Read the next frame from reader, or None if reader ends before the frame starts. A frame whose
+/// length prefix exceeds max_len is an `InvalidData` error, raised before any of it is read.
+pub fn read_frame<R: Read + ?Sized>(
+    reader: &mut R,
+    max_len: usize,
+) -> Result<Option<Vec<u8>>, Error> {
+    let mut prefix = [0; 4];
+    let mut filled = 0;
+    while filled < prefix.len() {
+        match reader.read(&mut prefix[filled..]) {
+            Ok(0) if filled == 0 => return Ok(None),
+            Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    let len = u32::from_be_bytes(prefix) as usize;
+    if len > max_len {
+        return Err(Error::new(ErrorKind::InvalidData, "frame too long"));
+    }
+    // the buffer grows as bytes arrive, rather than trusting the length up front
+    let mut message = Vec::new();
+    reader.take(len as u64).read_to_end(&mut message)?;
+    if message.len() < len {
+        return Err(ErrorKind::UnexpectedEof.into());
+    }
+    Ok(Some(message))
+}
+
+/// Write message to writer as one frame. Messages longer than `u32::MAX` bytes can't be framed.
+pub fn write_frame<W: Write + ?Sized>(writer: &mut W, message: &[u8]) -> Result<(), Error> {
+    let len = u32::try_from(message.len())
+        .map_err(|_| Error::new(ErrorKind::InvalidInput, "message too long for a frame"))?;
+    writer.write_all(&len.to_be_bytes())?;
+    writer.write_all(message)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{read_frame, serve_framed, write_frame};
+    use crate::{DynHandler, RequestLimits};
+    use serde_json::{json, Value};
+    use std::io::{Cursor, ErrorKind};
+
+    fn handler() -> DynHandler {
+        let mut handler = DynHandler::new();
+        handler.register("echo", |params| Ok(params.get_positional::<Value>(0)?));
+        handler
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn serve_unix_socket() {
+        use std::net::Shutdown;
+        use std::os::unix::net::UnixStream;
+
+        let (client, server) = UnixStream::pair().unwrap();
+        let served = std::thread::spawn(move || {
+            serve_framed(&mut handler(), &server, &server, &RequestLimits::default())
+        });
+
+        let mut client = &client;
+        let request = |method: &str, params: Value, id: Option<u64>| {
+            let mut request = json!({"jsonrpc": "2.0", "method": method, "params": params});
+            if let Some(id) = id {
+                request["id"] = json!(id);
+            }
+            request.to_string()
+        };
+        write_frame(
+            &mut client,
+            request("echo", json!(["a\nb"]), Some(1)).as_bytes(),
+        )
+        .unwrap();
+        write_frame(&mut client, request("echo", json!([2]), None).as_bytes()).unwrap();
+        write_frame(&mut client, request("echo", json!([3]), Some(3)).as_bytes()).unwrap();
+        write_frame(&mut client, b"{").unwrap();
+        // the method name isn't valid utf-8, so the request can't be parsed
+        write_frame(
+            &mut client,
+            b"{\"jsonrpc\": \"2.0\", \"method\": \"echo\xff\", \"params\": [4], \"id\": 4}",
+        )
+        .unwrap();
+        client.shutdown(Shutdown::Write).unwrap();
+
+        let mut responses = Vec::new();
+        while let Some(frame) = read_frame(&mut client, 1024).unwrap() {
+            responses.push(serde_json::from_slice::<Value>(&frame).unwrap());
+        }
+        served.join().unwrap().unwrap();
+        // the notification gets no response
+        assert_eq!(
+            responses,
+            [
+                json!({"jsonrpc": "2.0", "result": "a\nb", "id": 1}),
+                json!({"jsonrpc": "2.0", "result": 3, "id": 3}),
+                json!({"jsonrpc": "2.0", "error": {"code": -32700, "message": "Parse error"}, "id": null}),
+                json!({"jsonrpc": "2.0", "error": {"code": -32700, "message": "Parse error"}, "id": null}),
+            ]
+        );
+    }
+
+    #[test]
+    fn truncated() {
+        let mut frames = Vec::new();
+        write_frame(
+            &mut frames,
+            br#"{"jsonrpc": "2.0", "method": "echo", "params": [1], "id": 1}"#,
+        )
+        .unwrap();
+        for end in [2, frames.len() - 1] {
+            let mut responses = Vec::new();
+            let err = serve_framed(
+                &mut handler(),
+                Cursor::new(&frames[..end]),
+                &mut responses,
+                &RequestLimits::default(),
+            )
+            .unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+            assert!(responses.is_empty());
+        }
+        assert_eq!(read_frame(&mut Cursor::new(&[][..]), 1024).unwrap(), None);
+    }
+
+    #[test]
+    fn limits() {
+        let request = br#"{"jsonrpc": "2.0", "method": "echo", "params": [[1, [2]]], "id": 1}"#;
+        let mut frames = Vec::new();
+        write_frame(&mut frames, request).unwrap();
+
+        // the payload isn't read, or even sent, when the length prefix is over the limit
+        let err = read_frame(&mut Cursor::new(&frames[..4]), request.len() - 1).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        let mut responses = Vec::new();
+        let limits = RequestLimits::default().max_size(request.len() - 1);
+        let err = serve_framed(
+            &mut handler(),
+            Cursor::new(&frames),
+            &mut responses,
+            &limits,
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(responses.is_empty());
+
+        // frames within the size limit are checked against the other limits
+        let limits = RequestLimits::default().max_depth(2);
+        serve_framed(
+            &mut handler(),
+            Cursor::new(&frames),
+            &mut responses,
+            &limits,
+        )
+        .unwrap();
+        let response = read_frame(&mut Cursor::new(responses), 1024)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&response).unwrap()["error"]["code"],
+            json!(-32600)
+        );
+    }
+}
@@ -0,0 +1,9 @@
+//! Ready made glue for serving a [Handler](../trait.Handler.html) over common transports. Each
+//! transport is behind a feature flag so the base crate carries no networking dependencies.
+
+#[cfg(feature = "axum")]
+pub mod axum;
+#[cfg(feature = "framed")]
+pub mod framed;
+#[cfg(feature = "ws")]
+pub mod ws;
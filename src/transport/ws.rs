@@ -0,0 +1,422 @@
+//! Serve jsonrpc over a WebSocket connection. Requires the `ws` feature.
+//!
+//! Each text frame is treated as one jsonrpc request (single or batch), and the response, if
+//! any, is sent back as a single text frame.
+
+use crate::{with_sink, HandleWithSink, Handler, RequestLimits};
+#[cfg(feature = "subscriptions")]
+use crate::{HandleSubscribe, Subscriptions};
+#[cfg(feature = "subscriptions")]
+use futures_util::future::{select, Either};
+use futures_util::{SinkExt, StreamExt};
+use std::ops::ControlFlow;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::{Error, Message};
+use tokio_tungstenite::WebSocketStream;
+
+/// Handle requests arriving on stream until the peer closes the connection.
+///
+/// Frames are handled by
+/// [handle_request_bytes](../../trait.Handler.html#method.handle_request_bytes), so binary frames
+/// are answered with a parse error unless they are valid utf-8, and frames exceeding limits with
+/// an invalid request error. Requests consisting solely of notifications get no response frame.
+pub async fn serve_ws<H, S>(
+    handler: &mut H,
+    mut stream: WebSocketStream<S>,
+    limits: &RequestLimits,
+) -> Result<(), Error>
+where
+    H: Handler + ?Sized,
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    while let Some(message) = stream.next().await {
+        if answer(handler, &mut stream, message?, limits)
+            .await?
+            .is_break()
+        {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Like [serve_ws](fn.serve_ws.html), also sending the progress reported by handler's methods,
+/// see [with_sink](../../fn.with_sink.html). Each progress notification is sent as a text frame as
+/// soon as it is reported, ahead of the response to its request.
+///
+/// So that notifications can be sent while a method runs, methods are called with
+/// `tokio::task::spawn_blocking`, which handler moves to for each request. A panic in a method is
+/// resumed here.
+pub async fn serve_ws_progress<H, S>(
+    mut handler: H,
+    mut stream: WebSocketStream<S>,
+    limits: &RequestLimits,
+) -> Result<(), Error>
+where
+    H: HandleWithSink + Send + 'static,
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    while let Some(message) = stream.next().await {
+        let request = match request_bytes(message?) {
+            ControlFlow::Break(()) => break,
+            ControlFlow::Continue(None) => continue,
+            ControlFlow::Continue(Some(request)) => request,
+        };
+        let (sender, mut notifications) = mpsc::unbounded_channel();
+        let limits = *limits;
+        let call = tokio::task::spawn_blocking(move || {
+            let response = with_sink(&mut handler, &mut |notification| {
+                // fails only once the connection has, and the response will be lost with it
+                let _ = sender.send(notification);
+            })
+            .handle_request_bytes(&request, &limits);
+            (handler, response)
+        });
+        // ends once the call returns, dropping the sender
+        while let Some(notification) = notifications.recv().await {
+            stream.send(Message::Text(notification.to_string())).await?;
+        }
+        let (returned, response) = call
+            .await
+            .unwrap_or_else(|err| std::panic::resume_unwind(err.into_panic()));
+        handler = returned;
+        if let Some(response) = response {
+            stream.send(Message::Text(response)).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Like [serve_ws](fn.serve_ws.html), also sending the notifications of handler's subscriptions,
+/// each as a text frame, as soon as they are ready. Requires the `subscriptions` feature too.
+#[cfg(feature = "subscriptions")]
+pub async fn serve_ws_subscriptions<H, S>(
+    handler: &mut Subscriptions<H>,
+    mut stream: WebSocketStream<S>,
+    limits: &RequestLimits,
+) -> Result<(), Error>
+where
+    H: HandleSubscribe,
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    loop {
+        let next = {
+            let notification = Box::pin(handler.next_notification());
+            match select(stream.next(), notification).await {
+                Either::Left((message, _)) => Either::Left(message),
+                Either::Right((notification, _)) => Either::Right(notification),
+            }
+        };
+        match next {
+            Either::Left(None) => break,
+            Either::Left(Some(message)) => {
+                if answer(handler, &mut stream, message?, limits)
+                    .await?
+                    .is_break()
+                {
+                    break;
+                }
+            }
+            Either::Right(notification) => {
+                stream.send(Message::Text(notification.to_string())).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Send the response to the request carried by message, if any. Breaks once the peer closes the
+// connection.
+async fn answer<H, S>(
+    handler: &mut H,
+    stream: &mut WebSocketStream<S>,
+    message: Message,
+    limits: &RequestLimits,
+) -> Result<ControlFlow<()>, Error>
+where
+    H: Handler + ?Sized,
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let response = match request_bytes(message) {
+        ControlFlow::Break(()) => return Ok(ControlFlow::Break(())),
+        ControlFlow::Continue(None) => return Ok(ControlFlow::Continue(())),
+        ControlFlow::Continue(Some(request)) => handler.handle_request_bytes(&request, limits),
+    };
+    if let Some(response) = response {
+        stream.send(Message::Text(response)).await?;
+    }
+    Ok(ControlFlow::Continue(()))
+}
+
+// The request carried by message, if any. Breaks once the peer closes the connection.
+fn request_bytes(message: Message) -> ControlFlow<(), Option<Vec<u8>>> {
+    ControlFlow::Continue(Some(match message {
+        Message::Text(text) => text.into_bytes(),
+        Message::Binary(bytes) => bytes,
+        Message::Close(_) => return ControlFlow::Break(()),
+        Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => {
+            return ControlFlow::Continue(None)
+        }
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::serve_ws;
+    use crate::{DynHandler, RequestLimits};
+    use futures_util::{SinkExt, StreamExt};
+    use serde_json::{json, Value};
+    use tokio::io::DuplexStream;
+    use tokio_tungstenite::tungstenite::protocol::Role;
+    use tokio_tungstenite::tungstenite::Message;
+    use tokio_tungstenite::WebSocketStream;
+
+    async fn send(stream: &mut WebSocketStream<DuplexStream>, frame: &str) {
+        stream.send(Message::Text(frame.into())).await.unwrap();
+    }
+
+    async fn recv(stream: &mut WebSocketStream<DuplexStream>) -> Value {
+        let frame = stream.next().await.unwrap().unwrap();
+        serde_json::from_str(frame.to_text().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn serve() {
+        let mut handler = DynHandler::new();
+        handler.register("echo", |params| Ok(params.get_positional::<Value>(0)?));
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async move {
+            let (client, server) = tokio::io::duplex(1024);
+            let server = async move {
+                let stream = WebSocketStream::from_raw_socket(server, Role::Server, None).await;
+                let limits = RequestLimits::default().max_batch_size(2);
+                serve_ws(&mut handler, stream, &limits).await.unwrap();
+            };
+            let client = async move {
+                let mut stream = WebSocketStream::from_raw_socket(client, Role::Client, None).await;
+
+                // a notification gets no response, so the next frame answers the call after it
+                send(
+                    &mut stream,
+                    r#"{"jsonrpc": "2.0", "method": "echo", "params": [0]}"#,
+                )
+                .await;
+                send(
+                    &mut stream,
+                    r#"{"jsonrpc": "2.0", "method": "echo", "params": [1], "id": 1}"#,
+                )
+                .await;
+                assert_eq!(
+                    recv(&mut stream).await,
+                    json!({"jsonrpc": "2.0", "result": 1, "id": 1})
+                );
+
+                // batches are answered in a single frame
+                send(
+                    &mut stream,
+                    r#"[
+                        {"jsonrpc": "2.0", "method": "echo", "params": [2], "id": 2},
+                        {"jsonrpc": "2.0", "method": "echo", "params": [3], "id": 3}
+                    ]"#,
+                )
+                .await;
+                assert_eq!(
+                    recv(&mut stream).await,
+                    json!([
+                        {"jsonrpc": "2.0", "result": 2, "id": 2},
+                        {"jsonrpc": "2.0", "result": 3, "id": 3},
+                    ])
+                );
+
+                // batches over the limit are rejected whole
+                send(
+                    &mut stream,
+                    r#"[
+                        {"jsonrpc": "2.0", "method": "echo", "params": [2], "id": 2},
+                        {"jsonrpc": "2.0", "method": "echo", "params": [3], "id": 3},
+                        {"jsonrpc": "2.0", "method": "echo", "params": [4], "id": 4}
+                    ]"#,
+                )
+                .await;
+                assert_eq!(
+                    recv(&mut stream).await,
+                    json!({
+                        "jsonrpc": "2.0",
+                        "error": {"code": -32600, "message": "Invalid request"},
+                        "id": null
+                    })
+                );
+
+                // malformed frames get a parse error and the connection stays open
+                send(&mut stream, "{").await;
+                assert_eq!(recv(&mut stream).await["error"]["code"], json!(-32700));
+
+                // so do binary frames which aren't valid utf-8
+                let request = b"{\"jsonrpc\": \"2.0\", \"method\": \"echo\xff\", \"id\": 4}";
+                stream
+                    .send(Message::Binary(request.to_vec()))
+                    .await
+                    .unwrap();
+                assert_eq!(recv(&mut stream).await["error"]["code"], json!(-32700));
+
+                stream.close(None).await.unwrap();
+            };
+            tokio::join!(server, client);
+        });
+    }
+
+    #[cfg(feature = "subscriptions")]
+    #[test]
+    fn serve_subscriptions() {
+        use super::serve_ws_subscriptions;
+        use crate::{Subscription, Subscriptions};
+
+        // generated code refers to easy_jsonrpc
+        mod easy_jsonrpc {
+            pub use crate::*;
+        }
+
+        #[easy_jsonrpc::rpc]
+        trait Ticker {
+            fn ticks(&self, count: u32) -> Subscription<u32>;
+        }
+
+        struct TickerImpl;
+        impl Ticker for TickerImpl {
+            fn ticks(&self, count: u32) -> Subscription<u32> {
+                Subscription::new(futures_util::stream::iter(0..count))
+            }
+        }
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async move {
+            let (client, server) = tokio::io::duplex(1024);
+            let server = async move {
+                let mut handler = Subscriptions::new(&TickerImpl as &dyn Ticker);
+                let stream = WebSocketStream::from_raw_socket(server, Role::Server, None).await;
+                serve_ws_subscriptions(&mut handler, stream, &RequestLimits::default())
+                    .await
+                    .unwrap();
+            };
+            let client = async move {
+                let mut stream = WebSocketStream::from_raw_socket(client, Role::Client, None).await;
+                send(
+                    &mut stream,
+                    r#"{"jsonrpc": "2.0", "method": "ticks", "params": [2], "id": 1}"#,
+                )
+                .await;
+                assert_eq!(
+                    recv(&mut stream).await,
+                    json!({"jsonrpc": "2.0", "result": 1, "id": 1})
+                );
+                for tick in 0..2 {
+                    assert_eq!(
+                        recv(&mut stream).await,
+                        json!({
+                            "jsonrpc": "2.0",
+                            "method": "ticks",
+                            "params": {"subscription": 1, "result": tick}
+                        })
+                    );
+                }
+                stream.close(None).await.unwrap();
+            };
+            tokio::join!(server, client);
+        });
+    }
+
+    #[test]
+    fn serve_progress() {
+        use super::serve_ws_progress;
+        use crate::{Error, HandleWithSink, Handler, Params};
+        use std::sync::mpsc::{channel, Receiver};
+        use std::sync::Mutex;
+
+        // generated code refers to easy_jsonrpc
+        mod easy_jsonrpc {
+            pub use crate::*;
+        }
+
+        #[easy_jsonrpc::rpc]
+        trait Counter {
+            fn count(&self, #[rpc_progress] progress: &dyn Fn(Value), to: u32) -> u32;
+        }
+
+        // reports each number, then waits for the client to see it before going on
+        struct CounterImpl(Mutex<Receiver<()>>);
+        impl Counter for CounterImpl {
+            fn count(&self, progress: &dyn Fn(Value), to: u32) -> u32 {
+                for n in 0..to {
+                    progress(json!(n));
+                    self.0.lock().unwrap().recv().unwrap();
+                }
+                to
+            }
+        }
+
+        // &dyn Counter isn't Send, so the handler owns the implementation
+        struct Server(CounterImpl);
+        impl Handler for Server {
+            fn handle(&mut self, method: &str, params: Params) -> Result<Value, Error> {
+                (&self.0 as &dyn Counter).handle(method, params)
+            }
+        }
+        impl HandleWithSink for Server {
+            fn handle_with_sink(
+                &mut self,
+                method: &str,
+                params: Params,
+                sink: &mut dyn FnMut(Value),
+            ) -> Result<Value, Error> {
+                (&self.0 as &dyn Counter).handle_with_sink(method, params, sink)
+            }
+        }
+
+        let (seen, wait) = channel();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async move {
+            let (client, server) = tokio::io::duplex(1024);
+            let server = async move {
+                let stream = WebSocketStream::from_raw_socket(server, Role::Server, None).await;
+                let handler = Server(CounterImpl(Mutex::new(wait)));
+                serve_ws_progress(handler, stream, &RequestLimits::default())
+                    .await
+                    .unwrap();
+            };
+            let client = async move {
+                let mut stream = WebSocketStream::from_raw_socket(client, Role::Client, None).await;
+                send(
+                    &mut stream,
+                    r#"{"jsonrpc": "2.0", "method": "count", "params": [2], "id": 1}"#,
+                )
+                .await;
+                // the method is still running, waiting for this notification to be seen
+                for n in 0..2 {
+                    assert_eq!(
+                        recv(&mut stream).await,
+                        json!({
+                            "jsonrpc": "2.0",
+                            "method": "rpc.progress",
+                            "params": {"id": 1, "value": n}
+                        })
+                    );
+                    seen.send(()).unwrap();
+                }
+                assert_eq!(
+                    recv(&mut stream).await,
+                    json!({"jsonrpc": "2.0", "result": 2, "id": 1})
+                );
+                stream.close(None).await.unwrap();
+            };
+            tokio::join!(server, client);
+        });
+    }
+}
@@ -1,9 +1,600 @@
-use crate::serde_json;
+use crate::{serde_json, InvalidArgs};
+use serde::de::Error;
 
+// used as a bound on the return type of generated client functions
 #[doc(hidden)]
-pub fn from_serde_json_value_ref<'de, T>(value: &'de serde_json::Value) -> Result<T, serde_json::Error>
+pub use serde::de::Deserialize;
+use serde_json::value::RawValue;
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::io;
+use std::str::FromStr;
+
+// Hash of a method name, which the generated handlers of traits with many methods dispatch on.
+// 64 bit FNV-1a, matching the macro's own copy.
+#[doc(hidden)]
+pub fn method_hash(method: &str) -> u64 {
+    method.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+// Called from generated code with the span of an argument's type, so a type which can't be
+// deserialized is reported at the argument.
+#[doc(hidden)]
+pub fn assert_deserialize<'de, T: Deserialize<'de>>() {}
+
+#[doc(hidden)]
+pub fn from_serde_json_value_ref<'de, T>(
+    value: &'de serde_json::Value,
+) -> Result<T, serde_json::Error>
 where
     T: serde::de::Deserialize<'de>,
 {
-    T::deserialize(value)
+    deserialize(value, |e| explain(e, value))
+}
+
+// Like from_serde_json_value_ref, moving the strings, arrays and objects held by value into the
+// result. Only what explain reports is kept: scalars, with strings cut to just past what's shown.
+#[doc(hidden)]
+pub fn from_serde_json_value<'de, T>(value: serde_json::Value) -> Result<T, serde_json::Error>
+where
+    T: serde::de::Deserialize<'de>,
+{
+    use serde_json::Value;
+
+    let kept = match &value {
+        Value::Array(_) => Value::Array(vec![]),
+        Value::Object(_) => Value::Object(Default::default()),
+        Value::String(s) => Value::String(s.chars().take(SHOWN_CHARS + 1).collect()),
+        scalar => scalar.clone(),
+    };
+    deserialize(value, |e| explain(e, &kept))
+}
+
+// With the serde_path feature, errors inside an argument are prefixed with the path to the value
+// that failed, e.g. "settings.retries: invalid type: string \"3\", expected u32". Only errors in
+// the argument itself are passed to explain, as the path already locates the others.
+#[cfg(feature = "serde_path")]
+fn deserialize<'de, D, T>(
+    deserializer: D,
+    explain: impl FnOnce(serde_json::Error) -> serde_json::Error,
+) -> Result<T, serde_json::Error>
+where
+    D: serde::de::Deserializer<'de, Error = serde_json::Error>,
+    T: Deserialize<'de>,
+{
+    serde_path_to_error::deserialize(deserializer).map_err(|e| {
+        let path = e.path().to_string();
+        let e = e.into_inner();
+        match path.as_str() {
+            "." => explain(e),
+            _ => serde_json::Error::custom(format!("{}: {}", path, without_location(&e))),
+        }
+    })
+}
+
+#[cfg(not(feature = "serde_path"))]
+fn deserialize<'de, D, T>(
+    deserializer: D,
+    explain: impl FnOnce(serde_json::Error) -> serde_json::Error,
+) -> Result<T, serde_json::Error>
+where
+    D: serde::de::Deserializer<'de, Error = serde_json::Error>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map_err(explain)
+}
+
+// The most characters of a string argument shown by explain
+const SHOWN_CHARS: usize = 32;
+
+// Some of serde's errors don't say what was received, e.g. for an untagged enum matching none of
+// its variants, so the argument is described after the error unless the error already describes
+// it, e.g. "data did not match any variant of untagged enum Id (the argument was boolean `true`)".
+// Arrays and objects are described by type alone, and long strings are cut short.
+fn explain(e: serde_json::Error, value: &serde_json::Value) -> serde_json::Error {
+    use serde::de::Unexpected;
+    use serde_json::Value;
+
+    let unexpected = match value {
+        Value::Null => Unexpected::Other("null"),
+        Value::Bool(b) => Unexpected::Bool(*b),
+        Value::Number(n) => match (n.as_u64(), n.as_i64(), n.as_f64()) {
+            (Some(n), _, _) => Unexpected::Unsigned(n),
+            (None, Some(n), _) => Unexpected::Signed(n),
+            (None, None, Some(n)) => Unexpected::Float(n),
+            (None, None, None) => Unexpected::Other("number"),
+        },
+        Value::String(s) => {
+            let shown = match s.char_indices().nth(SHOWN_CHARS) {
+                Some((end, _)) => &s[..end],
+                None => s,
+            };
+            Unexpected::Str(shown)
+        }
+        Value::Array(_) => Unexpected::Seq,
+        Value::Object(_) => Unexpected::Map,
+    };
+    let mut received = unexpected.to_string();
+    let message = without_location(&e);
+    if matches!(value, Value::String(s) if s.chars().nth(SHOWN_CHARS).is_some()) {
+        // an error quoting the whole string starts out the same as the part shown
+        received.pop();
+        if message.contains(&received) {
+            return e;
+        }
+        received.push_str("…\"");
+    } else if message.contains(&received) {
+        return e;
+    }
+    serde_json::Error::custom(format!("{} (the argument was {})", message, received))
+}
+
+// Deserialize an integer argument, explaining why a number was rejected. serde_json parses
+// integers too large for u64 or i64 as floats, so an integral float is reported as out of range
+// only when it really is outside the range of every integer type it could have been. expected is
+// the type as written in the method, reported in errors.
+#[doc(hidden)]
+pub fn from_integer_value<'de, T>(
+    value: &'de serde_json::Value,
+    name: &'static str,
+    index: usize,
+    expected: &'static str,
+) -> Result<T, InvalidArgs>
+where
+    T: Deserialize<'de>,
+{
+    T::deserialize(value).map_err(|e| match value.as_f64() {
+        Some(_) if value.is_i64() || value.is_u64() => InvalidArgs::IntegerOutOfRange {
+            name,
+            index,
+            ty: expected,
+        },
+        Some(f) if f.fract() == 0.0 && !(i64::MIN as f64..u64::MAX as f64).contains(&f) => {
+            InvalidArgs::IntegerOutOfRange {
+                name,
+                index,
+                ty: expected,
+            }
+        }
+        Some(_) => InvalidArgs::NotAnInteger { name, index },
+        None => InvalidArgs::InvalidArgValue {
+            name,
+            index,
+            expected,
+            error: e.to_string(),
+        },
+    })
+}
+
+// The argument at index, missing from the args split off the params. get_rpc_args checks the
+// number of args, so this is not expected, but is reported as invalid params rather than panicking.
+#[doc(hidden)]
+pub fn missing_arg(index: usize) -> crate::Error {
+    InvalidArgs::MissingPositionalParameter { index }.into()
+}
+
+// The error sent for Err(err) by a method marked #[rpc(error_code)].
+#[doc(hidden)]
+pub fn coded_error(code: i64, err: &dyn std::fmt::Display) -> crate::Error {
+    crate::Error {
+        code: crate::ErrorCode::from(code),
+        message: err.to_string(),
+        data: None,
+    }
+}
+
+// An argument which failed to deserialize. serde_json locates errors in the text it was reading,
+// which for an argument read from a RawValue is only the argument itself. The location is dropped
+// so both paths give the same error.
+#[doc(hidden)]
+pub fn invalid_arg_value(
+    name: &'static str,
+    index: usize,
+    expected: &'static str,
+    e: serde_json::Error,
+) -> crate::Error {
+    InvalidArgs::InvalidArgValue {
+        name,
+        index,
+        expected,
+        error: without_location(&e),
+    }
+    .into()
+}
+
+// e's message, without the location serde_json appends to errors found while reading text
+fn without_location(e: &serde_json::Error) -> String {
+    let mut error = e.to_string();
+    if e.line() != 0 {
+        let location = format!(" at line {} column {}", e.line(), e.column());
+        if error.ends_with(&location) {
+            error.truncate(error.len() - location.len());
+        }
+    }
+    error
+}
+
+// Reject any of args which serializes to more than max bytes, for methods marked
+// #[rpc(max_param_bytes = max)]. Measuring stops as soon as the limit is passed, so an oversized
+// param costs no more than max bytes of serialization.
+#[doc(hidden)]
+pub fn check_param_bytes(
+    args: &[serde_json::Value],
+    names: &[&'static str],
+    max: usize,
+) -> Result<(), InvalidArgs> {
+    match args.iter().position(|arg| exceeds_bytes(arg, max)) {
+        Some(index) => Err(InvalidArgs::ParamTooLarge {
+            name: names[index],
+            index,
+            max,
+        }),
+        None => Ok(()),
+    }
+}
+
+// Like check_param_bytes, for #[rpc(params_struct)] methods, whose one argument is the params as
+// a whole.
+#[doc(hidden)]
+pub fn check_params_bytes(
+    params: &crate::Params,
+    name: &'static str,
+    max: usize,
+) -> Result<(), InvalidArgs> {
+    let exceeds = match params {
+        crate::Params::Positional(ar) => ar.iter().any(|arg| exceeds_bytes(arg, max)),
+        crate::Params::Absent => false,
+        crate::Params::Named(map) => {
+            serde_json::to_writer(Limited { remaining: max }, map).is_err()
+        }
+    };
+    if exceeds {
+        Err(InvalidArgs::ParamTooLarge {
+            name,
+            index: 0,
+            max,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+fn exceeds_bytes(value: &serde_json::Value, max: usize) -> bool {
+    // serializing a Value only fails when the writer does
+    serde_json::to_writer(Limited { remaining: max }, value).is_err()
+}
+
+// a writer which fails once more than remaining bytes are written to it
+struct Limited {
+    remaining: usize,
+}
+
+impl io::Write for Limited {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.remaining = self
+            .remaining
+            .checked_sub(buf.len())
+            .ok_or_else(|| io::Error::from(io::ErrorKind::WriteZero))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[doc(hidden)]
+pub fn from_raw_value<T>(raw: &RawValue) -> Result<T, serde_json::Error>
+where
+    T: serde::de::DeserializeOwned,
+{
+    from_raw_with(
+        raw,
+        |raw| deserialize(raw, |e| e),
+        |value| from_serde_json_value_ref(value),
+    )
+}
+
+// Deserialize an argument from raw json with from_raw. serde words some errors differently when
+// reading text, e.g. for numbers with the arbitrary_precision feature, so a rejected argument is
+// parsed into a Value and deserialized again with from_value, to give the error the argument gets
+// when params are passed as Values.
+#[doc(hidden)]
+pub fn from_raw_with<'de, T>(
+    raw: &'de RawValue,
+    from_raw: impl FnOnce(&'de RawValue) -> Result<T, serde_json::Error>,
+    from_value: impl FnOnce(&serde_json::Value) -> Result<T, serde_json::Error>,
+) -> Result<T, serde_json::Error> {
+    from_raw(raw).map_err(|e| match serde_json::Value::deserialize(raw) {
+        Ok(value) => from_value(&value).err().unwrap_or(e),
+        Err(_) => e,
+    })
+}
+
+// Like from_integer_value, parsing raw into a Value only once it has been rejected.
+#[doc(hidden)]
+pub fn from_raw_integer<T>(
+    raw: &RawValue,
+    name: &'static str,
+    index: usize,
+    expected: &'static str,
+) -> Result<T, InvalidArgs>
+where
+    T: serde::de::DeserializeOwned,
+{
+    T::deserialize(raw).or_else(|_| {
+        let value = serde_json::Value::deserialize(raw)
+            .map_err(|_| InvalidArgs::InvalidArgStructure { name, index })?;
+        from_integer_value(&value, name, index, expected)
+    })
+}
+
+// Deserialize an i128 or u128 argument, sent either as a decimal string or as a number.
+#[doc(hidden)]
+pub fn from_int128_value<'de, T>(
+    value: &'de serde_json::Value,
+    name: &'static str,
+    index: usize,
+    expected: &'static str,
+) -> Result<T, InvalidArgs>
+where
+    T: Deserialize<'de> + FromStr,
+    T::Err: Display,
+{
+    match value {
+        serde_json::Value::String(num) => num.parse().map_err(|e| InvalidArgs::InvalidArgValue {
+            name,
+            index,
+            expected,
+            error: format!("invalid number {:?}: {}", num, e),
+        }),
+        _ => from_integer_value(value, name, index, expected),
+    }
+}
+
+#[doc(hidden)]
+pub fn from_raw_int128<T>(
+    raw: &RawValue,
+    name: &'static str,
+    index: usize,
+    expected: &'static str,
+) -> Result<T, InvalidArgs>
+where
+    T: serde::de::DeserializeOwned + FromStr,
+    T::Err: Display,
+{
+    let value = serde_json::Value::deserialize(raw)
+        .map_err(|_| InvalidArgs::InvalidArgStructure { name, index })?;
+    from_int128_value(&value, name, index, expected)
+}
+
+// Read a primitive argument directly from a Value, without going through serde. Returns None
+// exactly when deserializing through serde would fail.
+#[doc(hidden)]
+pub trait FromValueFast: Sized {
+    fn from_value_fast(value: &serde_json::Value) -> Option<Self>;
+}
+
+macro_rules! impl_from_value_fast_integer {
+    ($($int:ty)*) => {$(
+        impl FromValueFast for $int {
+            fn from_value_fast(value: &serde_json::Value) -> Option<Self> {
+                match value.as_u64() {
+                    Some(n) => <$int>::try_from(n).ok(),
+                    None => value.as_i64().and_then(|n| <$int>::try_from(n).ok()),
+                }
+            }
+        }
+    )*};
+}
+
+impl_from_value_fast_integer!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize);
+
+impl FromValueFast for bool {
+    fn from_value_fast(value: &serde_json::Value) -> Option<Self> {
+        value.as_bool()
+    }
+}
+
+impl FromValueFast for f64 {
+    fn from_value_fast(value: &serde_json::Value) -> Option<Self> {
+        value.as_f64()
+    }
+}
+
+impl FromValueFast for f32 {
+    fn from_value_fast(value: &serde_json::Value) -> Option<Self> {
+        value.as_f64().map(|n| n as f32)
+    }
+}
+
+// The escapes in a json string were already resolved when the Value was parsed, so a string
+// argument can always be borrowed.
+#[doc(hidden)]
+pub fn borrow_cow_str(value: &serde_json::Value) -> Result<Cow<'_, str>, serde_json::Error> {
+    match value {
+        serde_json::Value::String(s) => Ok(Cow::Borrowed(s)),
+        _ => Err(serde_json::Error::custom("expected a string")),
+    }
+}
+
+// Bytes are sent as an array of numbers, the way Cow<[u8]> serializes, so they must be collected.
+// A string isn't taken as its utf-8 encoding, to match the client side.
+#[doc(hidden)]
+pub fn borrow_cow_bytes(value: &serde_json::Value) -> Result<Cow<'_, [u8]>, serde_json::Error> {
+    match value {
+        serde_json::Value::Array(_) => Vec::<u8>::deserialize(value).map(Cow::Owned),
+        _ => Err(serde_json::Error::custom("expected an array of bytes")),
+    }
+}
+
+// called before a method marked #[rpc(deprecated = "...")] is dispatched
+#[doc(hidden)]
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub fn warn_deprecated(method: &str, note: &str) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!(method, note, "call to deprecated method");
+}
+
+// Split the fields of C out of named params, for traits with the common option. C is
+// deserialized from the whole object, noting the field names its Deserialize implementation asks
+// for, and those fields are then removed so the rest can be matched to the method's arguments.
+// Positional params have no fields to take, so C is deserialized from an empty object.
+#[doc(hidden)]
+pub fn extract_common<C: serde::de::DeserializeOwned>(
+    params: crate::Params,
+) -> Result<(C, crate::Params), crate::Error> {
+    let invalid = |e: serde_json::Error| crate::Error {
+        data: Some(serde_json::json!({ "error": e.to_string() })),
+        ..InvalidArgs::InvalidParamsStructure.into()
+    };
+    match params {
+        crate::Params::Named(map) => {
+            let object = serde_json::Value::Object(map);
+            let mut fields: &[&str] = &[];
+            let common = C::deserialize(FieldNames {
+                value: &object,
+                fields: &mut fields,
+            })
+            .map_err(invalid)?;
+            let mut map = match object {
+                serde_json::Value::Object(map) => map,
+                _ => unreachable!(),
+            };
+            for field in fields {
+                map.remove(*field);
+            }
+            Ok((common, crate::Params::Named(map)))
+        }
+        params => {
+            let common = C::deserialize(serde_json::json!({})).map_err(invalid)?;
+            Ok((common, params))
+        }
+    }
+}
+
+// Deserializes like value, recording the fields of the struct deserialized from it.
+struct FieldNames<'a, 'f> {
+    value: &'a serde_json::Value,
+    fields: &'f mut &'static [&'static str],
+}
+
+impl<'de> serde::Deserializer<'de> for FieldNames<'de, '_> {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: serde::de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.value.deserialize_any(visitor)
+    }
+
+    fn deserialize_struct<V: serde::de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.fields = fields;
+        self.value.deserialize_struct(name, fields, visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier
+        ignored_any
+    }
+}
+
+// Rename each (alias, name) pair's alias to name in named params, for arguments marked
+// #[rpc_arg(alias = ..)]. Where both are given, name wins and the alias is dropped.
+#[doc(hidden)]
+pub fn resolve_aliases(params: crate::Params, aliases: &[(&str, &str)]) -> crate::Params {
+    match params {
+        crate::Params::Named(mut map) => {
+            for (alias, name) in aliases {
+                if let Some(value) = map.remove(*alias) {
+                    if !map.contains_key(*name) {
+                        map.insert((*name).to_owned(), value);
+                    }
+                }
+            }
+            crate::Params::Named(map)
+        }
+        params => params,
+    }
+}
+
+// Take the cursor and limit of a #[rpc(paginate)] method out of its params. They are named
+// "cursor" and "limit", or are the two positional params following the method's args.
+#[doc(hidden)]
+pub fn take_page_params(
+    params: crate::Params,
+    args: usize,
+) -> Result<(crate::Params, Option<u64>, Option<usize>), crate::Error> {
+    let (params, cursor, limit) = match params {
+        crate::Params::Named(mut map) => {
+            let cursor = map.remove("cursor");
+            let limit = map.remove("limit");
+            (crate::Params::Named(map), cursor, limit)
+        }
+        crate::Params::Positional(mut values) => {
+            if values.len() > args + 2 {
+                return Err(InvalidArgs::WrongNumberOfArgs {
+                    expected: args + 2,
+                    actual: values.len(),
+                }
+                .into());
+            }
+            let mut page = values.split_off(values.len().min(args)).into_iter();
+            (crate::Params::Positional(values), page.next(), page.next())
+        }
+        crate::Params::Absent => (crate::Params::Absent, None, None),
+    };
+    let cursor = match cursor {
+        None | Some(serde_json::Value::Null) => None,
+        Some(value) => Some(from_integer_value::<u64>(&value, "cursor", args, "u64")?),
+    };
+    // an empty page would never get to the next one
+    let limit = match limit {
+        None | Some(serde_json::Value::Null) => None,
+        Some(value) => Some(
+            from_integer_value::<std::num::NonZeroUsize>(
+                &value,
+                "limit",
+                args + 1,
+                "NonZeroUsize",
+            )?
+            .get(),
+        ),
+    };
+    Ok((params, cursor, limit))
+}
+
+// Serialize the page of items a #[rpc(paginate)] method's caller asked for.
+#[doc(hidden)]
+pub fn page<T: serde::Serialize>(
+    items: &[T],
+    cursor: Option<u64>,
+    limit: Option<usize>,
+) -> Result<serde_json::Value, crate::Error> {
+    #[derive(serde::Serialize)]
+    struct PageRef<'a, T> {
+        items: &'a [T],
+        next_cursor: Option<u64>,
+    }
+
+    let start = cursor
+        .map_or(0, |cursor| usize::try_from(cursor).unwrap_or(usize::MAX))
+        .min(items.len());
+    let len = limit
+        .unwrap_or(crate::DEFAULT_PAGE_LIMIT)
+        .min(items.len() - start);
+    let end = start + len;
+    crate::try_serialize(&PageRef {
+        items: &items[start..end],
+        next_cursor: (end < items.len()).then_some(end as u64),
+    })
 }
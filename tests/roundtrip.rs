@@ -0,0 +1,115 @@
+// Calls made through the generated client functions and answered by the generated handler must
+// return what calling the method directly returns, whichever path the handler parses params on.
+// Slow, so only built with the proptests feature: cargo test --features proptests --test roundtrip
+
+use easy_jsonrpc::{rpc, BoundMethod, HandleRaw, Handler, Response};
+use proptest::prelude::*;
+use serde::Deserialize;
+
+#[rpc]
+pub trait Api {
+    fn flip(&self, a: bool) -> bool;
+    fn widen(&self, a: u8, b: i64) -> (u8, i64);
+    fn big(&self, a: u64, b: u128) -> u128;
+    fn letter(&self, a: char) -> String;
+    fn echo(&self, a: String) -> String;
+    fn borrowed(&self, a: &str) -> usize;
+    fn reverse(&self, a: Vec<u32>) -> Vec<u32>;
+    fn nested(&self, a: Vec<Option<String>>) -> Vec<Option<String>>;
+    fn optional(&self, a: u32, b: Option<String>, c: Option<Vec<i16>>) -> Option<String>;
+}
+
+struct ApiImpl;
+impl Api for ApiImpl {
+    fn flip(&self, a: bool) -> bool {
+        !a
+    }
+
+    fn widen(&self, a: u8, b: i64) -> (u8, i64) {
+        (a, b)
+    }
+
+    fn big(&self, a: u64, b: u128) -> u128 {
+        b.wrapping_add(a.into())
+    }
+
+    fn letter(&self, a: char) -> String {
+        a.to_string().repeat(2)
+    }
+
+    fn echo(&self, a: String) -> String {
+        a
+    }
+
+    fn borrowed(&self, a: &str) -> usize {
+        a.chars().count()
+    }
+
+    fn reverse(&self, mut a: Vec<u32>) -> Vec<u32> {
+        a.reverse();
+        a
+    }
+
+    fn nested(&self, a: Vec<Option<String>>) -> Vec<Option<String>> {
+        a
+    }
+
+    fn optional(&self, a: u32, b: Option<String>, c: Option<Vec<i16>>) -> Option<String> {
+        Some(format!("{} {:?} {:?}", a, b?, c))
+    }
+}
+
+// The result of bound as returned by each way of handling a request, which must all agree.
+fn call<T: Deserialize<'static> + std::fmt::Debug + PartialEq>(bound: BoundMethod<'_, T>) -> T {
+    let (call, tracker) = bound.call();
+    let request = call.as_request();
+    let mut handler = &ApiImpl as &dyn Api;
+
+    let mut response =
+        Response::from_json_response(handler.handle_request(request.clone()).as_option().unwrap())
+            .unwrap();
+    let result = tracker.get_return(&mut response).unwrap();
+
+    let raw_response = handler.handle_request_str(&request.to_string()).unwrap();
+    let mut response =
+        Response::from_json_response(serde_json::from_str(&raw_response).unwrap()).unwrap();
+    assert_eq!(tracker.get_return(&mut response).unwrap(), result);
+
+    let raw_response = HandleRaw::handle_request_raw(&mut handler, &request.to_string()).unwrap();
+    let mut response =
+        Response::from_json_response(serde_json::from_str(&raw_response).unwrap()).unwrap();
+    assert_eq!(tracker.get_return(&mut response).unwrap(), result);
+
+    result
+}
+
+proptest! {
+    #[test]
+    fn primitives(a: bool, b: u8, c: i64, d: u64, e: u128, f: char) {
+        prop_assert_eq!(call(api::flip(a).unwrap()), ApiImpl.flip(a));
+        prop_assert_eq!(call(api::widen(b, c).unwrap()), ApiImpl.widen(b, c));
+        prop_assert_eq!(call(api::big(d, e).unwrap()), ApiImpl.big(d, e));
+        prop_assert_eq!(call(api::letter(f).unwrap()), ApiImpl.letter(f));
+    }
+
+    #[test]
+    fn strings(a: String) {
+        prop_assert_eq!(call(api::echo(a.clone()).unwrap()), ApiImpl.echo(a.clone()));
+        // escape sequences keep &str from borrowing the request
+        prop_assert_eq!(call(api::borrowed(&a).unwrap()), ApiImpl.borrowed(&a));
+    }
+
+    #[test]
+    fn vecs(a: Vec<u32>, b: Vec<Option<String>>) {
+        prop_assert_eq!(call(api::reverse(a.clone()).unwrap()), ApiImpl.reverse(a));
+        prop_assert_eq!(call(api::nested(b.clone()).unwrap()), ApiImpl.nested(b));
+    }
+
+    #[test]
+    fn options(a: u32, b: Option<String>, c: Option<Vec<i16>>) {
+        prop_assert_eq!(
+            call(api::optional(a, b.clone(), c.clone()).unwrap()),
+            ApiImpl.optional(a, b, c)
+        );
+    }
+}
@@ -0,0 +1,10 @@
+use easy_jsonrpc::rpc;
+
+pub struct Opaque;
+
+#[rpc]
+pub trait Api {
+    fn take(&self, a: u8, b: Opaque);
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+#[easy_jsonrpc::rpc]
+pub trait Versioned {
+    const VERSION: u32 = 2;
+    const NAME: &'static str;
+
+    fn version(&self) -> u32;
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+#[easy_jsonrpc::rpc]
+trait Api {
+    #[rpc(client_only, server_only)]
+    fn nowhere(&self);
+}
+
+fn main() {}
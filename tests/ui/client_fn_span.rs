@@ -0,0 +1,9 @@
+#[easy_jsonrpc::rpc]
+pub trait Api {
+    fn add(&self, a: u8, b: u8) -> u8;
+}
+
+fn main() {
+    let _ = api::add(1);
+    let _ = api::subtract(1, 2);
+}
@@ -0,0 +1,12 @@
+#[easy_jsonrpc::rpc]
+trait Load {
+    #[rpc(hex, base64)]
+    fn load(&self) -> Vec<u8>;
+}
+
+#[easy_jsonrpc::rpc]
+trait Store {
+    fn store(&self, #[rpc_arg(base64, hex)] data: Vec<u8>);
+}
+
+fn main() {}
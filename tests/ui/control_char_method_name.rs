@@ -0,0 +1,7 @@
+#[easy_jsonrpc::rpc]
+trait Api {
+    #[rpc(name = "tab\tbed")]
+    fn tabbed(&self);
+}
+
+fn main() {}
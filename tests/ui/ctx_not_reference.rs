@@ -0,0 +1,8 @@
+struct Ctx;
+
+#[easy_jsonrpc::rpc]
+trait Api {
+    fn owned(&self, #[rpc_ctx] ctx: Ctx);
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+#![deny(deprecated)]
+
+#[easy_jsonrpc::rpc(client_trait)]
+pub trait Api {
+    #[rpc(deprecated = "use g instead")]
+    fn f(&self);
+    fn g(&self);
+}
+
+struct Transport;
+impl ApiClient for Transport {}
+
+fn main() {
+    let _ = api::f();
+    let _ = Transport.f();
+    let _ = api::g();
+}
@@ -0,0 +1,7 @@
+#[easy_jsonrpc::rpc]
+trait Api {
+    fn f(&self, a: u8, #[rpc_arg(alias = "a")] b: u8);
+    fn g(&self, #[rpc_arg(alias = "x")] a: u8, #[rpc_arg(alias = "x")] b: u8);
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+#[easy_jsonrpc::rpc]
+trait Api {
+    #[rpc(name = "")]
+    fn unnamed(&self);
+}
+
+fn main() {}
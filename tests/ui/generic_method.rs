@@ -0,0 +1,19 @@
+#[easy_jsonrpc::rpc]
+trait TypeParam {
+    fn f<T>(&self, t: T);
+}
+
+#[easy_jsonrpc::rpc]
+trait ConstParam {
+    fn g<const N: usize>(&self);
+}
+
+#[easy_jsonrpc::rpc]
+trait WhereClause {
+    fn h<'a, 'b>(&self, a: &'a str, b: &'b str) -> &'a str
+    where
+        'b: 'a,
+        Self: Sized;
+}
+
+fn main() {}
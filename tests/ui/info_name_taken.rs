@@ -0,0 +1,9 @@
+#[easy_jsonrpc::rpc]
+pub trait Api {
+    fn idempotent(&self);
+    fn foo_bar(&self);
+    #[allow(non_snake_case)]
+    fn fooBar(&self);
+}
+
+fn main() {}
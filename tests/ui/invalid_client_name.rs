@@ -0,0 +1,6 @@
+#[easy_jsonrpc::rpc(client = "not an ident")]
+trait Api {
+    fn f(&self);
+}
+
+fn main() {}
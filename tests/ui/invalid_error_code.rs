@@ -0,0 +1,9 @@
+#[easy_jsonrpc::rpc]
+trait Api {
+    #[rpc(error_code = 1)]
+    fn not_result(&self) -> u8;
+    #[rpc(error_code = 2)]
+    fn raw(&self) -> Result<easy_jsonrpc::Value, easy_jsonrpc::Error>;
+}
+
+fn main() {}
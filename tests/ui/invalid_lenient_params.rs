@@ -0,0 +1,11 @@
+use easy_jsonrpc::rpc;
+
+#[rpc]
+pub trait Api {
+    #[rpc(lenient_params)]
+    fn none(&self);
+    #[rpc(lenient_params)]
+    fn two(&self, a: u8, b: u8);
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+#[easy_jsonrpc::rpc]
+trait Api {
+    #[rpc(paginate)]
+    fn count(&self) -> u64;
+    #[rpc(paginate)]
+    fn list(&self, cursor: u64) -> Vec<u64>;
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use easy_jsonrpc::rpc;
+
+#[rpc]
+pub trait Api {
+    #[rpc(params_struct)]
+    fn two(&self, a: u8, b: u8);
+    #[rpc(params_struct)]
+    fn borrowed(&self, a: &u8);
+    #[rpc(params_struct)]
+    fn coded(&self, #[rpc_arg(hex)] a: Vec<u8>);
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+#[easy_jsonrpc::rpc]
+trait Api {
+    fn not_last(&self, #[rpc_arg(rest)] nums: Vec<u8>, label: String);
+    fn not_vec(&self, #[rpc_arg(rest)] nums: u8);
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+#[derive(easy_jsonrpc::RpcError)]
+enum ApiError {
+    #[rpc_error(message = "Not found")]
+    NotFound,
+}
+
+#[derive(easy_jsonrpc::RpcError)]
+struct NotAnEnum;
+
+fn main() {}
@@ -0,0 +1,8 @@
+struct Ctx;
+
+#[easy_jsonrpc::rpc]
+trait Api {
+    fn late(&self, a: u8, #[rpc_ctx] ctx: &Ctx);
+}
+
+fn main() {}
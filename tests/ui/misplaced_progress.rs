@@ -0,0 +1,8 @@
+use easy_jsonrpc::Value;
+
+#[easy_jsonrpc::rpc]
+trait Api {
+    fn late(&self, a: u8, #[rpc_progress] progress: &dyn Fn(Value));
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+struct Db;
+struct Logger;
+
+#[easy_jsonrpc::rpc]
+trait Api {
+    fn query(&self, #[rpc_ctx] db: &Db);
+    fn log(&self, #[rpc_ctx] logger: &Logger);
+}
+
+fn main() {}
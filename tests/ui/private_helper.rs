@@ -0,0 +1,10 @@
+mod api {
+    #[easy_jsonrpc::rpc]
+    trait Api {
+        fn ping(&self);
+    }
+}
+
+fn main() {
+    let _ = api::api::ping();
+}
@@ -0,0 +1,15 @@
+#[easy_jsonrpc::rpc]
+trait Api {
+    #[rpc(readonly)]
+    fn bump(&mut self);
+}
+
+struct Ctx;
+
+#[easy_jsonrpc::rpc]
+trait WithCtx {
+    #[rpc(readonly)]
+    fn get(&self, #[rpc_ctx] ctx: &Ctx) -> u8;
+}
+
+fn main() {}
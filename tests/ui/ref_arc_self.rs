@@ -0,0 +1,9 @@
+use easy_jsonrpc::rpc;
+use std::sync::Arc;
+
+#[rpc]
+pub trait Actor {
+    fn start(self: &Arc<Self>) -> bool;
+}
+
+fn main() {}
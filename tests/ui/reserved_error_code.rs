@@ -0,0 +1,16 @@
+#[easy_jsonrpc::rpc(error_base = -31990)]
+trait Api {
+    #[rpc(error_code = 1)]
+    fn allowed(&self) -> Result<u8, String>;
+    // -31990 - 20 = -32010, which is reserved by the spec
+    #[rpc(error_code = -20)]
+    fn reserved(&self) -> Result<u8, String>;
+}
+
+#[easy_jsonrpc::rpc]
+trait Absolute {
+    #[rpc(error_code = -32600)]
+    fn reserved(&self) -> Result<u8, String>;
+}
+
+fn main() {}
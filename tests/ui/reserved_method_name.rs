@@ -0,0 +1,11 @@
+#[easy_jsonrpc::rpc]
+trait Api {
+    #[rpc(name = "rpc.internal")]
+    fn internal(&self);
+    #[rpc(name = "rpc.foo")]
+    fn foo(&self);
+    #[rpc(name = "rpc.discover")]
+    fn discover(&self);
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+#[easy_jsonrpc::rpc]
+trait Api {
+    #[rpc(server_only)]
+    fn internal(&self);
+}
+
+fn main() {
+    let _ = api::internal();
+}
@@ -0,0 +1,10 @@
+#[easy_jsonrpc::rpc(strict_sync)]
+pub trait Api {
+    fn both(&self);
+    #[rpc(server_only, unsynced)]
+    fn admin(&self);
+    #[rpc(client_only)]
+    fn forgotten(&self);
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use std::collections::HashMap;
+
+#[easy_jsonrpc::rpc]
+trait Api {
+    #[rpc(stringify_keys)]
+    fn pairs(&self) -> HashMap<(u8, u8), u8>;
+}
+
+fn main() {}
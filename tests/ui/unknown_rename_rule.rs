@@ -0,0 +1,8 @@
+use easy_jsonrpc::rpc;
+
+#[rpc(rename_all = "CamelCase")]
+pub trait Api {
+    fn get_balance(&self, account_id: u64) -> u64;
+}
+
+fn main() {}